@@ -24,7 +24,7 @@
 //! ```
 
 use clap::{arg, value_parser, ArgAction};
-use phidget::{devices::DigitalInput, Phidget};
+use phidget::{devices::DigitalInput, Channel, HubPort, Phidget, SerialNumber};
 use std::{thread, time::Duration};
 
 // The open/connect timeout
@@ -70,16 +70,16 @@ fn main() -> anyhow::Result<()> {
     // and if so, which one?
     digin.set_is_hub_port_device(use_hub)?;
     if let Some(&port) = opts.get_one::<i32>("port") {
-        digin.set_hub_port(port)?;
+        digin.set_hub_port(HubPort::new(port)?)?;
     }
 
     // Some other device selection filters...
     if let Some(&num) = opts.get_one::<i32>("serial") {
-        digin.set_serial_number(num)?;
+        digin.set_serial_number(SerialNumber::new(num)?)?;
     }
 
     if let Some(&chan) = opts.get_one::<i32>("channel") {
-        digin.set_channel(chan)?;
+        digin.set_channel(Channel::new(chan)?)?;
     }
 
     digin.open_wait(TIMEOUT)?;
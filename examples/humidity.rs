@@ -14,7 +14,7 @@
 //!
 
 use clap::{arg, value_parser, ArgAction};
-use phidget::{devices::HumiditySensor, Phidget};
+use phidget::{devices::HumiditySensor, Channel, HubPort, Phidget, SerialNumber};
 use std::{thread, time::Duration};
 
 // Open/connect timeout
@@ -60,15 +60,15 @@ fn main() -> anyhow::Result<()> {
 
     // Some device selection filters...
     if let Some(&port) = opts.get_one::<i32>("port") {
-        sensor.set_hub_port(port)?;
+        sensor.set_hub_port(HubPort::new(port)?)?;
     }
 
     if let Some(&num) = opts.get_one::<i32>("serial") {
-        sensor.set_serial_number(num)?;
+        sensor.set_serial_number(SerialNumber::new(num)?)?;
     }
 
     if let Some(&chan) = opts.get_one::<i32>("channel") {
-        sensor.set_channel(chan)?;
+        sensor.set_channel(Channel::new(chan)?)?;
     }
 
     sensor.open_wait(TIMEOUT)?;
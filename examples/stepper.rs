@@ -13,7 +13,7 @@
 //! Rust Phidget example application to read temperature.
 //!
 use clap::{arg, value_parser, ArgAction};
-use phidget::Phidget;
+use phidget::{Channel, HubPort, Phidget, SerialNumber};
 use std::{thread, time::Duration};
 // The open/connect timeout
 const TIMEOUT: Duration = phidget::TIMEOUT_DEFAULT;
@@ -59,16 +59,16 @@ fn main() -> anyhow::Result<()> {
     // and if so, which one?
     stepper.set_is_hub_port_device(use_hub)?;
     if let Some(&port) = opts.get_one::<i32>("port") {
-        stepper.set_hub_port(port)?;
+        stepper.set_hub_port(HubPort::new(port)?)?;
     }
 
     // Some other device selection filters...
     if let Some(&serial) = opts.get_one::<i32>("serial") {
-        stepper.set_serial_number(serial)?;
+        stepper.set_serial_number(SerialNumber::new(serial)?)?;
     }
 
     if let Some(&channel) = opts.get_one::<i32>("channel") {
-        stepper.set_channel(channel)?;
+        stepper.set_channel(Channel::new(channel)?)?;
     }
     let mut target_position = 0f64;
     if let Some(&target) = opts.get_one::<f64>("target") {
@@ -14,7 +14,11 @@
 //!
 
 use clap::{arg, value_parser, ArgAction};
-use phidget::{devices::TemperatureSensor, Phidget};
+use phidget::{
+    devices::TemperatureSensor,
+    profile::{ChannelProfile, Profiled},
+    Channel, HubPort, Phidget, SerialNumber,
+};
 use std::{thread, time::Duration};
 
 // The open/connect timeout
@@ -65,30 +69,29 @@ fn main() -> anyhow::Result<()> {
 
     // Some device selection filters...
     if let Some(&port) = opts.get_one::<i32>("port") {
-        sensor.set_hub_port(port)?;
+        sensor.set_hub_port(HubPort::new(port)?)?;
     }
 
     if let Some(&num) = opts.get_one::<i32>("serial") {
-        sensor.set_serial_number(num)?;
+        sensor.set_serial_number(SerialNumber::new(num)?)?;
     }
 
     if let Some(&chan) = opts.get_one::<i32>("channel") {
-        sensor.set_channel(chan)?;
+        sensor.set_channel(Channel::new(chan)?)?;
     }
 
+    // The acquisition interval (sampling period), staged to be set when
+    // the sensor attaches, before the first reading
+    let profile = ChannelProfile {
+        data_interval: opts.get_one::<u32>("interval").copied(),
+        ..ChannelProfile::default()
+    };
+    sensor.stage_profile(profile)?;
     sensor.open_wait(TIMEOUT)?;
 
     let port = sensor.hub_port()?;
     println!("Opened on hub port: {}", port);
 
-    // Set the acquisition interval (sampling period)
-    if let Some(&interval) = opts.get_one::<u32>("interval") {
-        let dur = Duration::from_millis(interval as u64);
-        if let Err(err) = sensor.set_data_interval(dur) {
-            eprintln!("Error setting interval: {}", err);
-        }
-    }
-
     println!("\nReading temperature. Hit ^C to exit.");
 
     // Read a single value...
@@ -44,7 +44,7 @@
 //! ```
 
 use clap::{arg, value_parser, ArgAction};
-use phidget::{devices::VoltageInput, Phidget};
+use phidget::{devices::VoltageInput, Channel, HubPort, Phidget, SerialNumber};
 use std::{thread, time::Duration};
 
 // The open/connect timeout
@@ -105,16 +105,16 @@ fn main() -> anyhow::Result<()> {
     // and if so, which one?
     vin.set_is_hub_port_device(use_hub)?;
     if let Some(&port) = opts.get_one::<i32>("port") {
-        vin.set_hub_port(port)?;
+        vin.set_hub_port(HubPort::new(port)?)?;
     }
 
     // Some other device selection filters...
     if let Some(&num) = opts.get_one::<i32>("serial") {
-        vin.set_serial_number(num)?;
+        vin.set_serial_number(SerialNumber::new(num)?)?;
     }
 
     if let Some(&chan) = opts.get_one::<i32>("channel") {
-        vin.set_channel(chan)?;
+        vin.set_channel(Channel::new(chan)?)?;
     }
 
     let offset = *opts.get_one::<f64>("offset").unwrap();
@@ -33,7 +33,7 @@
 //!
 
 use clap::{arg, value_parser, ArgAction};
-use phidget::{devices::VoltageRatioInput, Phidget};
+use phidget::{devices::VoltageRatioInput, Channel, Phidget, SerialNumber};
 use std::{thread, time::Duration};
 
 // The open/connect timeout
@@ -81,11 +81,11 @@ fn main() -> anyhow::Result<()> {
 
     //Some other device selection filters..
     if let Some(&num) = opts.get_one::<i32>("serial") {
-        vin.set_serial_number(num)?;
+        vin.set_serial_number(SerialNumber::new(num)?)?;
     }
 
     if let Some(&chan) = opts.get_one::<i32>("channel") {
-        vin.set_channel(chan)?;
+        vin.set_channel(Channel::new(chan)?)?;
     }
 
     let offset = *opts.get_one::<f64>("offset").unwrap();
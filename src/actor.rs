@@ -0,0 +1,277 @@
+// phidget-rs/src/actor.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Async device actors for use with tokio.
+//!
+//! A [`DeviceActor`] owns a device on a dedicated thread, and hands out a
+//! cheap, cloneable handle that sends commands to it and awaits the
+//! responses. This sidesteps the friction of sharing a device between
+//! async tasks and the library callbacks, and keeps the blocking calls
+//! into the phidget22 library off of the async worker threads. Each actor
+//! has a thread of its own, rather than one from the tokio blocking pool,
+//! so that any number of devices can be owned by actors without starving
+//! the pool.
+//!
+//! The async operations that wait on the hardware, like opening a device
+//! or moving a motor, have variants that take a [`CancellationToken`], so
+//! that shutting down a service doesn't hang waiting for a device that
+//! never appears.
+//!
+//! This requires the `tokio` feature.
+
+use crate::{
+    devices::{DigitalInput, DigitalOutput, Stepper, TemperatureSensor, VoltageInput},
+    ErrorCode, Phidget, Result,
+};
+use std::{thread, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time,
+};
+pub use tokio_util::sync::CancellationToken;
+
+// The number of commands that can be queued for an actor.
+const QUEUE_SIZE: usize = 32;
+
+// The interval at which to poll the device while waiting on it.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// A command to run against the device owned by an actor.
+type Command<T> = Box<dyn FnOnce(&mut T) + Send + 'static>;
+
+/// A handle to a device owned by an actor thread.
+///
+/// Each clone of the handle sends commands to the same device. The actor
+/// thread exits, and the device is dropped, when the last handle is
+/// dropped.
+///
+/// ```rust,no_run
+/// # async fn run() -> phidget::Result<()> {
+/// use phidget::{actor::DeviceActor, devices::DigitalOutput};
+/// use std::time::Duration;
+///
+/// let led = DeviceActor::spawn(DigitalOutput::new());
+/// led.open_wait(Duration::from_secs(5)).await?;
+/// led.set_state(true).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DeviceActor<T> {
+    tx: mpsc::Sender<Command<T>>,
+}
+
+impl<T> DeviceActor<T>
+where
+    T: Phidget + 'static,
+{
+    /// Moves the device onto a new actor thread, returning a handle to it.
+    ///
+    /// This starts an OS thread for the actor, which lives until the last
+    /// handle is dropped. The handle itself is used from async code.
+    pub fn spawn(dev: T) -> Self {
+        let (tx, mut rx) = mpsc::channel::<Command<T>>(QUEUE_SIZE);
+        thread::spawn(move || {
+            let mut dev = dev;
+            while let Some(cmd) = rx.blocking_recv() {
+                cmd(&mut dev);
+            }
+        });
+        Self { tx }
+    }
+
+    /// Runs a function against the device on the actor thread, and returns
+    /// its result.
+    ///
+    /// This returns a `Closed` error if the actor is no longer running.
+    pub async fn call<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut T) -> Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let cmd: Command<T> = Box::new(move |dev| {
+            let _ = tx.send(f(dev));
+        });
+        self.tx.send(cmd).await.map_err(|_| ErrorCode::Closed)?;
+        rx.await.map_err(|_| ErrorCode::Closed)?
+    }
+
+    /// Opens the device, waiting a limited time for it to attach.
+    pub async fn open_wait(&self, to: Duration) -> Result<()> {
+        self.call(move |dev| dev.open_wait(to)).await
+    }
+
+    /// Opens the device, waiting a limited time for it to attach, or until
+    /// the token is cancelled.
+    ///
+    /// If the device doesn't attach, it is closed again, and this returns
+    /// a `Timeout` error, or an `Interrupted` error if it was cancelled.
+    pub async fn open_wait_cancellable(
+        &self,
+        to: Duration,
+        token: &CancellationToken,
+    ) -> Result<()> {
+        self.call(|dev| dev.open()).await?;
+
+        let res = tokio::select! {
+            _ = token.cancelled() => Err(ErrorCode::Interrupted),
+            res = time::timeout(to, self.wait_attached()) => {
+                res.unwrap_or(Err(ErrorCode::Timeout))
+            }
+        };
+
+        if res.is_err() {
+            let _ = self.close().await;
+        }
+        res
+    }
+
+    /// Waits for an opened device to attach.
+    pub async fn wait_attached(&self) -> Result<()> {
+        while !self.is_attached().await? {
+            time::sleep(POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+
+    /// Closes the device.
+    pub async fn close(&self) -> Result<()> {
+        self.call(|dev| dev.close()).await
+    }
+
+    /// Determines if the device is attached.
+    pub async fn is_attached(&self) -> Result<bool> {
+        self.call(|dev| dev.is_attached()).await
+    }
+}
+
+impl<T> Clone for DeviceActor<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+impl DeviceActor<DigitalOutput> {
+    /// Sets the state of the digital output.
+    pub async fn set_state(&self, on: bool) -> Result<()> {
+        self.call(move |dev| dev.set_state_bool(on)).await
+    }
+
+    /// Gets the state of the digital output.
+    pub async fn state(&self) -> Result<bool> {
+        self.call(|dev| dev.state().map(|s| s != 0)).await
+    }
+
+    /// Sets the duty cycle of the digital output.
+    pub async fn set_duty_cycle(&self, duty_cycle: f64) -> Result<()> {
+        self.call(move |dev| dev.set_duty_cycle(duty_cycle)).await
+    }
+}
+
+impl DeviceActor<DigitalInput> {
+    /// Gets the state of the digital input.
+    pub async fn state(&self) -> Result<bool> {
+        self.call(|dev| dev.state().map(|s| s != 0)).await
+    }
+}
+
+impl DeviceActor<TemperatureSensor> {
+    /// Gets the most recent temperature reading.
+    pub async fn temperature(&self) -> Result<f64> {
+        self.call(|dev| dev.temperature()).await
+    }
+}
+
+impl DeviceActor<VoltageInput> {
+    /// Gets the most recent voltage reading.
+    pub async fn voltage(&self) -> Result<f64> {
+        self.call(|dev| dev.voltage()).await
+    }
+}
+
+impl DeviceActor<Stepper> {
+    /// Engages or disengages the motor.
+    pub async fn set_engaged(&self, engaged: bool) -> Result<()> {
+        self.call(move |dev| dev.set_engaged(engaged)).await
+    }
+
+    /// Sets the target position of the motor.
+    pub async fn set_target_position(&self, pos: f64) -> Result<()> {
+        self.call(move |dev| dev.set_target_position(pos)).await
+    }
+
+    /// Gets the current position of the motor.
+    pub async fn position(&self) -> Result<f64> {
+        self.call(|dev| dev.position()).await
+    }
+
+    /// Determines if the motor is moving.
+    pub async fn is_moving(&self) -> Result<bool> {
+        self.call(|dev| dev.is_moving()).await
+    }
+
+    /// Moves the motor to the target position, and waits for it to stop,
+    /// or until the token is cancelled.
+    ///
+    /// The motor must already be engaged. If cancelled, the motor is
+    /// stopped at its current position, and this returns an `Interrupted`
+    /// error.
+    pub async fn move_to_cancellable(&self, pos: f64, token: &CancellationToken) -> Result<()> {
+        self.set_target_position(pos).await?;
+
+        let res = tokio::select! {
+            _ = token.cancelled() => Err(ErrorCode::Interrupted),
+            res = self.wait_stopped() => res,
+        };
+
+        if res.is_err() {
+            let _ = self
+                .call(|dev| dev.position().and_then(|pos| dev.set_target_position(pos)))
+                .await;
+        }
+        res
+    }
+
+    /// Waits for the motor to stop moving.
+    pub async fn wait_stopped(&self) -> Result<()> {
+        while self.is_moving().await? {
+            time::sleep(POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GenericPhidget;
+    use std::ptr;
+
+    #[test]
+    fn test_actor_call() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        rt.block_on(async {
+            let actor = DeviceActor::spawn(GenericPhidget::new(ptr::null_mut()));
+            let n = actor.clone().call(|_| Ok(42)).await;
+            assert_eq!(n, Ok(42));
+
+            let res: Result<()> = actor.call(|_| Err(ErrorCode::Timeout)).await;
+            assert_eq!(res, Err(ErrorCode::Timeout));
+        });
+    }
+}
@@ -0,0 +1,219 @@
+// phidget-rs/src/aliases.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Named device aliases.
+//!
+//! An alias gives an application-level name, like "boiler_temp" or
+//! "door_switch", to a [`DeviceSpec`]. The mapping is normally loaded from
+//! a configuration file, so that application code never needs to
+//! hard-code serial numbers or hub ports.
+//!
+//! The text form of the mapping has one alias per line, with the name and
+//! the device specification separated by an equal sign. Blank lines and
+//! lines starting with `#` are ignored:
+//!
+//! ```text
+//! # Boiler room
+//! boiler_temp = sn=12345,port=2
+//! door_switch = label=door
+//! ```
+//!
+//! A set of aliases can be installed for the whole application, after
+//! which devices can be opened by name:
+//!
+//! ```rust,no_run
+//! use phidget::{aliases::{self, open_named, Aliases}, devices::TemperatureSensor};
+//!
+//! let cfg: Aliases = std::fs::read_to_string("devices.conf")
+//!     .unwrap()
+//!     .parse()
+//!     .unwrap();
+//! aliases::install(cfg);
+//!
+//! let sensor = open_named::<TemperatureSensor>("boiler_temp").unwrap();
+//! println!("{}", sensor.temperature().unwrap());
+//! ```
+
+use crate::{DeviceSpec, ErrorCode, Phidget, Result, TIMEOUT_DEFAULT};
+use std::{collections::BTreeMap, fmt, str::FromStr, sync::Mutex, time::Duration};
+
+/// The aliases installed for the application
+static ALIASES: Mutex<BTreeMap<String, DeviceSpec>> = Mutex::new(BTreeMap::new());
+
+/// A mapping of names to device specifications.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Aliases {
+    map: BTreeMap<String, DeviceSpec>,
+}
+
+impl Aliases {
+    /// Creates an empty set of aliases.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an alias, replacing any previous one with the same name.
+    pub fn insert(&mut self, name: &str, spec: DeviceSpec) -> Option<DeviceSpec> {
+        self.map.insert(name.to_string(), spec)
+    }
+
+    /// Adds an alias, builder style.
+    pub fn with(mut self, name: &str, spec: DeviceSpec) -> Self {
+        self.insert(name, spec);
+        self
+    }
+
+    /// Removes an alias.
+    pub fn remove(&mut self, name: &str) -> Option<DeviceSpec> {
+        self.map.remove(name)
+    }
+
+    /// Gets the specification for the named device.
+    pub fn get(&self, name: &str) -> Option<&DeviceSpec> {
+        self.map.get(name)
+    }
+
+    /// Gets the number of aliases.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Determines if there are no aliases.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Gets an iterator over the names and their specifications.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DeviceSpec)> {
+        self.map.iter().map(|(name, spec)| (name.as_str(), spec))
+    }
+
+    /// Opens the named device as a channel of the requested type, waiting
+    /// a limited time for it to attach.
+    ///
+    /// Returns a `NoEnt` error if there is no alias with that name.
+    pub fn open<P>(&self, name: &str, to: Duration) -> Result<P>
+    where
+        P: Phidget + Default,
+    {
+        self.get(name).ok_or(ErrorCode::NoEnt)?.open(to)
+    }
+}
+
+impl fmt::Display for Aliases {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, spec) in &self.map {
+            writeln!(f, "{} = {}", name, spec)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Aliases {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut aliases = Self::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, spec) = line.split_once('=').ok_or(ErrorCode::InvalidArg)?;
+            let name = name.trim();
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(ErrorCode::InvalidArg);
+            }
+            aliases.insert(name, spec.parse()?);
+        }
+        Ok(aliases)
+    }
+}
+
+impl FromIterator<(String, DeviceSpec)> for Aliases {
+    fn from_iter<I: IntoIterator<Item = (String, DeviceSpec)>>(iter: I) -> Self {
+        Self {
+            map: iter.into_iter().collect(),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// Installs the aliases for the application, replacing any that were
+/// previously installed.
+pub fn install(aliases: Aliases) {
+    *ALIASES.lock().unwrap() = aliases.map;
+}
+
+/// Gets a copy of the aliases installed for the application.
+pub fn installed() -> Aliases {
+    Aliases {
+        map: ALIASES.lock().unwrap().clone(),
+    }
+}
+
+/// Gets the specification of a device from the installed aliases.
+pub fn lookup(name: &str) -> Option<DeviceSpec> {
+    ALIASES.lock().unwrap().get(name).cloned()
+}
+
+/// Opens a device by name from the installed aliases, waiting up to the
+/// default timeout for it to attach.
+///
+/// Returns a `NoEnt` error if there is no alias with that name.
+pub fn open_named<P>(name: &str) -> Result<P>
+where
+    P: Phidget + Default,
+{
+    open_named_timeout(name, TIMEOUT_DEFAULT)
+}
+
+/// Opens a device by name from the installed aliases, waiting a limited
+/// time for it to attach.
+pub fn open_named_timeout<P>(name: &str, to: Duration) -> Result<P>
+where
+    P: Phidget + Default,
+{
+    lookup(name).ok_or(ErrorCode::NoEnt)?.open(to)
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cfg = "# Boiler room\n\
+                   boiler_temp = sn=12345,port=2\n\
+                   \n\
+                   door_switch=label=door\n";
+
+        let aliases: Aliases = cfg.parse().unwrap();
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(
+            aliases.get("boiler_temp"),
+            Some(&"sn=12345,port=2".parse().unwrap())
+        );
+        assert_eq!(
+            aliases.get("door_switch"),
+            Some(&DeviceSpec::new().label("door"))
+        );
+        assert_eq!(aliases.to_string().parse(), Ok(aliases));
+
+        assert!("boiler temp = sn=1".parse::<Aliases>().is_err());
+        assert!("boiler_temp".parse::<Aliases>().is_err());
+        assert!("boiler_temp = sn=abc".parse::<Aliases>().is_err());
+    }
+}
@@ -0,0 +1,254 @@
+// phidget-rs/src/bus.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! An application-wide bus for device events.
+//!
+//! Any part of an application can subscribe to the [`bus()`] for attach,
+//! detach, and error events from the channels that match a
+//! [`DeviceSpec`], without needing access to the code that owns the
+//! channel handles.
+//!
+//! The bus starts a [`Manager`] when the first subscription is made, to
+//! report the attach and detach events for every channel on the system.
+//! Error events are reported for the channels that were created by this
+//! crate.
+//!
+//! ```rust,no_run
+//! use phidget::{bus::{self, BusEvent}, DeviceSpec, SerialNumber};
+//!
+//! let spec = DeviceSpec::new().serial_number(SerialNumber::new(12345).unwrap());
+//! bus::bus()
+//!     .subscribe(spec, |evt| match evt {
+//!         BusEvent::Attached(info) => println!("Attached: {}", info.channel_name),
+//!         BusEvent::Detached(info) => println!("Detached: {}", info.channel_name),
+//!         BusEvent::Error { code, description, .. } => println!("{}: {}", code, description),
+//!     })
+//!     .unwrap();
+//! ```
+
+use crate::{DeviceSpec, ErrorEventCode, GenericPhidget, Manager, PhidgetInfo, Result};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex, OnceLock,
+};
+
+/// An event published on the bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BusEvent {
+    /// A channel was attached to the system
+    Attached(PhidgetInfo),
+    /// A channel was detached from the system
+    Detached(PhidgetInfo),
+    /// A channel reported an error event
+    Error {
+        /// The channel that reported the error
+        info: PhidgetInfo,
+        /// The error code
+        code: ErrorEventCode,
+        /// The description of the error, from the library
+        description: String,
+    },
+}
+
+impl BusEvent {
+    /// Gets information about the channel that triggered the event.
+    pub fn info(&self) -> &PhidgetInfo {
+        match self {
+            BusEvent::Attached(info) | BusEvent::Detached(info) => info,
+            BusEvent::Error { info, .. } => info,
+        }
+    }
+}
+
+/// The identifier of a subscription, used to cancel it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubscriptionId(u64);
+
+// The function type for a subscriber
+type Subscriber = dyn Fn(&BusEvent) + Send + Sync + 'static;
+
+/// The application-wide event bus.
+///
+/// This is obtained with [`bus()`].
+pub struct EventBus {
+    // The ID for the next subscription
+    next_id: AtomicU64,
+    // Whether there are any subscribers, checked without the lock
+    active: AtomicBool,
+    // The subscribers, with their filters
+    subs: Mutex<Vec<(SubscriptionId, DeviceSpec, Arc<Subscriber>)>>,
+    // The manager reporting attach and detach events, once started
+    mgr: Mutex<Option<Manager>>,
+}
+
+impl EventBus {
+    // Creates an empty bus
+    fn new() -> Self {
+        Self {
+            next_id: AtomicU64::new(1),
+            active: AtomicBool::new(false),
+            subs: Mutex::new(Vec::new()),
+            mgr: Mutex::new(None),
+        }
+    }
+
+    /// Subscribes to the events from the channels that match the filter.
+    ///
+    /// The callback runs in the context of the library's event threads.
+    /// It may subscribe and unsubscribe, but should return quickly.
+    ///
+    /// The first subscription starts the manager, which reports an attach
+    /// event for each of the channels that are already attached. Later
+    /// subscribers only see the changes from then on.
+    pub fn subscribe<F>(&self, filter: DeviceSpec, cb: F) -> Result<SubscriptionId>
+    where
+        F: Fn(&BusEvent) + Send + Sync + 'static,
+    {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.subs.lock().unwrap().push((id, filter, Arc::new(cb)));
+        self.active.store(true, Ordering::Release);
+
+        if let Err(err) = self.start() {
+            self.unsubscribe(id);
+            return Err(err);
+        }
+        Ok(id)
+    }
+
+    /// Cancels a subscription.
+    ///
+    /// Returns whether the subscription was found.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut subs = self.subs.lock().unwrap();
+        let n = subs.len();
+        subs.retain(|(sub_id, ..)| *sub_id != id);
+        self.active.store(!subs.is_empty(), Ordering::Release);
+        subs.len() != n
+    }
+
+    /// Gets the number of subscriptions.
+    pub fn subscriber_count(&self) -> usize {
+        self.subs.lock().unwrap().len()
+    }
+
+    // Starts the manager, if it isn't running already.
+    fn start(&self) -> Result<()> {
+        let mut mgr_opt = self.mgr.lock().unwrap();
+        if mgr_opt.is_none() {
+            let mut mgr = Manager::new();
+            mgr.set_on_attach_handler(|ph| {
+                if let Ok(info) = PhidgetInfo::try_from(ph) {
+                    bus().publish(&BusEvent::Attached(info));
+                }
+            })?;
+            mgr.set_on_detach_handler(|ph| {
+                if let Ok(info) = PhidgetInfo::try_from(ph) {
+                    bus().publish(&BusEvent::Detached(info));
+                }
+            })?;
+            mgr.open()?;
+            *mgr_opt = Some(mgr);
+        }
+        Ok(())
+    }
+
+    /// Publishes an event to the matching subscribers.
+    pub fn publish(&self, evt: &BusEvent) {
+        // Call the subscribers outside the lock, so they can (un)subscribe.
+        let subs: Vec<_> = self
+            .subs
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, filter, _)| filter.matches(evt.info()))
+            .map(|(_, _, cb)| Arc::clone(cb))
+            .collect();
+
+        for cb in subs {
+            cb(evt);
+        }
+    }
+}
+
+/// Gets the application-wide event bus.
+pub fn bus() -> &'static EventBus {
+    static BUS: OnceLock<EventBus> = OnceLock::new();
+    BUS.get_or_init(EventBus::new)
+}
+
+/// Publishes an error event from a channel, if there are any subscribers.
+pub(crate) fn publish_error(ph: &GenericPhidget, code: ErrorEventCode, descr: &str) {
+    let bus = bus();
+    if bus.active.load(Ordering::Acquire) {
+        let mut ph = GenericPhidget::from(*ph.as_channel());
+        if let Ok(info) = PhidgetInfo::new(&mut ph) {
+            bus.publish(&BusEvent::Error {
+                info,
+                code,
+                description: descr.into(),
+            });
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Channel, ChannelClass, DeviceClass, DeviceId, HubPort, SerialNumber};
+    use std::sync::atomic::AtomicUsize;
+
+    fn info(serial_number: i32) -> PhidgetInfo {
+        PhidgetInfo {
+            serial_number: SerialNumber::new(serial_number).unwrap(),
+            hub_port: HubPort::new(0).unwrap(),
+            channel: Channel::new(0).unwrap(),
+            is_hub_port_device: false,
+            channel_class: ChannelClass::TemperatureSensor,
+            device_class: DeviceClass::Vint,
+            device_id: DeviceId::Tmp1101,
+            channel_name: String::new(),
+            device_name: String::new(),
+            device_sku: String::new(),
+            device_version: 100,
+            device_label: String::new(),
+            server_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_publish_filter() {
+        // Use a private bus, so as not to start a manager.
+        let bus = EventBus::new();
+        let n = Arc::new(AtomicUsize::new(0));
+
+        let spec = DeviceSpec::new().serial_number(SerialNumber::new(12345).unwrap());
+        let cb = {
+            let n = Arc::clone(&n);
+            move |_: &BusEvent| {
+                n.fetch_add(1, Ordering::Relaxed);
+            }
+        };
+        let id = SubscriptionId(1);
+        bus.subs.lock().unwrap().push((id, spec, Arc::new(cb)));
+
+        bus.publish(&BusEvent::Attached(info(12345)));
+        bus.publish(&BusEvent::Detached(info(54321)));
+        assert_eq!(n.load(Ordering::Relaxed), 1);
+
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        bus.publish(&BusEvent::Attached(info(12345)));
+        assert_eq!(n.load(Ordering::Relaxed), 1);
+    }
+}
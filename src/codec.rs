@@ -0,0 +1,180 @@
+// phidget-rs/src/codec.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Compact binary encoding of device information and events.
+//!
+//! When sensor streams are shipped between processes, a binary format is
+//! much smaller, and faster to encode, than JSON. This module encodes any
+//! of the serializable types in the crate, like [`PhidgetInfo`](crate::PhidgetInfo)
+//! and [`DeviceEvent`](crate::watcher::DeviceEvent), as either:
+//!
+//! - CBOR, with the `cbor` feature
+//! - MessagePack, with the `msgpack` feature
+//!
+//! Either feature enables `serde`. The `write_` and `read_` functions work
+//! on a stream, like a pipe or socket, one value at a time.
+//!
+//! ```rust,ignore
+//! use phidget::{codec, watcher::{DeviceEvent, Watcher}};
+//!
+//! let watcher = Watcher::new()?;
+//! while let Some(evt) = watcher.recv() {
+//!     codec::write_cbor(&evt, &mut sock)?;
+//! }
+//!
+//! // ...in the other process
+//! let evt: DeviceEvent = codec::read_cbor(&mut sock)?;
+//! ```
+//!
+//! Encoding errors are reported as `Unexpected`, errors writing to the
+//! stream as `Io`, and values that can't be decoded as `InvalidPacket`.
+
+use crate::{ErrorCode, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::io::{Read, Write};
+
+/// Encodes a value as CBOR.
+#[cfg(feature = "cbor")]
+pub fn to_cbor<T: Serialize + ?Sized>(val: &T) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    write_cbor(val, &mut buf)?;
+    Ok(buf)
+}
+
+/// Decodes a value from CBOR.
+#[cfg(feature = "cbor")]
+pub fn from_cbor<T: DeserializeOwned>(buf: &[u8]) -> Result<T> {
+    read_cbor(buf)
+}
+
+/// Encodes a value as CBOR to a stream.
+#[cfg(feature = "cbor")]
+pub fn write_cbor<T, W>(val: &T, wr: W) -> Result<()>
+where
+    T: Serialize + ?Sized,
+    W: Write,
+{
+    use ciborium::ser::Error;
+    ciborium::into_writer(val, wr).map_err(|err| match err {
+        Error::Io(_) => ErrorCode::Io,
+        Error::Value(_) => ErrorCode::Unexpected,
+    })
+}
+
+/// Decodes the next value from a CBOR stream.
+#[cfg(feature = "cbor")]
+pub fn read_cbor<T, R>(rd: R) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    use ciborium::de::Error;
+    ciborium::from_reader(rd).map_err(|err| match err {
+        Error::Io(_) => ErrorCode::Io,
+        _ => ErrorCode::InvalidPacket,
+    })
+}
+
+/// Encodes a value as MessagePack.
+///
+/// Structs are encoded as maps, with the field names, so that the data
+/// can be read by other languages, and by later versions of the crate.
+#[cfg(feature = "msgpack")]
+pub fn to_msgpack<T: Serialize + ?Sized>(val: &T) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    write_msgpack(val, &mut buf)?;
+    Ok(buf)
+}
+
+/// Decodes a value from MessagePack.
+#[cfg(feature = "msgpack")]
+pub fn from_msgpack<T: DeserializeOwned>(buf: &[u8]) -> Result<T> {
+    read_msgpack(buf)
+}
+
+/// Encodes a value as MessagePack to a stream.
+#[cfg(feature = "msgpack")]
+pub fn write_msgpack<T, W>(val: &T, mut wr: W) -> Result<()>
+where
+    T: Serialize + ?Sized,
+    W: Write,
+{
+    use rmp_serde::encode::Error;
+    val.serialize(&mut rmp_serde::Serializer::new(&mut wr).with_struct_map())
+        .map_err(|err| match err {
+            Error::InvalidValueWrite(_) => ErrorCode::Io,
+            _ => ErrorCode::Unexpected,
+        })
+}
+
+/// Decodes the next value from a MessagePack stream.
+#[cfg(feature = "msgpack")]
+pub fn read_msgpack<T, R>(rd: R) -> Result<T>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    use rmp_serde::decode::Error;
+    rmp_serde::from_read(rd).map_err(|err| match err {
+        Error::InvalidMarkerRead(_) | Error::InvalidDataRead(_) => ErrorCode::Io,
+        _ => ErrorCode::InvalidPacket,
+    })
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        watcher::DeviceEvent, Channel, ChannelClass, DeviceClass, DeviceId, HubPort, PhidgetInfo,
+        SerialNumber,
+    };
+
+    fn event() -> DeviceEvent {
+        DeviceEvent::Attached(PhidgetInfo {
+            serial_number: SerialNumber::new(12345).unwrap(),
+            hub_port: HubPort::new(2).unwrap(),
+            channel: Channel::new(0).unwrap(),
+            is_hub_port_device: false,
+            channel_class: ChannelClass::TemperatureSensor,
+            device_class: DeviceClass::Vint,
+            device_id: DeviceId::Unknown,
+            channel_name: "Temperature Sensor".into(),
+            device_name: "Temperature Phidget".into(),
+            device_sku: "TMP1000".into(),
+            device_version: 100,
+            device_label: String::new(),
+            server_name: String::new(),
+        })
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor() {
+        let evt = event();
+        let buf = to_cbor(&evt).unwrap();
+        assert_eq!(from_cbor::<DeviceEvent>(&buf), Ok(evt));
+        assert_eq!(
+            from_cbor::<DeviceEvent>(&buf[..buf.len() / 2]),
+            Err(ErrorCode::Io)
+        );
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack() {
+        let evt = event();
+        let buf = to_msgpack(&evt).unwrap();
+        assert_eq!(from_msgpack::<DeviceEvent>(&buf), Ok(evt));
+        assert!(from_msgpack::<DeviceEvent>(b"\xc1").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+// phidget-rs/src/dbus.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Exposing sensors over D-Bus, on Linux.
+//!
+//! A [`DbusService`] claims a well-known name on the session or system
+//! bus, and publishes an object for each sensor that the application adds
+//! to it. Each object implements the `io.github.phidget_rs.Sensor`
+//! interface, with:
+//!
+//! - A read-only `Name` property, for display
+//! - A read-only `Value` property, with the latest reading, which emits
+//!   the standard `PropertiesChanged` signal when it changes
+//! - A `Changed(d)` signal with each new reading
+//!
+//! This lets system services and desktop applets consume Phidget data
+//! with any D-Bus library, or from the command line with `busctl`:
+//!
+//! ```rust,no_run
+//! use phidget::{dbus::DbusService, devices::TemperatureSensor, Phidget};
+//!
+//! let svc = DbusService::session("io.github.phidget_rs.Demo").unwrap();
+//! let obj = svc.add_sensor("/boiler", "Boiler temperature").unwrap();
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! sensor
+//!     .set_on_temperature_change_handler(move |_, t| {
+//!         let _ = obj.update(t);
+//!     })
+//!     .unwrap();
+//! sensor.open_wait_default().unwrap();
+//! ```
+//!
+//! This requires the `dbus` feature.
+
+use crate::{ErrorCode, Result};
+use zbus::{blocking::Connection, dbus_interface, SignalContext};
+
+// Converts a D-Bus error to an error code for the crate.
+fn dbus_err(err: zbus::Error) -> ErrorCode {
+    match err {
+        zbus::Error::NameTaken => ErrorCode::Duplicate,
+        zbus::Error::InputOutput(_) => ErrorCode::Io,
+        zbus::Error::Address(_) => ErrorCode::InvalidArg,
+        _ => ErrorCode::Unexpected,
+    }
+}
+
+// The D-Bus interface for a sensor object.
+struct SensorIface {
+    name: String,
+    value: f64,
+}
+
+#[dbus_interface(name = "io.github.phidget_rs.Sensor")]
+impl SensorIface {
+    #[dbus_interface(property)]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn value(&self) -> f64 {
+        self.value
+    }
+
+    #[dbus_interface(signal)]
+    async fn changed(ctxt: &SignalContext<'_>, value: f64) -> zbus::Result<()>;
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// A service that exposes sensors over D-Bus.
+///
+/// The name is released, and the objects removed, when the service and
+/// all of its sensor objects are dropped.
+pub struct DbusService {
+    conn: Connection,
+}
+
+impl DbusService {
+    /// Connects to the session bus, and claims the well-known name.
+    ///
+    /// Returns a `Duplicate` error if the name is already taken.
+    pub fn session(name: &str) -> Result<Self> {
+        Self::with_connection(Connection::session().map_err(dbus_err)?, name)
+    }
+
+    /// Connects to the system bus, and claims the well-known name.
+    ///
+    /// The system bus normally needs a policy file to allow the
+    /// application to claim the name.
+    pub fn system(name: &str) -> Result<Self> {
+        Self::with_connection(Connection::system().map_err(dbus_err)?, name)
+    }
+
+    // Claims the name on a connection.
+    fn with_connection(conn: Connection, name: &str) -> Result<Self> {
+        conn.request_name(name).map_err(dbus_err)?;
+        Ok(Self { conn })
+    }
+
+    /// Adds a sensor object at the specified path, like "/boiler".
+    ///
+    /// The object starts with a value of zero. Returns a `Duplicate` error
+    /// if there is already an object at the path.
+    pub fn add_sensor(&self, path: &str, name: &str) -> Result<SensorObject> {
+        let iface = SensorIface {
+            name: name.to_string(),
+            value: 0.0,
+        };
+        if !self
+            .conn
+            .object_server()
+            .at(path, iface)
+            .map_err(dbus_err)?
+        {
+            return Err(ErrorCode::Duplicate);
+        }
+        Ok(SensorObject {
+            conn: self.conn.clone(),
+            path: path.to_string(),
+        })
+    }
+
+    /// Removes the sensor object at the specified path.
+    ///
+    /// Returns whether there was an object to remove.
+    pub fn remove_sensor(&self, path: &str) -> Result<bool> {
+        self.conn
+            .object_server()
+            .remove::<SensorIface, _>(path)
+            .map_err(dbus_err)
+    }
+}
+
+/// A handle to a sensor object published by a [`DbusService`].
+///
+/// This is cheap to clone, and can be moved into a device callback.
+#[derive(Clone)]
+pub struct SensorObject {
+    conn: Connection,
+    path: String,
+}
+
+impl SensorObject {
+    /// Gets the path of the object.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Sets the value of the sensor, and signals the change.
+    pub fn update(&self, value: f64) -> Result<()> {
+        let server = self.conn.object_server();
+        let iface_ref = server
+            .interface::<_, SensorIface>(self.path.as_str())
+            .map_err(dbus_err)?;
+        let ctxt = iface_ref.signal_context();
+
+        {
+            let mut iface = iface_ref.get_mut();
+            iface.value = value;
+            zbus::block_on(iface.value_changed(ctxt)).map_err(dbus_err)?;
+        }
+        zbus::block_on(SensorIface::changed(ctxt, value)).map_err(dbus_err)
+    }
+}
@@ -0,0 +1,408 @@
+// phidget-rs/src/devices/capacitive_touch.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget capacitive touch channel.
+//!
+//! Capacitive touch pads, like the HIN1000 button and the HIN1001 wheel,
+//! report a touch value while they're touched, which is the position on
+//! a wheel or slider, and an event when the touch ends:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::CapacitiveTouch, Phidget};
+//!
+//! let mut pad = CapacitiveTouch::new();
+//! pad.set_on_touch_handler(|_, val| println!("Touched: {:.2}", val))
+//!     .unwrap();
+//! pad.set_on_touch_end_handler(|_| println!("Released"))
+//!     .unwrap();
+//! pad.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    properties::{self, Properties},
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
+use phidget_sys::{
+    self as ffi, PhidgetCapacitiveTouchHandle as CapacitiveTouchHandle, PhidgetHandle,
+};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+};
+
+/// The function signature for the safe Rust touch callback.
+///
+/// This receives the touch value, each time that it changes while the
+/// pad is touched.
+pub type TouchCallback = dyn Fn(&CapacitiveTouch, f64) + Send + 'static;
+/// The function signature for the safe Rust touch end callback.
+pub type TouchEndCallback = dyn Fn(&CapacitiveTouch) + Send + 'static;
+
+/// Phidget capacitive touch pad
+pub struct CapacitiveTouch {
+    // Handle to the sensor for the phidget22 library
+    chan: CapacitiveTouchHandle,
+    // Double-boxed TouchCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed TouchEndCallback, if registered
+    touch_end_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl CapacitiveTouch {
+    /// Create a new touch pad.
+    pub fn new() -> Self {
+        let mut chan: CapacitiveTouchHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetCapacitiveTouch_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for touch events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_touch(chan: CapacitiveTouchHandle, ctx: *mut c_void, touch_value: f64) {
+        if !ctx.is_null() {
+            let cb: &mut Box<TouchCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            cb(&sensor, touch_value);
+            mem::forget(sensor);
+        }
+    }
+
+    // Low-level, unsafe, callback for touch end events.
+    unsafe extern "C" fn on_touch_end(chan: CapacitiveTouchHandle, ctx: *mut c_void) {
+        if !ctx.is_null() {
+            let cb: &mut Box<TouchEndCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            cb(&sensor);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &CapacitiveTouchHandle {
+        &self.chan
+    }
+
+    /// Read the current touch value, from 0.0 to 1.0.
+    pub fn touch_value(&self) -> Result<f64> {
+        let mut touch_value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getTouchValue(self.chan, &mut touch_value)
+        })?;
+        Ok(touch_value)
+    }
+
+    /// Gets the touch value, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_touch_value(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.touch_value())
+    }
+
+    /// Gets the minimum touch value that the sensor can measure.
+    pub fn min_touch_value(&self) -> Result<f64> {
+        let mut touch_value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getMinTouchValue(self.chan, &mut touch_value)
+        })?;
+        Ok(touch_value)
+    }
+
+    /// Gets the maximum touch value that the sensor can measure.
+    pub fn max_touch_value(&self) -> Result<f64> {
+        let mut touch_value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getMaxTouchValue(self.chan, &mut touch_value)
+        })?;
+        Ok(touch_value)
+    }
+
+    /// Determines if the pad is currently being touched.
+    pub fn is_touched(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getIsTouched(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Gets the sensitivity of the pad.
+    pub fn sensitivity(&self) -> Result<f64> {
+        let mut sensitivity = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getSensitivity(self.chan, &mut sensitivity)
+        })?;
+        Ok(sensitivity)
+    }
+
+    /// Sets the sensitivity of the pad.
+    ///
+    /// Higher values detect a touch more readily, such as through a
+    /// thicker cover over the pad, but are more prone to false touches.
+    pub fn set_sensitivity(&self, sensitivity: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_setSensitivity(self.chan, sensitivity)
+        })
+    }
+
+    /// Gets the range that the sensitivity can be set within.
+    pub fn sensitivity_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getMinSensitivity(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getMaxSensitivity(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the touch value change trigger.
+    /// Change events are only fired when the touch value changes by at least
+    /// this amount.
+    pub fn touch_value_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getTouchValueChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the touch value change trigger.
+    pub fn set_touch_value_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_setTouchValueChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum touch value change trigger.
+    pub fn min_touch_value_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getMinTouchValueChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum touch value change trigger.
+    pub fn max_touch_value_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_getMaxTouchValueChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and touch value change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_touch_value_change_trigger()?,
+                self.max_touch_value_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the touch value reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_touch_value()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.touch_value_change_trigger()?,
+        })
+    }
+
+    /// Sets a handler to receive the touch value while the pad is
+    /// touched.
+    pub fn set_on_touch_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&CapacitiveTouch, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<TouchCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        let res = ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_setOnTouchHandler(self.chan, Some(Self::on_touch), ctx)
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<TouchCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<TouchCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent touch value reading.
+    ///
+    /// This sets the touch handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_touch_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Sets a handler to receive an event when the touch ends.
+    pub fn set_on_touch_end_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&CapacitiveTouch) + Send + 'static,
+    {
+        let cb: Box<Box<TouchEndCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.touch_end_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCapacitiveTouch_setOnTouchEndHandler(
+                self.chan,
+                Some(Self::on_touch_end),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for CapacitiveTouch {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as CapacitiveTouchHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.touch_value_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_touch_value_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_touch_value_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_touch_value_change_trigger()
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        let lim = self.sensitivity_limits();
+        properties::add_ranged(
+            &mut props,
+            "sensitivity",
+            self.sensitivity(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for CapacitiveTouch {}
+
+impl Default for CapacitiveTouch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<CapacitiveTouchHandle> for CapacitiveTouch {
+    fn from(chan: CapacitiveTouchHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            touch_end_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for CapacitiveTouch {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetCapacitiveTouch_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<TouchCallback>(self.cb.take());
+        crate::drop_cb::<TouchEndCallback>(self.touch_end_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -0,0 +1,303 @@
+// phidget-rs/src/devices/current_input.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget current input
+
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetCurrentInputHandle as CurrentInputHandle, PhidgetHandle};
+use std::{mem, os::raw::c_void, ptr};
+
+/// The function signature for the safe Rust current change callback.
+pub type CurrentCallback = dyn Fn(&CurrentInput, f64) + Send + 'static;
+
+/// Phidget current input
+pub struct CurrentInput {
+    // Handle to the sensor for the phidget22 library
+    chan: CurrentInputHandle,
+    // Double-boxed CurrentCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl CurrentInput {
+    /// Create a new current input.
+    pub fn new() -> Self {
+        let mut chan: CurrentInputHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetCurrentInput_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for current change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_current_change(
+        chan: CurrentInputHandle,
+        ctx: *mut c_void,
+        current: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<CurrentCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            cb(&sensor, current);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &CurrentInputHandle {
+        &self.chan
+    }
+
+    /// Read the current, in amperes.
+    pub fn current(&self) -> Result<f64> {
+        let mut current = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCurrentInput_getCurrent(self.chan, &mut current)
+        })?;
+        Ok(current)
+    }
+
+    /// Gets the minimum current that the channel can measure.
+    pub fn min_current(&self) -> Result<f64> {
+        let mut current = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCurrentInput_getMinCurrent(self.chan, &mut current)
+        })?;
+        Ok(current)
+    }
+
+    /// Gets the maximum current that the channel can measure.
+    pub fn max_current(&self) -> Result<f64> {
+        let mut current = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCurrentInput_getMaxCurrent(self.chan, &mut current)
+        })?;
+        Ok(current)
+    }
+
+    /// Gets the current, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_current(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.current())
+    }
+
+    /// Gets the current change trigger.
+    /// Change events are only fired when the current changes by at least
+    /// this amount.
+    pub fn current_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCurrentInput_getCurrentChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the current change trigger.
+    pub fn set_current_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCurrentInput_setCurrentChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum current change trigger.
+    pub fn min_current_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCurrentInput_getMinCurrentChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum current change trigger.
+    pub fn max_current_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetCurrentInput_getMaxCurrentChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and current change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_current_change_trigger()?,
+                self.max_current_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the current reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_current()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.current_change_trigger()?,
+        })
+    }
+
+    /// Sets a handler to receive current change callbacks.
+    pub fn set_on_current_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&CurrentInput, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<CurrentCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        let res = ReturnCode::result(unsafe {
+            ffi::PhidgetCurrentInput_setOnCurrentChangeHandler(
+                self.chan,
+                Some(Self::on_current_change),
+                ctx,
+            )
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<CurrentCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<CurrentCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent current reading.
+    ///
+    /// This sets the current change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_current_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for CurrentInput {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as CurrentInputHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.current_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_current_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_current_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_current_change_trigger()
+    }
+}
+
+unsafe impl Send for CurrentInput {}
+
+impl Default for CurrentInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<CurrentInputHandle> for CurrentInput {
+    fn from(chan: CurrentInputHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for CurrentInput {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetCurrentInput_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<CurrentCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -0,0 +1,469 @@
+// phidget-rs/src/devices/dc_motor.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget DC motor controller channel.
+//!
+//! A DC motor is driven at a target velocity, as a duty cycle from -1.0
+//! (full reverse) to 1.0 (full forward). The controller ramps the motor
+//! to the target at the set acceleration:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::DcMotor, Phidget};
+//!
+//! let mut motor = DcMotor::new();
+//! motor.open_wait_default().unwrap();
+//!
+//! motor.set_acceleration(2.0).unwrap();
+//! motor.set_target_velocity(0.5).unwrap();
+//! ```
+//!
+//! The current drawn by the motor is reported by a separate
+//! [`CurrentInput`](crate::devices::CurrentInput) channel on the
+//! controller.
+
+use crate::{
+    devices::Bounds,
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorCode, ErrorEventCode, GenericPhidget,
+    Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetDCMotorHandle as DcMotorHandle, PhidgetHandle};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The function type for the safe Rust velocity update callback.
+pub type VelocityUpdateCallback = dyn Fn(&DcMotor, f64) + Send + 'static;
+/// The function type for the safe Rust back EMF change callback.
+pub type BackEmfChangeCallback = dyn Fn(&DcMotor, f64) + Send + 'static;
+/// The function type for the safe Rust braking strength change callback.
+pub type BrakingStrengthChangeCallback = dyn Fn(&DcMotor, f64) + Send + 'static;
+
+/// Phidget DC motor
+pub struct DcMotor {
+    // Handle to the motor in the phidget22 library
+    chan: DcMotorHandle,
+    // Double-boxed VelocityUpdateCallback, if registered
+    velocity_cb: Option<*mut c_void>,
+    // Double-boxed BackEmfChangeCallback, if registered
+    back_emf_cb: Option<*mut c_void>,
+    // Double-boxed BrakingStrengthChangeCallback, if registered
+    braking_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl DcMotor {
+    /// Create a new DC motor.
+    pub fn new() -> Self {
+        let mut chan: DcMotorHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetDCMotor_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    /// Get a reference to the underlying motor handle
+    pub fn as_channel(&self) -> &DcMotorHandle {
+        &self.chan
+    }
+
+    /// Gets the most recent velocity reported by the controller, as a
+    /// duty cycle.
+    pub fn velocity(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getVelocity(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Gets the target velocity.
+    pub fn target_velocity(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_getTargetVelocity(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the velocity that the motor is driven at, as a duty cycle.
+    ///
+    /// A negative value drives the motor in reverse.
+    pub fn set_target_velocity(&self, velocity: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_setTargetVelocity(self.chan, velocity) })
+    }
+
+    /// Gets the range that the target velocity can be set within.
+    pub fn velocity_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getMinVelocity(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getMaxVelocity(self.chan, &mut max) })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the acceleration.
+    pub fn acceleration(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getAcceleration(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the rate at which the velocity changes towards the target, in
+    /// duty cycle per second.
+    pub fn set_acceleration(&self, acceleration: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_setAcceleration(self.chan, acceleration) })
+    }
+
+    /// Gets the range that the acceleration can be set within.
+    pub fn acceleration_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getMinAcceleration(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getMaxAcceleration(self.chan, &mut max) })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the current limit, in amperes.
+    pub fn current_limit(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getCurrentLimit(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the maximum current that the controller supplies to the motor,
+    /// in amperes.
+    pub fn set_current_limit(&self, current_limit: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_setCurrentLimit(self.chan, current_limit) })
+    }
+
+    /// Gets the range that the current limit can be set within.
+    pub fn current_limit_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getMinCurrentLimit(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getMaxCurrentLimit(self.chan, &mut max) })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the most recent braking strength reported by the controller.
+    pub fn braking_strength(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_getBrakingStrength(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Gets the target braking strength.
+    pub fn target_braking_strength(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_getTargetBrakingStrength(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets how hard the motor is braked when the velocity is zero, from
+    /// 0.0 (coasting) to 1.0 (full braking).
+    pub fn set_target_braking_strength(&self, strength: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_setTargetBrakingStrength(self.chan, strength)
+        })
+    }
+
+    /// Gets the range that the braking strength can be set within.
+    pub fn braking_strength_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_getMinBrakingStrength(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_getMaxBrakingStrength(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the most recent back EMF reported by the controller, in volts.
+    pub fn back_emf(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getBackEMF(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Determines if back EMF sensing is enabled.
+    pub fn back_emf_sensing(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_getBackEMFSensingState(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables back EMF sensing.
+    pub fn set_back_emf_sensing(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_setBackEMFSensingState(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Enables the failsafe for the channel with the specified timeout.
+    ///
+    /// Once enabled, the failsafe must be reset within the time, or the
+    /// motor is stopped.
+    pub fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        let ms = u32::try_from(time.as_millis()).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_enableFailsafe(self.chan, ms) })
+    }
+
+    /// Resets the failsafe timer.
+    pub fn reset_failsafe(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_resetFailsafe(self.chan) })
+    }
+
+    /// Gets the range of the failsafe time.
+    pub fn failsafe_time_limits(&self) -> Result<Bounds<Duration>> {
+        let (mut min, mut max) = (0, 0);
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getMinFailsafeTime(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetDCMotor_getMaxFailsafeTime(self.chan, &mut max) })?;
+        Ok(Bounds::new(
+            Duration::from_millis(min.into()),
+            Duration::from_millis(max.into()),
+        ))
+    }
+
+    // Low-level, unsafe, callback for velocity update events.
+    // The context is a double-boxed pointer to the safe Rust callback.
+    unsafe extern "C" fn on_velocity_update(chan: DcMotorHandle, ctx: *mut c_void, vel: f64) {
+        if !ctx.is_null() {
+            let cb: &mut Box<VelocityUpdateCallback> = &mut *(ctx as *mut _);
+            let motor = Self::from(chan);
+            cb(&motor, vel);
+            mem::forget(motor);
+        }
+    }
+
+    // Low-level, unsafe, callback for back EMF change events.
+    unsafe extern "C" fn on_back_emf_change(chan: DcMotorHandle, ctx: *mut c_void, emf: f64) {
+        if !ctx.is_null() {
+            let cb: &mut Box<BackEmfChangeCallback> = &mut *(ctx as *mut _);
+            let motor = Self::from(chan);
+            cb(&motor, emf);
+            mem::forget(motor);
+        }
+    }
+
+    // Low-level, unsafe, callback for braking strength change events.
+    unsafe extern "C" fn on_braking_strength_change(
+        chan: DcMotorHandle,
+        ctx: *mut c_void,
+        strength: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<BrakingStrengthChangeCallback> = &mut *(ctx as *mut _);
+            let motor = Self::from(chan);
+            cb(&motor, strength);
+            mem::forget(motor);
+        }
+    }
+
+    /// Sets a handler to receive the velocity, each data interval.
+    pub fn set_on_velocity_update_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&DcMotor, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<VelocityUpdateCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.velocity_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_setOnVelocityUpdateHandler(
+                self.chan,
+                Some(Self::on_velocity_update),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive back EMF change callbacks.
+    pub fn set_on_back_emf_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&DcMotor, f64) + Send + 'static,
+    {
+        let cb: Box<Box<BackEmfChangeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.back_emf_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_setOnBackEMFChangeHandler(
+                self.chan,
+                Some(Self::on_back_emf_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive braking strength change callbacks.
+    pub fn set_on_braking_strength_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&DcMotor, f64) + Send + 'static,
+    {
+        let cb: Box<Box<BrakingStrengthChangeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.braking_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDCMotor_setOnBrakingStrengthChangeHandler(
+                self.chan,
+                Some(Self::on_braking_strength_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for DcMotor {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as DcMotorHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        let lim = self.velocity_limits();
+        properties::add_ranged(
+            &mut props,
+            "target_velocity",
+            self.target_velocity(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        let lim = self.acceleration_limits();
+        properties::add_ranged(
+            &mut props,
+            "acceleration",
+            self.acceleration(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        let lim = self.current_limit_limits();
+        properties::add_ranged(
+            &mut props,
+            "current_limit",
+            self.current_limit(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        let lim = self.braking_strength_limits();
+        properties::add_ranged(
+            &mut props,
+            "target_braking_strength",
+            self.target_braking_strength(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        properties::add(&mut props, "back_emf_sensing", self.back_emf_sensing())?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for DcMotor {}
+
+impl Default for DcMotor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<DcMotorHandle> for DcMotor {
+    fn from(chan: DcMotorHandle) -> Self {
+        Self {
+            chan,
+            velocity_cb: None,
+            back_emf_cb: None,
+            braking_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for DcMotor {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetDCMotor_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<VelocityUpdateCallback>(self.velocity_cb.take());
+        crate::drop_cb::<BackEmfChangeCallback>(self.back_emf_cb.take());
+        crate::drop_cb::<BrakingStrengthChangeCallback>(self.braking_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -0,0 +1,367 @@
+// phidget-rs/src/devices/dictionary.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget dictionary channel.
+//!
+//! A dictionary is a set of string key-value pairs, kept by a Phidget
+//! network server, which any number of processes can open to share
+//! state. Each dictionary has a serial number, which is used to open it,
+//! like any other channel. Every client is notified as the pairs are
+//! added, updated, and removed:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::Dictionary, Phidget, SerialNumber};
+//!
+//! let mut dict = Dictionary::new();
+//! dict.set_on_update_handler(|_, key, val| println!("{} = {}", key, val))
+//!     .unwrap();
+//! dict.set_serial_number(SerialNumber::new(5000).unwrap())
+//!     .unwrap();
+//! dict.open_wait_default().unwrap();
+//!
+//! dict.set("setpoint", "22.5").unwrap();
+//! ```
+
+use crate::{
+    lifecycle::Registration, AttachCallback, DetachCallback, ErrorCallback, ErrorCode,
+    ErrorEventCode, GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetDictionaryHandle as DictionaryHandle, PhidgetHandle};
+use std::{
+    ffi::{CStr, CString},
+    mem,
+    os::raw::{c_char, c_void},
+    ptr,
+};
+
+/// The size of a buffer that can hold any value
+const VALUE_BUF_LEN: usize = 1024;
+
+/// The size of a buffer for a list of keys
+const KEY_LIST_BUF_LEN: usize = 8192;
+
+/// The function type for the safe Rust add callback.
+///
+/// This receives the key and value of the new pair.
+pub type AddCallback = dyn Fn(&Dictionary, &str, &str) + Send + 'static;
+/// The function type for the safe Rust update callback.
+///
+/// This receives the key and the new value.
+pub type UpdateCallback = dyn Fn(&Dictionary, &str, &str) + Send + 'static;
+/// The function type for the safe Rust remove callback.
+///
+/// This receives the key that was removed.
+pub type RemoveCallback = dyn Fn(&Dictionary, &str) + Send + 'static;
+
+// Converts a string to a C string for the library
+fn c_string(s: &str) -> Result<CString> {
+    CString::new(s).map_err(|_| ErrorCode::InvalidArg)
+}
+
+/// Phidget dictionary
+pub struct Dictionary {
+    // Handle to the dictionary in the phidget22 library
+    chan: DictionaryHandle,
+    // Double-boxed AddCallback, if registered
+    add_cb: Option<*mut c_void>,
+    // Double-boxed UpdateCallback, if registered
+    update_cb: Option<*mut c_void>,
+    // Double-boxed RemoveCallback, if registered
+    remove_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Dictionary {
+    /// Create a new dictionary.
+    pub fn new() -> Self {
+        let mut chan: DictionaryHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetDictionary_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for add events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_add(
+        chan: DictionaryHandle,
+        ctx: *mut c_void,
+        key: *const c_char,
+        val: *const c_char,
+    ) {
+        if !ctx.is_null() && !key.is_null() && !val.is_null() {
+            let cb: &mut Box<AddCallback> = &mut *(ctx as *mut _);
+            let dict = Self::from(chan);
+            let key = CStr::from_ptr(key).to_string_lossy();
+            let val = CStr::from_ptr(val).to_string_lossy();
+            cb(&dict, &key, &val);
+            mem::forget(dict);
+        }
+    }
+
+    // Low-level, unsafe, callback for update events.
+    unsafe extern "C" fn on_update(
+        chan: DictionaryHandle,
+        ctx: *mut c_void,
+        key: *const c_char,
+        val: *const c_char,
+    ) {
+        if !ctx.is_null() && !key.is_null() && !val.is_null() {
+            let cb: &mut Box<UpdateCallback> = &mut *(ctx as *mut _);
+            let dict = Self::from(chan);
+            let key = CStr::from_ptr(key).to_string_lossy();
+            let val = CStr::from_ptr(val).to_string_lossy();
+            cb(&dict, &key, &val);
+            mem::forget(dict);
+        }
+    }
+
+    // Low-level, unsafe, callback for remove events.
+    unsafe extern "C" fn on_remove(chan: DictionaryHandle, ctx: *mut c_void, key: *const c_char) {
+        if !ctx.is_null() && !key.is_null() {
+            let cb: &mut Box<RemoveCallback> = &mut *(ctx as *mut _);
+            let dict = Self::from(chan);
+            let key = CStr::from_ptr(key).to_string_lossy();
+            cb(&dict, &key);
+            mem::forget(dict);
+        }
+    }
+
+    /// Get a reference to the underlying dictionary handle
+    pub fn as_channel(&self) -> &DictionaryHandle {
+        &self.chan
+    }
+
+    /// Gets the value for the key, or `None` if the key isn't in the
+    /// dictionary.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let key = c_string(key)?;
+        let mut buf = [0 as c_char; VALUE_BUF_LEN];
+        let ret = ReturnCode::result(unsafe {
+            ffi::PhidgetDictionary_get(self.chan, key.as_ptr(), buf.as_mut_ptr(), buf.len())
+        });
+        match ret {
+            Ok(()) => {
+                let val = unsafe { CStr::from_ptr(buf.as_ptr()) };
+                Ok(Some(val.to_string_lossy().into_owned()))
+            }
+            Err(ErrorCode::NoEnt) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Adds a new key-value pair.
+    ///
+    /// This fails with [`ErrorCode::Exist`] if the key is already in the
+    /// dictionary.
+    pub fn add(&self, key: &str, val: &str) -> Result<()> {
+        let (key, val) = (c_string(key)?, c_string(val)?);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDictionary_add(self.chan, key.as_ptr(), val.as_ptr())
+        })
+    }
+
+    /// Sets the value for the key, adding the pair if the key isn't
+    /// already in the dictionary.
+    pub fn set(&self, key: &str, val: &str) -> Result<()> {
+        let (key, val) = (c_string(key)?, c_string(val)?);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDictionary_set(self.chan, key.as_ptr(), val.as_ptr())
+        })
+    }
+
+    /// Updates the value for an existing key.
+    ///
+    /// This fails with [`ErrorCode::NoEnt`] if the key isn't in the
+    /// dictionary.
+    pub fn update(&self, key: &str, val: &str) -> Result<()> {
+        let (key, val) = (c_string(key)?, c_string(val)?);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDictionary_update(self.chan, key.as_ptr(), val.as_ptr())
+        })
+    }
+
+    /// Removes the key, and its value, from the dictionary.
+    pub fn remove(&self, key: &str) -> Result<()> {
+        let key = c_string(key)?;
+        ReturnCode::result(unsafe { ffi::PhidgetDictionary_remove(self.chan, key.as_ptr()) })
+    }
+
+    /// Removes all the key-value pairs from the dictionary.
+    pub fn remove_all(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetDictionary_removeAll(self.chan) })
+    }
+
+    /// Gets the keys in the dictionary, in order, starting after the
+    /// `start` key, if one is given.
+    ///
+    /// The list is limited to what fits in a fixed-size buffer, so a
+    /// large dictionary can be read in pages, by starting each one from
+    /// the last key of the previous one.
+    pub fn keys(&self, start: Option<&str>) -> Result<Vec<String>> {
+        let start = start.map(c_string).transpose()?;
+        let start_ptr = start.as_ref().map_or(ptr::null(), |s| s.as_ptr());
+        let mut buf = vec![0 as c_char; KEY_LIST_BUF_LEN];
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDictionary_scan(self.chan, start_ptr, buf.as_mut_ptr(), buf.len())
+        })?;
+        let list = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        Ok(list
+            .to_string_lossy()
+            .lines()
+            .filter(|key| !key.is_empty())
+            .map(String::from)
+            .collect())
+    }
+
+    /// Sets a handler to receive the pairs as they're added.
+    pub fn set_on_add_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Dictionary, &str, &str) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<AddCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.add_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDictionary_setOnAddHandler(self.chan, Some(Self::on_add), ctx)
+        })
+    }
+
+    /// Sets a handler to receive the new values as they're updated.
+    pub fn set_on_update_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Dictionary, &str, &str) + Send + 'static,
+    {
+        let cb: Box<Box<UpdateCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.update_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDictionary_setOnUpdateHandler(self.chan, Some(Self::on_update), ctx)
+        })
+    }
+
+    /// Sets a handler to receive the keys as they're removed.
+    pub fn set_on_remove_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Dictionary, &str) + Send + 'static,
+    {
+        let cb: Box<Box<RemoveCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.remove_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDictionary_setOnRemoveHandler(self.chan, Some(Self::on_remove), ctx)
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for Dictionary {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as DictionaryHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+}
+
+unsafe impl Send for Dictionary {}
+
+impl Default for Dictionary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<DictionaryHandle> for Dictionary {
+    fn from(chan: DictionaryHandle) -> Self {
+        Self {
+            chan,
+            add_cb: None,
+            update_cb: None,
+            remove_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for Dictionary {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetDictionary_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<AddCallback>(self.add_cb.take());
+        crate::drop_cb::<UpdateCallback>(self.update_cb.take());
+        crate::drop_cb::<RemoveCallback>(self.remove_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -10,29 +10,61 @@
 // to those terms.
 //
 
-use crate::{AttachCallback, DetachCallback, Error, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    lifecycle::Registration,
+    properties::{self, Properties},
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, HubPort, Phidget, Result, ReturnCode,
+};
 use phidget_sys::{self as ffi, PhidgetDigitalInputHandle, PhidgetHandle};
 use std::{
     mem,
     os::raw::{c_int, c_uint, c_void},
     ptr,
+    sync::{Mutex, MutexGuard},
 };
 
 /// The function signature for the safe Rust digital input state change callback.
 pub type DigitalInputCallback = dyn Fn(&DigitalInput, u8) + Send + 'static;
 
+/// The function signature for the safe Rust rising and falling edge callbacks.
+pub type EdgeCallback = dyn Fn(&DigitalInput) + Send + 'static;
+
+// The state change handlers of a channel, which are all dispatched from
+// the one library callback. Each can be replaced while the channel is
+// open, so they're kept behind locks.
+#[derive(Default)]
+struct Handlers {
+    // The state change handler, if set
+    state: Mutex<Option<Box<DigitalInputCallback>>>,
+    // The rising edge handler, if set
+    rising: Mutex<Option<Box<EdgeCallback>>>,
+    // The falling edge handler, if set
+    falling: Mutex<Option<Box<EdgeCallback>>>,
+}
+
+// Locks one of the handlers, even if a thread panicked in it.
+fn lock<T>(slot: &Mutex<T>) -> MutexGuard<'_, T> {
+    slot.lock().unwrap_or_else(|err| err.into_inner())
+}
+
 /////////////////////////////////////////////////////////////////////////////
 
 /// Phidget digital input
 pub struct DigitalInput {
     // Handle to the digital input in the phidget22 library
     chan: PhidgetDigitalInputHandle,
-    // Double-boxed DigitalInputCallback, if registered
-    cb: Option<*mut c_void>,
+    // The state change and edge handlers, once any is registered
+    handlers: Option<Box<Handlers>>,
     // Double-boxed attach callback, if registered
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 /// InputMode for digital input
@@ -54,7 +86,7 @@ impl TryFrom<u32> for InputMode {
         match value {
             0 => Ok(PNP),
             1 => Ok(NPN),
-            _ => Err(ReturnCode::UnknownVal),
+            _ => Err(ErrorCode::UnknownVal),
         }
     }
 }
@@ -81,7 +113,7 @@ impl TryFrom<u32> for PowerSupply {
             1 => Ok(OFF),
             2 => Ok(V12),
             3 => Ok(V24),
-            _ => Err(ReturnCode::UnknownVal),
+            _ => Err(ErrorCode::UnknownVal),
         }
     }
 }
@@ -93,7 +125,21 @@ impl DigitalInput {
         unsafe {
             ffi::PhidgetDigitalInput_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    /// Creates a digital input for a VINT hub port in digital input mode.
+    ///
+    /// This marks the channel as a hub port device, and sets the port
+    /// number, ready to be opened.
+    pub fn hub_port(port: HubPort) -> Result<Self> {
+        let mut dev = Self::new();
+        dev.set_is_hub_port_device(true)?;
+        dev.set_hub_port(port)?;
+        Ok(dev)
     }
 
     /// Set input mode
@@ -133,45 +179,113 @@ impl DigitalInput {
         Ok(value as u8)
     }
 
+    /// Gets the state of the input, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_state(&self) -> Result<Option<u8>> {
+        crate::errors::optional(self.state())
+    }
+
+    /// Determines if the input is on.
+    pub fn state_bool(&self) -> Result<bool> {
+        Ok(self.state()? != 0)
+    }
+
     // ---------------------------------------------------
 
     // Low-level, unsafe, callback for the digital input state change event.
-    // The context is a double-boxed pointer to the safe Rust callback.
+    // The context is a pointer to the handlers of the channel, which get
+    // the state change and then the edge that it makes.
     unsafe extern "C" fn on_state_change(
         chan: PhidgetDigitalInputHandle,
         ctx: *mut c_void,
         state: c_int,
     ) {
         if !ctx.is_null() {
-            let cb: &mut Box<DigitalInputCallback> = &mut *(ctx as *mut _);
+            let handlers: &Handlers = &*(ctx as *const _);
             let sensor = Self::from(chan);
-            cb(&sensor, state as u8);
+            if let Some(cb) = &*lock(&handlers.state) {
+                cb(&sensor, state as u8);
+            }
+            let edge = if state != 0 {
+                &handlers.rising
+            }
+            else {
+                &handlers.falling
+            };
+            if let Some(cb) = &*lock(edge) {
+                cb(&sensor);
+            }
             mem::forget(sensor);
         }
     }
 
+    // Gets the handlers of the channel, installing the library callback
+    // the first time.
+    fn handlers(&mut self) -> Result<&Handlers> {
+        if self.handlers.is_none() {
+            let handlers = Box::<Handlers>::default();
+            let ctx: *const Handlers = &*handlers;
+            ReturnCode::result(unsafe {
+                ffi::PhidgetDigitalInput_setOnStateChangeHandler(
+                    self.chan,
+                    Some(Self::on_state_change),
+                    ctx as *mut c_void,
+                )
+            })?;
+            self.handlers = Some(handlers);
+        }
+        Ok(self.handlers.as_deref().unwrap())
+    }
+
     /// Get a reference to the underlying sensor handle
     pub fn as_channel(&self) -> &PhidgetDigitalInputHandle {
         &self.chan
     }
 
     /// Sets a handler to receive digital input state change callbacks.
+    ///
+    /// This replaces any state change handler that was previously set,
+    /// but leaves the edge handlers in place.
     pub fn set_on_state_change_handler<F>(&mut self, cb: F) -> Result<()>
     where
         F: Fn(&DigitalInput, u8) + Send + 'static,
     {
-        // 1st box is fat ptr, 2nd is regular pointer.
-        let cb: Box<Box<DigitalInputCallback>> = Box::new(Box::new(cb));
-        let ctx = Box::into_raw(cb) as *mut c_void;
-        self.cb = Some(ctx);
+        *lock(&self.handlers()?.state) = Some(Box::new(cb));
+        Ok(())
+    }
 
-        ReturnCode::result(unsafe {
-            ffi::PhidgetDigitalInput_setOnStateChangeHandler(
-                self.chan,
-                Some(Self::on_state_change),
-                ctx,
-            )
-        })
+    /// Sets a handler to be called when the input turns on.
+    ///
+    /// This replaces any rising edge handler that was previously set, but
+    /// leaves the state change and falling edge handlers in place.
+    pub fn set_on_rising_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&DigitalInput) + Send + 'static,
+    {
+        *lock(&self.handlers()?.rising) = Some(Box::new(cb));
+        Ok(())
+    }
+
+    /// Sets a handler to be called when the input turns off.
+    ///
+    /// This replaces any falling edge handler that was previously set, but
+    /// leaves the state change and rising edge handlers in place.
+    pub fn set_on_falling_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&DigitalInput) + Send + 'static,
+    {
+        *lock(&self.handlers()?.falling) = Some(Box::new(cb));
+        Ok(())
+    }
+
+    /// Gets a receiver that always holds the most recent state reading.
+    ///
+    /// This sets the state change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<u8>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_state_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
     }
 
     /// Sets a handler to receive attach callbacks
@@ -193,12 +307,42 @@ impl DigitalInput {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for DigitalInput {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PhidgetDigitalInputHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        let mode = self.input_mode().map(|m| format!("{:?}", m));
+        properties::add(&mut props, "input_mode", mode)?;
+        let supply = self.power_supply().map(|s| format!("{:?}", s));
+        properties::add(&mut props, "power_supply", supply)?;
+        Ok(props)
+    }
 }
 
 unsafe impl Send for DigitalInput {}
@@ -213,23 +357,28 @@ impl From<PhidgetDigitalInputHandle> for DigitalInput {
     fn from(chan: PhidgetDigitalInputHandle) -> Self {
         Self {
             chan,
-            cb: None,
+            handlers: None,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for DigitalInput {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetDigitalInput_delete(&mut self.chan);
-            crate::drop_cb::<DigitalInputCallback>(self.cb.take());
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetDigitalInput_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
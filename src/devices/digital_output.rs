@@ -10,13 +10,37 @@
 // to those terms.
 //
 
-use crate::{AttachCallback, DetachCallback, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    devices::Bounds,
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorCode, ErrorEventCode, GenericPhidget,
+    HubPort, Phidget, Result, ReturnCode,
+};
 use phidget_sys::{self as ffi, PhidgetDigitalOutputHandle, PhidgetHandle};
 use std::{
+    mem,
     os::raw::{c_int, c_void},
     ptr,
+    time::Duration,
 };
 
+/// The limits of the settings of a digital output, gathered in one call.
+///
+/// The settings that the channel doesn't support are `None`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DigitalOutputLimits {
+    /// The range of the duty cycle
+    pub duty_cycle: Bounds<f64>,
+    /// The range of the failsafe time
+    pub failsafe_time: Option<Bounds<Duration>>,
+    /// The range of the PWM frequency
+    pub frequency: Option<Bounds<f64>>,
+    /// The range of the LED current limit
+    pub led_current_limit: Option<Bounds<f64>>,
+}
+
 /// Phidget digital output
 pub struct DigitalOutput {
     // Handle to the digital output in the phidget22 library
@@ -25,6 +49,10 @@ pub struct DigitalOutput {
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 impl DigitalOutput {
@@ -34,15 +62,37 @@ impl DigitalOutput {
         unsafe {
             ffi::PhidgetDigitalOutput_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    /// Creates a digital output for a VINT hub port in digital output mode.
+    ///
+    /// This marks the channel as a hub port device, and sets the port
+    /// number, ready to be opened.
+    pub fn hub_port(port: HubPort) -> Result<Self> {
+        let mut dev = Self::new();
+        dev.set_is_hub_port_device(true)?;
+        dev.set_hub_port(port)?;
+        Ok(dev)
+    }
+
+    /// Enables the failsafe, with the time within which it must be reset
+    /// to keep the output from going to its safe state.
+    pub fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        let ms = u32::try_from(time.as_millis()).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe { ffi::PhidgetDigitalOutput_enableFailsafe(self.chan, ms) })
     }
 
     /// Set enable failsafe
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `enable_failsafe()`, which takes a `Duration`"
+    )]
     pub fn set_enable_failsafe(&self, failsafe_time: u32) -> Result<()> {
-        ReturnCode::result(unsafe {
-            ffi::PhidgetDigitalOutput_enableFailsafe(self.chan, failsafe_time)
-        })?;
-        Ok(())
+        self.enable_failsafe(Duration::from_millis(failsafe_time.into()))
     }
     /// Set reset failsafe
     pub fn set_reset_failsafe(&self) -> Result<()> {
@@ -93,7 +143,23 @@ impl DigitalOutput {
         Ok(value)
     }
 
+    /// Gets the range of the failsafe time.
+    pub fn failsafe_time_limits(&self) -> Result<Bounds<Duration>> {
+        let (mut min, mut max) = (0, 0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDigitalOutput_getMinFailsafeTime(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDigitalOutput_getMaxFailsafeTime(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(
+            Duration::from_millis(min.into()),
+            Duration::from_millis(max.into()),
+        ))
+    }
+
     /// Get minimum failsafe time
+    #[deprecated(since = "0.3.0", note = "use `failsafe_time_limits()`")]
     pub fn min_failsafe_time(&self) -> Result<u32> {
         let mut value = 0;
         ReturnCode::result(unsafe {
@@ -103,6 +169,7 @@ impl DigitalOutput {
     }
 
     /// Get maximum failsafe time
+    #[deprecated(since = "0.3.0", note = "use `failsafe_time_limits()`")]
     pub fn max_failsafe_time(&self) -> Result<u32> {
         let mut value = 0;
         ReturnCode::result(unsafe {
@@ -187,6 +254,20 @@ impl DigitalOutput {
         Ok(value)
     }
 
+    /// Gets the limits of the duty cycle, failsafe time, frequency, and
+    /// LED current limit.
+    pub fn limits(&self) -> Result<DigitalOutputLimits> {
+        Ok(DigitalOutputLimits {
+            duty_cycle: Bounds::new(self.min_duty_cycle()?, self.max_duty_cycle()?),
+            failsafe_time: crate::errors::supported(self.failsafe_time_limits())?,
+            frequency: Bounds::supported(self.min_frequency(), self.max_frequency())?,
+            led_current_limit: Bounds::supported(
+                self.min_led_current_limit(),
+                self.max_led_current_limit(),
+            )?,
+        })
+    }
+
     /// Get led forward voltage
     pub fn led_forward_voltage(&self) -> Result<u32> {
         let mut value: ffi::PhidgetDigitalOutput_LEDForwardVoltage = 0;
@@ -215,6 +296,34 @@ impl DigitalOutput {
         Ok(value as u8)
     }
 
+    /// Turns the output on or off.
+    /// This overrides any duty cycle that was previously set.
+    pub fn set_state_bool(&self, on: bool) -> Result<()> {
+        self.set_state(u8::from(on))
+    }
+
+    /// Determines if the output is on.
+    pub fn state_bool(&self) -> Result<bool> {
+        Ok(self.state()? != 0)
+    }
+
+    /// Turns the output on.
+    pub fn set_on(&self) -> Result<()> {
+        self.set_state_bool(true)
+    }
+
+    /// Turns the output off.
+    pub fn set_off(&self) -> Result<()> {
+        self.set_state_bool(false)
+    }
+
+    /// Switches the output to the opposite state, returning the new state.
+    pub fn toggle(&self) -> Result<bool> {
+        let on = !self.state_bool()?;
+        self.set_state_bool(on)?;
+        Ok(on)
+    }
+
     /// Sets a handler to receive attach callbacks
     pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
     where
@@ -234,12 +343,59 @@ impl DigitalOutput {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for DigitalOutput {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PhidgetDigitalOutputHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add_ranged(
+            &mut props,
+            "duty_cycle",
+            self.duty_cycle(),
+            self.min_duty_cycle(),
+            self.max_duty_cycle(),
+        )?;
+        properties::add_ranged(
+            &mut props,
+            "frequency",
+            self.frequency(),
+            self.min_frequency(),
+            self.max_frequency(),
+        )?;
+        properties::add_ranged(
+            &mut props,
+            "led_current_limit",
+            self.led_current_limit(),
+            self.min_led_current_limit(),
+            self.max_led_current_limit(),
+        )?;
+        Ok(props)
+    }
 }
 
 unsafe impl Send for DigitalOutput {}
@@ -256,19 +412,25 @@ impl From<PhidgetDigitalOutputHandle> for DigitalOutput {
             chan,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for DigitalOutput {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetDigitalOutput_delete(&mut self.chan);
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetDigitalOutput_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
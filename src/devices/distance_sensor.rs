@@ -0,0 +1,321 @@
+// phidget-rs/src/devices/distance_sensor.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget distance sensor
+//!
+//! Distance sensors, like the DST1000 (time-of-flight) and DST1200
+//! (sonar), report the distance to the nearest object, in millimeters.
+//! The distance is reported when it changes by at least the change
+//! trigger:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::DistanceSensor, Phidget};
+//!
+//! let mut sensor = DistanceSensor::new();
+//! sensor.set_on_distance_change_handler(|_, mm| {
+//!     println!("{} mm", mm);
+//! })
+//! .unwrap();
+//! sensor.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
+use phidget_sys::{
+    self as ffi, PhidgetDistanceSensorHandle as DistanceSensorHandle, PhidgetHandle,
+};
+use std::{mem, os::raw::c_void, ptr};
+
+/// The function signature for the safe Rust distance change callback.
+pub type DistanceCallback = dyn Fn(&DistanceSensor, u32) + Send + 'static;
+
+/// Phidget distance sensor
+pub struct DistanceSensor {
+    // Handle to the sensor for the phidget22 library
+    chan: DistanceSensorHandle,
+    // Double-boxed DistanceCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl DistanceSensor {
+    /// Create a new distance sensor.
+    pub fn new() -> Self {
+        let mut chan: DistanceSensorHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetDistanceSensor_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for distance change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_distance_change(
+        chan: DistanceSensorHandle,
+        ctx: *mut c_void,
+        distance: u32,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<DistanceCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            cb(&sensor, distance);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &DistanceSensorHandle {
+        &self.chan
+    }
+
+    /// Read the current distance, in millimeters.
+    pub fn distance(&self) -> Result<u32> {
+        let mut distance = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDistanceSensor_getDistance(self.chan, &mut distance)
+        })?;
+        Ok(distance)
+    }
+
+    /// Gets the distance, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_distance(&self) -> Result<Option<u32>> {
+        crate::errors::optional(self.distance())
+    }
+
+    /// Gets the minimum distance that the sensor can measure, in millimeters.
+    pub fn min_distance(&self) -> Result<u32> {
+        let mut distance = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDistanceSensor_getMinDistance(self.chan, &mut distance)
+        })?;
+        Ok(distance)
+    }
+
+    /// Gets the maximum distance that the sensor can measure, in millimeters.
+    pub fn max_distance(&self) -> Result<u32> {
+        let mut distance = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDistanceSensor_getMaxDistance(self.chan, &mut distance)
+        })?;
+        Ok(distance)
+    }
+
+    /// Gets the distance change trigger.
+    /// Change events are only fired when the distance changes by at least
+    /// this amount.
+    pub fn distance_change_trigger(&self) -> Result<u32> {
+        let mut trigger = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDistanceSensor_getDistanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the distance change trigger.
+    pub fn set_distance_change_trigger(&self, trigger: u32) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDistanceSensor_setDistanceChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum distance change trigger.
+    pub fn min_distance_change_trigger(&self) -> Result<u32> {
+        let mut trigger = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDistanceSensor_getMinDistanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum distance change trigger.
+    pub fn max_distance_change_trigger(&self) -> Result<u32> {
+        let mut trigger = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetDistanceSensor_getMaxDistanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and distance change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_distance_change_trigger()?.into(),
+                self.max_distance_change_trigger()?.into(),
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the distance reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_distance()?.map(f64::from),
+            data_interval: ph.data_interval()?,
+            change_trigger: self.distance_change_trigger()?.into(),
+        })
+    }
+
+    /// Sets a handler to receive distance change callbacks.
+    pub fn set_on_distance_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&DistanceSensor, u32) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<DistanceCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        let res = ReturnCode::result(unsafe {
+            ffi::PhidgetDistanceSensor_setOnDistanceChangeHandler(
+                self.chan,
+                Some(Self::on_distance_change),
+                ctx,
+            )
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<DistanceCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<DistanceCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent distance reading.
+    ///
+    /// This sets the distance change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<u32>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_distance_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for DistanceSensor {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as DistanceSensorHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.distance_change_trigger().map(f64::from)
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_distance_change_trigger(trigger as u32)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_distance_change_trigger().map(f64::from)
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_distance_change_trigger().map(f64::from)
+    }
+}
+
+unsafe impl Send for DistanceSensor {}
+
+impl Default for DistanceSensor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<DistanceSensorHandle> for DistanceSensor {
+    fn from(chan: DistanceSensorHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for DistanceSensor {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetDistanceSensor_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<DistanceCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
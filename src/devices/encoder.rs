@@ -0,0 +1,356 @@
+// phidget-rs/src/devices/encoder.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget quadrature encoder input channel.
+//!
+//! The channel counts the quadrature transitions of the encoder, which
+//! gives the position. Each position change event reports the change in
+//! the count since the last event, and the time since then, which can be
+//! used to compute the velocity:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::Encoder, Phidget};
+//!
+//! let mut enc = Encoder::new();
+//! enc.set_on_position_change_handler(|_, chg| {
+//!     let vel = f64::from(chg.position_change) / chg.time_change.as_secs_f64();
+//!     println!("{} counts/s", vel);
+//! })
+//! .unwrap();
+//! enc.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetEncoderHandle as EncoderHandle, PhidgetHandle};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The electrical interface of the encoder inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum EncoderIoMode {
+    /// Push-pull outputs
+    PushPull = ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_PUSH_PULL, // 1
+    /// Line driver outputs, with 2.2k pull-down resistors
+    LineDriver2k2 = ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_LINE_DRIVER_2K2, // 2
+    /// Line driver outputs, with 10k pull-down resistors
+    LineDriver10k = ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_LINE_DRIVER_10K, // 3
+    /// Open collector outputs, with 2.2k pull-up resistors
+    OpenCollector2k2 = ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_OPEN_COLLECTOR_2K2, // 4
+    /// Open collector outputs, with 10k pull-up resistors
+    OpenCollector10k = ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_OPEN_COLLECTOR_10K, // 5
+}
+
+impl TryFrom<u32> for EncoderIoMode {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use EncoderIoMode::*;
+        match val {
+            ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_PUSH_PULL => Ok(PushPull), // 1
+            ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_LINE_DRIVER_2K2 => Ok(LineDriver2k2), // 2
+            ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_LINE_DRIVER_10K => Ok(LineDriver10k), // 3
+            ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_OPEN_COLLECTOR_2K2 => Ok(OpenCollector2k2), // 4
+            ffi::Phidget_EncoderIOMode_ENCODER_IO_MODE_OPEN_COLLECTOR_10K => Ok(OpenCollector10k), // 5
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// A position change event from an encoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionChange {
+    /// The change in the position since the last event, in counts
+    pub position_change: i32,
+    /// The time since the last event
+    pub time_change: Duration,
+    /// Whether the index channel was triggered during the interval
+    pub index_triggered: bool,
+}
+
+/// The function type for the safe Rust position change callback.
+pub type PositionChangeCallback = dyn Fn(&Encoder, PositionChange) + Send + 'static;
+
+/// Phidget encoder input
+pub struct Encoder {
+    // Handle to the encoder in the phidget22 library
+    chan: EncoderHandle,
+    // Double-boxed PositionChangeCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Encoder {
+    /// Create a new encoder input.
+    pub fn new() -> Self {
+        let mut chan: EncoderHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetEncoder_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for position change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_position_change(
+        chan: EncoderHandle,
+        ctx: *mut c_void,
+        position_change: c_int,
+        time_change: f64,
+        index_triggered: c_int,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<PositionChangeCallback> = &mut *(ctx as *mut _);
+            let enc = Self::from(chan);
+            let chg = PositionChange {
+                position_change,
+                time_change: Duration::from_secs_f64(time_change.max(0.0) / 1000.0),
+                index_triggered: index_triggered != 0,
+            };
+            cb(&enc, chg);
+            mem::forget(enc);
+        }
+    }
+
+    /// Get a reference to the underlying encoder handle
+    pub fn as_channel(&self) -> &EncoderHandle {
+        &self.chan
+    }
+
+    /// Gets the position, in quadrature counts.
+    pub fn position(&self) -> Result<i64> {
+        let mut value = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetEncoder_getPosition(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the position, such as to zero it at a home position.
+    pub fn set_position(&self, pos: i64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetEncoder_setPosition(self.chan, pos) })
+    }
+
+    /// Gets the position at which the index channel was last triggered.
+    pub fn index_position(&self) -> Result<i64> {
+        let mut value = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetEncoder_getIndexPosition(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Determines if the encoder input is enabled.
+    pub fn enabled(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetEncoder_getEnabled(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables the encoder input.
+    pub fn set_enabled(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetEncoder_setEnabled(self.chan, c_int::from(on)) })
+    }
+
+    /// Gets the electrical interface of the encoder inputs.
+    pub fn io_mode(&self) -> Result<EncoderIoMode> {
+        let mut mode = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetEncoder_getIOMode(self.chan, &mut mode) })?;
+        EncoderIoMode::try_from(mode)
+    }
+
+    /// Sets the electrical interface of the encoder inputs, to match the
+    /// outputs of the encoder.
+    pub fn set_io_mode(&self, mode: EncoderIoMode) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetEncoder_setIOMode(self.chan, mode as u32) })
+    }
+
+    /// Gets the position change trigger.
+    /// Change events are only fired when the position changes by at least
+    /// this many counts.
+    pub fn position_change_trigger(&self) -> Result<u32> {
+        let mut trigger = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetEncoder_getPositionChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the position change trigger.
+    pub fn set_position_change_trigger(&self, trigger: u32) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetEncoder_setPositionChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum position change trigger.
+    pub fn min_position_change_trigger(&self) -> Result<u32> {
+        let mut trigger = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetEncoder_getMinPositionChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum position change trigger.
+    pub fn max_position_change_trigger(&self) -> Result<u32> {
+        let mut trigger = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetEncoder_getMaxPositionChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets a handler to receive position change callbacks.
+    pub fn set_on_position_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Encoder, PositionChange) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<PositionChangeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetEncoder_setOnPositionChangeHandler(
+                self.chan,
+                Some(Self::on_position_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for Encoder {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as EncoderHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add(&mut props, "enabled", self.enabled())?;
+        let mode = self.io_mode().map(|m| format!("{:?}", m));
+        properties::add(&mut props, "io_mode", mode)?;
+        Ok(props)
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.position_change_trigger().map(f64::from)
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_position_change_trigger(trigger as u32)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_position_change_trigger().map(f64::from)
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_position_change_trigger().map(f64::from)
+    }
+}
+
+unsafe impl Send for Encoder {}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<EncoderHandle> for Encoder {
+    fn from(chan: EncoderHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetEncoder_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<PositionChangeCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -0,0 +1,427 @@
+// phidget-rs/src/devices/frequency_counter.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget frequency counter channel.
+//!
+//! Frequency counters, like the DAQ1400, count the pulses on an input,
+//! and calculate their frequency. The counts are reported each data
+//! interval, along with the time over which they were counted:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::FrequencyCounter, Phidget};
+//!
+//! let mut counter = FrequencyCounter::new();
+//! counter
+//!     .set_on_count_change_handler(|_, counts, dt| {
+//!         println!("{} pulses in {:?}", counts, dt);
+//!     })
+//!     .unwrap();
+//! counter.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    devices::{
+        digital_input::{InputMode, PowerSupply},
+        Bounds,
+    },
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{
+    self as ffi, PhidgetFrequencyCounterHandle as FrequencyCounterHandle, PhidgetHandle,
+};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The type of filter applied to the input signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum FilterType {
+    /// Counts the times that the signal crosses zero, for AC signals
+    ZeroCrossing = ffi::PhidgetFrequencyCounter_FilterType_FILTER_TYPE_ZERO_CROSSING, // 1
+    /// Counts the rising edges of a logic level signal
+    LogicLevel = ffi::PhidgetFrequencyCounter_FilterType_FILTER_TYPE_LOGIC_LEVEL, // 2
+}
+
+impl TryFrom<u32> for FilterType {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use FilterType::*;
+        match val {
+            ffi::PhidgetFrequencyCounter_FilterType_FILTER_TYPE_ZERO_CROSSING => Ok(ZeroCrossing), // 1
+            ffi::PhidgetFrequencyCounter_FilterType_FILTER_TYPE_LOGIC_LEVEL => Ok(LogicLevel), // 2
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// The function type for the safe Rust count change callback.
+///
+/// This receives the number of pulses counted since the last event, and
+/// the time over which they were counted.
+pub type CountChangeCallback = dyn Fn(&FrequencyCounter, u64, Duration) + Send + 'static;
+/// The function type for the safe Rust frequency change callback.
+pub type FrequencyChangeCallback = dyn Fn(&FrequencyCounter, f64) + Send + 'static;
+
+/// Phidget frequency counter
+pub struct FrequencyCounter {
+    // Handle to the counter in the phidget22 library
+    chan: FrequencyCounterHandle,
+    // Double-boxed CountChangeCallback, if registered
+    count_cb: Option<*mut c_void>,
+    // Double-boxed FrequencyChangeCallback, if registered
+    frequency_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl FrequencyCounter {
+    /// Create a new frequency counter.
+    pub fn new() -> Self {
+        let mut chan: FrequencyCounterHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetFrequencyCounter_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for count change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_count_change(
+        chan: FrequencyCounterHandle,
+        ctx: *mut c_void,
+        counts: u64,
+        time_change: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<CountChangeCallback> = &mut *(ctx as *mut _);
+            let counter = Self::from(chan);
+            let dt = Duration::from_secs_f64(time_change.max(0.0) / 1000.0);
+            cb(&counter, counts, dt);
+            mem::forget(counter);
+        }
+    }
+
+    // Low-level, unsafe, callback for frequency change events.
+    unsafe extern "C" fn on_frequency_change(
+        chan: FrequencyCounterHandle,
+        ctx: *mut c_void,
+        frequency: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<FrequencyChangeCallback> = &mut *(ctx as *mut _);
+            let counter = Self::from(chan);
+            cb(&counter, frequency);
+            mem::forget(counter);
+        }
+    }
+
+    /// Get a reference to the underlying counter handle
+    pub fn as_channel(&self) -> &FrequencyCounterHandle {
+        &self.chan
+    }
+
+    /// Gets the total number of pulses counted since the channel was
+    /// opened, or last reset.
+    pub fn count(&self) -> Result<u64> {
+        let mut count = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getCount(self.chan, &mut count)
+        })?;
+        Ok(count)
+    }
+
+    /// Gets the total time that pulses have been counted, since the
+    /// channel was opened, or last reset.
+    pub fn time_elapsed(&self) -> Result<Duration> {
+        let mut ms = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getTimeElapsed(self.chan, &mut ms)
+        })?;
+        Ok(Duration::from_secs_f64(ms.max(0.0) / 1000.0))
+    }
+
+    /// Resets the count and the time elapsed.
+    pub fn reset(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetFrequencyCounter_reset(self.chan) })
+    }
+
+    /// Gets the most recent frequency of the pulses, in Hz.
+    pub fn frequency(&self) -> Result<f64> {
+        let mut frequency = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getFrequency(self.chan, &mut frequency)
+        })?;
+        Ok(frequency)
+    }
+
+    /// Gets the maximum frequency that can be measured, in Hz.
+    pub fn max_frequency(&self) -> Result<f64> {
+        let mut frequency = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getMaxFrequency(self.chan, &mut frequency)
+        })?;
+        Ok(frequency)
+    }
+
+    /// Gets the frequency cutoff, in Hz.
+    pub fn frequency_cutoff(&self) -> Result<f64> {
+        let mut cutoff = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getFrequencyCutoff(self.chan, &mut cutoff)
+        })?;
+        Ok(cutoff)
+    }
+
+    /// Sets the frequency cutoff, in Hz.
+    ///
+    /// A frequency below this is reported as zero. Lower cutoffs take
+    /// longer to detect that the pulses have stopped.
+    pub fn set_frequency_cutoff(&self, cutoff: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_setFrequencyCutoff(self.chan, cutoff)
+        })
+    }
+
+    /// Gets the range that the frequency cutoff can be set within.
+    pub fn frequency_cutoff_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getMinFrequencyCutoff(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getMaxFrequencyCutoff(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the type of filter applied to the input.
+    pub fn filter_type(&self) -> Result<FilterType> {
+        let mut filter = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getFilterType(self.chan, &mut filter)
+        })?;
+        FilterType::try_from(filter)
+    }
+
+    /// Sets the type of filter applied to the input, to match the signal.
+    pub fn set_filter_type(&self, filter: FilterType) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_setFilterType(self.chan, filter as u32)
+        })
+    }
+
+    /// Determines if counting is enabled.
+    pub fn enabled(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getEnabled(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables counting.
+    pub fn set_enabled(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_setEnabled(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Gets the input mode.
+    pub fn input_mode(&self) -> Result<InputMode> {
+        let mut mode = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getInputMode(self.chan, &mut mode)
+        })?;
+        InputMode::try_from(mode)
+    }
+
+    /// Sets the input mode, to match the output of the sensor.
+    pub fn set_input_mode(&self, mode: InputMode) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_setInputMode(self.chan, mode as u32)
+        })
+    }
+
+    /// Gets the power supply voltage for the sensor.
+    pub fn power_supply(&self) -> Result<PowerSupply> {
+        let mut supply = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_getPowerSupply(self.chan, &mut supply)
+        })?;
+        PowerSupply::try_from(supply)
+    }
+
+    /// Sets the power supply voltage for the sensor.
+    pub fn set_power_supply(&self, supply: PowerSupply) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_setPowerSupply(self.chan, supply as u32)
+        })
+    }
+
+    /// Sets a handler to receive the count, each data interval.
+    pub fn set_on_count_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&FrequencyCounter, u64, Duration) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<CountChangeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.count_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_setOnCountChangeHandler(
+                self.chan,
+                Some(Self::on_count_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive the frequency, each data interval.
+    pub fn set_on_frequency_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&FrequencyCounter, f64) + Send + 'static,
+    {
+        let cb: Box<Box<FrequencyChangeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.frequency_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetFrequencyCounter_setOnFrequencyChangeHandler(
+                self.chan,
+                Some(Self::on_frequency_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for FrequencyCounter {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as FrequencyCounterHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add(&mut props, "enabled", self.enabled())?;
+        let filter = self.filter_type().map(|f| format!("{:?}", f));
+        properties::add(&mut props, "filter_type", filter)?;
+        let lim = self.frequency_cutoff_limits();
+        properties::add_ranged(
+            &mut props,
+            "frequency_cutoff",
+            self.frequency_cutoff(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for FrequencyCounter {}
+
+impl Default for FrequencyCounter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<FrequencyCounterHandle> for FrequencyCounter {
+    fn from(chan: FrequencyCounterHandle) -> Self {
+        Self {
+            chan,
+            count_cb: None,
+            frequency_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for FrequencyCounter {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetFrequencyCounter_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<CountChangeCallback>(self.count_cb.take());
+        crate::drop_cb::<FrequencyChangeCallback>(self.frequency_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -0,0 +1,277 @@
+// phidget-rs/src/devices/gyroscope.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget gyroscope channel.
+//!
+//! The gyroscope of a spatial Phidget, like the MOT0110, reports the
+//! angular rate around each axis, in degrees per second. The rates are
+//! reported each data interval:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::Gyroscope, Phidget};
+//!
+//! let mut gyro = Gyroscope::new();
+//! gyro.set_on_angular_rate_update_handler(|_, rate, _ts| {
+//!     println!("x: {:.2}, y: {:.2}, z: {:.2} °/s", rate[0], rate[1], rate[2]);
+//! })
+//! .unwrap();
+//! gyro.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetGyroscopeHandle as GyroscopeHandle, PhidgetHandle};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The function type for the safe Rust angular rate update callback.
+///
+/// This receives the angular rate around each axis, in degrees per
+/// second, and the timestamp of the reading.
+pub type AngularRateCallback = dyn Fn(&Gyroscope, [f64; 3], Duration) + Send + 'static;
+
+/// Phidget gyroscope
+pub struct Gyroscope {
+    // Handle to the sensor in the phidget22 library
+    chan: GyroscopeHandle,
+    // Double-boxed AngularRateCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Gyroscope {
+    /// Create a new gyroscope.
+    pub fn new() -> Self {
+        let mut chan: GyroscopeHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetGyroscope_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for angular rate update events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_angular_rate_update(
+        chan: GyroscopeHandle,
+        ctx: *mut c_void,
+        rate: *const f64,
+        timestamp: f64,
+    ) {
+        if !ctx.is_null() && !rate.is_null() {
+            let cb: &mut Box<AngularRateCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            let rate = *(rate as *const [f64; 3]);
+            let ts = Duration::from_secs_f64(timestamp.max(0.0) / 1000.0);
+            cb(&sensor, rate, ts);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &GyroscopeHandle {
+        &self.chan
+    }
+
+    /// Gets the most recent angular rate around each axis, in degrees per
+    /// second.
+    pub fn angular_rate(&self) -> Result<[f64; 3]> {
+        let mut rate = [0.0; 3];
+        ReturnCode::result(unsafe { ffi::PhidgetGyroscope_getAngularRate(self.chan, &mut rate) })?;
+        Ok(rate)
+    }
+
+    /// Gets the minimum angular rate that can be measured on each axis.
+    pub fn min_angular_rate(&self) -> Result<[f64; 3]> {
+        let mut rate = [0.0; 3];
+        ReturnCode::result(unsafe {
+            ffi::PhidgetGyroscope_getMinAngularRate(self.chan, &mut rate)
+        })?;
+        Ok(rate)
+    }
+
+    /// Gets the maximum angular rate that can be measured on each axis.
+    pub fn max_angular_rate(&self) -> Result<[f64; 3]> {
+        let mut rate = [0.0; 3];
+        ReturnCode::result(unsafe {
+            ffi::PhidgetGyroscope_getMaxAngularRate(self.chan, &mut rate)
+        })?;
+        Ok(rate)
+    }
+
+    /// Gets the number of axes that the gyroscope measures.
+    pub fn axis_count(&self) -> Result<u32> {
+        let mut count: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetGyroscope_getAxisCount(self.chan, &mut count) })?;
+        Ok(count as u32)
+    }
+
+    /// Gets the timestamp of the most recent reading, as the time since
+    /// the channel was opened.
+    pub fn timestamp(&self) -> Result<Duration> {
+        let mut ts = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetGyroscope_getTimestamp(self.chan, &mut ts) })?;
+        Ok(Duration::from_secs_f64(ts.max(0.0) / 1000.0))
+    }
+
+    /// Determines if the heater of the sensor is enabled.
+    pub fn heating_enabled(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetGyroscope_getHeatingEnabled(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables the heater, which keeps the sensor at a steady
+    /// temperature to reduce its drift.
+    pub fn set_heating_enabled(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetGyroscope_setHeatingEnabled(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Re-zeros the gyroscope.
+    ///
+    /// This takes a couple of seconds, during which the sensor must be
+    /// kept still.
+    pub fn zero(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetGyroscope_zero(self.chan) })
+    }
+
+    /// Sets a handler to receive the angular rate, each data interval.
+    pub fn set_on_angular_rate_update_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Gyroscope, [f64; 3], Duration) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<AngularRateCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetGyroscope_setOnAngularRateUpdateHandler(
+                self.chan,
+                Some(Self::on_angular_rate_update),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for Gyroscope {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as GyroscopeHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add(&mut props, "heating_enabled", self.heating_enabled())?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for Gyroscope {}
+
+impl Default for Gyroscope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<GyroscopeHandle> for Gyroscope {
+    fn from(chan: GyroscopeHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for Gyroscope {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetGyroscope_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<AngularRateCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
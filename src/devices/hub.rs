@@ -10,17 +10,28 @@
 // to those terms.
 //
 
-use crate::{AttachCallback, DetachCallback, Error, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    devices::{DigitalInput, DigitalOutput, VoltageInput, VoltageRatioInput},
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, HubPort, Phidget, Result, ReturnCode,
+};
 use phidget_sys::{self as ffi, PhidgetHandle, PhidgetHubHandle as HubHandle};
 use std::{
+    mem,
     os::raw::{c_int, c_uint, c_void},
     ptr,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 /////////////////////////////////////////////////////////////////////////////
 
 /// Possible operational modes for a hub port
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum HubPortMode {
     /// Communicate with a smart VINT device
@@ -46,11 +57,199 @@ impl TryFrom<u32> for HubPortMode {
             ffi::PhidgetHub_PortMode_PORT_MODE_DIGITAL_OUTPUT => Ok(DigitalOutput), // 2
             ffi::PhidgetHub_PortMode_PORT_MODE_VOLTAGE_INPUT => Ok(VoltageInput), // 3
             ffi::PhidgetHub_PortMode_PORT_MODE_VOLTAGE_RATIO_INPUT => Ok(VoltageRatioInput), // 4
-            _ => Err(ReturnCode::InvalidArg),
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// A channel opened on a hub port, in one of the port modes.
+///
+/// This is returned by [`Hub::open_port_as()`], with the channel type that
+/// matches the requested mode.
+pub enum AnyDevice {
+    /// A digital input, from [`HubPortMode::DigitalInput`]
+    DigitalInput(DigitalInput),
+    /// A digital output, from [`HubPortMode::DigitalOutput`]
+    DigitalOutput(DigitalOutput),
+    /// A voltage input, from [`HubPortMode::VoltageInput`]
+    VoltageInput(VoltageInput),
+    /// A voltage ratio input, from [`HubPortMode::VoltageRatioInput`]
+    VoltageRatioInput(VoltageRatioInput),
+}
+
+impl AnyDevice {
+    /// Gets the channel as a generic Phidget, for the common operations.
+    pub fn as_phidget(&mut self) -> &mut dyn Phidget {
+        match self {
+            AnyDevice::DigitalInput(dev) => dev,
+            AnyDevice::DigitalOutput(dev) => dev,
+            AnyDevice::VoltageInput(dev) => dev,
+            AnyDevice::VoltageRatioInput(dev) => dev,
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// A reference to the hub, held by a power cycle thread, so that the handle
+// stays valid even if the hub is dropped.
+struct RetainedHandle(HubHandle);
+
+impl RetainedHandle {
+    fn new(chan: HubHandle) -> Result<Self> {
+        ReturnCode::result(unsafe { ffi::Phidget_retain(chan as PhidgetHandle) })?;
+        Ok(Self(chan))
+    }
+
+    fn set_port_power(&self, port: HubPort, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetHub_setPortPower(self.0, port.value() as c_int, c_int::from(on))
+        })
+    }
+}
+
+unsafe impl Send for RetainedHandle {}
+
+impl Drop for RetainedHandle {
+    fn drop(&mut self) {
+        let mut phid = self.0 as PhidgetHandle;
+        unsafe {
+            ffi::Phidget_release(&mut phid);
+        }
+    }
+}
+
+/// A power cycle of a hub port, scheduled to run in the background, from
+/// [`HubPortPower::schedule_power_cycle()`].
+///
+/// Dropping the handle lets the power cycle run.
+pub struct ScheduledPowerCycle {
+    // Sends a request to cancel the power cycle
+    cancel_tx: mpsc::Sender<()>,
+    // The thread running the power cycle
+    thr: Option<JoinHandle<Result<()>>>,
+}
+
+impl ScheduledPowerCycle {
+    /// Determines if the power cycle has finished, been cancelled, or
+    /// stopped on an error.
+    pub fn is_finished(&self) -> bool {
+        self.thr.as_ref().map_or(true, |thr| thr.is_finished())
+    }
+
+    /// Blocks until the power cycle has finished.
+    ///
+    /// Returns the error that stopped it, if any, like the hub being
+    /// closed.
+    pub fn wait(mut self) -> Result<()> {
+        match self.thr.take() {
+            Some(thr) => thr.join().map_err(|_| ErrorCode::Unexpected)?,
+            None => Ok(()),
         }
     }
+
+    /// Cancels the power cycle, if the port has not been turned off yet.
+    ///
+    /// Once the port is off, the power cycle runs to completion, so that
+    /// the port is never left without power.
+    pub fn cancel(self) -> Result<()> {
+        let _ = self.cancel_tx.send(());
+        self.wait()
+    }
+}
+
+/// The power of a single port on a VINT hub, from [`Hub::port()`].
+///
+/// Power cycling a port is a way to reset a stuck device on it. The
+/// channels on the port detach while it's off, and reattach on their own
+/// if they were left open.
+///
+/// ```rust,no_run
+/// use phidget::{devices::Hub, HubPort, Phidget};
+/// use std::time::Duration;
+///
+/// let mut hub = Hub::new();
+/// hub.open_wait_default().unwrap();
+///
+/// let port = hub.port(HubPort::new(2).unwrap());
+/// if port.is_on().unwrap() {
+///     port.power_cycle(Duration::from_millis(500)).unwrap();
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct HubPortPower<'a> {
+    // The hub with the port
+    hub: &'a Hub,
+    // The port index
+    port: HubPort,
+}
+
+impl HubPortPower<'_> {
+    /// Gets the index of the port.
+    pub fn port(&self) -> HubPort {
+        self.port
+    }
+
+    /// Determines if power is supplied to the port.
+    pub fn is_on(&self) -> Result<bool> {
+        self.hub.port_power(self.port)
+    }
+
+    /// Turns on the power to the port.
+    pub fn on(&self) -> Result<()> {
+        self.hub.set_port_power(self.port, true)
+    }
+
+    /// Turns off the power to the port.
+    pub fn off(&self) -> Result<()> {
+        self.hub.set_port_power(self.port, false)
+    }
+
+    /// Turns the port off for the specified time, then back on, blocking
+    /// the calling thread until it's done.
+    pub fn power_cycle(&self, off_time: Duration) -> Result<()> {
+        self.hub.power_cycle_port(self.port, off_time)
+    }
+
+    /// Schedules a power cycle of the port on a background thread.
+    ///
+    /// After the `delay`, the port is turned off for `off_time`, then back
+    /// on. This returns immediately, with a handle that can wait for the
+    /// power cycle, or cancel it before the port is turned off.
+    pub fn schedule_power_cycle(
+        &self,
+        delay: Duration,
+        off_time: Duration,
+    ) -> Result<ScheduledPowerCycle> {
+        let chan = RetainedHandle::new(self.hub.chan)?;
+        let port = self.port;
+        let (cancel_tx, cancel_rx) = mpsc::channel();
+
+        let thr = thread::spawn(move || {
+            // Only an explicit cancel stops it. If the handle is dropped,
+            // the rest of the delay still has to run out.
+            let start = Instant::now();
+            match cancel_rx.recv_timeout(delay) {
+                Ok(()) => return Ok(()),
+                Err(RecvTimeoutError::Disconnected) => {
+                    thread::sleep(delay.saturating_sub(start.elapsed()))
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+            }
+            chan.set_port_power(port, false)?;
+            thread::sleep(off_time);
+            chan.set_port_power(port, true)
+        });
+
+        Ok(ScheduledPowerCycle {
+            cancel_tx,
+            thr: Some(thr),
+        })
+    }
 }
 
+/////////////////////////////////////////////////////////////////////////////
+
 /// Phidget Hub
 pub struct Hub {
     // Handle to the hub in the phidget22 library
@@ -59,6 +258,10 @@ pub struct Hub {
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 impl Hub {
@@ -68,23 +271,95 @@ impl Hub {
         unsafe {
             ffi::PhidgetHub_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
     }
 
     /// Get the mode of the specified hub port
-    pub fn port_mode(&self, port: i32) -> Result<HubPortMode> {
-        let port = port as c_int;
+    pub fn port_mode(&self, port: HubPort) -> Result<HubPortMode> {
+        let port = port.value() as c_int;
         let mut mode: c_uint = 0;
         ReturnCode::result(unsafe { ffi::PhidgetHub_getPortMode(self.chan, port, &mut mode) })?;
         HubPortMode::try_from(mode)
     }
 
     /// Set the mode of the specified hub port
-    pub fn set_port_mode(&self, port: i32, mode: HubPortMode) -> Result<()> {
-        let port = port as c_int;
+    pub fn set_port_mode(&self, port: HubPort, mode: HubPortMode) -> Result<()> {
+        let port = port.value() as c_int;
         ReturnCode::result(unsafe { ffi::PhidgetHub_setPortMode(self.chan, port, mode as c_uint) })
     }
 
+    /// Sets the mode of the specified hub port, and opens the channel
+    /// that the port provides in that mode.
+    ///
+    /// The port takes a moment to switch modes, so this waits for the hub
+    /// to report the new mode, then for the channel to attach, using the
+    /// default timeout for each. Returns an `InvalidArg` error for
+    /// [`HubPortMode::Vint`], which has no channel of its own.
+    pub fn open_port_as(&mut self, port: HubPort, mode: HubPortMode) -> Result<AnyDevice> {
+        if mode == HubPortMode::Vint {
+            return Err(ErrorCode::InvalidArg);
+        }
+        self.set_port_mode(port, mode)?;
+
+        let deadline = Instant::now() + crate::TIMEOUT_DEFAULT;
+        while self.port_mode(port)? != mode {
+            if Instant::now() >= deadline {
+                return Err(ErrorCode::Timeout);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let spec = crate::DeviceSpec::new()
+            .serial_number(self.serial_number()?)
+            .hub_port(port)
+            .is_hub_port_device(true);
+        let to = crate::TIMEOUT_DEFAULT;
+
+        Ok(match mode {
+            HubPortMode::DigitalInput => AnyDevice::DigitalInput(spec.open(to)?),
+            HubPortMode::DigitalOutput => AnyDevice::DigitalOutput(spec.open(to)?),
+            HubPortMode::VoltageInput => AnyDevice::VoltageInput(spec.open(to)?),
+            HubPortMode::VoltageRatioInput => AnyDevice::VoltageRatioInput(spec.open(to)?),
+            HubPortMode::Vint => unreachable!(),
+        })
+    }
+
+    /// Determines if power is supplied to the specified hub port
+    pub fn port_power(&self, port: HubPort) -> Result<bool> {
+        let port = port.value() as c_int;
+        let mut on: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetHub_getPortPower(self.chan, port, &mut on) })?;
+        Ok(on != 0)
+    }
+
+    /// Turns the power to the specified hub port on or off
+    pub fn set_port_power(&self, port: HubPort, on: bool) -> Result<()> {
+        let port = port.value() as c_int;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetHub_setPortPower(self.chan, port, c_int::from(on))
+        })
+    }
+
+    /// Gets the power control of the specified hub port.
+    pub fn port(&self, port: HubPort) -> HubPortPower<'_> {
+        HubPortPower { hub: self, port }
+    }
+
+    /// Power cycles the specified hub port, turning it off for the
+    /// specified time, then back on.
+    ///
+    /// This is the remedy for a device that reports bad power. Any
+    /// channels on the port detach, and must be reopened, or will reattach
+    /// on their own if they were left open.
+    pub fn power_cycle_port(&self, port: HubPort, off_time: Duration) -> Result<()> {
+        self.set_port_power(port, false)?;
+        thread::sleep(off_time);
+        self.set_port_power(port, true)
+    }
+
     /// Sets a handler to receive attach callbacks
     pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
     where
@@ -104,12 +379,44 @@ impl Hub {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for Hub {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as HubHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        for port in 0..self.hub_port_count()? {
+            let mode = self
+                .port_mode(HubPort::new(port)?)
+                .map(|m| format!("{:?}", m));
+            properties::add(&mut props, &format!("port_mode.{}", port), mode)?;
+        }
+        Ok(props)
+    }
 }
 
 unsafe impl Send for Hub {}
@@ -126,19 +433,25 @@ impl From<HubHandle> for Hub {
             chan,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for Hub {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetHub_delete(&mut self.chan);
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetHub_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
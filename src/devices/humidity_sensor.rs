@@ -12,7 +12,13 @@
 //! Phidget Humidity sensor
 //!
 
-use crate::{AttachCallback, DetachCallback, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
 use phidget_sys::{
     self as ffi, PhidgetHandle, PhidgetHumiditySensorHandle as HumiditySensorHandle,
 };
@@ -31,6 +37,10 @@ pub struct HumiditySensor {
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 impl HumiditySensor {
@@ -40,7 +50,10 @@ impl HumiditySensor {
         unsafe {
             ffi::PhidgetHumiditySensor_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
     }
 
     // Low-level, unsafe, callback for humidity change events.
@@ -72,6 +85,70 @@ impl HumiditySensor {
         Ok(humidity)
     }
 
+    /// Gets the humidity, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_humidity(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.humidity())
+    }
+
+    /// Gets the humidity change trigger.
+    /// Change events are only fired when the humidity changes by at least
+    /// this amount.
+    pub fn humidity_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetHumiditySensor_getHumidityChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the humidity change trigger.
+    pub fn set_humidity_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetHumiditySensor_setHumidityChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum humidity change trigger.
+    pub fn min_humidity_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetHumiditySensor_getMinHumidityChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum humidity change trigger.
+    pub fn max_humidity_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetHumiditySensor_getMaxHumidityChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and humidity change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_humidity_change_trigger()?,
+                self.max_humidity_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the humidity reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_humidity()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.humidity_change_trigger()?,
+        })
+    }
+
     /// Sets a handler to receive humitity change callbacks.
     pub fn set_on_humidity_change_handler<F>(&mut self, cb: F) -> Result<()>
     where
@@ -80,15 +157,31 @@ impl HumiditySensor {
         // 1st box is fat ptr, 2nd is regular pointer.
         let cb: Box<Box<HumidityCallback>> = Box::new(Box::new(cb));
         let ctx = Box::into_raw(cb) as *mut c_void;
-        self.cb = Some(ctx);
 
-        ReturnCode::result(unsafe {
+        let res = ReturnCode::result(unsafe {
             ffi::PhidgetHumiditySensor_setOnHumidityChangeHandler(
                 self.chan,
                 Some(Self::on_humidity_change),
                 ctx,
             )
-        })
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<HumidityCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<HumidityCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent humidity reading.
+    ///
+    /// This sets the humidity change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_humidity_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
     }
 
     /// Sets a handler to receive attach callbacks
@@ -110,12 +203,49 @@ impl HumiditySensor {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for HumiditySensor {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as HumiditySensorHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.humidity_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_humidity_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_humidity_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_humidity_change_trigger()
+    }
 }
 
 unsafe impl Send for HumiditySensor {}
@@ -133,20 +263,26 @@ impl From<HumiditySensorHandle> for HumiditySensor {
             cb: None,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for HumiditySensor {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetHumiditySensor_delete(&mut self.chan);
-            crate::drop_cb::<HumidityCallback>(self.cb.take());
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetHumiditySensor_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<HumidityCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
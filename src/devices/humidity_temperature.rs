@@ -0,0 +1,153 @@
+// phidget-rs/src/devices/humidity_temperature.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! A combined humidity and temperature sensor, like the HUM1000 and HUM1001.
+//!
+//! These devices have a humidity channel and a temperature channel that
+//! share one physical sensor. A [`HumidityTemperatureSensor`] opens both
+//! of them, and reports the readings together:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::HumidityTemperatureSensor, DeviceSpec};
+//! use std::time::Duration;
+//!
+//! let spec: DeviceSpec = "sn=12345,port=2".parse().unwrap();
+//! let mut sensor = HumidityTemperatureSensor::open(&spec, Duration::from_secs(5)).unwrap();
+//!
+//! sensor
+//!     .set_on_change_handler(|rd| println!("{:.1}%RH, {:.1}°C", rd.humidity, rd.temperature))
+//!     .unwrap();
+//! ```
+
+use crate::{
+    devices::{HumiditySensor, TemperatureSensor},
+    DeviceSpec, Phidget, Result,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// A reading from a combined humidity and temperature sensor.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HumidityTemperature {
+    /// The relative humidity, in percent
+    pub humidity: f64,
+    /// The temperature, in degrees Celsius
+    pub temperature: f64,
+}
+
+// The latest values from each of the channels, for the combined callback
+type Latest = Arc<Mutex<(Option<f64>, Option<f64>)>>;
+
+/// The humidity and temperature channels of a combined sensor.
+pub struct HumidityTemperatureSensor {
+    // The humidity channel
+    hum: HumiditySensor,
+    // The temperature channel
+    temp: TemperatureSensor,
+}
+
+impl HumidityTemperatureSensor {
+    /// Opens the humidity and temperature channels of the device that
+    /// matches the specification, waiting a limited time for each of them
+    /// to attach.
+    pub fn open(spec: &DeviceSpec, to: Duration) -> Result<Self> {
+        let hum = spec.open(to)?;
+        let temp = spec.open(to)?;
+        Ok(Self { hum, temp })
+    }
+
+    /// Creates the combined sensor from channels that were already opened.
+    pub fn from_channels(hum: HumiditySensor, temp: TemperatureSensor) -> Self {
+        Self { hum, temp }
+    }
+
+    /// Gets the humidity channel, for settings that are specific to it.
+    pub fn humidity_sensor(&mut self) -> &mut HumiditySensor {
+        &mut self.hum
+    }
+
+    /// Gets the temperature channel, for settings that are specific to it.
+    pub fn temperature_sensor(&mut self) -> &mut TemperatureSensor {
+        &mut self.temp
+    }
+
+    /// Splits the combined sensor back into its channels.
+    pub fn into_channels(self) -> (HumiditySensor, TemperatureSensor) {
+        (self.hum, self.temp)
+    }
+
+    /// Gets the relative humidity, in percent.
+    pub fn humidity(&self) -> Result<f64> {
+        self.hum.humidity()
+    }
+
+    /// Gets the temperature, in degrees Celsius.
+    pub fn temperature(&self) -> Result<f64> {
+        self.temp.temperature()
+    }
+
+    /// Gets the humidity and temperature together.
+    pub fn reading(&self) -> Result<HumidityTemperature> {
+        Ok(HumidityTemperature {
+            humidity: self.humidity()?,
+            temperature: self.temperature()?,
+        })
+    }
+
+    /// Sets the data interval for both channels.
+    pub fn set_data_interval(&mut self, interval: Duration) -> Result<()> {
+        self.hum.set_data_interval(interval)?;
+        self.temp.set_data_interval(interval)
+    }
+
+    /// Sets a handler to receive the readings when either value changes.
+    ///
+    /// The handler is first called once both channels have reported a
+    /// value, then with the latest of both each time either one changes.
+    /// This replaces the change handlers of both channels.
+    pub fn set_on_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(HumidityTemperature) + Send + Sync + 'static,
+    {
+        let cb = Arc::new(cb);
+        let latest = Latest::default();
+
+        // Updates one of the values, and calls the handler when both are known.
+        fn update<F>(latest: &Latest, cb: &F, set: impl FnOnce(&mut (Option<f64>, Option<f64>)))
+        where
+            F: Fn(HumidityTemperature),
+        {
+            let rd = {
+                let mut latest = latest.lock().unwrap();
+                set(&mut latest);
+                match *latest {
+                    (Some(humidity), Some(temperature)) => HumidityTemperature {
+                        humidity,
+                        temperature,
+                    },
+                    _ => return,
+                }
+            };
+            cb(rd);
+        }
+
+        self.hum.set_on_humidity_change_handler({
+            let (latest, cb) = (Arc::clone(&latest), Arc::clone(&cb));
+            move |_, val| update(&latest, &*cb, |l| l.0 = Some(val))
+        })?;
+        self.temp.set_on_temperature_change_handler(move |_, val| {
+            update(&latest, &*cb, |l| l.1 = Some(val))
+        })
+    }
+}
@@ -0,0 +1,555 @@
+// phidget-rs/src/devices/ir.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget IR transceiver channel, like the 1055 IR Phidget.
+//!
+//! Codes from a remote control are received as hex strings. To send a
+//! code back out, the transmitter also needs the details of how the remote
+//! encodes it, which the channel can figure out by "learning" the code.
+//! The remote button is held down until a learn event fires with the code
+//! and its [`IrCodeInfo`], which can be saved, and later used to transmit
+//! the code:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::Ir, Phidget};
+//! use std::{thread, time::Duration};
+//!
+//! let mut ir = Ir::new();
+//! ir.set_on_learn_handler(|_, code, info| {
+//!     println!("Learned {} ({} bits, {:?})", code, info.bit_count, info.encoding);
+//! })
+//! .unwrap();
+//! ir.open_wait_default().unwrap();
+//!
+//! // Hold down a remote button until the code is learned.
+//! thread::sleep(Duration::from_secs(5));
+//! let (code, info) = ir.last_learned_code().unwrap();
+//! ir.transmit(&code, &info).unwrap();
+//! ```
+
+use crate::{
+    lifecycle::Registration, AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode,
+    ErrorEventCode, GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetIRHandle as IrHandle};
+use std::{
+    ffi::{CStr, CString},
+    mem,
+    os::raw::{c_char, c_int, c_void},
+    ptr, slice,
+};
+
+/// The size of a buffer that can hold any code, as a hex string
+const CODE_BUF_LEN: usize = ffi::IR_MAX_CODE_STR_LENGTH as usize;
+
+/// A raw data value that means a space too long to be measured.
+pub const RAW_DATA_LONG_SPACE: u32 = ffi::IR_RAWDATA_LONGSPACE;
+
+/// The way that the bits of a code are encoded
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum IrEncoding {
+    /// Unknown, to be determined when learning
+    #[default]
+    Unknown = ffi::PhidgetIR_Encoding_IR_ENCODING_UNKNOWN, // 1
+    /// Space encoding, also known as pulse distance
+    Space = ffi::PhidgetIR_Encoding_IR_ENCODING_SPACE, // 2
+    /// Pulse encoding, also known as pulse width
+    Pulse = ffi::PhidgetIR_Encoding_IR_ENCODING_PULSE, // 3
+    /// Bi-phase encoding
+    BiPhase = ffi::PhidgetIR_Encoding_IR_ENCODING_BIPHASE, // 4
+    /// RC5, a type of bi-phase encoding
+    Rc5 = ffi::PhidgetIR_Encoding_IR_ENCODING_RC5, // 5
+    /// RC6, a type of bi-phase encoding
+    Rc6 = ffi::PhidgetIR_Encoding_IR_ENCODING_RC6, // 6
+}
+
+impl TryFrom<u32> for IrEncoding {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use IrEncoding::*;
+        match val {
+            ffi::PhidgetIR_Encoding_IR_ENCODING_UNKNOWN => Ok(Unknown), // 1
+            ffi::PhidgetIR_Encoding_IR_ENCODING_SPACE => Ok(Space),     // 2
+            ffi::PhidgetIR_Encoding_IR_ENCODING_PULSE => Ok(Pulse),     // 3
+            ffi::PhidgetIR_Encoding_IR_ENCODING_BIPHASE => Ok(BiPhase), // 4
+            ffi::PhidgetIR_Encoding_IR_ENCODING_RC5 => Ok(Rc5),         // 5
+            ffi::PhidgetIR_Encoding_IR_ENCODING_RC6 => Ok(Rc6),         // 6
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// Whether the length of a code is constant or varies with its data
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum IrLength {
+    /// Unknown, to be determined when learning
+    #[default]
+    Unknown = ffi::PhidgetIR_Length_IR_LENGTH_UNKNOWN, // 1
+    /// The codes are all the same length of time
+    Constant = ffi::PhidgetIR_Length_IR_LENGTH_CONSTANT, // 2
+    /// The length of the codes varies with the data
+    Variable = ffi::PhidgetIR_Length_IR_LENGTH_VARIABLE, // 3
+}
+
+impl TryFrom<u32> for IrLength {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use IrLength::*;
+        match val {
+            ffi::PhidgetIR_Length_IR_LENGTH_UNKNOWN => Ok(Unknown), // 1
+            ffi::PhidgetIR_Length_IR_LENGTH_CONSTANT => Ok(Constant), // 2
+            ffi::PhidgetIR_Length_IR_LENGTH_VARIABLE => Ok(Variable), // 3
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// The details of how a remote control encodes its codes.
+///
+/// The times are in microseconds. This is normally filled in by learning
+/// a code, but for a known protocol, the items that are left at zero are
+/// filled in by the library with the usual values for the encoding.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IrCodeInfo {
+    /// The number of bits in the code
+    pub bit_count: u32,
+    /// The encoding of the bits
+    pub encoding: IrEncoding,
+    /// Whether the length of the code is constant
+    pub length: IrLength,
+    /// The gap between repeats of the code
+    pub gap: u32,
+    /// The trailing pulse, or zero for none
+    pub trail: u32,
+    /// The header pulse and space, or zeros for none
+    pub header: [u32; 2],
+    /// The pulse and space for a one bit
+    pub one: [u32; 2],
+    /// The pulse and space for a zero bit
+    pub zero: [u32; 2],
+    /// The pulses and spaces of a special repeat code, if any
+    pub repeat: Vec<u32>,
+    /// The minimum number of times to repeat the code when transmitting
+    pub min_repeat: u32,
+    /// The duty cycle of the carrier, from 0.1 to 0.5
+    pub duty_cycle: f64,
+    /// The carrier frequency, in Hz
+    pub carrier_frequency: u32,
+    /// The bits that toggle on each button press, as a hex string, if any
+    pub toggle_mask: String,
+}
+
+impl IrCodeInfo {
+    /// Creates the info for a code with the specified number of bits and
+    /// encoding, with everything else to be filled in by the library.
+    pub fn new(bit_count: u32, encoding: IrEncoding) -> Self {
+        Self {
+            bit_count,
+            encoding,
+            ..Self::default()
+        }
+    }
+
+    // Converts to the struct used by the library.
+    fn to_ffi(&self) -> Result<ffi::PhidgetIR_CodeInfo> {
+        // The repeat array is zero-terminated
+        let mut repeat = [0u32; 26];
+        if self.repeat.len() >= repeat.len() || self.toggle_mask.len() >= CODE_BUF_LEN {
+            return Err(ErrorCode::InvalidArg);
+        }
+        repeat[..self.repeat.len()].copy_from_slice(&self.repeat);
+
+        let mut toggle_mask = [0 as c_char; CODE_BUF_LEN];
+        for (dst, src) in toggle_mask.iter_mut().zip(self.toggle_mask.bytes()) {
+            *dst = src as c_char;
+        }
+
+        Ok(ffi::PhidgetIR_CodeInfo {
+            bitCount: self.bit_count,
+            encoding: self.encoding as u32,
+            length: self.length as u32,
+            gap: self.gap,
+            trail: self.trail,
+            header: self.header,
+            one: self.one,
+            zero: self.zero,
+            repeat,
+            minRepeat: self.min_repeat,
+            dutyCycle: self.duty_cycle,
+            carrierFrequency: self.carrier_frequency,
+            toggleMask: toggle_mask,
+        })
+    }
+}
+
+impl From<&ffi::PhidgetIR_CodeInfo> for IrCodeInfo {
+    fn from(info: &ffi::PhidgetIR_CodeInfo) -> Self {
+        let repeat = info
+            .repeat
+            .iter()
+            .copied()
+            .take_while(|&t| t != 0)
+            .collect();
+        let toggle_mask = unsafe { CStr::from_ptr(info.toggleMask.as_ptr()) };
+        Self {
+            bit_count: info.bitCount,
+            encoding: IrEncoding::try_from(info.encoding).unwrap_or_default(),
+            length: IrLength::try_from(info.length).unwrap_or_default(),
+            gap: info.gap,
+            trail: info.trail,
+            header: info.header,
+            one: info.one,
+            zero: info.zero,
+            repeat,
+            min_repeat: info.minRepeat,
+            duty_cycle: info.dutyCycle,
+            carrier_frequency: info.carrierFrequency,
+            toggle_mask: toggle_mask.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+/// The function type for the safe Rust code callback.
+///
+/// This receives the code, as a hex string, the number of bits in the
+/// code, and whether it's a repeat of the previous code, from the button
+/// being held down.
+pub type CodeCallback = dyn Fn(&Ir, &str, u32, bool) + Send + 'static;
+/// The function type for the safe Rust learn callback.
+pub type LearnCallback = dyn Fn(&Ir, &str, &IrCodeInfo) + Send + 'static;
+/// The function type for the safe Rust raw data callback.
+///
+/// This receives the alternating pulse and space times, in microseconds.
+pub type RawDataCallback = dyn Fn(&Ir, &[u32]) + Send + 'static;
+
+/// Phidget IR transceiver
+pub struct Ir {
+    // Handle to the channel in the phidget22 library
+    chan: IrHandle,
+    // Double-boxed CodeCallback, if registered
+    code_cb: Option<*mut c_void>,
+    // Double-boxed LearnCallback, if registered
+    learn_cb: Option<*mut c_void>,
+    // Double-boxed RawDataCallback, if registered
+    raw_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Ir {
+    /// Create a new IR channel.
+    pub fn new() -> Self {
+        let mut chan: IrHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetIR_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for code events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_code(
+        chan: IrHandle,
+        ctx: *mut c_void,
+        code: *const c_char,
+        bit_count: u32,
+        is_repeat: c_int,
+    ) {
+        if !ctx.is_null() && !code.is_null() {
+            let cb: &mut Box<CodeCallback> = &mut *(ctx as *mut _);
+            let ir = Self::from(chan);
+            let code = CStr::from_ptr(code).to_string_lossy();
+            cb(&ir, &code, bit_count, is_repeat != 0);
+            mem::forget(ir);
+        }
+    }
+
+    // Low-level, unsafe, callback for learn events.
+    unsafe extern "C" fn on_learn(
+        chan: IrHandle,
+        ctx: *mut c_void,
+        code: *const c_char,
+        info: *mut ffi::PhidgetIR_CodeInfo,
+    ) {
+        if !ctx.is_null() && !code.is_null() && !info.is_null() {
+            let cb: &mut Box<LearnCallback> = &mut *(ctx as *mut _);
+            let ir = Self::from(chan);
+            let code = CStr::from_ptr(code).to_string_lossy();
+            cb(&ir, &code, &IrCodeInfo::from(&*info));
+            mem::forget(ir);
+        }
+    }
+
+    // Low-level, unsafe, callback for raw data events.
+    unsafe extern "C" fn on_raw_data(chan: IrHandle, ctx: *mut c_void, data: *const u32, n: usize) {
+        if !ctx.is_null() {
+            let cb: &mut Box<RawDataCallback> = &mut *(ctx as *mut _);
+            let ir = Self::from(chan);
+            let data = if data.is_null() {
+                &[]
+            }
+            else {
+                slice::from_raw_parts(data, n)
+            };
+            cb(&ir, data);
+            mem::forget(ir);
+        }
+    }
+
+    /// Get a reference to the underlying channel handle
+    pub fn as_channel(&self) -> &IrHandle {
+        &self.chan
+    }
+
+    /// Gets the last code that was received, as a hex string, and the
+    /// number of bits in it.
+    pub fn last_code(&self) -> Result<(String, u32)> {
+        let mut buf = [0 as c_char; CODE_BUF_LEN];
+        let mut bit_count = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetIR_getLastCode(self.chan, buf.as_mut_ptr(), buf.len(), &mut bit_count)
+        })?;
+        let code = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        Ok((code.to_string_lossy().into_owned(), bit_count))
+    }
+
+    /// Gets the last code that was learned, as a hex string, and the
+    /// details of how it's encoded.
+    pub fn last_learned_code(&self) -> Result<(String, IrCodeInfo)> {
+        let mut buf = [0 as c_char; CODE_BUF_LEN];
+        let mut info = IrCodeInfo::default().to_ffi()?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetIR_getLastLearnedCode(self.chan, buf.as_mut_ptr(), buf.len(), &mut info)
+        })?;
+        let code = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        Ok((code.to_string_lossy().into_owned(), IrCodeInfo::from(&info)))
+    }
+
+    /// Transmits a code, given as a hex string.
+    pub fn transmit(&self, code: &str, info: &IrCodeInfo) -> Result<()> {
+        let code = CString::new(code).map_err(|_| ErrorCode::InvalidArg)?;
+        let mut info = info.to_ffi()?;
+        ReturnCode::result(unsafe { ffi::PhidgetIR_transmit(self.chan, code.as_ptr(), &mut info) })
+    }
+
+    /// Transmits a repeat of the last code, as if the button was held
+    /// down.
+    ///
+    /// This should be called within the gap time of the code, after the
+    /// previous transmit.
+    pub fn transmit_repeat(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetIR_transmitRepeat(self.chan) })
+    }
+
+    /// Transmits raw data, as alternating pulse and space times in
+    /// microseconds, starting and ending with a pulse.
+    ///
+    /// A carrier frequency or duty cycle of zero uses the default, and the
+    /// gap is the time to wait afterward, before transmitting again.
+    pub fn transmit_raw(
+        &self,
+        data: &[u32],
+        carrier_frequency: u32,
+        duty_cycle: f64,
+        gap: u32,
+    ) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetIR_transmitRaw(
+                self.chan,
+                data.as_ptr(),
+                data.len(),
+                carrier_frequency,
+                duty_cycle,
+                gap,
+            )
+        })
+    }
+
+    /// Sets a handler to receive the codes that are recognized.
+    pub fn set_on_code_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Ir, &str, u32, bool) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<CodeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.code_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetIR_setOnCodeHandler(self.chan, Some(Self::on_code), ctx)
+        })
+    }
+
+    /// Sets a handler to receive the codes that are learned.
+    pub fn set_on_learn_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Ir, &str, &IrCodeInfo) + Send + 'static,
+    {
+        let cb: Box<Box<LearnCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.learn_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetIR_setOnLearnHandler(self.chan, Some(Self::on_learn), ctx)
+        })
+    }
+
+    /// Sets a handler to receive the raw pulse and space times of
+    /// everything that's received.
+    pub fn set_on_raw_data_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Ir, &[u32]) + Send + 'static,
+    {
+        let cb: Box<Box<RawDataCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.raw_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetIR_setOnRawDataHandler(self.chan, Some(Self::on_raw_data), ctx)
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for Ir {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as IrHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+}
+
+unsafe impl Send for Ir {}
+
+impl Default for Ir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<IrHandle> for Ir {
+    fn from(chan: IrHandle) -> Self {
+        Self {
+            chan,
+            code_cb: None,
+            learn_cb: None,
+            raw_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for Ir {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetIR_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<CodeCallback>(self.code_cb.take());
+        crate::drop_cb::<LearnCallback>(self.learn_cb.take());
+        crate::drop_cb::<RawDataCallback>(self.raw_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_info() {
+        let info = IrCodeInfo {
+            bit_count: 12,
+            encoding: IrEncoding::Space,
+            length: IrLength::Constant,
+            header: [2400, 600],
+            repeat: vec![2400, 600, 1200],
+            carrier_frequency: 40000,
+            toggle_mask: "0f0".into(),
+            ..IrCodeInfo::default()
+        };
+        let raw = info.to_ffi().unwrap();
+        assert_eq!(raw.repeat[3], 0);
+        assert_eq!(IrCodeInfo::from(&raw), info);
+
+        let info = IrCodeInfo {
+            repeat: vec![100; 26],
+            ..IrCodeInfo::default()
+        };
+        assert!(info.to_ffi().is_err());
+    }
+}
@@ -0,0 +1,574 @@
+// phidget-rs/src/devices/lcd.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget LCD, like the LCD1100 graphic display.
+//!
+//! Besides writing text at pixel positions, the display can be used as a
+//! text terminal through an [`LcdWriter`], which implements
+//! [`fmt::Write`], so values can be put on the screen with the standard
+//! Rust formatting, using the [`lcd_write!`](crate::lcd_write) macro:
+//!
+//! ```rust,no_run
+//! use phidget::{
+//!     devices::{Lcd, LcdFont},
+//!     lcd_write, Phidget,
+//! };
+//!
+//! let mut lcd = Lcd::new();
+//! lcd.open_wait_default().unwrap();
+//!
+//! let mut wr = lcd.writer(LcdFont::Font6x12).unwrap().scroll(true);
+//! lcd_write!(wr, "Temp: {:.1}°C\nRH: {:.0}%", 21.37, 45.2).unwrap();
+//! ```
+
+use crate::{
+    devices::Bounds,
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetLCDHandle};
+use std::{
+    ffi::CString,
+    fmt, mem,
+    os::raw::{c_int, c_uint, c_void},
+    ptr,
+};
+
+/// The fonts for writing text to an LCD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum LcdFont {
+    /// The first user-defined font
+    User1 = ffi::PhidgetLCD_Font_FONT_User1, // 1
+    /// The second user-defined font
+    User2 = ffi::PhidgetLCD_Font_FONT_User2, // 2
+    /// A 6x10 pixel font
+    Font6x10 = ffi::PhidgetLCD_Font_FONT_6x10, // 3
+    /// A 5x8 pixel font
+    Font5x8 = ffi::PhidgetLCD_Font_FONT_5x8, // 4
+    /// A 6x12 pixel font
+    Font6x12 = ffi::PhidgetLCD_Font_FONT_6x12, // 5
+}
+
+impl TryFrom<u32> for LcdFont {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use LcdFont::*;
+        match val {
+            ffi::PhidgetLCD_Font_FONT_User1 => Ok(User1),   // 1
+            ffi::PhidgetLCD_Font_FONT_User2 => Ok(User2),   // 2
+            ffi::PhidgetLCD_Font_FONT_6x10 => Ok(Font6x10), // 3
+            ffi::PhidgetLCD_Font_FONT_5x8 => Ok(Font5x8),   // 4
+            ffi::PhidgetLCD_Font_FONT_6x12 => Ok(Font6x12), // 5
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// Phidget LCD
+pub struct Lcd {
+    // Handle to the LCD in the phidget22 library
+    chan: PhidgetLCDHandle,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Lcd {
+    /// Create a new LCD.
+    pub fn new() -> Self {
+        let mut chan: PhidgetLCDHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetLCD_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    /// Writes text to the frame buffer, with its top-left corner at the
+    /// specified pixel position.
+    ///
+    /// The text appears on the screen after a [`flush()`](Self::flush),
+    /// unless auto-flush is on.
+    pub fn write_text(&self, font: LcdFont, x: i32, y: i32, text: &str) -> Result<()> {
+        let text = CString::new(text).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLCD_writeText(
+                self.chan,
+                font as c_uint,
+                x as c_int,
+                y as c_int,
+                text.as_ptr(),
+            )
+        })
+    }
+
+    /// Clears the frame buffer.
+    pub fn clear(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_clear(self.chan) })
+    }
+
+    /// Sends the frame buffer to the screen.
+    pub fn flush(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_flush(self.chan) })
+    }
+
+    /// Determines if changes to the frame buffer are sent to the screen
+    /// right away.
+    pub fn auto_flush(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getAutoFlush(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Sets whether changes to the frame buffer are sent to the screen
+    /// right away.
+    pub fn set_auto_flush(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_setAutoFlush(self.chan, c_int::from(on)) })
+    }
+
+    /// Gets the size of a character in the font, as (width, height) in
+    /// pixels.
+    pub fn font_size(&self, font: LcdFont) -> Result<(i32, i32)> {
+        let (mut width, mut height): (c_int, c_int) = (0, 0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLCD_getFontSize(self.chan, font as c_uint, &mut width, &mut height)
+        })?;
+        Ok((width as i32, height as i32))
+    }
+
+    /// Gets the width of the screen, in pixels.
+    pub fn width(&self) -> Result<i32> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getWidth(self.chan, &mut value) })?;
+        Ok(value as i32)
+    }
+
+    /// Gets the height of the screen, in pixels.
+    pub fn height(&self) -> Result<i32> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getHeight(self.chan, &mut value) })?;
+        Ok(value as i32)
+    }
+
+    /// Gets the brightness of the backlight, from 0.0 (off) to 1.0.
+    pub fn backlight(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getBacklight(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the brightness of the backlight, from 0.0 (off) to 1.0.
+    pub fn set_backlight(&self, backlight: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_setBacklight(self.chan, backlight) })
+    }
+
+    /// Gets the range that the backlight can be set within.
+    pub fn backlight_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getMinBacklight(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getMaxBacklight(self.chan, &mut max) })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the contrast of the screen.
+    pub fn contrast(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getContrast(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the contrast of the screen, from 0.0 to 1.0.
+    pub fn set_contrast(&self, contrast: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_setContrast(self.chan, contrast) })
+    }
+
+    /// Gets the range that the contrast can be set within.
+    pub fn contrast_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getMinContrast(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getMaxContrast(self.chan, &mut max) })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Determines if the cursor is shown.
+    pub fn cursor_on(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getCursorOn(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Shows or hides the cursor, which is an underline after the last
+    /// character written.
+    ///
+    /// This is only supported by character displays.
+    pub fn set_cursor_on(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_setCursorOn(self.chan, c_int::from(on)) })
+    }
+
+    /// Determines if the cursor blinks.
+    pub fn cursor_blink(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getCursorBlink(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Sets whether the cursor blinks.
+    ///
+    /// This is only supported by character displays.
+    pub fn set_cursor_blink(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_setCursorBlink(self.chan, c_int::from(on)) })
+    }
+
+    /// Determines if the screen is asleep.
+    pub fn sleeping(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_getSleeping(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Puts the screen to sleep, or wakes it up.
+    ///
+    /// A sleeping screen is turned off to save power, but keeps its
+    /// contents, which are shown again when it wakes up.
+    pub fn set_sleeping(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_setSleeping(self.chan, c_int::from(on)) })
+    }
+
+    /// Initializes the screen.
+    ///
+    /// This is needed for some displays after they're powered on.
+    pub fn initialize(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetLCD_initialize(self.chan) })
+    }
+
+    /// Creates a writer to use the screen as a text terminal, in the
+    /// specified font.
+    ///
+    /// The number of rows and columns is determined from the size of the
+    /// screen and the font, so the LCD must be open.
+    pub fn writer(&self, font: LcdFont) -> Result<LcdWriter<'_>> {
+        let (font_width, font_height) = self.font_size(font)?;
+        if font_width <= 0 || font_height <= 0 {
+            return Err(ErrorCode::InvalidArg);
+        }
+        let cols = (self.width()? / font_width) as usize;
+        let rows = (self.height()? / font_height) as usize;
+        Ok(LcdWriter {
+            lcd: self,
+            font,
+            line_height: font_height,
+            buf: TextBuffer::new(cols, rows),
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for Lcd {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PhidgetLCDHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        let lim = self.backlight_limits();
+        properties::add_ranged(
+            &mut props,
+            "backlight",
+            self.backlight(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        let lim = self.contrast_limits();
+        properties::add_ranged(
+            &mut props,
+            "contrast",
+            self.contrast(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        properties::add(&mut props, "auto_flush", self.auto_flush())?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for Lcd {}
+
+impl Default for Lcd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<PhidgetLCDHandle> for Lcd {
+    fn from(chan: PhidgetLCDHandle) -> Self {
+        Self {
+            chan,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for Lcd {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetLCD_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// The lines of text on the screen, and the position of the cursor.
+#[derive(Debug, Clone, PartialEq)]
+struct TextBuffer {
+    cols: usize,
+    rows: usize,
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+    wrap: bool,
+    scroll: bool,
+}
+
+impl TextBuffer {
+    fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols,
+            rows,
+            lines: vec![String::new(); rows],
+            row: 0,
+            col: 0,
+            wrap: false,
+            scroll: false,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.lines.iter_mut().for_each(String::clear);
+        self.row = 0;
+        self.col = 0;
+    }
+
+    // Moves the cursor to the start of the next line, scrolling the text
+    // up if it's on the last line and scrolling is on.
+    fn newline(&mut self) {
+        self.col = 0;
+        self.row += 1;
+        if self.row >= self.rows && self.scroll && self.rows > 0 {
+            self.lines.remove(0);
+            self.lines.push(String::new());
+            self.row = self.rows - 1;
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        match c {
+            '\n' => self.newline(),
+            '\r' => self.col = 0,
+            _ => {
+                if self.col >= self.cols {
+                    if !self.wrap {
+                        return;
+                    }
+                    self.newline();
+                }
+                // Text below the screen is dropped
+                if let Some(line) = self.lines.get_mut(self.row) {
+                    let n = line.chars().count();
+                    if self.col < n {
+                        let start = line.char_indices().nth(self.col).map_or(0, |(i, _)| i);
+                        let end = line
+                            .char_indices()
+                            .nth(self.col + 1)
+                            .map_or(line.len(), |(i, _)| i);
+                        line.replace_range(start..end, c.encode_utf8(&mut [0; 4]));
+                    }
+                    else {
+                        line.extend(std::iter::repeat(' ').take(self.col - n));
+                        line.push(c);
+                    }
+                    self.col += 1;
+                }
+            }
+        }
+    }
+}
+
+/// A text terminal on an LCD screen.
+///
+/// This implements [`fmt::Write`], so text can be formatted onto the
+/// screen with `write!()`, or with [`lcd_write!`](crate::lcd_write), which
+/// also flushes the text to the screen. Newlines move to the start of the
+/// next row. By default, text past the end of a row, or below the last
+/// row, is dropped. Either can be changed with [`wrap()`](Self::wrap) and
+/// [`scroll()`](Self::scroll).
+///
+/// The text is kept in the writer, and the whole screen is redrawn by
+/// [`flush()`](Self::flush).
+pub struct LcdWriter<'a> {
+    lcd: &'a Lcd,
+    font: LcdFont,
+    line_height: i32,
+    buf: TextBuffer,
+}
+
+impl LcdWriter<'_> {
+    /// Sets whether text that runs past the end of a row wraps onto the
+    /// next one.
+    pub fn wrap(mut self, on: bool) -> Self {
+        self.buf.wrap = on;
+        self
+    }
+
+    /// Sets whether the text scrolls up when a line is added below the
+    /// last row.
+    pub fn scroll(mut self, on: bool) -> Self {
+        self.buf.scroll = on;
+        self
+    }
+
+    /// Gets the size of the terminal, as (columns, rows).
+    pub fn size(&self) -> (usize, usize) {
+        (self.buf.cols, self.buf.rows)
+    }
+
+    /// Gets the position of the cursor, as (column, row).
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.buf.col, self.buf.row)
+    }
+
+    /// Clears the text, and moves the cursor to the top-left corner.
+    ///
+    /// The screen is cleared on the next flush.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    /// Redraws the screen with the text.
+    pub fn flush(&mut self) -> Result<()> {
+        self.lcd.clear()?;
+        for (i, line) in self.buf.lines.iter().enumerate() {
+            if !line.is_empty() {
+                self.lcd
+                    .write_text(self.font, 0, i as i32 * self.line_height, line)?;
+            }
+        }
+        self.lcd.flush()
+    }
+}
+
+impl fmt::Write for LcdWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars().for_each(|c| self.buf.push(c));
+        Ok(())
+    }
+}
+
+/// Writes formatted text to an [`LcdWriter`], then flushes it to the
+/// screen.
+///
+/// This takes the writer and the same arguments as `format!()`, and
+/// evaluates to a `Result<()>` from the flush.
+#[macro_export]
+macro_rules! lcd_write {
+    ($wr:expr, $($arg:tt)*) => {{
+        let wr: &mut $crate::devices::LcdWriter = &mut $wr;
+        match ::std::fmt::Write::write_fmt(wr, format_args!($($arg)*)) {
+            Ok(()) => wr.flush(),
+            Err(_) => Err($crate::ErrorCode::Unexpected),
+        }
+    }};
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_buffer() {
+        let mut buf = TextBuffer::new(4, 2);
+        "abcdef\nxy\nz".chars().for_each(|c| buf.push(c));
+        assert_eq!(buf.lines, ["abcd", "xy"]);
+
+        buf.clear();
+        buf.wrap = true;
+        buf.scroll = true;
+        "abcdef\nxy\rz".chars().for_each(|c| buf.push(c));
+        assert_eq!(buf.lines, ["ef", "zy"]);
+        assert_eq!((buf.col, buf.row), (1, 1));
+    }
+}
@@ -0,0 +1,318 @@
+// phidget-rs/src/devices/light_sensor.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget light sensor
+//!
+//! Light sensors, like the LUX1000, report the illuminance in lux. The
+//! illuminance is reported when it changes by at least the change
+//! trigger:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::LightSensor, Phidget};
+//!
+//! let mut sensor = LightSensor::new();
+//! sensor.set_on_illuminance_change_handler(|_, lux| {
+//!     println!("{:.1} lx", lux);
+//! })
+//! .unwrap();
+//! sensor.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetLightSensorHandle as LightSensorHandle};
+use std::{mem, os::raw::c_void, ptr};
+
+/// The function signature for the safe Rust illuminance change callback.
+pub type IlluminanceCallback = dyn Fn(&LightSensor, f64) + Send + 'static;
+
+/// Phidget light sensor
+pub struct LightSensor {
+    // Handle to the sensor for the phidget22 library
+    chan: LightSensorHandle,
+    // Double-boxed IlluminanceCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl LightSensor {
+    /// Create a new light sensor.
+    pub fn new() -> Self {
+        let mut chan: LightSensorHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetLightSensor_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for illuminance change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_illuminance_change(
+        chan: LightSensorHandle,
+        ctx: *mut c_void,
+        illuminance: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<IlluminanceCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            cb(&sensor, illuminance);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &LightSensorHandle {
+        &self.chan
+    }
+
+    /// Read the current illuminance, in lux.
+    pub fn illuminance(&self) -> Result<f64> {
+        let mut illuminance = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLightSensor_getIlluminance(self.chan, &mut illuminance)
+        })?;
+        Ok(illuminance)
+    }
+
+    /// Gets the illuminance, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_illuminance(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.illuminance())
+    }
+
+    /// Gets the minimum illuminance that the sensor can measure, in lux.
+    pub fn min_illuminance(&self) -> Result<f64> {
+        let mut illuminance = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLightSensor_getMinIlluminance(self.chan, &mut illuminance)
+        })?;
+        Ok(illuminance)
+    }
+
+    /// Gets the maximum illuminance that the sensor can measure, in lux.
+    pub fn max_illuminance(&self) -> Result<f64> {
+        let mut illuminance = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLightSensor_getMaxIlluminance(self.chan, &mut illuminance)
+        })?;
+        Ok(illuminance)
+    }
+
+    /// Gets the illuminance change trigger.
+    /// Change events are only fired when the illuminance changes by at least
+    /// this amount.
+    pub fn illuminance_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLightSensor_getIlluminanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the illuminance change trigger.
+    pub fn set_illuminance_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLightSensor_setIlluminanceChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum illuminance change trigger.
+    pub fn min_illuminance_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLightSensor_getMinIlluminanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum illuminance change trigger.
+    pub fn max_illuminance_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetLightSensor_getMaxIlluminanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and illuminance change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_illuminance_change_trigger()?,
+                self.max_illuminance_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the illuminance reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_illuminance()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.illuminance_change_trigger()?,
+        })
+    }
+
+    /// Sets a handler to receive illuminance change callbacks.
+    pub fn set_on_illuminance_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&LightSensor, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<IlluminanceCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        let res = ReturnCode::result(unsafe {
+            ffi::PhidgetLightSensor_setOnIlluminanceChangeHandler(
+                self.chan,
+                Some(Self::on_illuminance_change),
+                ctx,
+            )
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<IlluminanceCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<IlluminanceCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent illuminance reading.
+    ///
+    /// This sets the illuminance change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_illuminance_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for LightSensor {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as LightSensorHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.illuminance_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_illuminance_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_illuminance_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_illuminance_change_trigger()
+    }
+}
+
+unsafe impl Send for LightSensor {}
+
+impl Default for LightSensor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<LightSensorHandle> for LightSensor {
+    fn from(chan: LightSensorHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for LightSensor {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetLightSensor_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<IlluminanceCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
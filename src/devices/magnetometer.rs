@@ -0,0 +1,325 @@
+// phidget-rs/src/devices/magnetometer.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget magnetometer channel.
+//!
+//! The magnetometer of a spatial Phidget, like the MOT0109, reports the
+//! strength of the magnetic field along each axis, in gauss. A change
+//! event is fired when the field changes by more than the change trigger:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::Magnetometer, Phidget};
+//!
+//! let mut mag = Magnetometer::new();
+//! mag.set_on_magnetic_field_change_handler(|_, field, _ts| {
+//!     let heading = field[1].atan2(field[0]).to_degrees();
+//!     println!("heading: {:.0}°", heading);
+//! })
+//! .unwrap();
+//! mag.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetMagnetometerHandle as MagnetometerHandle};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The function type for the safe Rust magnetic field change callback.
+///
+/// This receives the field strength along each axis, in gauss, and the
+/// timestamp of the reading.
+pub type MagneticFieldCallback = dyn Fn(&Magnetometer, [f64; 3], Duration) + Send + 'static;
+
+/// Phidget magnetometer
+pub struct Magnetometer {
+    // Handle to the sensor in the phidget22 library
+    chan: MagnetometerHandle,
+    // Double-boxed MagneticFieldCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Magnetometer {
+    /// Create a new magnetometer.
+    pub fn new() -> Self {
+        let mut chan: MagnetometerHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetMagnetometer_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for magnetic field change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_magnetic_field_change(
+        chan: MagnetometerHandle,
+        ctx: *mut c_void,
+        field: *const f64,
+        timestamp: f64,
+    ) {
+        if !ctx.is_null() && !field.is_null() {
+            let cb: &mut Box<MagneticFieldCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            let field = *(field as *const [f64; 3]);
+            let ts = Duration::from_secs_f64(timestamp.max(0.0) / 1000.0);
+            cb(&sensor, field, ts);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &MagnetometerHandle {
+        &self.chan
+    }
+
+    /// Gets the most recent field strength along each axis, in gauss.
+    pub fn magnetic_field(&self) -> Result<[f64; 3]> {
+        let mut field = [0.0; 3];
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_getMagneticField(self.chan, &mut field)
+        })?;
+        Ok(field)
+    }
+
+    /// Gets the minimum field strength that can be measured on each axis.
+    pub fn min_magnetic_field(&self) -> Result<[f64; 3]> {
+        let mut field = [0.0; 3];
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_getMinMagneticField(self.chan, &mut field)
+        })?;
+        Ok(field)
+    }
+
+    /// Gets the maximum field strength that can be measured on each axis.
+    pub fn max_magnetic_field(&self) -> Result<[f64; 3]> {
+        let mut field = [0.0; 3];
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_getMaxMagneticField(self.chan, &mut field)
+        })?;
+        Ok(field)
+    }
+
+    /// Gets the number of axes that the magnetometer measures.
+    pub fn axis_count(&self) -> Result<u32> {
+        let mut count: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_getAxisCount(self.chan, &mut count)
+        })?;
+        Ok(count as u32)
+    }
+
+    /// Gets the timestamp of the most recent reading, as the time since
+    /// the channel was opened.
+    pub fn timestamp(&self) -> Result<Duration> {
+        let mut ts = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetMagnetometer_getTimestamp(self.chan, &mut ts) })?;
+        Ok(Duration::from_secs_f64(ts.max(0.0) / 1000.0))
+    }
+
+    /// Determines if the heater of the sensor is enabled.
+    pub fn heating_enabled(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_getHeatingEnabled(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables the heater, which keeps the sensor at a steady
+    /// temperature to reduce its drift.
+    pub fn set_heating_enabled(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_setHeatingEnabled(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Gets the magnetic field change trigger.
+    /// Change events are only fired when the field changes by at least
+    /// this amount.
+    pub fn magnetic_field_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_getMagneticFieldChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the magnetic field change trigger.
+    pub fn set_magnetic_field_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_setMagneticFieldChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum magnetic field change trigger.
+    pub fn min_magnetic_field_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_getMinMagneticFieldChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum magnetic field change trigger.
+    pub fn max_magnetic_field_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_getMaxMagneticFieldChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets a handler to receive magnetic field change callbacks.
+    pub fn set_on_magnetic_field_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Magnetometer, [f64; 3], Duration) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<MagneticFieldCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMagnetometer_setOnMagneticFieldChangeHandler(
+                self.chan,
+                Some(Self::on_magnetic_field_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for Magnetometer {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as MagnetometerHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add(&mut props, "heating_enabled", self.heating_enabled())?;
+        Ok(props)
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.magnetic_field_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_magnetic_field_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_magnetic_field_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_magnetic_field_change_trigger()
+    }
+}
+
+unsafe impl Send for Magnetometer {}
+
+impl Default for Magnetometer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<MagnetometerHandle> for Magnetometer {
+    fn from(chan: MagnetometerHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for Magnetometer {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetMagnetometer_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<MagneticFieldCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -1,18 +1,119 @@
+use crate::Result;
+use std::time::Duration;
+
+/// Phidget brushless DC motor
+pub mod bldc_motor;
+pub use crate::devices::bldc_motor::BldcMotor;
+
+/// Phidget capacitive touch pad
+pub mod capacitive_touch;
+pub use crate::devices::capacitive_touch::CapacitiveTouch;
+
+/// Phidget current input
+pub mod current_input;
+pub use crate::devices::current_input::CurrentInput;
+
+/// Phidget DC motor
+pub mod dc_motor;
+pub use crate::devices::dc_motor::DcMotor;
+
+/// Phidget distance sensor
+pub mod distance_sensor;
+pub use crate::devices::distance_sensor::DistanceSensor;
+
+/// Phidget dictionary
+pub mod dictionary;
+pub use crate::devices::dictionary::Dictionary;
+
+/// Phidget encoder input
+pub mod encoder;
+pub use crate::devices::encoder::{Encoder, EncoderIoMode, PositionChange};
+
+/// Phidget frequency counter
+pub mod frequency_counter;
+pub use crate::devices::frequency_counter::{FilterType, FrequencyCounter};
+
+/// Phidget gyroscope
+pub mod gyroscope;
+pub use crate::devices::gyroscope::Gyroscope;
+
 /// Phidget hub
 pub mod hub;
-pub use crate::devices::hub::{Hub, HubPortMode};
+pub use crate::devices::hub::{AnyDevice, Hub, HubPortMode, HubPortPower, ScheduledPowerCycle};
 
 /// Phidget hmidity sensor
 pub mod humidity_sensor;
 pub use crate::devices::humidity_sensor::HumiditySensor;
 
+/// Combined humidity and temperature sensor
+pub mod humidity_temperature;
+pub use crate::devices::humidity_temperature::{HumidityTemperature, HumidityTemperatureSensor};
+
+/// Phidget IR transceiver
+pub mod ir;
+pub use crate::devices::ir::{Ir, IrCodeInfo, IrEncoding, IrLength};
+
+/// Phidget LCD
+pub mod lcd;
+pub use crate::devices::lcd::{Lcd, LcdFont, LcdWriter};
+
+/// Phidget light sensor
+pub mod light_sensor;
+pub use crate::devices::light_sensor::LightSensor;
+
+/// Phidget magnetometer
+pub mod magnetometer;
+pub use crate::devices::magnetometer::Magnetometer;
+
+/// Phidget pH sensor
+pub mod ph_sensor;
+pub use crate::devices::ph_sensor::PhSensor;
+
+/// Phidget motor position controller
+pub mod motor_position_controller;
+pub use crate::devices::motor_position_controller::MotorPositionController;
+
 /// Phidget stepper
 pub mod stepper;
 pub use crate::devices::stepper::Stepper;
 
+/// Phidget power guard
+pub mod power_guard;
+pub use crate::devices::power_guard::{FanMode, PowerGuard, PowerGuardFault};
+
+/// Power meter from current and voltage inputs
+pub mod power_meter;
+pub use crate::devices::power_meter::{PowerMeter, PowerReading};
+
+/// Phidget pressure sensor
+pub mod pressure_sensor;
+pub use crate::devices::pressure_sensor::PressureSensor;
+
+/// Phidget RC servo
+pub mod rc_servo;
+pub use crate::devices::rc_servo::{AngleCalibration, RcServo};
+
+/// Phidget resistance input
+pub mod resistance_input;
+pub use crate::devices::resistance_input::{ResistanceInput, RtdWireSetup};
+
+/// Phidget RFID reader
+pub mod rfid;
+pub use crate::devices::rfid::{Rfid, RfidProtocol};
+
+/// Phidget sound sensor
+pub mod sound_sensor;
+pub use crate::devices::sound_sensor::{SoundSensor, SplRange, SplReading};
+
+/// Phidget spatial (IMU)
+pub mod spatial;
+pub use crate::devices::spatial::{
+    EulerAngles, Quaternion, Spatial, SpatialAlgorithm, SpatialData,
+};
+
 /// Phidget temperature sensor
 pub mod temperature_sensor;
-pub use crate::devices::temperature_sensor::TemperatureSensor;
+pub use crate::devices::temperature_sensor::{TcFault, TemperatureSensor, ThermocoupleType};
 
 /// Phidget digital input
 pub mod digital_output;
@@ -34,4 +135,72 @@ pub use crate::devices::voltage_ratio_input::VoltageRatioInput;
 pub mod voltage_output;
 // mod voltage_ratio_input;
 
-pub use crate::devices::voltage_output::VoltageOutput;
+pub use crate::devices::voltage_output::{VoltageOutput, VoltageRamp};
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// The range of values supported by a setting of a channel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounds<T> {
+    /// The minimum value
+    pub min: T,
+    /// The maximum value
+    pub max: T,
+}
+
+impl<T: PartialOrd + Copy> Bounds<T> {
+    /// Creates a range from the minimum and maximum values.
+    pub fn new(min: T, max: T) -> Self {
+        Self { min, max }
+    }
+
+    /// Determines if the value is within the range, inclusive.
+    pub fn contains(&self, val: T) -> bool {
+        val >= self.min && val <= self.max
+    }
+
+    /// Creates a range from the results of reading the minimum and
+    /// maximum values, which is `None` if the channel doesn't support the
+    /// setting.
+    pub(crate) fn supported(min: Result<T>, max: Result<T>) -> Result<Option<Self>> {
+        let min = crate::errors::supported(min)?;
+        let max = crate::errors::supported(max)?;
+        Ok(min.zip(max).map(|(min, max)| Self { min, max }))
+    }
+
+    /// Limits the value to the range.
+    pub fn clamp(&self, val: T) -> T {
+        if val < self.min {
+            self.min
+        }
+        else if val > self.max {
+            self.max
+        }
+        else {
+            val
+        }
+    }
+}
+
+/// The limits of the settings of a sensor channel, gathered in one call.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorLimits {
+    /// The range of the data interval
+    pub data_interval: Bounds<Duration>,
+    /// The range of the change trigger
+    pub change_trigger: Bounds<f64>,
+}
+
+/// A snapshot of the reading and settings of a sensor channel.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorState {
+    /// The current reading, if valid
+    pub value: Option<f64>,
+    /// The data interval
+    pub data_interval: Duration,
+    /// The change trigger
+    pub change_trigger: f64,
+}
@@ -0,0 +1,609 @@
+// phidget-rs/src/devices/motor_position_controller.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget motor position controller channel.
+//!
+//! A position controller, like the DCC1000 with an encoder on the motor,
+//! runs a PID control loop that drives a DC motor to a target position.
+//! The gains of the loop need to be tuned for the motor and its load
+//! before it's engaged:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::MotorPositionController, Phidget};
+//!
+//! let mut ctrl = MotorPositionController::new();
+//! ctrl.set_on_position_change_handler(|_, pos| println!("Position: {}", pos))
+//!     .unwrap();
+//! ctrl.open_wait_default().unwrap();
+//!
+//! ctrl.set_gains(20_000.0, 2.0, 40_000.0).unwrap();
+//! ctrl.set_target_position(1000.0).unwrap();
+//! ctrl.set_engaged(true).unwrap();
+//! ```
+
+use crate::{
+    devices::{Bounds, EncoderIoMode},
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorCode, ErrorEventCode, GenericPhidget,
+    Phidget, Result, ReturnCode,
+};
+use phidget_sys::{
+    self as ffi, PhidgetHandle,
+    PhidgetMotorPositionControllerHandle as MotorPositionControllerHandle,
+};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The function type for the safe Rust position change callback.
+pub type PositionChangeCallback = dyn Fn(&MotorPositionController, f64) + Send + 'static;
+/// The function type for the safe Rust duty cycle update callback.
+pub type DutyCycleUpdateCallback = dyn Fn(&MotorPositionController, f64) + Send + 'static;
+
+/// Phidget motor position controller
+pub struct MotorPositionController {
+    // Handle to the controller in the phidget22 library
+    chan: MotorPositionControllerHandle,
+    // Double-boxed PositionChangeCallback, if registered
+    position_cb: Option<*mut c_void>,
+    // Double-boxed DutyCycleUpdateCallback, if registered
+    duty_cycle_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl MotorPositionController {
+    /// Create a new motor position controller.
+    pub fn new() -> Self {
+        let mut chan: MotorPositionControllerHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetMotorPositionController_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    /// Get a reference to the underlying controller handle
+    pub fn as_channel(&self) -> &MotorPositionControllerHandle {
+        &self.chan
+    }
+
+    /// Gets the most recent position of the motor.
+    pub fn position(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getPosition(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Gets the range that the target position can be set within.
+    pub fn position_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMinPosition(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMaxPosition(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the target position.
+    pub fn target_position(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getTargetPosition(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the position that the controller drives the motor to.
+    ///
+    /// The motor only moves while the controller is engaged.
+    pub fn set_target_position(&self, position: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setTargetPosition(self.chan, position)
+        })
+    }
+
+    /// Adds an offset to the position, such as to zero it at a home
+    /// position.
+    pub fn add_position_offset(&self, offset: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_addPositionOffset(self.chan, offset)
+        })
+    }
+
+    /// Gets the rescale factor.
+    pub fn rescale_factor(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getRescaleFactor(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the rescale factor, which converts the encoder counts into the
+    /// units of the position.
+    pub fn set_rescale_factor(&self, factor: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setRescaleFactor(self.chan, factor)
+        })
+    }
+
+    /// Determines if the controller is engaged.
+    pub fn engaged(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getEngaged(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Engages or disengages the controller.
+    ///
+    /// While engaged, the controller actively drives the motor to the
+    /// target position. When disengaged, the motor is free to turn.
+    pub fn set_engaged(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setEngaged(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Gets the proportional gain of the control loop.
+    pub fn kp(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getKp(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the proportional gain of the control loop.
+    pub fn set_kp(&self, kp: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetMotorPositionController_setKp(self.chan, kp) })
+    }
+
+    /// Gets the integral gain of the control loop.
+    pub fn ki(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getKi(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the integral gain of the control loop.
+    pub fn set_ki(&self, ki: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetMotorPositionController_setKi(self.chan, ki) })
+    }
+
+    /// Gets the derivative gain of the control loop.
+    pub fn kd(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getKd(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the derivative gain of the control loop.
+    pub fn set_kd(&self, kd: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetMotorPositionController_setKd(self.chan, kd) })
+    }
+
+    /// Sets the proportional, integral, and derivative gains of the
+    /// control loop, together.
+    pub fn set_gains(&self, kp: f64, ki: f64, kd: f64) -> Result<()> {
+        self.set_kp(kp)?;
+        self.set_ki(ki)?;
+        self.set_kd(kd)
+    }
+
+    /// Gets the dead band.
+    pub fn dead_band(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getDeadBand(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the dead band, which is how far the position can be from the
+    /// target before the controller drives the motor to correct it.
+    pub fn set_dead_band(&self, dead_band: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setDeadBand(self.chan, dead_band)
+        })
+    }
+
+    /// Gets the most recent duty cycle that the motor is driven at.
+    pub fn duty_cycle(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getDutyCycle(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Gets the velocity limit.
+    pub fn velocity_limit(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getVelocityLimit(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the maximum velocity that the controller moves the motor at,
+    /// in position units per second.
+    pub fn set_velocity_limit(&self, limit: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setVelocityLimit(self.chan, limit)
+        })
+    }
+
+    /// Gets the range that the velocity limit can be set within.
+    pub fn velocity_limit_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMinVelocityLimit(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMaxVelocityLimit(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the acceleration.
+    pub fn acceleration(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getAcceleration(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the rate at which the controller changes the velocity, in
+    /// position units per second squared.
+    pub fn set_acceleration(&self, acceleration: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setAcceleration(self.chan, acceleration)
+        })
+    }
+
+    /// Gets the range that the acceleration can be set within.
+    pub fn acceleration_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMinAcceleration(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMaxAcceleration(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the current limit, in amperes.
+    pub fn current_limit(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getCurrentLimit(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the maximum current that the controller supplies to the motor,
+    /// in amperes.
+    pub fn set_current_limit(&self, current_limit: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setCurrentLimit(self.chan, current_limit)
+        })
+    }
+
+    /// Gets the range that the current limit can be set within.
+    pub fn current_limit_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMinCurrentLimit(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMaxCurrentLimit(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the stall velocity.
+    pub fn stall_velocity(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getStallVelocity(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the velocity below which the motor is considered stalled, when
+    /// it's being driven, which disengages the controller.
+    pub fn set_stall_velocity(&self, velocity: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setStallVelocity(self.chan, velocity)
+        })
+    }
+
+    /// Gets the range that the stall velocity can be set within.
+    pub fn stall_velocity_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMinStallVelocity(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMaxStallVelocity(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the electrical interface of the encoder inputs.
+    pub fn io_mode(&self) -> Result<EncoderIoMode> {
+        let mut mode = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getIOMode(self.chan, &mut mode)
+        })?;
+        EncoderIoMode::try_from(mode)
+    }
+
+    /// Sets the electrical interface of the encoder inputs, to match the
+    /// outputs of the encoder.
+    pub fn set_io_mode(&self, mode: EncoderIoMode) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setIOMode(self.chan, mode as u32)
+        })
+    }
+
+    /// Enables the failsafe for the channel with the specified timeout.
+    ///
+    /// Once enabled, the failsafe must be reset within the time, or the
+    /// controller is disengaged.
+    pub fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        let ms = u32::try_from(time.as_millis()).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_enableFailsafe(self.chan, ms)
+        })
+    }
+
+    /// Resets the failsafe timer.
+    pub fn reset_failsafe(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetMotorPositionController_resetFailsafe(self.chan) })
+    }
+
+    /// Gets the range of the failsafe time.
+    pub fn failsafe_time_limits(&self) -> Result<Bounds<Duration>> {
+        let (mut min, mut max) = (0, 0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMinFailsafeTime(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_getMaxFailsafeTime(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(
+            Duration::from_millis(min.into()),
+            Duration::from_millis(max.into()),
+        ))
+    }
+
+    /// Determines if the motor is away from the target position, by more
+    /// than the dead band.
+    pub fn is_moving(&self) -> Result<bool> {
+        let err = (self.target_position()? - self.position()?).abs();
+        Ok(err > self.dead_band()?)
+    }
+
+    // Low-level, unsafe, callback for position change events.
+    // The context is a double-boxed pointer to the safe Rust callback.
+    unsafe extern "C" fn on_position_change(
+        chan: MotorPositionControllerHandle,
+        ctx: *mut c_void,
+        pos: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<PositionChangeCallback> = &mut *(ctx as *mut _);
+            let ctrl = Self::from(chan);
+            cb(&ctrl, pos);
+            mem::forget(ctrl);
+        }
+    }
+
+    // Low-level, unsafe, callback for duty cycle update events.
+    unsafe extern "C" fn on_duty_cycle_update(
+        chan: MotorPositionControllerHandle,
+        ctx: *mut c_void,
+        duty_cycle: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<DutyCycleUpdateCallback> = &mut *(ctx as *mut _);
+            let ctrl = Self::from(chan);
+            cb(&ctrl, duty_cycle);
+            mem::forget(ctrl);
+        }
+    }
+
+    /// Sets a handler to receive position change callbacks.
+    pub fn set_on_position_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&MotorPositionController, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<PositionChangeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.position_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setOnPositionChangeHandler(
+                self.chan,
+                Some(Self::on_position_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive the duty cycle, each data interval.
+    pub fn set_on_duty_cycle_update_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&MotorPositionController, f64) + Send + 'static,
+    {
+        let cb: Box<Box<DutyCycleUpdateCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.duty_cycle_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetMotorPositionController_setOnDutyCycleUpdateHandler(
+                self.chan,
+                Some(Self::on_duty_cycle_update),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for MotorPositionController {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as MotorPositionControllerHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add(&mut props, "engaged", self.engaged())?;
+        properties::add(&mut props, "kp", self.kp())?;
+        properties::add(&mut props, "ki", self.ki())?;
+        properties::add(&mut props, "kd", self.kd())?;
+        properties::add(&mut props, "dead_band", self.dead_band())?;
+        properties::add(&mut props, "rescale_factor", self.rescale_factor())?;
+        let lim = self.velocity_limit_limits();
+        properties::add_ranged(
+            &mut props,
+            "velocity_limit",
+            self.velocity_limit(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        let lim = self.acceleration_limits();
+        properties::add_ranged(
+            &mut props,
+            "acceleration",
+            self.acceleration(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        let lim = self.current_limit_limits();
+        properties::add_ranged(
+            &mut props,
+            "current_limit",
+            self.current_limit(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for MotorPositionController {}
+
+impl Default for MotorPositionController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<MotorPositionControllerHandle> for MotorPositionController {
+    fn from(chan: MotorPositionControllerHandle) -> Self {
+        Self {
+            chan,
+            position_cb: None,
+            duty_cycle_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for MotorPositionController {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetMotorPositionController_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<PositionChangeCallback>(self.position_cb.take());
+        crate::drop_cb::<DutyCycleUpdateCallback>(self.duty_cycle_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -0,0 +1,335 @@
+// phidget-rs/src/devices/ph_sensor.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget pH sensor
+//!
+//! The pH adapter, ADP1000, reads a pH probe, typically for monitoring
+//! water quality. The pH is reported when it changes by at least the
+//! change trigger:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::PhSensor, Phidget};
+//!
+//! let mut sensor = PhSensor::new();
+//! sensor.set_on_ph_change_handler(|_, ph| {
+//!     println!("pH {:.2}", ph);
+//! })
+//! .unwrap();
+//! sensor.open_wait_default().unwrap();
+//! sensor.set_correction_temperature(22.0).unwrap();
+//! ```
+
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetPHSensorHandle as PhSensorHandle};
+use std::{mem, os::raw::c_void, ptr};
+
+/// The function signature for the safe Rust pH change callback.
+pub type PhCallback = dyn Fn(&PhSensor, f64) + Send + 'static;
+
+/// Phidget pH sensor
+pub struct PhSensor {
+    // Handle to the sensor for the phidget22 library
+    chan: PhSensorHandle,
+    // Double-boxed PhCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl PhSensor {
+    /// Create a new pH sensor.
+    pub fn new() -> Self {
+        let mut chan: PhSensorHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetPHSensor_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for pH change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_ph_change(chan: PhSensorHandle, ctx: *mut c_void, ph: f64) {
+        if !ctx.is_null() {
+            let cb: &mut Box<PhCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            cb(&sensor, ph);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &PhSensorHandle {
+        &self.chan
+    }
+
+    /// Read the current pH.
+    pub fn ph(&self) -> Result<f64> {
+        let mut ph = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetPHSensor_getPH(self.chan, &mut ph) })?;
+        Ok(ph)
+    }
+
+    /// Gets the pH, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_ph(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.ph())
+    }
+
+    /// Gets the minimum pH that the sensor can measure.
+    pub fn min_ph(&self) -> Result<f64> {
+        let mut ph = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetPHSensor_getMinPH(self.chan, &mut ph) })?;
+        Ok(ph)
+    }
+
+    /// Gets the maximum pH that the sensor can measure.
+    pub fn max_ph(&self) -> Result<f64> {
+        let mut ph = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetPHSensor_getMaxPH(self.chan, &mut ph) })?;
+        Ok(ph)
+    }
+
+    /// Gets the temperature used to correct the pH reading, in °C.
+    pub fn correction_temperature(&self) -> Result<f64> {
+        let mut temperature = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPHSensor_getCorrectionTemperature(self.chan, &mut temperature)
+        })?;
+        Ok(temperature)
+    }
+
+    /// Sets the temperature of the solution being measured, in °C.
+    ///
+    /// The response of the probe changes with the temperature, so this
+    /// should be kept up to date, such as from a separate temperature
+    /// sensor, for accurate readings.
+    pub fn set_correction_temperature(&self, temperature: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPHSensor_setCorrectionTemperature(self.chan, temperature)
+        })
+    }
+
+    /// Gets the range that the correction temperature can be set within.
+    pub fn correction_temperature_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPHSensor_getMinCorrectionTemperature(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPHSensor_getMaxCorrectionTemperature(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the pH change trigger.
+    /// Change events are only fired when the pH changes by at least
+    /// this amount.
+    pub fn ph_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPHSensor_getPHChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the pH change trigger.
+    pub fn set_ph_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetPHSensor_setPHChangeTrigger(self.chan, trigger) })
+    }
+
+    /// Gets the minimum pH change trigger.
+    pub fn min_ph_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPHSensor_getMinPHChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum pH change trigger.
+    pub fn max_ph_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPHSensor_getMaxPHChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and pH change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_ph_change_trigger()?,
+                self.max_ph_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the pH reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_ph()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.ph_change_trigger()?,
+        })
+    }
+
+    /// Sets a handler to receive pH change callbacks.
+    pub fn set_on_ph_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&PhSensor, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<PhCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        let res = ReturnCode::result(unsafe {
+            ffi::PhidgetPHSensor_setOnPHChangeHandler(self.chan, Some(Self::on_ph_change), ctx)
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<PhCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<PhCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent pH reading.
+    ///
+    /// This sets the pH change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_ph_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for PhSensor {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PhSensorHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.ph_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_ph_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_ph_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_ph_change_trigger()
+    }
+}
+
+unsafe impl Send for PhSensor {}
+
+impl Default for PhSensor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<PhSensorHandle> for PhSensor {
+    fn from(chan: PhSensorHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for PhSensor {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetPHSensor_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<PhCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
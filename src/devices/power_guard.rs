@@ -0,0 +1,372 @@
+// phidget-rs/src/devices/power_guard.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget power guard channel.
+//!
+//! A power guard, like the SAF1000, sits between a power supply and a
+//! load, such as motor controllers, and cuts the power when the supply
+//! voltage goes over a set threshold, as it can when a motor is
+//! back-driven. The conditions that trip it are reported as faults:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::PowerGuard, Phidget};
+//!
+//! let mut guard = PowerGuard::new();
+//! guard
+//!     .set_on_fault_handler(|_, fault| match fault {
+//!         Some(fault) => println!("Power guard tripped: {:?}", fault),
+//!         None => println!("Power guard fault cleared"),
+//!     })
+//!     .unwrap();
+//! guard.open_wait_default().unwrap();
+//!
+//! guard.set_over_voltage(26.0).unwrap();
+//! guard.set_power_enabled(true).unwrap();
+//! ```
+
+use crate::{
+    devices::Bounds,
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetPowerGuardHandle as PowerGuardHandle};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The mode of a cooling fan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum FanMode {
+    /// The fan is always off
+    Off = ffi::Phidget_FanMode_FAN_MODE_OFF, // 1
+    /// The fan is always on
+    On = ffi::Phidget_FanMode_FAN_MODE_ON, // 2
+    /// The fan turns on as needed, by the temperature of the device
+    Auto = ffi::Phidget_FanMode_FAN_MODE_AUTO, // 3
+}
+
+impl TryFrom<u32> for FanMode {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use FanMode::*;
+        match val {
+            ffi::Phidget_FanMode_FAN_MODE_OFF => Ok(Off),   // 1
+            ffi::Phidget_FanMode_FAN_MODE_ON => Ok(On),     // 2
+            ffi::Phidget_FanMode_FAN_MODE_AUTO => Ok(Auto), // 3
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// A condition that cuts the power through a power guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PowerGuardFault {
+    /// The supply voltage is over the threshold
+    OverVoltage,
+    /// The device is too hot
+    OverTemp,
+    /// The supply voltage is too low, or otherwise bad
+    BadPower,
+    /// The failsafe timer expired
+    Failsafe,
+}
+
+impl PowerGuardFault {
+    /// Gets the fault for an error event, if the event is one that cuts
+    /// the power.
+    pub fn from_event(code: ErrorEventCode) -> Option<Self> {
+        use ErrorEventCode::*;
+        match code {
+            OverVoltage => Some(Self::OverVoltage),
+            OverTemp => Some(Self::OverTemp),
+            BadPower => Some(Self::BadPower),
+            Failsafe => Some(Self::Failsafe),
+            _ => None,
+        }
+    }
+}
+
+/// Phidget power guard
+pub struct PowerGuard {
+    // Handle to the power guard in the phidget22 library
+    chan: PowerGuardHandle,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl PowerGuard {
+    /// Create a new power guard.
+    pub fn new() -> Self {
+        let mut chan: PowerGuardHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetPowerGuard_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        crate::phidget::track_faults(chan as PhidgetHandle, |code| {
+            PowerGuardFault::from_event(code).is_some()
+        });
+        dev
+    }
+
+    /// Get a reference to the underlying power guard handle
+    pub fn as_channel(&self) -> &PowerGuardHandle {
+        &self.chan
+    }
+
+    /// Determines if power to the load is enabled.
+    pub fn power_enabled(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPowerGuard_getPowerEnabled(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables power to the load.
+    pub fn set_power_enabled(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPowerGuard_setPowerEnabled(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Gets the over-voltage threshold, in volts.
+    pub fn over_voltage(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPowerGuard_getOverVoltage(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the over-voltage threshold, in volts.
+    ///
+    /// The power to the load is cut if the supply voltage goes over this.
+    pub fn set_over_voltage(&self, volts: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetPowerGuard_setOverVoltage(self.chan, volts) })
+    }
+
+    /// Gets the range that the over-voltage threshold can be set within.
+    pub fn over_voltage_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPowerGuard_getMinOverVoltage(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPowerGuard_getMaxOverVoltage(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the mode of the cooling fan.
+    pub fn fan_mode(&self) -> Result<FanMode> {
+        let mut mode = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetPowerGuard_getFanMode(self.chan, &mut mode) })?;
+        FanMode::try_from(mode)
+    }
+
+    /// Sets the mode of the cooling fan.
+    pub fn set_fan_mode(&self, mode: FanMode) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetPowerGuard_setFanMode(self.chan, mode as u32) })
+    }
+
+    /// Enables the failsafe for the channel with the specified timeout.
+    ///
+    /// Once enabled, the failsafe must be reset within the time, or the
+    /// power to the load is cut.
+    pub fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        let ms = u32::try_from(time.as_millis()).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe { ffi::PhidgetPowerGuard_enableFailsafe(self.chan, ms) })
+    }
+
+    /// Resets the failsafe timer.
+    pub fn reset_failsafe(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetPowerGuard_resetFailsafe(self.chan) })
+    }
+
+    /// Gets the range of the failsafe time.
+    pub fn failsafe_time_limits(&self) -> Result<Bounds<Duration>> {
+        let (mut min, mut max) = (0, 0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPowerGuard_getMinFailsafeTime(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPowerGuard_getMaxFailsafeTime(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(
+            Duration::from_millis(min.into()),
+            Duration::from_millis(max.into()),
+        ))
+    }
+
+    /// Gets the fault that cut the power, or `None` if there is none.
+    ///
+    /// The fault is cleared when the channel reports that the condition
+    /// is gone.
+    pub fn last_fault(&self) -> Option<PowerGuardFault> {
+        crate::phidget::fault(self.chan as PhidgetHandle).and_then(PowerGuardFault::from_event)
+    }
+
+    /// Sets a handler to receive the faults that cut the power, and
+    /// `None` when a fault clears.
+    ///
+    /// The faults come from the error events of the channel, which are
+    /// still reported to any error handler.
+    pub fn set_on_fault_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&PowerGuard, Option<PowerGuardFault>) + Send + 'static,
+    {
+        let hook = move |phid: PhidgetHandle, code: Option<ErrorEventCode>| {
+            let guard = Self::from(phid as PowerGuardHandle);
+            cb(&guard, code.and_then(PowerGuardFault::from_event));
+            mem::forget(guard);
+        };
+        crate::phidget::set_fault_hook(self.chan as PhidgetHandle, Box::new(hook));
+        Ok(())
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for PowerGuard {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PowerGuardHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add(&mut props, "power_enabled", self.power_enabled())?;
+        let lim = self.over_voltage_limits();
+        properties::add_ranged(
+            &mut props,
+            "over_voltage",
+            self.over_voltage(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        let mode = self.fan_mode().map(|m| format!("{:?}", m));
+        properties::add(&mut props, "fan_mode", mode)?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for PowerGuard {}
+
+impl Default for PowerGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<PowerGuardHandle> for PowerGuard {
+    fn from(chan: PowerGuardHandle) -> Self {
+        Self {
+            chan,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for PowerGuard {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetPowerGuard_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_from_event() {
+        assert_eq!(
+            PowerGuardFault::from_event(ErrorEventCode::OverVoltage),
+            Some(PowerGuardFault::OverVoltage)
+        );
+        assert_eq!(
+            PowerGuardFault::from_event(ErrorEventCode::Failsafe),
+            Some(PowerGuardFault::Failsafe)
+        );
+        assert_eq!(PowerGuardFault::from_event(ErrorEventCode::Ok), None);
+        assert_eq!(PowerGuardFault::from_event(ErrorEventCode::Overrun), None);
+    }
+}
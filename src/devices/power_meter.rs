@@ -0,0 +1,221 @@
+// phidget-rs/src/devices/power_meter.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! A power meter, from a current input and a voltage input.
+//!
+//! A [`PowerMeter`] pairs the current and the voltage of a load, like the
+//! readings of a VCP1100 current sensor and a VCP1000 voltage sensor. Each
+//! time either channel reports a new value, it's combined with the latest
+//! value of the other to give the instantaneous power, which is
+//! integrated over time to accumulate the energy used:
+//!
+//! ```rust,no_run
+//! use phidget::{
+//!     devices::{CurrentInput, PowerMeter, VoltageInput},
+//!     Phidget,
+//! };
+//!
+//! let mut cur = CurrentInput::new();
+//! cur.open_wait_default().unwrap();
+//! let mut vin = VoltageInput::new();
+//! vin.open_wait_default().unwrap();
+//!
+//! let mut meter = PowerMeter::from_channels(cur, vin).unwrap();
+//! meter
+//!     .set_on_power_handler(|rd| println!("{:.2} W, {:.4} Wh", rd.power, rd.energy))
+//!     .unwrap();
+//! ```
+
+use crate::{
+    devices::{CurrentInput, VoltageInput},
+    Result,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// A reading from a power meter.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerReading {
+    /// The voltage, in volts
+    pub voltage: f64,
+    /// The current, in amperes
+    pub current: f64,
+    /// The instantaneous power, in watts
+    pub power: f64,
+    /// The energy accumulated since the meter was created or reset, in
+    /// watt-hours
+    pub energy: f64,
+}
+
+// Accumulates the energy from the samples of the two channels.
+#[derive(Debug, Default)]
+struct Integrator {
+    // The latest voltage, if reported
+    voltage: Option<f64>,
+    // The latest current, if reported
+    current: Option<f64>,
+    // The previous reading, and when it was taken
+    last: Option<(Instant, PowerReading)>,
+    // The accumulated energy, in watt-hours
+    energy: f64,
+}
+
+impl Integrator {
+    // Computes a new reading, once both channels have reported a value.
+    // The energy is integrated with the trapezoid rule, which is exact
+    // when the power changes linearly between the samples.
+    fn update(&mut self, now: Instant) -> Option<PowerReading> {
+        let (voltage, current) = (self.voltage?, self.current?);
+        let power = voltage * current;
+
+        if let Some((t, prev)) = self.last {
+            let hours = now.saturating_duration_since(t).as_secs_f64() / 3600.0;
+            self.energy += 0.5 * (prev.power + power) * hours;
+        }
+
+        let rd = PowerReading {
+            voltage,
+            current,
+            power,
+            energy: self.energy,
+        };
+        self.last = Some((now, rd));
+        Some(rd)
+    }
+}
+
+// The handler for the readings
+type PowerCallback = dyn Fn(PowerReading) + Send + 'static;
+
+// The state shared with the channel callbacks
+#[derive(Default)]
+struct Shared {
+    integ: Mutex<Integrator>,
+    cb: Mutex<Option<Box<PowerCallback>>>,
+}
+
+impl Shared {
+    // Updates one of the values, and reports the new reading.
+    fn update(&self, set: impl FnOnce(&mut Integrator)) {
+        let rd = {
+            let mut integ = self.integ.lock().unwrap();
+            set(&mut integ);
+            integ.update(Instant::now())
+        };
+        if let (Some(rd), Some(cb)) = (rd, &*self.cb.lock().unwrap()) {
+            cb(rd);
+        }
+    }
+}
+
+/// A power meter, from a current input and a voltage input.
+pub struct PowerMeter {
+    // The current channel
+    cur: CurrentInput,
+    // The voltage channel
+    volt: VoltageInput,
+    // The state shared with the callbacks
+    shared: Arc<Shared>,
+}
+
+impl PowerMeter {
+    /// Creates a power meter from the channels, which should already be
+    /// open.
+    ///
+    /// This takes over the change handlers of both channels.
+    pub fn from_channels(mut cur: CurrentInput, mut volt: VoltageInput) -> Result<Self> {
+        let shared = Arc::new(Shared::default());
+
+        cur.set_on_current_change_handler({
+            let shared = Arc::clone(&shared);
+            move |_, val| shared.update(|integ| integ.current = Some(val))
+        })?;
+        volt.set_on_voltage_change_handler({
+            let shared = Arc::clone(&shared);
+            move |_, val| shared.update(|integ| integ.voltage = Some(val))
+        })?;
+
+        Ok(Self { cur, volt, shared })
+    }
+
+    /// Gets the current channel, for settings that are specific to it.
+    pub fn current_input(&mut self) -> &mut CurrentInput {
+        &mut self.cur
+    }
+
+    /// Gets the voltage channel, for settings that are specific to it.
+    pub fn voltage_input(&mut self) -> &mut VoltageInput {
+        &mut self.volt
+    }
+
+    /// Gets the instantaneous power, in watts, from the present readings
+    /// of the channels.
+    pub fn power(&self) -> Result<f64> {
+        Ok(self.volt.voltage()? * self.cur.current()?)
+    }
+
+    /// Gets the most recent reading, or `None` if the channels haven't
+    /// both reported a value yet.
+    pub fn reading(&self) -> Option<PowerReading> {
+        self.shared.integ.lock().unwrap().last.map(|(_, rd)| rd)
+    }
+
+    /// Gets the energy accumulated since the meter was created or reset,
+    /// in watt-hours.
+    pub fn energy(&self) -> f64 {
+        self.shared.integ.lock().unwrap().energy
+    }
+
+    /// Resets the accumulated energy to zero.
+    pub fn reset_energy(&self) {
+        self.shared.integ.lock().unwrap().energy = 0.0;
+    }
+
+    /// Sets a handler to receive the readings, each time either channel
+    /// reports a new value.
+    pub fn set_on_power_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(PowerReading) + Send + 'static,
+    {
+        *self.shared.cb.lock().unwrap() = Some(Box::new(cb));
+        Ok(())
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_integrator() {
+        let t = Instant::now();
+        let mut integ = Integrator {
+            voltage: Some(12.0),
+            ..Integrator::default()
+        };
+        assert!(integ.update(t).is_none());
+
+        integ.current = Some(1.0);
+        assert_eq!(integ.update(t).unwrap().power, 12.0);
+
+        // Ramping from 12W to 24W over an hour averages 18W
+        integ.current = Some(2.0);
+        let rd = integ.update(t + Duration::from_secs(3600)).unwrap();
+        assert_eq!(rd.power, 24.0);
+        assert_eq!(rd.energy, 18.0);
+    }
+}
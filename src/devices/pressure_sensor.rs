@@ -0,0 +1,398 @@
+// phidget-rs/src/devices/pressure_sensor.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget Pressure sensor
+//!
+//! Barometric pressure sensors, like the PRE1000, report the pressure in
+//! kPa. Besides weather logging, the main use of these is to estimate the
+//! altitude, which is done with the international barometric formula,
+//! relative to a reference pressure at sea level.
+//!
+//! The standard reference is 101.325 kPa, but the actual sea level
+//! pressure changes with the weather, so for accurate readings the
+//! reference should be set from a local weather report, or calibrated at
+//! a known altitude:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::PressureSensor, Phidget};
+//!
+//! let mut sensor = PressureSensor::new();
+//! sensor.open_wait_default().unwrap();
+//!
+//! sensor.calibrate_altitude(250.0).unwrap();
+//! println!("{:.1} m", sensor.altitude().unwrap());
+//! ```
+
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, Phidget, Result,
+    ReturnCode,
+};
+use phidget_sys::{
+    self as ffi, PhidgetHandle, PhidgetPressureSensorHandle as PressureSensorHandle,
+};
+use std::{mem, os::raw::c_void, ptr};
+
+/// The standard atmospheric pressure at sea level, in kPa.
+pub const STANDARD_SEA_LEVEL_PRESSURE: f64 = 101.325;
+
+// The constants for the international barometric formula
+const ALTITUDE_SCALE: f64 = 44_330.77;
+const ALTITUDE_EXPONENT: f64 = 0.190_263;
+
+/// Estimates the altitude, in meters, from the barometric pressure.
+///
+/// This uses the international barometric formula, relative to the
+/// reference pressure at sea level. The two pressures can be in any units,
+/// as long as they're the same.
+pub fn altitude_from_pressure(p: f64, sea_level_ref: f64) -> f64 {
+    ALTITUDE_SCALE * (1.0 - (p / sea_level_ref).powf(ALTITUDE_EXPONENT))
+}
+
+/// Calculates the sea level reference pressure from the barometric
+/// pressure measured at a known altitude, in meters.
+///
+/// This is the inverse of [`altitude_from_pressure()`].
+pub fn sea_level_pressure(p: f64, altitude: f64) -> f64 {
+    p / (1.0 - altitude / ALTITUDE_SCALE).powf(1.0 / ALTITUDE_EXPONENT)
+}
+
+/// The function signature for the safe Rust pressure change callback.
+pub type PressureCallback = dyn Fn(&PressureSensor, f64) + Send + 'static;
+
+/// Phidget pressure sensor
+pub struct PressureSensor {
+    // Handle to the sensor for the phidget22 library
+    chan: PressureSensorHandle,
+    // Double-boxed PressureCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+    // The reference pressure at sea level, in kPa, for the altitude
+    sea_level_ref: f64,
+}
+
+impl PressureSensor {
+    /// Create a new pressure sensor.
+    pub fn new() -> Self {
+        let mut chan: PressureSensorHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetPressureSensor_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for pressure change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_pressure_change(
+        chan: PressureSensorHandle,
+        ctx: *mut c_void,
+        pressure: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<PressureCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            cb(&sensor, pressure);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &PressureSensorHandle {
+        &self.chan
+    }
+
+    /// Read the current pressure value, in kPa.
+    pub fn pressure(&self) -> Result<f64> {
+        let mut pressure = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPressureSensor_getPressure(self.chan, &mut pressure)
+        })?;
+        Ok(pressure)
+    }
+
+    /// Gets the pressure, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_pressure(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.pressure())
+    }
+
+    /// Gets the minimum pressure that the sensor can measure, in kPa.
+    pub fn min_pressure(&self) -> Result<f64> {
+        let mut pressure = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPressureSensor_getMinPressure(self.chan, &mut pressure)
+        })?;
+        Ok(pressure)
+    }
+
+    /// Gets the maximum pressure that the sensor can measure, in kPa.
+    pub fn max_pressure(&self) -> Result<f64> {
+        let mut pressure = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPressureSensor_getMaxPressure(self.chan, &mut pressure)
+        })?;
+        Ok(pressure)
+    }
+
+    /// Gets the reference pressure at sea level, in kPa, used to
+    /// calculate the altitude.
+    ///
+    /// This defaults to [`STANDARD_SEA_LEVEL_PRESSURE`].
+    pub fn sea_level_pressure(&self) -> f64 {
+        self.sea_level_ref
+    }
+
+    /// Sets the reference pressure at sea level, in kPa, used to
+    /// calculate the altitude.
+    pub fn set_sea_level_pressure(&mut self, p: f64) {
+        self.sea_level_ref = p;
+    }
+
+    /// Sets the reference pressure at sea level from the current pressure,
+    /// taken at a known altitude, in meters.
+    pub fn calibrate_altitude(&mut self, altitude: f64) -> Result<()> {
+        self.sea_level_ref = sea_level_pressure(self.pressure()?, altitude);
+        Ok(())
+    }
+
+    /// Gets the estimated altitude, in meters, from the current pressure.
+    pub fn altitude(&self) -> Result<f64> {
+        Ok(altitude_from_pressure(self.pressure()?, self.sea_level_ref))
+    }
+
+    /// Gets the pressure change trigger.
+    /// Change events are only fired when the pressure changes by at least
+    /// this amount.
+    pub fn pressure_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPressureSensor_getPressureChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the pressure change trigger.
+    pub fn set_pressure_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPressureSensor_setPressureChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum pressure change trigger.
+    pub fn min_pressure_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPressureSensor_getMinPressureChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum pressure change trigger.
+    pub fn max_pressure_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetPressureSensor_getMaxPressureChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and pressure change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_pressure_change_trigger()?,
+                self.max_pressure_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the pressure reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_pressure()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.pressure_change_trigger()?,
+        })
+    }
+
+    /// Sets a handler to receive pressure change callbacks.
+    pub fn set_on_pressure_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&PressureSensor, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<PressureCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        let res = ReturnCode::result(unsafe {
+            ffi::PhidgetPressureSensor_setOnPressureChangeHandler(
+                self.chan,
+                Some(Self::on_pressure_change),
+                ctx,
+            )
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<PressureCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<PressureCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent pressure reading.
+    ///
+    /// This sets the pressure change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_pressure_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for PressureSensor {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PressureSensorHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.pressure_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_pressure_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_pressure_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_pressure_change_trigger()
+    }
+}
+
+unsafe impl Send for PressureSensor {}
+
+impl Default for PressureSensor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<PressureSensorHandle> for PressureSensor {
+    fn from(chan: PressureSensorHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+            sea_level_ref: STANDARD_SEA_LEVEL_PRESSURE,
+        }
+    }
+}
+
+impl Drop for PressureSensor {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetPressureSensor_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<PressureCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_altitude() {
+        let p0 = STANDARD_SEA_LEVEL_PRESSURE;
+        assert_eq!(altitude_from_pressure(p0, p0), 0.0);
+
+        // The standard atmosphere at 1000m
+        let h = altitude_from_pressure(89.876, p0);
+        assert!((h - 1000.0).abs() < 1.0, "{}", h);
+
+        let p0 = sea_level_pressure(89.876, 1000.0);
+        assert!((p0 - STANDARD_SEA_LEVEL_PRESSURE).abs() < 0.01, "{}", p0);
+    }
+}
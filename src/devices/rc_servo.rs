@@ -0,0 +1,664 @@
+// phidget-rs/src/devices/rc_servo.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget RC servo controller channel.
+//!
+//! The position of a servo is normally set in the units of the position
+//! range, which map linearly onto the range of pulse widths. Most servos
+//! are specified by the pulse widths that move them to 0° and 180°, so the
+//! channel can also be positioned in degrees, after calibrating those
+//! endpoints:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::RcServo, Phidget};
+//!
+//! let mut servo = RcServo::new();
+//! servo.open_wait_default().unwrap();
+//!
+//! servo.calibrate_angle(544.0, 2400.0).unwrap();
+//! servo.set_angle(90.0).unwrap();
+//! servo.set_engaged(true).unwrap();
+//! ```
+
+use crate::{
+    devices::Bounds,
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorCode, ErrorEventCode, GenericPhidget,
+    Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetRCServoHandle as RcServoHandle};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The full range of angles for a servo, in degrees
+pub const MAX_ANGLE: f64 = 180.0;
+
+/// The pulse widths, in microseconds, that move a servo to the ends of
+/// its range of angles.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AngleCalibration {
+    /// The pulse width for 0°
+    pub pulse_at_0: f64,
+    /// The pulse width for 180°
+    pub pulse_at_180: f64,
+}
+
+impl AngleCalibration {
+    /// Creates a calibration from the pulse widths for 0° and 180°.
+    ///
+    /// The pulse width for 0° can be the larger one, for a servo that
+    /// turns the other way.
+    pub fn new(pulse_at_0: f64, pulse_at_180: f64) -> Self {
+        Self {
+            pulse_at_0,
+            pulse_at_180,
+        }
+    }
+
+    /// Gets the pulse width for the angle.
+    pub fn pulse_width(&self, angle: f64) -> f64 {
+        scale(
+            angle,
+            (0.0, MAX_ANGLE),
+            (self.pulse_at_0, self.pulse_at_180),
+        )
+    }
+
+    /// Gets the angle for the pulse width.
+    pub fn angle(&self, pulse_width: f64) -> f64 {
+        scale(
+            pulse_width,
+            (self.pulse_at_0, self.pulse_at_180),
+            (0.0, MAX_ANGLE),
+        )
+    }
+}
+
+// Maps a value linearly from one range to another.
+fn scale(val: f64, from: (f64, f64), to: (f64, f64)) -> f64 {
+    to.0 + (val - from.0) * (to.1 - to.0) / (from.1 - from.0)
+}
+
+/// The function type for the safe Rust position change callback.
+pub type PositionChangeCallback = dyn Fn(&RcServo, f64) + Send + 'static;
+/// The function type for the safe Rust velocity change callback.
+pub type VelocityChangeCallback = dyn Fn(&RcServo, f64) + Send + 'static;
+/// The function type for the safe Rust target position reached callback.
+pub type TargetPositionReachedCallback = dyn Fn(&RcServo, f64) + Send + 'static;
+
+/// Phidget RC servo
+pub struct RcServo {
+    // Handle to the servo in the phidget22 library
+    chan: RcServoHandle,
+    // Double-boxed PositionChangeCallback, if registered
+    position_cb: Option<*mut c_void>,
+    // Double-boxed VelocityChangeCallback, if registered
+    velocity_cb: Option<*mut c_void>,
+    // Double-boxed TargetPositionReachedCallback, if registered
+    target_reached_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+    // The calibration for positioning in degrees, if set
+    angle_cal: Option<AngleCalibration>,
+}
+
+impl RcServo {
+    /// Create a new RC servo.
+    pub fn new() -> Self {
+        let mut chan: RcServoHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetRCServo_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    /// Determines if the servo is engaged.
+    pub fn engaged(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getEngaged(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Engages or disengages the servo.
+    ///
+    /// The servo only moves to the target position while it's engaged.
+    pub fn set_engaged(&self, engaged: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_setEngaged(self.chan, c_int::from(engaged))
+        })
+    }
+
+    /// Gets the most recent position reported by the controller.
+    pub fn position(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getPosition(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Determines if the servo is moving towards the target position.
+    pub fn is_moving(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getIsMoving(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Gets the target position.
+    pub fn target_position(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_getTargetPosition(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Sets the position that the servo moves to, in the units of the
+    /// position range.
+    pub fn set_target_position(&self, pos: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_setTargetPosition(self.chan, pos) })
+    }
+
+    /// Gets the most recent velocity reported by the controller, in
+    /// position units per second.
+    pub fn velocity(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getVelocity(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Gets the velocity limit.
+    pub fn velocity_limit(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getVelocityLimit(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the maximum velocity that the servo moves towards the target
+    /// position, in position units per second.
+    ///
+    /// A limit of zero stops the servo where it is.
+    pub fn set_velocity_limit(&self, velocity_limit: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_setVelocityLimit(self.chan, velocity_limit)
+        })
+    }
+
+    /// Gets the range that the velocity limit can be set within.
+    pub fn velocity_limit_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_getMinVelocityLimit(self.chan, &mut min)
+        })?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_getMaxVelocityLimit(self.chan, &mut max)
+        })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Gets the acceleration.
+    pub fn acceleration(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getAcceleration(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the rate at which the servo speeds up and slows down, in
+    /// position units per second squared.
+    ///
+    /// This only applies when speed ramping is enabled.
+    pub fn set_acceleration(&self, acceleration: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_setAcceleration(self.chan, acceleration) })
+    }
+
+    /// Gets the range that the acceleration can be set within.
+    pub fn acceleration_limits(&self) -> Result<Bounds<f64>> {
+        let (mut min, mut max) = (0.0, 0.0);
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getMinAcceleration(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getMaxAcceleration(self.chan, &mut max) })?;
+        Ok(Bounds::new(min, max))
+    }
+
+    /// Determines if speed ramping is enabled.
+    pub fn speed_ramping(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_getSpeedRampingState(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables speed ramping.
+    ///
+    /// When enabled, the servo accelerates up to the velocity limit.
+    /// Otherwise it moves at the velocity limit immediately.
+    pub fn set_speed_ramping(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_setSpeedRampingState(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Enables the failsafe for the channel with the specified timeout.
+    ///
+    /// Once enabled, the failsafe must be reset within the time, or the
+    /// servo is disengaged.
+    pub fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        let ms = u32::try_from(time.as_millis()).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_enableFailsafe(self.chan, ms) })
+    }
+
+    /// Resets the failsafe timer.
+    pub fn reset_failsafe(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_resetFailsafe(self.chan) })
+    }
+
+    /// Gets the range of the failsafe time.
+    pub fn failsafe_time_limits(&self) -> Result<Bounds<Duration>> {
+        let (mut min, mut max) = (0, 0);
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getMinFailsafeTime(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getMaxFailsafeTime(self.chan, &mut max) })?;
+        Ok(Bounds::new(
+            Duration::from_millis(min.into()),
+            Duration::from_millis(max.into()),
+        ))
+    }
+
+    /// Gets the position that maps to the minimum pulse width.
+    pub fn min_position(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getMinPosition(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the position that maps to the minimum pulse width.
+    pub fn set_min_position(&self, pos: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_setMinPosition(self.chan, pos) })
+    }
+
+    /// Gets the position that maps to the maximum pulse width.
+    pub fn max_position(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getMaxPosition(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the position that maps to the maximum pulse width.
+    pub fn set_max_position(&self, pos: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_setMaxPosition(self.chan, pos) })
+    }
+
+    /// Gets the minimum pulse width, in microseconds.
+    pub fn min_pulse_width(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getMinPulseWidth(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the minimum pulse width, in microseconds.
+    pub fn set_min_pulse_width(&self, pulse_width: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_setMinPulseWidth(self.chan, pulse_width) })
+    }
+
+    /// Gets the maximum pulse width, in microseconds.
+    pub fn max_pulse_width(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_getMaxPulseWidth(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Sets the maximum pulse width, in microseconds.
+    pub fn set_max_pulse_width(&self, pulse_width: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetRCServo_setMaxPulseWidth(self.chan, pulse_width) })
+    }
+
+    /// Gets the lowest value that the minimum pulse width can be set to.
+    pub fn min_pulse_width_limit(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_getMinPulseWidthLimit(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Gets the highest value that the maximum pulse width can be set to.
+    pub fn max_pulse_width_limit(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_getMaxPulseWidthLimit(self.chan, &mut value)
+        })?;
+        Ok(value)
+    }
+
+    /// Gets the range that the pulse widths can be set within.
+    pub fn pulse_width_limits(&self) -> Result<Bounds<f64>> {
+        Ok(Bounds::new(
+            self.min_pulse_width_limit()?,
+            self.max_pulse_width_limit()?,
+        ))
+    }
+
+    /// Sets the pulse widths, in microseconds, that move the servo to 0°
+    /// and 180°, for positioning it in degrees.
+    ///
+    /// This also sets the minimum and maximum pulse widths to the
+    /// calibrated ones, so the servo can't be driven past its endpoints.
+    pub fn calibrate_angle(&mut self, pulse_at_0: f64, pulse_at_180: f64) -> Result<()> {
+        let limits = self.pulse_width_limits()?;
+        if pulse_at_0 == pulse_at_180
+            || !limits.contains(pulse_at_0)
+            || !limits.contains(pulse_at_180)
+        {
+            return Err(ErrorCode::InvalidArg);
+        }
+        // The minimum can't be set above the current maximum
+        let (min, max) = (pulse_at_0.min(pulse_at_180), pulse_at_0.max(pulse_at_180));
+        if min >= self.max_pulse_width()? {
+            self.set_max_pulse_width(max)?;
+            self.set_min_pulse_width(min)?;
+        }
+        else {
+            self.set_min_pulse_width(min)?;
+            self.set_max_pulse_width(max)?;
+        }
+        self.angle_cal = Some(AngleCalibration::new(pulse_at_0, pulse_at_180));
+        Ok(())
+    }
+
+    /// Gets the calibration for positioning the servo in degrees.
+    ///
+    /// If the angle wasn't calibrated, this is the minimum pulse width at
+    /// 0° and the maximum at 180°.
+    pub fn angle_calibration(&self) -> Result<AngleCalibration> {
+        match self.angle_cal {
+            Some(cal) => Ok(cal),
+            None => Ok(AngleCalibration::new(
+                self.min_pulse_width()?,
+                self.max_pulse_width()?,
+            )),
+        }
+    }
+
+    // Gets the ranges of the pulse widths and the positions.
+    fn position_scale(&self) -> Result<((f64, f64), (f64, f64))> {
+        Ok((
+            (self.min_pulse_width()?, self.max_pulse_width()?),
+            (self.min_position()?, self.max_position()?),
+        ))
+    }
+
+    /// Sets the target position as an angle, in degrees, from 0 to 180.
+    pub fn set_angle(&self, angle: f64) -> Result<()> {
+        if !(0.0..=MAX_ANGLE).contains(&angle) {
+            return Err(ErrorCode::InvalidArg);
+        }
+        let pulse_width = self.angle_calibration()?.pulse_width(angle);
+        let (pulse, pos) = self.position_scale()?;
+        self.set_target_position(scale(pulse_width, pulse, pos))
+    }
+
+    /// Gets the most recent position reported by the controller, as an
+    /// angle in degrees.
+    pub fn angle(&self) -> Result<f64> {
+        let (pulse, pos) = self.position_scale()?;
+        let pulse_width = scale(self.position()?, pos, pulse);
+        Ok(self.angle_calibration()?.angle(pulse_width))
+    }
+
+    // Low-level, unsafe, callback for position change events.
+    // The context is a double-boxed pointer to the safe Rust callback.
+    unsafe extern "C" fn on_position_change(chan: RcServoHandle, ctx: *mut c_void, pos: f64) {
+        if !ctx.is_null() {
+            let cb: &mut Box<PositionChangeCallback> = &mut *(ctx as *mut _);
+            let servo = Self::from(chan);
+            cb(&servo, pos);
+            mem::forget(servo);
+        }
+    }
+
+    // Low-level, unsafe, callback for velocity change events.
+    unsafe extern "C" fn on_velocity_change(chan: RcServoHandle, ctx: *mut c_void, vel: f64) {
+        if !ctx.is_null() {
+            let cb: &mut Box<VelocityChangeCallback> = &mut *(ctx as *mut _);
+            let servo = Self::from(chan);
+            cb(&servo, vel);
+            mem::forget(servo);
+        }
+    }
+
+    // Low-level, unsafe, callback for target position reached events.
+    unsafe extern "C" fn on_target_position_reached(
+        chan: RcServoHandle,
+        ctx: *mut c_void,
+        pos: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<TargetPositionReachedCallback> = &mut *(ctx as *mut _);
+            let servo = Self::from(chan);
+            cb(&servo, pos);
+            mem::forget(servo);
+        }
+    }
+
+    /// Sets a handler to receive position change callbacks.
+    pub fn set_on_position_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&RcServo, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<PositionChangeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.position_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_setOnPositionChangeHandler(
+                self.chan,
+                Some(Self::on_position_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive velocity change callbacks.
+    pub fn set_on_velocity_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&RcServo, f64) + Send + 'static,
+    {
+        let cb: Box<Box<VelocityChangeCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.velocity_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_setOnVelocityChangeHandler(
+                self.chan,
+                Some(Self::on_velocity_change),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler that is called when the servo reaches the target
+    /// position.
+    pub fn set_on_target_position_reached_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&RcServo, f64) + Send + 'static,
+    {
+        let cb: Box<Box<TargetPositionReachedCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.target_reached_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRCServo_setOnTargetPositionReachedHandler(
+                self.chan,
+                Some(Self::on_target_position_reached),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for RcServo {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as RcServoHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add(&mut props, "engaged", self.engaged())?;
+        properties::add_ranged(
+            &mut props,
+            "target_position",
+            self.target_position(),
+            self.min_position(),
+            self.max_position(),
+        )?;
+        let lim = self.velocity_limit_limits();
+        properties::add_ranged(
+            &mut props,
+            "velocity_limit",
+            self.velocity_limit(),
+            lim.map(|lim| lim.min),
+            lim.map(|lim| lim.max),
+        )?;
+        properties::add_ranged(
+            &mut props,
+            "min_pulse_width",
+            self.min_pulse_width(),
+            self.min_pulse_width_limit(),
+            self.max_pulse_width_limit(),
+        )?;
+        properties::add_ranged(
+            &mut props,
+            "max_pulse_width",
+            self.max_pulse_width(),
+            self.min_pulse_width_limit(),
+            self.max_pulse_width_limit(),
+        )?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for RcServo {}
+
+impl Default for RcServo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<RcServoHandle> for RcServo {
+    fn from(chan: RcServoHandle) -> Self {
+        Self {
+            chan,
+            position_cb: None,
+            velocity_cb: None,
+            target_reached_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+            angle_cal: None,
+        }
+    }
+}
+
+impl Drop for RcServo {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetRCServo_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<PositionChangeCallback>(self.position_cb.take());
+        crate::drop_cb::<VelocityChangeCallback>(self.velocity_cb.take());
+        crate::drop_cb::<TargetPositionReachedCallback>(self.target_reached_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_angle_calibration() {
+        let cal = AngleCalibration::new(544.0, 2400.0);
+        assert_eq!(cal.pulse_width(0.0), 544.0);
+        assert_eq!(cal.pulse_width(90.0), 1472.0);
+        assert_eq!(cal.angle(2400.0), 180.0);
+
+        // A servo that turns the other way
+        let cal = AngleCalibration::new(2000.0, 1000.0);
+        assert_eq!(cal.pulse_width(45.0), 1750.0);
+        assert_eq!(cal.angle(1000.0), 180.0);
+    }
+}
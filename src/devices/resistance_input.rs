@@ -0,0 +1,381 @@
+// phidget-rs/src/devices/resistance_input.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget resistance input
+//!
+//! Resistance inputs, like the channels of the TMP1200 RTD Phidget,
+//! measure the resistance of a sensor, in ohms. This gives access to the
+//! raw resistance of an RTD, or any other resistive sensor. The
+//! resistance is reported when it changes by at least the change
+//! trigger:
+//!
+//! ```rust,no_run
+//! use phidget::{
+//!     devices::{ResistanceInput, RtdWireSetup},
+//!     Phidget,
+//! };
+//!
+//! let mut input = ResistanceInput::new();
+//! input
+//!     .set_on_resistance_change_handler(|_, ohms| {
+//!         println!("{:.2} Ω", ohms);
+//!     })
+//!     .unwrap();
+//! input.open_wait_default().unwrap();
+//! input.set_rtd_wire_setup(RtdWireSetup::FourWire).unwrap();
+//! ```
+
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    properties::{self, Properties},
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{
+    self as ffi, PhidgetHandle, PhidgetResistanceInputHandle as ResistanceInputHandle,
+};
+use std::{mem, os::raw::c_void, ptr};
+
+/// The wiring of an RTD
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum RtdWireSetup {
+    /// Two wires, with no lead compensation
+    TwoWire = ffi::Phidget_RTDWireSetup_RTD_WIRE_SETUP_2WIRE, // 1
+    /// Three wires
+    ThreeWire = ffi::Phidget_RTDWireSetup_RTD_WIRE_SETUP_3WIRE, // 2
+    /// Four wires, for the most accurate readings
+    FourWire = ffi::Phidget_RTDWireSetup_RTD_WIRE_SETUP_4WIRE, // 3
+}
+
+impl TryFrom<u32> for RtdWireSetup {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use RtdWireSetup::*;
+        match val {
+            ffi::Phidget_RTDWireSetup_RTD_WIRE_SETUP_2WIRE => Ok(TwoWire), // 1
+            ffi::Phidget_RTDWireSetup_RTD_WIRE_SETUP_3WIRE => Ok(ThreeWire), // 2
+            ffi::Phidget_RTDWireSetup_RTD_WIRE_SETUP_4WIRE => Ok(FourWire), // 3
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// The function signature for the safe Rust resistance change callback.
+pub type ResistanceCallback = dyn Fn(&ResistanceInput, f64) + Send + 'static;
+
+/// Phidget resistance input
+pub struct ResistanceInput {
+    // Handle to the sensor for the phidget22 library
+    chan: ResistanceInputHandle,
+    // Double-boxed ResistanceCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl ResistanceInput {
+    /// Create a new resistance input.
+    pub fn new() -> Self {
+        let mut chan: ResistanceInputHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetResistanceInput_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for resistance change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_resistance_change(
+        chan: ResistanceInputHandle,
+        ctx: *mut c_void,
+        resistance: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<ResistanceCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            cb(&sensor, resistance);
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &ResistanceInputHandle {
+        &self.chan
+    }
+
+    /// Read the current resistance, in ohms.
+    pub fn resistance(&self) -> Result<f64> {
+        let mut resistance = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_getResistance(self.chan, &mut resistance)
+        })?;
+        Ok(resistance)
+    }
+
+    /// Gets the resistance, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_resistance(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.resistance())
+    }
+
+    /// Gets the minimum resistance that the sensor can measure, in ohms.
+    pub fn min_resistance(&self) -> Result<f64> {
+        let mut resistance = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_getMinResistance(self.chan, &mut resistance)
+        })?;
+        Ok(resistance)
+    }
+
+    /// Gets the maximum resistance that the sensor can measure, in ohms.
+    pub fn max_resistance(&self) -> Result<f64> {
+        let mut resistance = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_getMaxResistance(self.chan, &mut resistance)
+        })?;
+        Ok(resistance)
+    }
+
+    /// Gets the wiring of the RTD.
+    pub fn rtd_wire_setup(&self) -> Result<RtdWireSetup> {
+        let mut setup = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_getRTDWireSetup(self.chan, &mut setup)
+        })?;
+        RtdWireSetup::try_from(setup)
+    }
+
+    /// Sets the wiring of the RTD, to match the number of wires that
+    /// connect it.
+    ///
+    /// More wires cancel more of the resistance of the leads.
+    pub fn set_rtd_wire_setup(&self, setup: RtdWireSetup) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_setRTDWireSetup(self.chan, setup as u32)
+        })
+    }
+
+    /// Gets the resistance change trigger.
+    /// Change events are only fired when the resistance changes by at least
+    /// this amount.
+    pub fn resistance_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_getResistanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the resistance change trigger.
+    pub fn set_resistance_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_setResistanceChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum resistance change trigger.
+    pub fn min_resistance_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_getMinResistanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum resistance change trigger.
+    pub fn max_resistance_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_getMaxResistanceChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and resistance change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_resistance_change_trigger()?,
+                self.max_resistance_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the resistance reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_resistance()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.resistance_change_trigger()?,
+        })
+    }
+
+    /// Sets a handler to receive resistance change callbacks.
+    pub fn set_on_resistance_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&ResistanceInput, f64) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<ResistanceCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        let res = ReturnCode::result(unsafe {
+            ffi::PhidgetResistanceInput_setOnResistanceChangeHandler(
+                self.chan,
+                Some(Self::on_resistance_change),
+                ctx,
+            )
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<ResistanceCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<ResistanceCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent resistance reading.
+    ///
+    /// This sets the resistance change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_resistance_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for ResistanceInput {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as ResistanceInputHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.resistance_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_resistance_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_resistance_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_resistance_change_trigger()
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        let setup = self.rtd_wire_setup().map(|s| format!("{:?}", s));
+        properties::add(&mut props, "rtd_wire_setup", setup)?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for ResistanceInput {}
+
+impl Default for ResistanceInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<ResistanceInputHandle> for ResistanceInput {
+    fn from(chan: ResistanceInputHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for ResistanceInput {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetResistanceInput_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<ResistanceCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
@@ -0,0 +1,330 @@
+// phidget-rs/src/devices/rfid.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget RFID reader channel, like the 1024 PhidgetRFID Read-Write.
+//!
+//! The reader reports each tag as it comes into range of the antenna,
+//! and again when it's lost:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::Rfid, Phidget};
+//!
+//! let mut rfid = Rfid::new();
+//! rfid.set_on_tag_handler(|_, tag, protocol| {
+//!     println!("Tag: {} ({:?})", tag, protocol);
+//! })
+//! .unwrap();
+//! rfid.set_on_tag_lost_handler(|_, tag, _| {
+//!     println!("Lost: {}", tag);
+//! })
+//! .unwrap();
+//! rfid.open_wait_default().unwrap();
+//! rfid.set_antenna_enabled(true).unwrap();
+//! ```
+
+use crate::{
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetRFIDHandle as RfidHandle};
+use std::{
+    ffi::{CStr, CString},
+    mem,
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+};
+
+/// The size of a buffer that can hold the string of any tag
+const TAG_BUF_LEN: usize = 64;
+
+/// The protocol of an RFID tag
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum RfidProtocol {
+    /// EM4100 (EM4102) 40-bit
+    #[default]
+    Em4100 = ffi::PhidgetRFID_Protocol_PROTOCOL_EM4100, // 1
+    /// ISO11785 FDX-B encoding (animal ID)
+    Iso11785FdxB = ffi::PhidgetRFID_Protocol_PROTOCOL_ISO11785_FDX_B, // 2
+    /// PhidgetTAG protocol, holding up to 24 ASCII characters
+    Phidgets = ffi::PhidgetRFID_Protocol_PROTOCOL_PHIDGETS, // 3
+}
+
+impl TryFrom<u32> for RfidProtocol {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use RfidProtocol::*;
+        match val {
+            ffi::PhidgetRFID_Protocol_PROTOCOL_EM4100 => Ok(Em4100), // 1
+            ffi::PhidgetRFID_Protocol_PROTOCOL_ISO11785_FDX_B => Ok(Iso11785FdxB), // 2
+            ffi::PhidgetRFID_Protocol_PROTOCOL_PHIDGETS => Ok(Phidgets), // 3
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// The function type for the safe Rust tag callback.
+///
+/// This receives the tag string and its protocol.
+pub type TagCallback = dyn Fn(&Rfid, &str, RfidProtocol) + Send + 'static;
+/// The function type for the safe Rust tag lost callback.
+pub type TagLostCallback = dyn Fn(&Rfid, &str, RfidProtocol) + Send + 'static;
+
+/// Phidget RFID reader
+pub struct Rfid {
+    // Handle to the reader in the phidget22 library
+    chan: RfidHandle,
+    // Double-boxed TagCallback, if registered
+    tag_cb: Option<*mut c_void>,
+    // Double-boxed TagLostCallback, if registered
+    tag_lost_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Rfid {
+    /// Create a new RFID reader.
+    pub fn new() -> Self {
+        let mut chan: RfidHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetRFID_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for tag events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_tag(
+        chan: RfidHandle,
+        ctx: *mut c_void,
+        tag: *const c_char,
+        protocol: u32,
+    ) {
+        if !ctx.is_null() && !tag.is_null() {
+            let cb: &mut Box<TagCallback> = &mut *(ctx as *mut _);
+            let rfid = Self::from(chan);
+            let tag = CStr::from_ptr(tag).to_string_lossy();
+            if let Ok(protocol) = RfidProtocol::try_from(protocol) {
+                cb(&rfid, &tag, protocol);
+            }
+            mem::forget(rfid);
+        }
+    }
+
+    // Low-level, unsafe, callback for tag lost events.
+    unsafe extern "C" fn on_tag_lost(
+        chan: RfidHandle,
+        ctx: *mut c_void,
+        tag: *const c_char,
+        protocol: u32,
+    ) {
+        if !ctx.is_null() && !tag.is_null() {
+            let cb: &mut Box<TagLostCallback> = &mut *(ctx as *mut _);
+            let rfid = Self::from(chan);
+            let tag = CStr::from_ptr(tag).to_string_lossy();
+            if let Ok(protocol) = RfidProtocol::try_from(protocol) {
+                cb(&rfid, &tag, protocol);
+            }
+            mem::forget(rfid);
+        }
+    }
+
+    /// Get a reference to the underlying reader handle
+    pub fn as_channel(&self) -> &RfidHandle {
+        &self.chan
+    }
+
+    /// Determines if the antenna is enabled.
+    pub fn antenna_enabled(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetRFID_getAntennaEnabled(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables the antenna.
+    ///
+    /// Tags can only be read or written while the antenna is enabled.
+    pub fn set_antenna_enabled(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRFID_setAntennaEnabled(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Determines if a tag is currently in range of the reader.
+    pub fn tag_present(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetRFID_getTagPresent(self.chan, &mut value) })?;
+        Ok(value != 0)
+    }
+
+    /// Gets the most recent tag that was read, and its protocol.
+    ///
+    /// This remains available after the tag is taken away from the reader.
+    pub fn last_tag(&self) -> Result<(String, RfidProtocol)> {
+        let mut buf = [0 as c_char; TAG_BUF_LEN];
+        let mut protocol = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRFID_getLastTag(self.chan, buf.as_mut_ptr(), buf.len(), &mut protocol)
+        })?;
+        let tag = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        Ok((
+            tag.to_string_lossy().into_owned(),
+            RfidProtocol::try_from(protocol)?,
+        ))
+    }
+
+    /// Writes a tag string to a writable tag that's in range, using the
+    /// specified protocol.
+    ///
+    /// If `lock` is true, the tag can never be written again.
+    pub fn write(&self, tag: &str, protocol: RfidProtocol, lock: bool) -> Result<()> {
+        let tag = CString::new(tag).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRFID_write(self.chan, tag.as_ptr(), protocol as u32, c_int::from(lock))
+        })
+    }
+
+    /// Sets a handler to receive the tags as they come into range.
+    pub fn set_on_tag_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Rfid, &str, RfidProtocol) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<TagCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.tag_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRFID_setOnTagHandler(self.chan, Some(Self::on_tag), ctx)
+        })
+    }
+
+    /// Sets a handler to receive the tags as they go out of range.
+    pub fn set_on_tag_lost_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Rfid, &str, RfidProtocol) + Send + 'static,
+    {
+        let cb: Box<Box<TagLostCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.tag_lost_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetRFID_setOnTagLostHandler(self.chan, Some(Self::on_tag_lost), ctx)
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for Rfid {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as RfidHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add(&mut props, "antenna_enabled", self.antenna_enabled())?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for Rfid {}
+
+impl Default for Rfid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<RfidHandle> for Rfid {
+    fn from(chan: RfidHandle) -> Self {
+        Self {
+            chan,
+            tag_cb: None,
+            tag_lost_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for Rfid {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetRFID_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<TagCallback>(self.tag_cb.take());
+        crate::drop_cb::<TagLostCallback>(self.tag_lost_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
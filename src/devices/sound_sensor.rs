@@ -0,0 +1,405 @@
+// phidget-rs/src/devices/sound_sensor.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget sound sensor
+//!
+//! Sound sensors, like the SND1000, report the sound pressure level (SPL)
+//! in decibels, unweighted as well as with the A and C weightings, along
+//! with the level in each octave band. The levels are reported together
+//! when the SPL changes by at least the change trigger:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::SoundSensor, Phidget};
+//!
+//! let mut sensor = SoundSensor::new();
+//! sensor.set_on_spl_change_handler(|_, spl| {
+//!     println!("{:.1} dBA", spl.dba);
+//! })
+//! .unwrap();
+//! sensor.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetSoundSensorHandle as SoundSensorHandle};
+use std::{mem, os::raw::c_void, ptr};
+
+/// The center frequencies of the octave bands, in Hz.
+pub const OCTAVE_FREQUENCIES: [f64; 10] = [
+    31.5, 63.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+
+/// The range of sound pressure levels that the sensor measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum SplRange {
+    /// Up to 102 dB
+    Db102 = ffi::PhidgetSoundSensor_SPLRange_SPL_RANGE_102dB, // 1
+    /// Up to 82 dB, with more sensitivity to quiet sounds
+    Db82 = ffi::PhidgetSoundSensor_SPLRange_SPL_RANGE_82dB, // 2
+}
+
+impl TryFrom<u32> for SplRange {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use SplRange::*;
+        match val {
+            ffi::PhidgetSoundSensor_SPLRange_SPL_RANGE_102dB => Ok(Db102), // 1
+            ffi::PhidgetSoundSensor_SPLRange_SPL_RANGE_82dB => Ok(Db82),   // 2
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// A reading of the sound pressure level, in decibels.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplReading {
+    /// The unweighted level
+    pub db: f64,
+    /// The A-weighted level, which approximates human hearing
+    pub dba: f64,
+    /// The C-weighted level, which is flatter for loud sounds
+    pub dbc: f64,
+    /// The unweighted level in each of the octave bands, centered on the
+    /// frequencies in [`OCTAVE_FREQUENCIES`].
+    pub octaves: [f64; 10],
+}
+
+/// The function signature for the safe Rust SPL change callback.
+pub type SplCallback = dyn Fn(&SoundSensor, SplReading) + Send + 'static;
+
+/// Phidget sound sensor
+pub struct SoundSensor {
+    // Handle to the sensor for the phidget22 library
+    chan: SoundSensorHandle,
+    // Double-boxed SplCallback, if registered
+    cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl SoundSensor {
+    /// Create a new sound sensor.
+    pub fn new() -> Self {
+        let mut chan: SoundSensorHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetSoundSensor_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for SPL change events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_spl_change(
+        chan: SoundSensorHandle,
+        ctx: *mut c_void,
+        db: f64,
+        dba: f64,
+        dbc: f64,
+        octaves: *const f64,
+    ) {
+        if !ctx.is_null() && !octaves.is_null() {
+            let cb: &mut Box<SplCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            let octaves = *(octaves as *const [f64; 10]);
+            cb(
+                &sensor,
+                SplReading {
+                    db,
+                    dba,
+                    dbc,
+                    octaves,
+                },
+            );
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &SoundSensorHandle {
+        &self.chan
+    }
+
+    /// Read the current unweighted sound pressure level, in dB.
+    pub fn db(&self) -> Result<f64> {
+        let mut db = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetSoundSensor_getdB(self.chan, &mut db) })?;
+        Ok(db)
+    }
+
+    /// Gets the unweighted sound pressure level, or `None` if the channel
+    /// is not attached or doesn't have a valid reading yet.
+    pub fn try_db(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.db())
+    }
+
+    /// Gets the maximum sound pressure level that the sensor can measure,
+    /// in dB.
+    ///
+    /// This depends on the SPL range.
+    pub fn max_db(&self) -> Result<f64> {
+        let mut db = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetSoundSensor_getMaxdB(self.chan, &mut db) })?;
+        Ok(db)
+    }
+
+    /// Read the current A-weighted sound pressure level, in dBA.
+    pub fn dba(&self) -> Result<f64> {
+        let mut db = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetSoundSensor_getdBA(self.chan, &mut db) })?;
+        Ok(db)
+    }
+
+    /// Read the current C-weighted sound pressure level, in dBC.
+    pub fn dbc(&self) -> Result<f64> {
+        let mut db = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetSoundSensor_getdBC(self.chan, &mut db) })?;
+        Ok(db)
+    }
+
+    /// Gets the noise floor of the sensor, in dB.
+    ///
+    /// Levels below this aren't accurate.
+    pub fn noise_floor(&self) -> Result<f64> {
+        let mut db = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetSoundSensor_getNoiseFloor(self.chan, &mut db) })?;
+        Ok(db)
+    }
+
+    /// Read the current sound pressure level in each of the octave bands,
+    /// in dB.
+    pub fn octaves(&self) -> Result<[f64; 10]> {
+        let mut octaves = [0.0; 10];
+        ReturnCode::result(unsafe { ffi::PhidgetSoundSensor_getOctaves(self.chan, &mut octaves) })?;
+        Ok(octaves)
+    }
+
+    /// Read all the current sound pressure levels.
+    pub fn spl(&self) -> Result<SplReading> {
+        Ok(SplReading {
+            db: self.db()?,
+            dba: self.dba()?,
+            dbc: self.dbc()?,
+            octaves: self.octaves()?,
+        })
+    }
+
+    /// Gets the range of sound pressure levels that the sensor measures.
+    pub fn spl_range(&self) -> Result<SplRange> {
+        let mut range = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetSoundSensor_getSPLRange(self.chan, &mut range) })?;
+        SplRange::try_from(range)
+    }
+
+    /// Sets the range of sound pressure levels that the sensor measures.
+    pub fn set_spl_range(&self, range: SplRange) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetSoundSensor_setSPLRange(self.chan, range as u32) })
+    }
+
+    /// Gets the SPL change trigger, in dB.
+    /// Change events are only fired when the unweighted level changes by
+    /// at least this amount.
+    pub fn spl_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSoundSensor_getSPLChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the SPL change trigger, in dB.
+    pub fn set_spl_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSoundSensor_setSPLChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum SPL change trigger.
+    pub fn min_spl_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSoundSensor_getMinSPLChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum SPL change trigger.
+    pub fn max_spl_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSoundSensor_getMaxSPLChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and SPL change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_spl_change_trigger()?,
+                self.max_spl_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the unweighted level and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_db()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.spl_change_trigger()?,
+        })
+    }
+
+    /// Sets a handler to receive SPL change callbacks.
+    pub fn set_on_spl_change_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&SoundSensor, SplReading) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<SplCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSoundSensor_setOnSPLChangeHandler(self.chan, Some(Self::on_spl_change), ctx)
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for SoundSensor {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as SoundSensorHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.spl_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_spl_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_spl_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_spl_change_trigger()
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        let range = self.spl_range().map(|r| format!("{:?}", r));
+        properties::add(&mut props, "spl_range", range)?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for SoundSensor {}
+
+impl Default for SoundSensor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<SoundSensorHandle> for SoundSensor {
+    fn from(chan: SoundSensorHandle) -> Self {
+        Self {
+            chan,
+            cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for SoundSensor {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetSoundSensor_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<SplCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
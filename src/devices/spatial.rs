@@ -0,0 +1,503 @@
+// phidget-rs/src/devices/spatial.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Phidget spatial (IMU) channel.
+//!
+//! The spatial channel of a Phidget like the MOT0109 or MOT0110 combines
+//! the readings of its accelerometer, gyroscope, and magnetometer into a
+//! single data event. The device can also fuse the readings into an
+//! orientation, using an AHRS or IMU algorithm, which is reported as a
+//! quaternion:
+//!
+//! ```rust,no_run
+//! use phidget::{
+//!     devices::{Spatial, SpatialAlgorithm},
+//!     Phidget,
+//! };
+//!
+//! let mut imu = Spatial::new();
+//! imu.open_wait_default().unwrap();
+//!
+//! imu.set_algorithm(SpatialAlgorithm::Ahrs).unwrap();
+//! imu.set_on_algorithm_data_handler(|imu, _q, _ts| {
+//!     if let Ok(ang) = imu.euler_angles() {
+//!         println!("heading: {:.1}°", ang.heading);
+//!     }
+//! })
+//! .unwrap();
+//! ```
+
+use crate::{
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetSpatialHandle as SpatialHandle};
+use std::{
+    mem,
+    os::raw::{c_int, c_void},
+    ptr,
+    time::Duration,
+};
+
+/// The algorithm used to fuse the sensor readings into an orientation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum SpatialAlgorithm {
+    /// No orientation is computed
+    None = ffi::Phidget_SpatialAlgorithm_SPATIAL_ALGORITHM_NONE, // 0
+    /// Attitude and heading, from all three sensors
+    Ahrs = ffi::Phidget_SpatialAlgorithm_SPATIAL_ALGORITHM_AHRS, // 1
+    /// Attitude only, from the accelerometer and gyroscope
+    Imu = ffi::Phidget_SpatialAlgorithm_SPATIAL_ALGORITHM_IMU, // 2
+}
+
+impl TryFrom<u32> for SpatialAlgorithm {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use SpatialAlgorithm::*;
+        match val {
+            ffi::Phidget_SpatialAlgorithm_SPATIAL_ALGORITHM_NONE => Ok(None), // 0
+            ffi::Phidget_SpatialAlgorithm_SPATIAL_ALGORITHM_AHRS => Ok(Ahrs), // 1
+            ffi::Phidget_SpatialAlgorithm_SPATIAL_ALGORITHM_IMU => Ok(Imu),   // 2
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// An orientation, as a unit quaternion.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quaternion {
+    /// The x component
+    pub x: f64,
+    /// The y component
+    pub y: f64,
+    /// The z component
+    pub z: f64,
+    /// The w (scalar) component
+    pub w: f64,
+}
+
+impl From<ffi::PhidgetSpatial_SpatialQuaternion> for Quaternion {
+    fn from(q: ffi::PhidgetSpatial_SpatialQuaternion) -> Self {
+        Self {
+            x: q.x,
+            y: q.y,
+            z: q.z,
+            w: q.w,
+        }
+    }
+}
+
+/// An orientation, as angles in degrees.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EulerAngles {
+    /// The pitch
+    pub pitch: f64,
+    /// The roll
+    pub roll: f64,
+    /// The heading
+    pub heading: f64,
+}
+
+impl From<ffi::PhidgetSpatial_SpatialEulerAngles> for EulerAngles {
+    fn from(ang: ffi::PhidgetSpatial_SpatialEulerAngles) -> Self {
+        Self {
+            pitch: ang.pitch,
+            roll: ang.roll,
+            heading: ang.heading,
+        }
+    }
+}
+
+/// The combined readings of the sensors, from a spatial data event.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpatialData {
+    /// The acceleration along each axis, in g
+    pub acceleration: [f64; 3],
+    /// The angular rate around each axis, in degrees per second
+    pub angular_rate: [f64; 3],
+    /// The magnetic field strength along each axis, in gauss
+    pub magnetic_field: [f64; 3],
+    /// The timestamp of the readings
+    pub timestamp: Duration,
+}
+
+/// The function type for the safe Rust spatial data callback.
+pub type SpatialDataCallback = dyn Fn(&Spatial, SpatialData) + Send + 'static;
+/// The function type for the safe Rust algorithm data callback.
+pub type AlgorithmDataCallback = dyn Fn(&Spatial, Quaternion, Duration) + Send + 'static;
+
+// Reads one of the three-axis readings from the library.
+unsafe fn axes(p: *const f64) -> [f64; 3] {
+    if p.is_null() {
+        [0.0; 3]
+    }
+    else {
+        *(p as *const [f64; 3])
+    }
+}
+
+// Converts a timestamp from the library, in milliseconds.
+fn timestamp(ms: f64) -> Duration {
+    Duration::from_secs_f64(ms.max(0.0) / 1000.0)
+}
+
+/// Phidget spatial
+pub struct Spatial {
+    // Handle to the sensor in the phidget22 library
+    chan: SpatialHandle,
+    // Double-boxed SpatialDataCallback, if registered
+    data_cb: Option<*mut c_void>,
+    // Double-boxed AlgorithmDataCallback, if registered
+    algorithm_cb: Option<*mut c_void>,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Spatial {
+    /// Create a new spatial channel.
+    pub fn new() -> Self {
+        let mut chan: SpatialHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetSpatial_create(&mut chan);
+        }
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    // Low-level, unsafe, callback for spatial data events.
+    // The context is a double-boxed pointer the the safe Rust callback.
+    unsafe extern "C" fn on_spatial_data(
+        chan: SpatialHandle,
+        ctx: *mut c_void,
+        acceleration: *const f64,
+        angular_rate: *const f64,
+        magnetic_field: *const f64,
+        ts: f64,
+    ) {
+        if !ctx.is_null() {
+            let cb: &mut Box<SpatialDataCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            let data = SpatialData {
+                acceleration: axes(acceleration),
+                angular_rate: axes(angular_rate),
+                magnetic_field: axes(magnetic_field),
+                timestamp: timestamp(ts),
+            };
+            cb(&sensor, data);
+            mem::forget(sensor);
+        }
+    }
+
+    // Low-level, unsafe, callback for algorithm data events.
+    unsafe extern "C" fn on_algorithm_data(
+        chan: SpatialHandle,
+        ctx: *mut c_void,
+        quaternion: *const f64,
+        ts: f64,
+    ) {
+        if !ctx.is_null() && !quaternion.is_null() {
+            let cb: &mut Box<AlgorithmDataCallback> = &mut *(ctx as *mut _);
+            let sensor = Self::from(chan);
+            let [x, y, z, w] = *(quaternion as *const [f64; 4]);
+            cb(&sensor, Quaternion { x, y, z, w }, timestamp(ts));
+            mem::forget(sensor);
+        }
+    }
+
+    /// Get a reference to the underlying sensor handle
+    pub fn as_channel(&self) -> &SpatialHandle {
+        &self.chan
+    }
+
+    /// Gets the most recent orientation computed by the algorithm, as a
+    /// quaternion.
+    pub fn quaternion(&self) -> Result<Quaternion> {
+        let mut q = ffi::PhidgetSpatial_SpatialQuaternion {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            w: 0.0,
+        };
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_getQuaternion(self.chan, &mut q) })?;
+        Ok(q.into())
+    }
+
+    /// Gets the most recent orientation computed by the algorithm, as
+    /// angles in degrees.
+    pub fn euler_angles(&self) -> Result<EulerAngles> {
+        let mut ang = ffi::PhidgetSpatial_SpatialEulerAngles {
+            pitch: 0.0,
+            roll: 0.0,
+            heading: 0.0,
+        };
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_getEulerAngles(self.chan, &mut ang) })?;
+        Ok(ang.into())
+    }
+
+    /// Gets the algorithm used to compute the orientation.
+    pub fn algorithm(&self) -> Result<SpatialAlgorithm> {
+        let mut alg = 0;
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_getAlgorithm(self.chan, &mut alg) })?;
+        SpatialAlgorithm::try_from(alg)
+    }
+
+    /// Sets the algorithm used to compute the orientation.
+    pub fn set_algorithm(&self, alg: SpatialAlgorithm) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_setAlgorithm(self.chan, alg as u32) })
+    }
+
+    /// Gets the weight of the magnetometer in the AHRS algorithm.
+    pub fn algorithm_magnetometer_gain(&self) -> Result<f64> {
+        let mut gain = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSpatial_getAlgorithmMagnetometerGain(self.chan, &mut gain)
+        })?;
+        Ok(gain)
+    }
+
+    /// Sets the weight of the magnetometer in the AHRS algorithm.
+    ///
+    /// A lower gain makes the heading less sensitive to magnetic noise,
+    /// but slower to correct the drift of the gyroscope.
+    pub fn set_algorithm_magnetometer_gain(&self, gain: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSpatial_setAlgorithmMagnetometerGain(self.chan, gain)
+        })
+    }
+
+    /// Zeros the orientation computed by the algorithm.
+    pub fn zero_algorithm(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_zeroAlgorithm(self.chan) })
+    }
+
+    /// Re-zeros the gyroscope.
+    ///
+    /// This takes a couple of seconds, during which the sensor must be
+    /// kept still.
+    pub fn zero_gyro(&self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_zeroGyro(self.chan) })
+    }
+
+    /// Gets the minimum acceleration that can be measured on each axis.
+    pub fn min_acceleration(&self) -> Result<[f64; 3]> {
+        let mut val = [0.0; 3];
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_getMinAcceleration(self.chan, &mut val) })?;
+        Ok(val)
+    }
+
+    /// Gets the maximum acceleration that can be measured on each axis.
+    pub fn max_acceleration(&self) -> Result<[f64; 3]> {
+        let mut val = [0.0; 3];
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_getMaxAcceleration(self.chan, &mut val) })?;
+        Ok(val)
+    }
+
+    /// Gets the minimum angular rate that can be measured on each axis.
+    pub fn min_angular_rate(&self) -> Result<[f64; 3]> {
+        let mut val = [0.0; 3];
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_getMinAngularRate(self.chan, &mut val) })?;
+        Ok(val)
+    }
+
+    /// Gets the maximum angular rate that can be measured on each axis.
+    pub fn max_angular_rate(&self) -> Result<[f64; 3]> {
+        let mut val = [0.0; 3];
+        ReturnCode::result(unsafe { ffi::PhidgetSpatial_getMaxAngularRate(self.chan, &mut val) })?;
+        Ok(val)
+    }
+
+    /// Gets the minimum field strength that can be measured on each axis.
+    pub fn min_magnetic_field(&self) -> Result<[f64; 3]> {
+        let mut val = [0.0; 3];
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSpatial_getMinMagneticField(self.chan, &mut val)
+        })?;
+        Ok(val)
+    }
+
+    /// Gets the maximum field strength that can be measured on each axis.
+    pub fn max_magnetic_field(&self) -> Result<[f64; 3]> {
+        let mut val = [0.0; 3];
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSpatial_getMaxMagneticField(self.chan, &mut val)
+        })?;
+        Ok(val)
+    }
+
+    /// Determines if the heater of the sensor is enabled.
+    pub fn heating_enabled(&self) -> Result<bool> {
+        let mut value: c_int = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSpatial_getHeatingEnabled(self.chan, &mut value)
+        })?;
+        Ok(value != 0)
+    }
+
+    /// Enables or disables the heater, which keeps the sensor at a steady
+    /// temperature to reduce its drift.
+    pub fn set_heating_enabled(&self, on: bool) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSpatial_setHeatingEnabled(self.chan, c_int::from(on))
+        })
+    }
+
+    /// Sets a handler to receive the combined sensor readings, each data
+    /// interval.
+    pub fn set_on_spatial_data_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Spatial, SpatialData) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<SpatialDataCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.data_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSpatial_setOnSpatialDataHandler(self.chan, Some(Self::on_spatial_data), ctx)
+        })
+    }
+
+    /// Sets a handler to receive the orientation computed by the
+    /// algorithm, each data interval.
+    pub fn set_on_algorithm_data_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&Spatial, Quaternion, Duration) + Send + 'static,
+    {
+        let cb: Box<Box<AlgorithmDataCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+        self.algorithm_cb = Some(ctx);
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetSpatial_setOnAlgorithmDataHandler(
+                self.chan,
+                Some(Self::on_algorithm_data),
+                ctx,
+            )
+        })
+    }
+
+    /// Sets a handler to receive attach callbacks
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_attach_handler(self, cb)?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach callbacks
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_detach_handler(self, cb)?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+impl Phidget for Spatial {
+    fn as_handle(&mut self) -> PhidgetHandle {
+        self.chan as PhidgetHandle
+    }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as SpatialHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        let alg = self.algorithm().map(|a| format!("{:?}", a));
+        properties::add(&mut props, "algorithm", alg)?;
+        properties::add(
+            &mut props,
+            "algorithm_magnetometer_gain",
+            self.algorithm_magnetometer_gain(),
+        )?;
+        properties::add(&mut props, "heating_enabled", self.heating_enabled())?;
+        Ok(props)
+    }
+}
+
+unsafe impl Send for Spatial {}
+
+impl Default for Spatial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<SpatialHandle> for Spatial {
+    fn from(chan: SpatialHandle) -> Self {
+        Self {
+            chan,
+            data_cb: None,
+            algorithm_cb: None,
+            attach_cb: None,
+            detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
+        }
+    }
+}
+
+impl Drop for Spatial {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetSpatial_delete(&mut self.chan);
+            }
+        }
+        crate::drop_cb::<SpatialDataCallback>(self.data_cb.take());
+        crate::drop_cb::<AlgorithmDataCallback>(self.algorithm_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
+    }
+}
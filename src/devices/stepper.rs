@@ -12,12 +12,21 @@
 // to those terms.
 //
 
-use crate::{AttachCallback, DetachCallback, Error, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    devices::Bounds,
+    lifecycle::Registration,
+    properties::{self, Properties},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
 use phidget_sys::{self as ffi, PhidgetHandle, PhidgetStepperHandle as StepperHandle};
 use std::{
+    collections::BTreeMap,
     mem,
     os::raw::{c_uint, c_void},
     ptr,
+    sync::{Mutex, MutexGuard},
+    time::Duration,
 };
 
 /// The function type for the safe Rust position change callback.
@@ -27,20 +36,44 @@ pub type VelocityChangeCallback = dyn Fn(&Stepper, f64) + Send + 'static;
 /// The function type for the safe Rust stop callback.
 pub type StoppedCallback = dyn Fn(&Stepper) + Send + 'static;
 
+// The software travel limits (min, max) of each channel, by handle.
+// These are kept by handle, rather than in the wrapper, so that the
+// wrappers passed to the callbacks see them as well.
+static SOFT_LIMITS: Mutex<BTreeMap<usize, (f64, f64)>> = Mutex::new(BTreeMap::new());
+
+// Gets the map of soft limits, even if a thread panicked with it locked.
+fn soft_limits() -> MutexGuard<'static, BTreeMap<usize, (f64, f64)>> {
+    SOFT_LIMITS.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Forgets the soft limits of all the channels, when the library is reset.
+pub(crate) fn forget_all_soft_limits() {
+    soft_limits().clear();
+}
+
 /// Phidget Stepper sensor
 pub struct Stepper {
     // Handle to the sensor for the phidget22 library
     chan: StepperHandle,
-    // Double-boxed StepperCallback, if registered
-    cb: Option<*mut c_void>,
+    // Double-boxed PositionChangeCallback, if registered
+    position_cb: Option<*mut c_void>,
+    // Double-boxed VelocityChangeCallback, if registered
+    velocity_cb: Option<*mut c_void>,
+    // Double-boxed StoppedCallback, if registered
+    stopped_cb: Option<*mut c_void>,
     // Double-boxed attach callback, if registered
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 /// ControlMode for stepper
 #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum ControlMode {
     /// Step: Control the motor by setting a target position.
@@ -57,11 +90,51 @@ impl TryFrom<u32> for ControlMode {
         match value {
             0 => Ok(Step),
             1 => Ok(Run),
-            _ => Err(ReturnCode::UnknownVal),
+            _ => Err(ErrorCode::UnknownVal),
         }
     }
 }
 
+/// The limits of the settings of a stepper, gathered in one call.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StepperLimits {
+    /// The range of the acceleration
+    pub acceleration: Bounds<f64>,
+    /// The range of the velocity limit
+    pub velocity_limit: Bounds<f64>,
+    /// The range of the current limit
+    pub current_limit: Bounds<f64>,
+    /// The range of the position
+    pub position: Bounds<f64>,
+    /// The range of the data interval
+    pub data_interval: Bounds<Duration>,
+}
+
+/// A snapshot of the state of a stepper.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StepperState {
+    /// The control mode
+    pub control_mode: ControlMode,
+    /// Whether the motor is engaged
+    pub engaged: bool,
+    /// Whether the motor is moving
+    pub is_moving: bool,
+    /// The current position, if known
+    pub position: Option<f64>,
+    /// The target position
+    pub target_position: f64,
+    /// The velocity limit
+    pub velocity_limit: f64,
+    /// The acceleration
+    pub acceleration: f64,
+    /// The current limit
+    pub current_limit: f64,
+    /// The holding current limit, if supported
+    pub holding_current_limit: Option<f64>,
+}
+
 impl Stepper {
     /// Create a new Stepper sensor.
     pub fn new() -> Self {
@@ -69,7 +142,19 @@ impl Stepper {
         unsafe {
             ffi::PhidgetStepper_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        // The position handler monitors any soft limits, even without an
+        // application callback.
+        unsafe {
+            ffi::PhidgetStepper_setOnPositionChangeHandler(
+                chan,
+                Some(Self::on_position_change),
+                ptr::null_mut(),
+            );
+        }
+        dev
     }
 
     /// Get a reference to the underlying sensor handle
@@ -77,12 +162,20 @@ impl Stepper {
         &self.chan
     }
 
+    /// Enables the failsafe, with the time within which it must be reset
+    /// to keep the motor from being disengaged.
+    pub fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        let ms = u32::try_from(time.as_millis()).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe { ffi::PhidgetStepper_enableFailsafe(self.chan, ms) })
+    }
+
     /// Set enable failsafe
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `enable_failsafe()`, which takes a `Duration`"
+    )]
     pub fn set_enable_failsafe(&self, failsafe_time: u32) -> Result<()> {
-        ReturnCode::result(unsafe {
-            ffi::PhidgetStepper_enableFailsafe(self.chan, failsafe_time)
-        })?;
-        Ok(())
+        self.enable_failsafe(Duration::from_millis(failsafe_time.into()))
     }
 
     /// Add position offset
@@ -177,6 +270,10 @@ impl Stepper {
     }
 
     /// Set data interval
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `Phidget::set_data_interval()`, which takes a `Duration`"
+    )]
     pub fn set_data_interval(&self, data_interval: u32) -> Result<()> {
         ReturnCode::result(unsafe {
             ffi::PhidgetStepper_setDataInterval(self.chan, data_interval)
@@ -184,6 +281,10 @@ impl Stepper {
         Ok(())
     }
     /// Get data interval
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `Phidget::data_interval()`, which returns a `Duration`"
+    )]
     pub fn data_interval(&self) -> Result<u32> {
         let mut value = 0;
         ReturnCode::result(unsafe { ffi::PhidgetStepper_getDataInterval(self.chan, &mut value) })?;
@@ -191,6 +292,10 @@ impl Stepper {
     }
 
     /// Get minimum data interval
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `Phidget::min_data_interval()`, which returns a `Duration`"
+    )]
     pub fn min_data_interval(&self) -> Result<u32> {
         let mut value = 0;
         ReturnCode::result(unsafe {
@@ -200,6 +305,10 @@ impl Stepper {
     }
 
     /// Get maximum data interval
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `Phidget::max_data_interval()`, which returns a `Duration`"
+    )]
     pub fn max_data_interval(&self) -> Result<u32> {
         let mut value = 0;
         ReturnCode::result(unsafe {
@@ -247,18 +356,27 @@ impl Stepper {
         ReturnCode::result(unsafe { ffi::PhidgetStepper_getEngaged(self.chan, &mut value) })?;
         Ok(value != 0)
     }
-    /// Get minimum data rate
+    /// Gets the range of the failsafe time.
+    pub fn failsafe_time_limits(&self) -> Result<Bounds<Duration>> {
+        let (mut min, mut max) = (0, 0);
+        ReturnCode::result(unsafe { ffi::PhidgetStepper_getMinFailsafeTime(self.chan, &mut min) })?;
+        ReturnCode::result(unsafe { ffi::PhidgetStepper_getMaxFailsafeTime(self.chan, &mut max) })?;
+        Ok(Bounds::new(
+            Duration::from_millis(min.into()),
+            Duration::from_millis(max.into()),
+        ))
+    }
+
+    /// Get minimum failsafe time, in milliseconds
+    #[deprecated(since = "0.3.0", note = "use `failsafe_time_limits()`")]
     pub fn min_failsafe_time(&self) -> Result<f64> {
-        let mut value = 0.0;
-        ReturnCode::result(unsafe { ffi::PhidgetStepper_getMinDataRate(self.chan, &mut value) })?;
-        Ok(value)
+        Ok(self.failsafe_time_limits()?.min.as_millis() as f64)
     }
 
-    /// Get maximum data rate
+    /// Get maximum failsafe time, in milliseconds
+    #[deprecated(since = "0.3.0", note = "use `failsafe_time_limits()`")]
     pub fn max_failsafe_time(&self) -> Result<f64> {
-        let mut value = 0.0;
-        ReturnCode::result(unsafe { ffi::PhidgetStepper_getMaxDataRate(self.chan, &mut value) })?;
-        Ok(value)
+        Ok(self.failsafe_time_limits()?.max.as_millis() as f64)
     }
 
     /// Set holding current limit
@@ -285,6 +403,22 @@ impl Stepper {
         Ok(value != 0)
     }
 
+    /// Gets the current velocity of the motor.
+    ///
+    /// This is the actual velocity, as it accelerates towards the velocity
+    /// limit, with the sign giving the direction.
+    pub fn velocity(&self) -> Result<f64> {
+        let mut value = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetStepper_getVelocity(self.chan, &mut value) })?;
+        Ok(value)
+    }
+
+    /// Gets the velocity, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_velocity(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.velocity())
+    }
+
     /// Get position
     pub fn position(&self) -> Result<f64> {
         let mut value = 0.0;
@@ -292,6 +426,12 @@ impl Stepper {
         Ok(value)
     }
 
+    /// Gets the position, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_position(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.position())
+    }
+
     /// Get minimum position
     pub fn min_position(&self) -> Result<f64> {
         let mut value = 0.0;
@@ -322,7 +462,15 @@ impl Stepper {
     }
 
     /// Set target position
+    ///
+    /// If software travel limits are set, a target outside of them is
+    /// rejected with an `InvalidArg` error.
     pub fn set_target_position(&self, stepper: f64) -> Result<()> {
+        if let Some((min, max)) = self.soft_limits() {
+            if stepper < min || stepper > max {
+                return Err(ErrorCode::InvalidArg);
+            }
+        }
         ReturnCode::result(unsafe { ffi::PhidgetStepper_setTargetPosition(self.chan, stepper) })?;
         Ok(())
     }
@@ -343,13 +491,68 @@ impl Stepper {
     }
 
     /// Set velocity limit
+    ///
+    /// In Run mode, if software travel limits are set, a velocity that
+    /// would drive the motor further past a limit is rejected with an
+    /// `InvalidArg` error.
     pub fn set_velocity_limit(&self, velocity_limit: f64) -> Result<()> {
+        if let Some((min, max)) = self.soft_limits() {
+            if velocity_limit != 0.0 && self.control_mode()? == ControlMode::Run {
+                let pos = self.position()?;
+                if (pos >= max && velocity_limit > 0.0) || (pos <= min && velocity_limit < 0.0) {
+                    return Err(ErrorCode::InvalidArg);
+                }
+            }
+        }
         ReturnCode::result(unsafe {
             ffi::PhidgetStepper_setVelocityLimit(self.chan, velocity_limit)
         })?;
         Ok(())
     }
 
+    /// Sets software travel limits for the motor position.
+    ///
+    /// This protects the mechanics when the hardware has no limit switches.
+    /// Target positions outside the limits are rejected, and while in Run
+    /// mode the position is monitored and the motor is stopped (velocity
+    /// limit set to zero) if it runs past a limit in the direction of
+    /// travel. Monitoring happens in the position change events, so its
+    /// resolution depends on the data interval.
+    ///
+    /// The limits apply to the channel, so they are also seen by the
+    /// devices passed to the callbacks and the attach hook.
+    pub fn set_soft_limits(&self, min: f64, max: f64) -> Result<()> {
+        if min.is_nan() || max.is_nan() || min >= max {
+            return Err(ErrorCode::InvalidArg);
+        }
+        soft_limits().insert(self.chan as usize, (min, max));
+        Ok(())
+    }
+
+    /// Removes the software travel limits.
+    pub fn clear_soft_limits(&self) {
+        soft_limits().remove(&(self.chan as usize));
+    }
+
+    /// Gets the software travel limits as (min, max), if set.
+    pub fn soft_limits(&self) -> Option<(f64, f64)> {
+        soft_limits().get(&(self.chan as usize)).copied()
+    }
+
+    // Stops the motor if it ran past one of the soft limits, in the
+    // direction of travel, while in Run mode.
+    fn check_soft_limits(&self, pos: f64) {
+        let Some((min, max)) = self.soft_limits()
+        else {
+            return;
+        };
+        if let (Ok(ControlMode::Run), Ok(vel)) = (self.control_mode(), self.velocity_limit()) {
+            if (pos >= max && vel > 0.0) || (pos <= min && vel < 0.0) {
+                let _ = self.set_velocity_limit(0.0);
+            }
+        }
+    }
+
     /// Get rescale factor
     pub fn velocity_limit(&self) -> Result<f64> {
         let mut value = 0.0;
@@ -375,15 +578,46 @@ impl Stepper {
         Ok(value)
     }
 
+    /// Gets the limits of the motion and current settings.
+    pub fn limits(&self) -> Result<StepperLimits> {
+        Ok(StepperLimits {
+            acceleration: Bounds::new(self.min_acceleration()?, self.max_acceleration()?),
+            velocity_limit: Bounds::new(self.min_velocity_limit()?, self.max_velocity_limit()?),
+            current_limit: Bounds::new(self.min_current_limit()?, self.max_current_limit()?),
+            position: Bounds::new(self.min_position()?, self.max_position()?),
+            data_interval: {
+                let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+                Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?)
+            },
+        })
+    }
+
+    /// Gets a snapshot of the position and motion settings.
+    pub fn state(&self) -> Result<StepperState> {
+        Ok(StepperState {
+            control_mode: self.control_mode()?,
+            engaged: self.engaged()?,
+            is_moving: self.is_moving()?,
+            position: self.try_position()?,
+            target_position: self.target_position()?,
+            velocity_limit: self.velocity_limit()?,
+            acceleration: self.acceleration()?,
+            current_limit: self.current_limit()?,
+            holding_current_limit: crate::errors::supported(self.holding_current_limit())?,
+        })
+    }
+
     // Low-level, unsafe, callback for position change events.
     // The context is a double-boxed pointer the safe Rust callback.
+    // Any soft limits are monitored ahead of the safe Rust callback.
     unsafe extern "C" fn on_position_change(chan: StepperHandle, ctx: *mut c_void, stepper: f64) {
+        let sensor = Self::from(chan);
+        sensor.check_soft_limits(stepper);
         if !ctx.is_null() {
             let cb: &mut Box<PositionChangeCallback> = &mut *(ctx as *mut _);
-            let sensor = Self::from(chan);
             cb(&sensor, stepper);
-            mem::forget(sensor);
         }
+        mem::forget(sensor);
     }
 
     /// Set a handler to receive position change callbacks.
@@ -394,7 +628,7 @@ impl Stepper {
         // 1st box is fat ptr, 2nd is regular pointer.
         let cb: Box<Box<PositionChangeCallback>> = Box::new(Box::new(cb));
         let ctx = Box::into_raw(cb) as *mut c_void;
-        self.cb = Some(ctx);
+        self.position_cb = Some(ctx);
 
         ReturnCode::result(unsafe {
             ffi::PhidgetStepper_setOnPositionChangeHandler(
@@ -424,7 +658,7 @@ impl Stepper {
         // 1st box is fat ptr, 2nd is regular pointer.
         let cb: Box<Box<StoppedCallback>> = Box::new(Box::new(cb));
         let ctx = Box::into_raw(cb) as *mut c_void;
-        self.cb = Some(ctx);
+        self.stopped_cb = Some(ctx);
 
         ReturnCode::result(unsafe {
             ffi::PhidgetStepper_setOnStoppedHandler(self.chan, Some(Self::on_stopped), ctx)
@@ -450,7 +684,7 @@ impl Stepper {
         // 1st box is fat ptr, 2nd is regular pointer.
         let cb: Box<Box<VelocityChangeCallback>> = Box::new(Box::new(cb));
         let ctx = Box::into_raw(cb) as *mut c_void;
-        self.cb = Some(ctx);
+        self.velocity_cb = Some(ctx);
 
         ReturnCode::result(unsafe {
             ffi::PhidgetStepper_setOnVelocityChangeHandler(
@@ -480,12 +714,75 @@ impl Stepper {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for Stepper {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as StepperHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        properties::add_ranged(
+            &mut props,
+            "acceleration",
+            self.acceleration(),
+            self.min_acceleration(),
+            self.max_acceleration(),
+        )?;
+        let mode = self.control_mode().map(|m| format!("{:?}", m));
+        properties::add(&mut props, "control_mode", mode)?;
+        properties::add_ranged(
+            &mut props,
+            "current_limit",
+            self.current_limit(),
+            self.min_current_limit(),
+            self.max_current_limit(),
+        )?;
+        properties::add(&mut props, "engaged", self.engaged())?;
+        properties::add(
+            &mut props,
+            "holding_current_limit",
+            self.holding_current_limit(),
+        )?;
+        properties::add(&mut props, "rescale_factor", self.rescale_factor())?;
+        properties::add_ranged(
+            &mut props,
+            "target_position",
+            self.target_position(),
+            self.min_position(),
+            self.max_position(),
+        )?;
+        properties::add_ranged(
+            &mut props,
+            "velocity_limit",
+            self.velocity_limit(),
+            self.min_velocity_limit(),
+            self.max_velocity_limit(),
+        )?;
+        Ok(props)
+    }
 }
 
 unsafe impl Send for Stepper {}
@@ -500,25 +797,34 @@ impl From<StepperHandle> for Stepper {
     fn from(chan: StepperHandle) -> Self {
         Self {
             chan,
-            cb: None,
+            position_cb: None,
+            velocity_cb: None,
+            stopped_cb: None,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for Stepper {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetStepper_delete(&mut self.chan);
-            crate::drop_cb::<PositionChangeCallback>(self.cb.take());
-            crate::drop_cb::<VelocityChangeCallback>(self.cb.take());
-            crate::drop_cb::<StoppedCallback>(self.cb.take());
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            soft_limits().remove(&(self.chan as usize));
+            unsafe {
+                ffi::PhidgetStepper_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<PositionChangeCallback>(self.position_cb.take());
+        crate::drop_cb::<VelocityChangeCallback>(self.velocity_cb.take());
+        crate::drop_cb::<StoppedCallback>(self.stopped_cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
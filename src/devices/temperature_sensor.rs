@@ -10,15 +10,91 @@
 // to those terms.
 //
 
-use crate::{AttachCallback, DetachCallback, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    properties::{self, Properties},
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, Error, ErrorCallback, ErrorCode, ErrorEventCode,
+    GenericPhidget, Phidget, Result, ReturnCode,
+};
 use phidget_sys::{
     self as ffi, PhidgetHandle, PhidgetTemperatureSensorHandle as TemperatureSensorHandle,
 };
 use std::{mem, os::raw::c_void, ptr};
 
+/// The type of thermocouple attached to a thermocouple input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum ThermocoupleType {
+    /// J-Type thermocouple
+    J = ffi::PhidgetTemperatureSensor_ThermocoupleType_THERMOCOUPLE_TYPE_J, // 1
+    /// K-Type thermocouple
+    K = ffi::PhidgetTemperatureSensor_ThermocoupleType_THERMOCOUPLE_TYPE_K, // 2
+    /// E-Type thermocouple
+    E = ffi::PhidgetTemperatureSensor_ThermocoupleType_THERMOCOUPLE_TYPE_E, // 3
+    /// T-Type thermocouple
+    T = ffi::PhidgetTemperatureSensor_ThermocoupleType_THERMOCOUPLE_TYPE_T, // 4
+}
+
+impl TryFrom<u32> for ThermocoupleType {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use ThermocoupleType::*;
+        match val {
+            ffi::PhidgetTemperatureSensor_ThermocoupleType_THERMOCOUPLE_TYPE_J => Ok(J), // 1
+            ffi::PhidgetTemperatureSensor_ThermocoupleType_THERMOCOUPLE_TYPE_K => Ok(K), // 2
+            ffi::PhidgetTemperatureSensor_ThermocoupleType_THERMOCOUPLE_TYPE_E => Ok(E), // 3
+            ffi::PhidgetTemperatureSensor_ThermocoupleType_THERMOCOUPLE_TYPE_T => Ok(T), // 4
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// A fault reported by a temperature sensor, like an open thermocouple.
+///
+/// These come from the error events of the channel, and mean that the
+/// channel can't give a valid temperature reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TcFault {
+    /// The sensor is disconnected, as with an open thermocouple
+    OpenCircuit,
+    /// The reading is out of the valid range of the sensor
+    OutOfRange,
+    /// The reading is above the valid range of the sensor
+    AboveRange,
+    /// The reading is below the valid range of the sensor
+    BelowRange,
+    /// The input is saturated
+    Saturation,
+}
+
+impl TcFault {
+    /// Gets the fault for an error event, if the event is one that makes
+    /// the temperature reading invalid.
+    pub fn from_event(code: ErrorEventCode) -> Option<Self> {
+        use ErrorEventCode::*;
+        match code {
+            BadConnection => Some(Self::OpenCircuit),
+            OutOfRange => Some(Self::OutOfRange),
+            OutOfRangeHigh => Some(Self::AboveRange),
+            OutOfRangeLow => Some(Self::BelowRange),
+            Saturation => Some(Self::Saturation),
+            _ => None,
+        }
+    }
+}
+
 /// The function type for the safe Rust temperature change callback.
 pub type TemperatureCallback = dyn Fn(&TemperatureSensor, f64) + Send + 'static;
 
+/// The function type for the safe Rust fault callback.
+/// This gets the new fault, or `None` when the fault clears.
+pub type TcFaultCallback = dyn Fn(&TemperatureSensor, Option<TcFault>) + Send + 'static;
+
 /// Phidget temperature sensor
 pub struct TemperatureSensor {
     // Handle to the sensor for the phidget22 library
@@ -29,6 +105,10 @@ pub struct TemperatureSensor {
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 impl TemperatureSensor {
@@ -38,7 +118,13 @@ impl TemperatureSensor {
         unsafe {
             ffi::PhidgetTemperatureSensor_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        crate::phidget::track_faults(chan as PhidgetHandle, |code| {
+            TcFault::from_event(code).is_some()
+        });
+        dev
     }
 
     // Low-level, unsafe, callback for temperature change events.
@@ -70,23 +156,154 @@ impl TemperatureSensor {
         Ok(temperature)
     }
 
+    /// Gets the temperature, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_temperature(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.temperature())
+    }
+
+    /// Gets the temperature change trigger.
+    /// Change events are only fired when the temperature changes by at least
+    /// this amount.
+    pub fn temperature_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetTemperatureSensor_getTemperatureChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the temperature change trigger.
+    pub fn set_temperature_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetTemperatureSensor_setTemperatureChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum temperature change trigger.
+    pub fn min_temperature_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetTemperatureSensor_getMinTemperatureChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum temperature change trigger.
+    pub fn max_temperature_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetTemperatureSensor_getMaxTemperatureChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and temperature change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_temperature_change_trigger()?,
+                self.max_temperature_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the temperature reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_temperature()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.temperature_change_trigger()?,
+        })
+    }
+
+    /// Gets the type of thermocouple attached to the channel.
+    ///
+    /// This is only supported by thermocouple inputs.
+    pub fn thermocouple_type(&self) -> Result<ThermocoupleType> {
+        let mut tc_type = 0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetTemperatureSensor_getThermocoupleType(self.chan, &mut tc_type)
+        })?;
+        ThermocoupleType::try_from(tc_type)
+    }
+
+    /// Sets the type of thermocouple attached to the channel.
+    pub fn set_thermocouple_type(&self, tc_type: ThermocoupleType) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetTemperatureSensor_setThermocoupleType(self.chan, tc_type as u32)
+        })
+    }
+
+    /// Gets the fault that is keeping the channel from giving a valid
+    /// reading, like an open thermocouple, or `None` if there is none.
+    ///
+    /// The fault is cleared when the channel reports that the error
+    /// condition is gone, or on the next temperature change event, if a
+    /// change handler is set.
+    pub fn last_fault(&self) -> Option<TcFault> {
+        crate::phidget::fault(self.chan as PhidgetHandle).and_then(TcFault::from_event)
+    }
+
+    /// Sets a handler to receive the faults of the channel.
+    ///
+    /// This is called with the new fault when one is reported, and with
+    /// `None` when it clears.
+    pub fn set_on_fault_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&TemperatureSensor, Option<TcFault>) + Send + 'static,
+    {
+        let hook = move |phid: PhidgetHandle, code: Option<ErrorEventCode>| {
+            let sensor = Self::from(phid as TemperatureSensorHandle);
+            cb(&sensor, code.and_then(TcFault::from_event));
+            mem::forget(sensor);
+        };
+        crate::phidget::set_fault_hook(self.chan as PhidgetHandle, Box::new(hook));
+        Ok(())
+    }
+
     /// Set a handler to receive temperature change callbacks.
     pub fn set_on_temperature_change_handler<F>(&mut self, cb: F) -> Result<()>
     where
         F: Fn(&TemperatureSensor, f64) + Send + 'static,
     {
+        // A valid reading means that any fault has cleared
+        let cb = move |sensor: &TemperatureSensor, val| {
+            crate::phidget::clear_fault(sensor.chan as PhidgetHandle);
+            cb(sensor, val);
+        };
+
         // 1st box is fat ptr, 2nd is regular pointer.
         let cb: Box<Box<TemperatureCallback>> = Box::new(Box::new(cb));
         let ctx = Box::into_raw(cb) as *mut c_void;
-        self.cb = Some(ctx);
 
-        ReturnCode::result(unsafe {
+        let res = ReturnCode::result(unsafe {
             ffi::PhidgetTemperatureSensor_setOnTemperatureChangeHandler(
                 self.chan,
                 Some(Self::on_temperature_change),
                 ctx,
             )
-        })
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<TemperatureCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<TemperatureCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Gets a receiver that always holds the most recent temperature reading.
+    ///
+    /// This sets the temperature change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_temperature_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
     }
 
     /// Sets a handler to receive attach callbacks
@@ -108,12 +325,59 @@ impl TemperatureSensor {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    ///
+    /// The faults of the channel are still tracked, and reported to the
+    /// fault handler.
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for TemperatureSensor {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as TemperatureSensorHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.temperature_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_temperature_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_temperature_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_temperature_change_trigger()
+    }
+
+    fn properties(&mut self) -> Result<Properties> {
+        let mut props = properties::common(self)?;
+        let tc_type = self.thermocouple_type().map(|t| format!("{:?}", t));
+        properties::add(&mut props, "thermocouple_type", tc_type)?;
+        Ok(props)
+    }
 }
 
 unsafe impl Send for TemperatureSensor {}
@@ -131,20 +395,26 @@ impl From<TemperatureSensorHandle> for TemperatureSensor {
             cb: None,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for TemperatureSensor {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetTemperatureSensor_delete(&mut self.chan);
-            crate::drop_cb::<TemperatureCallback>(self.cb.take());
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetTemperatureSensor_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<TemperatureCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
@@ -10,9 +10,17 @@
 // to those terms.
 //
 
-use crate::{AttachCallback, DetachCallback, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    ring::{self, RingConsumer},
+    throttle::Batcher,
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, HubPort,
+    Phidget, Result, ReturnCode,
+};
 use phidget_sys::{self as ffi, PhidgetHandle, PhidgetVoltageInputHandle};
-use std::{mem, os::raw::c_void, ptr};
+use std::{mem, os::raw::c_void, ptr, time::Duration};
 
 /// The function signature for the safe Rust voltage change callback.
 pub type VoltageChangeCallback = dyn Fn(&VoltageInput, f64) + Send + 'static;
@@ -29,6 +37,10 @@ pub struct VoltageInput {
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 impl VoltageInput {
@@ -38,7 +50,21 @@ impl VoltageInput {
         unsafe {
             ffi::PhidgetVoltageInput_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    /// Creates a voltage input for a VINT hub port in voltage input mode.
+    ///
+    /// This marks the channel as a hub port device, and sets the port
+    /// number, ready to be opened.
+    pub fn hub_port(port: HubPort) -> Result<Self> {
+        let mut dev = Self::new();
+        dev.set_is_hub_port_device(true)?;
+        dev.set_hub_port(port)?;
+        Ok(dev)
     }
 
     // Low-level, unsafe, callback for the voltage change event.
@@ -68,6 +94,70 @@ impl VoltageInput {
         Ok(v)
     }
 
+    /// Gets the voltage, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_voltage(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.voltage())
+    }
+
+    /// Gets the voltage change trigger.
+    /// Change events are only fired when the voltage changes by at least
+    /// this amount.
+    pub fn voltage_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetVoltageInput_getVoltageChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the voltage change trigger.
+    pub fn set_voltage_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetVoltageInput_setVoltageChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum voltage change trigger.
+    pub fn min_voltage_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetVoltageInput_getMinVoltageChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum voltage change trigger.
+    pub fn max_voltage_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetVoltageInput_getMaxVoltageChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and voltage change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_voltage_change_trigger()?,
+                self.max_voltage_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the voltage reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_voltage()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.voltage_change_trigger()?,
+        })
+    }
+
     /// Sets a handler to receive voltage change callbacks.
     pub fn set_on_voltage_change_handler<F>(&mut self, cb: F) -> Result<()>
     where
@@ -76,15 +166,66 @@ impl VoltageInput {
         // 1st box is fat ptr, 2nd is regular pointer.
         let cb: Box<Box<VoltageChangeCallback>> = Box::new(Box::new(cb));
         let ctx = Box::into_raw(cb) as *mut c_void;
-        self.cb = Some(ctx);
 
-        ReturnCode::result(unsafe {
+        let res = ReturnCode::result(unsafe {
             ffi::PhidgetVoltageInput_setOnVoltageChangeHandler(
                 self.chan,
                 Some(Self::on_voltage_change),
                 ctx,
             )
-        })
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<VoltageChangeCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<VoltageChangeCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Sets a handler to receive the voltage readings in chunks.
+    ///
+    /// The readings are delivered from a background thread, in slices of
+    /// up to `size` values, or after `period` has passed since the first
+    /// one in the chunk arrived. This replaces any voltage change handler
+    /// that was previously set. The previous handler is freed,
+    /// which stops the thread of a previous batch handler. See [`Batcher`].
+    pub fn set_on_voltage_batch_handler<F>(
+        &mut self,
+        size: usize,
+        period: Duration,
+        cb: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[f64]) + Send + 'static,
+    {
+        let batcher = Batcher::new(size, period, cb);
+        self.set_on_voltage_change_handler(move |_, val| batcher.push(val))
+    }
+
+    /// Gets a receiver that always holds the most recent voltage reading.
+    ///
+    /// This sets the voltage change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_voltage_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Gets a lock-free ring buffer that receives every voltage reading,
+    /// for high-rate streaming.
+    ///
+    /// The buffer holds up to the specified number of readings. Any that
+    /// arrive when it is full are dropped and counted. This sets the
+    /// voltage change handler, replacing any that was previously set, so
+    /// the consumer from an earlier call sees the producer disconnect.
+    pub fn ring_buffer(&mut self, capacity: usize) -> Result<RingConsumer<f64>> {
+        let (tx, rx) = ring::channel(capacity);
+        self.set_on_voltage_change_handler(move |_, val| {
+            let _ = tx.push(val);
+        })?;
+        Ok(rx)
     }
 
     /// Sets a handler to receive attach callbacks
@@ -106,12 +247,49 @@ impl VoltageInput {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for VoltageInput {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PhidgetVoltageInputHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.voltage_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_voltage_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_voltage_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_voltage_change_trigger()
+    }
 }
 
 unsafe impl Send for VoltageInput {}
@@ -129,20 +307,26 @@ impl From<PhidgetVoltageInputHandle> for VoltageInput {
             cb: None,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for VoltageInput {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetVoltageInput_delete(&mut self.chan);
-            crate::drop_cb::<VoltageChangeCallback>(self.cb.take());
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetVoltageInput_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<VoltageChangeCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
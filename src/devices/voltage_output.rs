@@ -10,9 +10,86 @@
 // to those terms.
 //
 
-use crate::{AttachCallback, DetachCallback, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    lifecycle::Registration, AttachCallback, DetachCallback, ErrorCallback, ErrorCode,
+    ErrorEventCode, GenericPhidget, Phidget, Result, ReturnCode,
+};
 use phidget_sys::{self as ffi, PhidgetHandle, PhidgetVoltageOutputHandle};
-use std::{os::raw::c_void, ptr};
+use std::{
+    mem,
+    os::raw::c_void,
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+// The time between the steps of a voltage ramp
+const RAMP_STEP: Duration = Duration::from_millis(20);
+
+// A reference to the channel, held by a ramp thread, so that the handle
+// stays valid even if the output is dropped.
+struct RetainedHandle(PhidgetVoltageOutputHandle);
+
+impl RetainedHandle {
+    fn new(chan: PhidgetVoltageOutputHandle) -> Result<Self> {
+        ReturnCode::result(unsafe { ffi::Phidget_retain(chan as PhidgetHandle) })?;
+        Ok(Self(chan))
+    }
+
+    fn set_voltage(&self, v: f64) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetVoltageOutput_setVoltage(self.0, v) })
+    }
+}
+
+unsafe impl Send for RetainedHandle {}
+
+impl Drop for RetainedHandle {
+    fn drop(&mut self) {
+        let mut phid = self.0 as PhidgetHandle;
+        unsafe {
+            ffi::Phidget_release(&mut phid);
+        }
+    }
+}
+
+/// A voltage ramp running in the background, from
+/// [`VoltageOutput::ramp_to()`].
+///
+/// Dropping the handle lets the ramp run to completion.
+pub struct VoltageRamp {
+    // Flag to stop the ramp early
+    stop: Arc<AtomicBool>,
+    // The thread stepping the output
+    thr: Option<JoinHandle<Result<()>>>,
+}
+
+impl VoltageRamp {
+    /// Determines if the ramp has finished, or stopped on an error.
+    pub fn is_finished(&self) -> bool {
+        self.thr.as_ref().map_or(true, |thr| thr.is_finished())
+    }
+
+    /// Blocks until the ramp has finished.
+    ///
+    /// Returns the error that stopped the ramp, if any, like the output
+    /// being closed.
+    pub fn wait(mut self) -> Result<()> {
+        match self.thr.take() {
+            Some(thr) => thr.join().map_err(|_| ErrorCode::Unexpected)?,
+            None => Ok(()),
+        }
+    }
+
+    /// Stops the ramp, leaving the output at the voltage it reached.
+    pub fn cancel(self) -> Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.wait()
+    }
+}
 
 /// Phidget voltage output
 pub struct VoltageOutput {
@@ -22,6 +99,10 @@ pub struct VoltageOutput {
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 impl VoltageOutput {
@@ -31,7 +112,10 @@ impl VoltageOutput {
         unsafe {
             ffi::PhidgetVoltageOutput_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
     }
 
     /// Get the voltage value that the channel will output
@@ -46,6 +130,58 @@ impl VoltageOutput {
         ReturnCode::result(unsafe { ffi::PhidgetVoltageOutput_setVoltage(self.chan, v) })
     }
 
+    /// Gets the minimum voltage that the channel can output.
+    pub fn min_voltage(&self) -> Result<f64> {
+        let mut v: f64 = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetVoltageOutput_getMinVoltage(self.chan, &mut v) })?;
+        Ok(v)
+    }
+
+    /// Gets the maximum voltage that the channel can output.
+    pub fn max_voltage(&self) -> Result<f64> {
+        let mut v: f64 = 0.0;
+        ReturnCode::result(unsafe { ffi::PhidgetVoltageOutput_getMaxVoltage(self.chan, &mut v) })?;
+        Ok(v)
+    }
+
+    /// Moves the output smoothly from its current voltage to the target,
+    /// over the specified time.
+    ///
+    /// The output is stepped by a background thread, and this returns
+    /// right away with a handle to wait for the ramp to complete, or to
+    /// cancel it. A target out of the range of the channel is rejected
+    /// with an `InvalidArg` error.
+    pub fn ramp_to(&self, target: f64, duration: Duration) -> Result<VoltageRamp> {
+        if target < self.min_voltage()? || target > self.max_voltage()? {
+            return Err(ErrorCode::InvalidArg);
+        }
+        let start = self.voltage()?;
+        let chan = RetainedHandle::new(self.chan)?;
+        let n = duration.as_nanos().div_ceil(RAMP_STEP.as_nanos()).max(1) as u32;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thr = thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || {
+                for i in 1..=n {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    chan.set_voltage(start + (target - start) * f64::from(i) / f64::from(n))?;
+                    if i < n {
+                        thread::sleep(RAMP_STEP);
+                    }
+                }
+                Ok(())
+            }
+        });
+
+        Ok(VoltageRamp {
+            stop,
+            thr: Some(thr),
+        })
+    }
+
     /// Sets a handler to receive attach callbacks
     pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
     where
@@ -65,12 +201,33 @@ impl VoltageOutput {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for VoltageOutput {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PhidgetVoltageOutputHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
 }
 
 unsafe impl Send for VoltageOutput {}
@@ -87,19 +244,25 @@ impl From<PhidgetVoltageOutputHandle> for VoltageOutput {
             chan,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for VoltageOutput {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetVoltageOutput_delete(&mut self.chan);
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetVoltageOutput_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
@@ -10,9 +10,17 @@
 // This file may not be copied, modified, or distributed except according
 // to those terms.
 //
-use crate::{AttachCallback, DetachCallback, GenericPhidget, Phidget, Result, ReturnCode};
+use crate::{
+    devices::{Bounds, SensorLimits, SensorState},
+    lifecycle::Registration,
+    ring::{self, RingConsumer},
+    throttle::Batcher,
+    watch::{self, WatchReceiver},
+    AttachCallback, DetachCallback, ErrorCallback, ErrorEventCode, GenericPhidget, HubPort,
+    Phidget, Result, ReturnCode,
+};
 use phidget_sys::{self as ffi, PhidgetHandle, PhidgetVoltageRatioInputHandle};
-use std::{mem, os::raw::c_void, ptr};
+use std::{mem, os::raw::c_void, ptr, time::Duration};
 
 /// The function type for the safe Rust position change callback.
 pub type VoltageRatioChangeCallback = dyn Fn(&VoltageRatioInput, f64) + Send + 'static;
@@ -27,6 +35,10 @@ pub struct VoltageRatioInput {
     attach_cb: Option<*mut c_void>,
     // Double-boxed detach callback, if registered
     detach_cb: Option<*mut c_void>,
+    // Double-boxed error callback, if registered
+    error_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
 }
 
 impl VoltageRatioInput {
@@ -36,7 +48,21 @@ impl VoltageRatioInput {
         unsafe {
             ffi::PhidgetVoltageRatioInput_create(&mut chan);
         }
-        Self::from(chan)
+        let mut dev = Self::from(chan);
+        dev.life = Registration::channel(chan as PhidgetHandle);
+        crate::phidget::init_error_handler(chan as PhidgetHandle);
+        dev
+    }
+
+    /// Creates a voltage ratio input for a VINT hub port in voltage ratio input mode.
+    ///
+    /// This marks the channel as a hub port device, and sets the port
+    /// number, ready to be opened.
+    pub fn hub_port(port: HubPort) -> Result<Self> {
+        let mut dev = Self::new();
+        dev.set_is_hub_port_device(true)?;
+        dev.set_hub_port(port)?;
+        Ok(dev)
     }
 
     // Low-level, unsafe, callback for the voltage ratio change event.
@@ -68,6 +94,70 @@ impl VoltageRatioInput {
         Ok(voltage_ratio)
     }
 
+    /// Gets the voltage ratio, or `None` if the channel is not attached or
+    /// doesn't have a valid reading yet.
+    pub fn try_voltage_ratio(&self) -> Result<Option<f64>> {
+        crate::errors::optional(self.voltage_ratio())
+    }
+
+    /// Gets the voltage ratio change trigger.
+    /// Change events are only fired when the voltage ratio changes by at least
+    /// this amount.
+    pub fn voltage_ratio_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetVoltageRatioInput_getVoltageRatioChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Sets the voltage ratio change trigger.
+    pub fn set_voltage_ratio_change_trigger(&self, trigger: f64) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::PhidgetVoltageRatioInput_setVoltageRatioChangeTrigger(self.chan, trigger)
+        })
+    }
+
+    /// Gets the minimum voltage ratio change trigger.
+    pub fn min_voltage_ratio_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetVoltageRatioInput_getMinVoltageRatioChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the maximum voltage ratio change trigger.
+    pub fn max_voltage_ratio_change_trigger(&self) -> Result<f64> {
+        let mut trigger = 0.0;
+        ReturnCode::result(unsafe {
+            ffi::PhidgetVoltageRatioInput_getMaxVoltageRatioChangeTrigger(self.chan, &mut trigger)
+        })?;
+        Ok(trigger)
+    }
+
+    /// Gets the limits of the data interval and voltage ratio change trigger.
+    pub fn limits(&self) -> Result<SensorLimits> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorLimits {
+            data_interval: Bounds::new(ph.min_data_interval()?, ph.max_data_interval()?),
+            change_trigger: Bounds::new(
+                self.min_voltage_ratio_change_trigger()?,
+                self.max_voltage_ratio_change_trigger()?,
+            ),
+        })
+    }
+
+    /// Gets a snapshot of the voltage ratio reading and the sampling settings.
+    pub fn state(&self) -> Result<SensorState> {
+        let mut ph = GenericPhidget::from(self.chan as PhidgetHandle);
+        Ok(SensorState {
+            value: self.try_voltage_ratio()?,
+            data_interval: ph.data_interval()?,
+            change_trigger: self.voltage_ratio_change_trigger()?,
+        })
+    }
+
     /// Sets a handler to receive voltage change callbacks.
     pub fn set_on_voltage_ratio_change_handler<F>(&mut self, cb: F) -> Result<()>
     where
@@ -76,15 +166,66 @@ impl VoltageRatioInput {
         // 1st box is fat ptr, 2nd is regular pointer.
         let cb: Box<Box<VoltageRatioChangeCallback>> = Box::new(Box::new(cb));
         let ctx = Box::into_raw(cb) as *mut c_void;
-        self.cb = Some(ctx);
 
-        ReturnCode::result(unsafe {
+        let res = ReturnCode::result(unsafe {
             ffi::PhidgetVoltageRatioInput_setOnVoltageRatioChangeHandler(
                 self.chan,
                 Some(Self::on_voltage_ratio_change),
                 ctx,
             )
-        })
+        });
+
+        // Free whichever handler the library is no longer using
+        match res {
+            Ok(()) => crate::drop_cb::<VoltageRatioChangeCallback>(self.cb.replace(ctx)),
+            Err(_) => crate::drop_cb::<VoltageRatioChangeCallback>(Some(ctx)),
+        }
+        res
+    }
+
+    /// Sets a handler to receive the voltage ratio readings in chunks.
+    ///
+    /// The readings are delivered from a background thread, in slices of
+    /// up to `size` values, or after `period` has passed since the first
+    /// one in the chunk arrived. This replaces any voltage ratio change handler
+    /// that was previously set. The previous handler is freed,
+    /// which stops the thread of a previous batch handler. See [`Batcher`].
+    pub fn set_on_voltage_ratio_batch_handler<F>(
+        &mut self,
+        size: usize,
+        period: Duration,
+        cb: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[f64]) + Send + 'static,
+    {
+        let batcher = Batcher::new(size, period, cb);
+        self.set_on_voltage_ratio_change_handler(move |_, val| batcher.push(val))
+    }
+
+    /// Gets a receiver that always holds the most recent voltage ratio reading.
+    ///
+    /// This sets the voltage ratio change handler, replacing any that was
+    /// previously set, which closes a receiver from an earlier call.
+    pub fn watch(&mut self) -> Result<WatchReceiver<f64>> {
+        let (tx, rx) = watch::channel();
+        self.set_on_voltage_ratio_change_handler(move |_, val| tx.send(val))?;
+        Ok(rx)
+    }
+
+    /// Gets a lock-free ring buffer that receives every voltage ratio reading,
+    /// for high-rate streaming.
+    ///
+    /// The buffer holds up to the specified number of readings. Any that
+    /// arrive when it is full are dropped and counted. This sets the
+    /// voltage ratio change handler, replacing any that was previously set, so
+    /// the consumer from an earlier call sees the producer disconnect.
+    pub fn ring_buffer(&mut self, capacity: usize) -> Result<RingConsumer<f64>> {
+        let (tx, rx) = ring::channel(capacity);
+        self.set_on_voltage_ratio_change_handler(move |_, val| {
+            let _ = tx.push(val);
+        })?;
+        Ok(rx)
     }
 
     /// Sets a handler to receive attach callbacks
@@ -106,12 +247,49 @@ impl VoltageRatioInput {
         self.detach_cb = Some(ctx);
         Ok(())
     }
+
+    /// Sets a handler to receive error events
+    pub fn set_on_error_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+    {
+        let ctx = crate::phidget::set_on_error_handler(self, cb)?;
+        self.error_cb = Some(ctx);
+        Ok(())
+    }
 }
 
 impl Phidget for VoltageRatioInput {
     fn as_handle(&mut self) -> PhidgetHandle {
         self.chan as PhidgetHandle
     }
+
+    fn set_attach_hook<F>(&mut self, hook: F) -> Result<()>
+    where
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_on_attach_handler(move |ph| {
+            let mut dev = Self::from(*ph.as_channel() as PhidgetVoltageRatioInputHandle);
+            hook(&mut dev);
+            mem::forget(dev);
+        })
+    }
+
+    fn change_trigger(&mut self) -> Result<f64> {
+        self.voltage_ratio_change_trigger()
+    }
+
+    fn set_change_trigger(&mut self, trigger: f64) -> Result<()> {
+        self.set_voltage_ratio_change_trigger(trigger)
+    }
+
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        self.min_voltage_ratio_change_trigger()
+    }
+
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        self.max_voltage_ratio_change_trigger()
+    }
 }
 
 unsafe impl Send for VoltageRatioInput {}
@@ -129,20 +307,26 @@ impl From<PhidgetVoltageRatioInputHandle> for VoltageRatioInput {
             cb: None,
             attach_cb: None,
             detach_cb: None,
+            error_cb: None,
+            life: Registration::default(),
         }
     }
 }
 
 impl Drop for VoltageRatioInput {
     fn drop(&mut self) {
-        if let Ok(true) = self.is_open() {
-            let _ = self.close();
-        }
-        unsafe {
-            ffi::PhidgetVoltageRatioInput_delete(&mut self.chan);
-            crate::drop_cb::<VoltageRatioChangeCallback>(self.cb.take());
-            crate::drop_cb::<AttachCallback>(self.attach_cb.take());
-            crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            if let Ok(true) = self.is_open() {
+                let _ = self.close();
+            }
+            unsafe {
+                ffi::PhidgetVoltageRatioInput_delete(&mut self.chan);
+            }
         }
+        crate::drop_cb::<VoltageRatioChangeCallback>(self.cb.take());
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+        crate::drop_cb::<ErrorCallback>(self.error_cb.take());
     }
 }
@@ -11,22 +11,24 @@
 //
 //! The error return type for the library.
 //!
-//! This uses the integer ReturnCode from the phidget22 library as the Error
-//! type for most most operations. In the underlying library, a value of zero
-//! indicates success and all non-zero values are errors. When returned as
-//! an error, it will always have a non-zero value. As these are internally
-//! represented by a u32, the integer error value is always >0.
+//! The phidget22 library returns an integer `ReturnCode` from each call,
+//! where a value of zero indicates success and all non-zero values are
+//! errors. The Rust [`ReturnCode`] enumeration mirrors these, including the
+//! success value, and is used at the FFI boundary.
 //!
-//! The Rust `ReturnCode` is an enumeration that fully implements
-//! std::error::Error.
+//! The [`ErrorCode`] is the error type for the crate. It contains only the
+//! non-zero failure codes, so an error can never be mistaken for success.
+//! It fully implements std::error::Error.
 //!
 
+use crate::{ChannelClass, DeviceSpec, Phidget};
 use phidget_sys as ffi;
 use std::{
     ffi::CStr,
     fmt,
     os::raw::{c_char, c_uint},
-    ptr,
+    ptr, thread,
+    time::Duration,
 };
 
 /////////////////////////////////////////////////////////////////////////////
@@ -87,44 +89,51 @@ pub enum ReturnCode {
     Failsafe = 59,
     UnknownValHigh = 60,
     UnknownValLow = 61,
+    BadPower = 62,
+    PowerCycle = 63,
 }
 
 impl ReturnCode {
     /// Convert the raw integer return code into a Result, where zero is Ok,
     /// and everything else is an error.
     pub fn result(rc: c_uint) -> Result<()> {
-        match rc {
-            0 => Ok(()),
-            _ => Err(ReturnCode::from(rc)),
+        Self::from(rc).into_result()
+    }
+
+    /// Convert the return code into a Result, where `Ok` is success and
+    /// everything else is an error.
+    pub fn into_result(self) -> Result<()> {
+        match self.error() {
+            None => Ok(()),
+            Some(err) => Err(err),
+        }
+    }
+
+    /// Gets the error code, if the return code is a failure.
+    pub fn error(self) -> Option<ErrorCode> {
+        match self {
+            ReturnCode::Ok => None,
+            _ => Some(ErrorCode::from_raw(self as c_uint)),
         }
     }
-}
 
-impl std::error::Error for ReturnCode {}
+    /// Determines if the return code indicates success.
+    pub fn is_ok(self) -> bool {
+        self == ReturnCode::Ok
+    }
+}
 
 impl fmt::Display for ReturnCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if *self == ReturnCode::Ok {
-            write!(f, "OK")
-        }
-        else {
-            let mut descr: *const c_char = ptr::null_mut();
-            unsafe {
-                if ffi::Phidget_getErrorDescription(*self as c_uint, &mut descr) == 0
-                    && !descr.is_null()
-                {
-                    write!(f, "{}", CStr::from_ptr(descr).to_string_lossy())
-                }
-                else {
-                    write!(f, "Unknown")
-                }
-            }
+        match self.error() {
+            None => write!(f, "OK"),
+            Some(err) => write!(f, "{}", err),
         }
     }
 }
 
 impl From<c_uint> for ReturnCode {
-    /// Converts an unsigned integer into a `ReturnCode` error.
+    /// Converts an unsigned integer into a `ReturnCode`.
     /// Note that instead of implementing `try_from`, any unknown integer
     /// value is returned as a `ReturnCode::Unexpected` error.
     fn from(val: c_uint) -> Self {
@@ -177,19 +186,623 @@ impl From<c_uint> for ReturnCode {
             59 => Failsafe,
             60 => UnknownValHigh,
             61 => UnknownValLow,
+            62 => BadPower,
+            63 => PowerCycle,
+            _ => Unexpected,
+        }
+    }
+}
+
+impl From<ErrorCode> for ReturnCode {
+    fn from(err: ErrorCode) -> Self {
+        Self::from(err as c_uint)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// Error codes from the phidgets22 library.
+/// These are the non-zero failure values of the [`ReturnCode`], and so
+/// can not represent success. This type is a Rust std::error::Error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u32)]
+#[allow(missing_docs)]
+pub enum ErrorCode {
+    Perm = 1,
+    NoEnt = 2,
+    Timeout = 3,
+    Interrupted = 4,
+    Io = 5,
+    NoMemory = 6,
+    Access = 7,
+    Fault = 8,
+    Busy = 9,
+    Exist = 10,
+    NotDir = 11,
+    IsDir = 12,
+    Invalid = 13,
+    NFile = 14,
+    MFile = 15,
+    NoSPC = 16,
+    FBig = 17,
+    ROFS = 18,
+    RO = 19,
+    Unsupported = 20,
+    InvalidArg = 21,
+    Again = 22,
+    NotEmpty = 26,
+    Duplicate = 27,
+    Unexpected = 28,
+    Eof = 31,
+    ConnRef = 35,
+    BadPassword = 37,
+    NoDev = 40,
+    Pipe = 41,
+    Resolv = 44,
+    NetUnavail = 45,
+    ConnReset = 46,
+    HostUnreach = 48,
+    WrongDevice = 50,
+    UnknownVal = 51,
+    NotAttached = 52,
+    InvalidPacket = 53,
+    TooBig = 54,
+    BadVersion = 55,
+    Closed = 56,
+    NotConfigured = 57,
+    KeepAlive = 58,
+    Failsafe = 59,
+    UnknownValHigh = 60,
+    UnknownValLow = 61,
+    BadPower = 62,
+    PowerCycle = 63,
+}
+
+impl ErrorCode {
+    /// Converts a raw, non-zero integer return code into an error.
+    /// Any unknown value, including zero, is returned as an
+    /// `ErrorCode::Unexpected` error.
+    pub fn from_raw(val: c_uint) -> Self {
+        use ErrorCode::*;
+        match val {
+            1 => Perm,
+            2 => NoEnt,
+            3 => Timeout,
+            4 => Interrupted,
+            5 => Io,
+            6 => NoMemory,
+            7 => Access,
+            8 => Fault,
+            9 => Busy,
+            10 => Exist,
+            11 => NotDir,
+            12 => IsDir,
+            13 => Invalid,
+            14 => NFile,
+            15 => MFile,
+            16 => NoSPC,
+            17 => FBig,
+            18 => ROFS,
+            19 => RO,
+            20 => Unsupported,
+            21 => InvalidArg,
+            22 => Again,
+            26 => NotEmpty,
+            27 => Duplicate,
+            28 => Unexpected,
+            31 => Eof,
+            35 => ConnRef,
+            37 => BadPassword,
+            40 => NoDev,
+            41 => Pipe,
+            44 => Resolv,
+            45 => NetUnavail,
+            46 => ConnReset,
+            48 => HostUnreach,
+            50 => WrongDevice,
+            51 => UnknownVal,
+            52 => NotAttached,
+            53 => InvalidPacket,
+            54 => TooBig,
+            55 => BadVersion,
+            56 => Closed,
+            57 => NotConfigured,
+            58 => KeepAlive,
+            59 => Failsafe,
+            60 => UnknownValHigh,
+            61 => UnknownValLow,
+            62 => BadPower,
+            63 => PowerCycle,
             _ => Unexpected,
         }
     }
 }
 
-impl From<std::str::Utf8Error> for ReturnCode {
+impl ErrorCode {
+    /// Determines if the error means that a value is temporarily
+    /// unavailable, because the channel is not attached, or has not yet
+    /// received a valid reading.
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, ErrorCode::NotAttached | ErrorCode::UnknownVal)
+    }
+
+    /// Determines if the error means that the device has bad power, and
+    /// should have its hub port power cycled.
+    pub fn needs_power_cycle(&self) -> bool {
+        matches!(self, ErrorCode::BadPower | ErrorCode::PowerCycle)
+    }
+
+    /// Determines if the error means that the connection to the channel
+    /// was lost, so that it might be recovered by reopening the channel.
+    pub fn is_connection_lost(&self) -> bool {
+        use ErrorCode::*;
+        matches!(
+            self,
+            NotAttached | Closed | ConnReset | ConnRef | Pipe | NetUnavail | HostUnreach
+        )
+    }
+}
+
+impl std::error::Error for ErrorCode {}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut descr: *const c_char = ptr::null_mut();
+        unsafe {
+            if ffi::Phidget_getErrorDescription(*self as c_uint, &mut descr) == 0
+                && !descr.is_null()
+            {
+                write!(f, "{}", CStr::from_ptr(descr).to_string_lossy())
+            }
+            else {
+                write!(f, "Unknown")
+            }
+        }
+    }
+}
+
+impl TryFrom<ReturnCode> for ErrorCode {
+    type Error = ReturnCode;
+
+    /// Converts a failure return code into an error, failing for
+    /// `ReturnCode::Ok`.
+    fn try_from(rc: ReturnCode) -> std::result::Result<Self, ReturnCode> {
+        rc.error().ok_or(rc)
+    }
+}
+
+impl From<std::str::Utf8Error> for ErrorCode {
     fn from(_: std::str::Utf8Error) -> Self {
-        ReturnCode::Invalid
+        ErrorCode::Invalid
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// An error opening a channel, with the filters that were used to find it.
+///
+/// A bare `Timeout` is not much help in a system with a dozen channels, so
+/// this carries the channel class, the filter settings (serial number, hub
+/// port, channel, etc), and the time that was spent waiting. It can be
+/// converted into the underlying [`ErrorCode`], so `?` still works in
+/// functions returning the crate's [`Result`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachError {
+    /// The error returned by the library
+    pub code: ErrorCode,
+    /// The class of the channel that was being opened, if known
+    pub class: Option<ChannelClass>,
+    /// The filters that were set on the channel
+    pub filter: DeviceSpec,
+    /// The time spent waiting for the channel to attach
+    pub timeout: Duration,
+}
+
+impl AttachError {
+    /// Determines if the channel failed to attach in time.
+    pub fn is_timeout(&self) -> bool {
+        self.code == ErrorCode::Timeout
     }
 }
 
-/// The error type for the crate is a phidget22 return code.
-pub type Error = ReturnCode;
+impl fmt::Display for AttachError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+        match self.class {
+            Some(cls) => write!(f, " opening {}", cls)?,
+            None => write!(f, " opening channel")?,
+        }
+        if self.is_timeout() {
+            write!(f, " after {}ms", self.timeout.as_millis())?;
+        }
+        let filter = self.filter.to_string();
+        if filter.is_empty() {
+            write!(f, " [any]")
+        }
+        else {
+            write!(f, " [{}]", filter)
+        }
+    }
+}
+
+impl std::error::Error for AttachError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.code)
+    }
+}
+
+impl From<AttachError> for ErrorCode {
+    fn from(err: AttachError) -> Self {
+        err.code
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// Error event codes.
+/// These are reported asynchronously by the error events of a channel,
+/// rather than returned from a call into the library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum ErrorEventCode {
+    /// Client and server protocol versions don't match
+    BadVersion = ffi::Phidget_ErrorEventCode_EEPHIDGET_BADVERSION, // 1
+    /// The channel is already open elsewhere
+    Busy = ffi::Phidget_ErrorEventCode_EEPHIDGET_BUSY, // 2
+    /// A network error occurred
+    Network = ffi::Phidget_ErrorEventCode_EEPHIDGET_NETWORK, // 3
+    /// An error occurred dispatching an event
+    Dispatch = ffi::Phidget_ErrorEventCode_EEPHIDGET_DISPATCH, // 4
+    /// A general failure occurred
+    Failure = ffi::Phidget_ErrorEventCode_EEPHIDGET_FAILURE, // 5
+    /// A previous error condition has cleared
+    Ok = ffi::Phidget_ErrorEventCode_EEPHIDGET_OK, // 4096
+    /// Samples were lost because they arrived faster than they were read
+    Overrun = ffi::Phidget_ErrorEventCode_EEPHIDGET_OVERRUN, // 4098
+    /// A packet was lost
+    PacketLost = ffi::Phidget_ErrorEventCode_EEPHIDGET_PACKETLOST, // 4099
+    /// A counter wrapped around
+    Wrap = ffi::Phidget_ErrorEventCode_EEPHIDGET_WRAP, // 4100
+    /// The device is over temperature
+    OverTemp = ffi::Phidget_ErrorEventCode_EEPHIDGET_OVERTEMP, // 4101
+    /// The device is over current
+    OverCurrent = ffi::Phidget_ErrorEventCode_EEPHIDGET_OVERCURRENT, // 4102
+    /// The value is out of range
+    OutOfRange = ffi::Phidget_ErrorEventCode_EEPHIDGET_OUTOFRANGE, // 4103
+    /// The device has bad power, or needs to be power cycled
+    BadPower = ffi::Phidget_ErrorEventCode_EEPHIDGET_BADPOWER, // 4104
+    /// The input is saturated
+    Saturation = ffi::Phidget_ErrorEventCode_EEPHIDGET_SATURATION, // 4105
+    /// The device is over voltage
+    OverVoltage = ffi::Phidget_ErrorEventCode_EEPHIDGET_OVERVOLTAGE, // 4107
+    /// The failsafe was triggered
+    Failsafe = ffi::Phidget_ErrorEventCode_EEPHIDGET_FAILSAFE, // 4108
+    /// The output voltage is in error
+    VoltageError = ffi::Phidget_ErrorEventCode_EEPHIDGET_VOLTAGEERROR, // 4109
+    /// The device is dumping excess energy
+    EnergyDump = ffi::Phidget_ErrorEventCode_EEPHIDGET_ENERGYDUMP, // 4110
+    /// The motor has stalled
+    MotorStall = ffi::Phidget_ErrorEventCode_EEPHIDGET_MOTORSTALL, // 4111
+    /// The device is in an invalid state
+    InvalidState = ffi::Phidget_ErrorEventCode_EEPHIDGET_INVALIDSTATE, // 4112
+    /// There is a bad connection to the sensor
+    BadConnection = ffi::Phidget_ErrorEventCode_EEPHIDGET_BADCONNECTION, // 4113
+    /// The value is above the valid range
+    OutOfRangeHigh = ffi::Phidget_ErrorEventCode_EEPHIDGET_OUTOFRANGEHIGH, // 4114
+    /// The value is below the valid range
+    OutOfRangeLow = ffi::Phidget_ErrorEventCode_EEPHIDGET_OUTOFRANGELOW, // 4115
+    /// The device has a fault
+    Fault = ffi::Phidget_ErrorEventCode_EEPHIDGET_FAULT, // 4116
+    /// The emergency stop was triggered
+    EStop = ffi::Phidget_ErrorEventCode_EEPHIDGET_ESTOP, // 4117
+}
+
+impl ErrorEventCode {
+    /// Determines if the event means that the device has bad power, and
+    /// should have its hub port power cycled.
+    pub fn needs_power_cycle(&self) -> bool {
+        *self == Self::BadPower
+    }
+}
+
+impl TryFrom<u32> for ErrorEventCode {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        match val {
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_BADVERSION => Ok(Self::BadVersion), // 1
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_BUSY => Ok(Self::Busy),             // 2
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_NETWORK => Ok(Self::Network),       // 3
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_DISPATCH => Ok(Self::Dispatch),     // 4
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_FAILURE => Ok(Self::Failure),       // 5
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_OK => Ok(Self::Ok),                 // 4096
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_OVERRUN => Ok(Self::Overrun),       // 4098
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_PACKETLOST => Ok(Self::PacketLost), // 4099
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_WRAP => Ok(Self::Wrap),             // 4100
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_OVERTEMP => Ok(Self::OverTemp),     // 4101
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_OVERCURRENT => Ok(Self::OverCurrent), // 4102
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_OUTOFRANGE => Ok(Self::OutOfRange), // 4103
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_BADPOWER => Ok(Self::BadPower),     // 4104
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_SATURATION => Ok(Self::Saturation), // 4105
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_OVERVOLTAGE => Ok(Self::OverVoltage), // 4107
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_FAILSAFE => Ok(Self::Failsafe),     // 4108
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_VOLTAGEERROR => Ok(Self::VoltageError), // 4109
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_ENERGYDUMP => Ok(Self::EnergyDump), // 4110
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_MOTORSTALL => Ok(Self::MotorStall), // 4111
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_INVALIDSTATE => Ok(Self::InvalidState), // 4112
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_BADCONNECTION => Ok(Self::BadConnection), // 4113
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_OUTOFRANGEHIGH => Ok(Self::OutOfRangeHigh), // 4114
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_OUTOFRANGELOW => Ok(Self::OutOfRangeLow), // 4115
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_FAULT => Ok(Self::Fault),                 // 4116
+            ffi::Phidget_ErrorEventCode_EEPHIDGET_ESTOP => Ok(Self::EStop),                 // 4117
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+impl fmt::Display for ErrorEventCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ErrorEventCode::*;
+        let descr = match self {
+            BadVersion => "Client and server protocol versions don't match",
+            Busy => "The channel is already open elsewhere",
+            Network => "A network error occurred",
+            Dispatch => "An error occurred dispatching an event",
+            Failure => "A general failure occurred",
+            Ok => "A previous error condition has cleared",
+            Overrun => "Samples were lost because they arrived faster than they were read",
+            PacketLost => "A packet was lost",
+            Wrap => "A counter wrapped around",
+            OverTemp => "The device is over temperature",
+            OverCurrent => "The device is over current",
+            OutOfRange => "The value is out of range",
+            BadPower => "The device has bad power, or needs to be power cycled",
+            Saturation => "The input is saturated",
+            OverVoltage => "The device is over voltage",
+            Failsafe => "The failsafe was triggered",
+            VoltageError => "The output voltage is in error",
+            EnergyDump => "The device is dumping excess energy",
+            MotorStall => "The motor has stalled",
+            InvalidState => "The device is in an invalid state",
+            BadConnection => "There is a bad connection to the sensor",
+            OutOfRangeHigh => "The value is above the valid range",
+            OutOfRangeLow => "The value is below the valid range",
+            Fault => "The device has a fault",
+            EStop => "The emergency stop was triggered",
+        };
+        write!(f, "{}", descr)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// Whether the reading of a channel is within the range of the sensor.
+///
+/// A sensor that is saturated, or pushed past its limits, reports this
+/// with error events, and with errors from the getters for its reading.
+/// A control loop can use this to hold or clamp its output, rather than
+/// treat the condition as a failure of the channel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Range {
+    /// The reading is within range
+    #[default]
+    Ok,
+    /// The reading is below the range of the sensor
+    UnderRange,
+    /// The reading is above the range of the sensor
+    OverRange,
+    /// The reading is out of range or saturated, in an unknown direction
+    OutOfRange,
+}
+
+impl Range {
+    /// Determines if the reading is within range.
+    pub fn is_ok(&self) -> bool {
+        *self == Range::Ok
+    }
+
+    /// Gets the range status reported by an error event, if the event is
+    /// about the range of the reading.
+    ///
+    /// An [`ErrorEventCode::Ok`] event means that the reading is back
+    /// within range.
+    pub fn from_event(code: ErrorEventCode) -> Option<Self> {
+        use ErrorEventCode::*;
+        match code {
+            Ok => Some(Range::Ok),
+            OutOfRangeLow => Some(Range::UnderRange),
+            OutOfRangeHigh => Some(Range::OverRange),
+            OutOfRange | Saturation => Some(Range::OutOfRange),
+            _ => None,
+        }
+    }
+
+    /// Gets the range status from the error returned by a getter, if the
+    /// error means that the reading is out of range.
+    pub fn from_error(err: ErrorCode) -> Option<Self> {
+        match err {
+            ErrorCode::UnknownValLow => Some(Range::UnderRange),
+            ErrorCode::UnknownValHigh => Some(Range::OverRange),
+            _ => None,
+        }
+    }
+
+    /// Gets the range status from the result of reading a value.
+    ///
+    /// A valid value is in range. Any error other than an out-of-range
+    /// reading is returned as is.
+    pub fn of<T>(res: &Result<T>) -> Result<Self> {
+        match res {
+            Result::Ok(_) => Result::Ok(Range::Ok),
+            Err(err) => Range::from_error(*err).ok_or(*err),
+        }
+    }
+}
+
+/// The error type for the crate is a non-zero phidget22 return code.
+pub type Error = ErrorCode;
 
 /// The default result type for the phidget-rs library
 pub type Result<T> = std::result::Result<T, Error>;
+
+/// Converts the result of a getter so that a value that is temporarily
+/// unavailable is `None` rather than an error.
+pub(crate) fn optional<T>(res: Result<T>) -> Result<Option<T>> {
+    match res {
+        Ok(val) => Ok(Some(val)),
+        Err(err) if err.is_unavailable() => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Converts the result of reading a setting so that one which the channel
+/// doesn't support, or doesn't yet know, is `None` rather than an error.
+pub(crate) fn supported<T>(res: Result<T>) -> Result<Option<T>> {
+    match res {
+        Err(ErrorCode::Unsupported) => Ok(None),
+        res => optional(res),
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// Recovery combinators for the results of calls to a channel.
+///
+/// Since a result can't be evaluated again, each combinator takes a
+/// closure that repeats the call if the recovery is attempted. This lets
+/// the common recovery flows be composed rather than copied around:
+///
+/// ```rust,no_run
+/// use phidget::{devices::TemperatureSensor, ErrorCode, ResultExt};
+/// use std::time::Duration;
+///
+/// let mut sensor = TemperatureSensor::new();
+/// // ...
+/// let temp = sensor
+///     .temperature()
+///     .retry_on(&[ErrorCode::Again], 3, Duration::from_millis(50), || sensor.temperature())
+///     .or_reconnect(&mut sensor, |s| s.temperature())
+///     .unwrap();
+/// ```
+pub trait ResultExt<T> {
+    /// Repeats a failed call while the error is one of the specified
+    /// codes, waiting `delay` before each retry, up to a total of
+    /// `attempts` tries, including the one that produced this result.
+    ///
+    /// The last result is returned, whether success or failure.
+    fn retry_on<F>(self, codes: &[ErrorCode], attempts: u32, delay: Duration, f: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>;
+
+    /// If the call failed because the connection to the channel was
+    /// lost, closes and reopens the channel, waiting the default time for
+    /// it to attach, and then repeats the call once.
+    ///
+    /// See [`ErrorCode::is_connection_lost()`] for the errors that trigger
+    /// the reconnect. If the channel can't be reopened, that error is
+    /// returned.
+    fn or_reconnect<P, F>(self, ph: &mut P, f: F) -> Result<T>
+    where
+        P: Phidget + ?Sized,
+        F: FnOnce(&mut P) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn retry_on<F>(self, codes: &[ErrorCode], attempts: u32, delay: Duration, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let mut res = self;
+        for _ in 1..attempts {
+            match res {
+                Err(err) if codes.contains(&err) => {
+                    thread::sleep(delay);
+                    res = f();
+                }
+                _ => break,
+            }
+        }
+        res
+    }
+
+    fn or_reconnect<P, F>(self, ph: &mut P, f: F) -> Result<T>
+    where
+        P: Phidget + ?Sized,
+        F: FnOnce(&mut P) -> Result<T>,
+    {
+        match self {
+            Err(err) if err.is_connection_lost() => {
+                let _ = ph.close();
+                ph.open_wait_default()?;
+                f(ph)
+            }
+            res => res,
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range() {
+        assert_eq!(
+            Range::from_event(ErrorEventCode::OutOfRangeHigh),
+            Some(Range::OverRange)
+        );
+        assert_eq!(
+            Range::from_event(ErrorEventCode::Saturation),
+            Some(Range::OutOfRange)
+        );
+        assert_eq!(Range::from_event(ErrorEventCode::Ok), Some(Range::Ok));
+        assert_eq!(Range::from_event(ErrorEventCode::PacketLost), None);
+
+        assert_eq!(Range::of(&Ok(1.0)), Ok(Range::Ok));
+        assert_eq!(
+            Range::of::<f64>(&Err(ErrorCode::UnknownValLow)),
+            Ok(Range::UnderRange)
+        );
+        assert_eq!(
+            Range::of::<f64>(&Err(ErrorCode::Timeout)),
+            Err(ErrorCode::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_retry_on() {
+        let mut n = 0;
+        let res = Err(ErrorCode::Again).retry_on(&[ErrorCode::Again], 3, Duration::ZERO, || {
+            n += 1;
+            if n < 2 {
+                Err(ErrorCode::Again)
+            }
+            else {
+                Ok(n)
+            }
+        });
+        assert_eq!(res, Ok(2));
+
+        let mut n = 0;
+        let res: Result<()> =
+            Err(ErrorCode::Again).retry_on(&[ErrorCode::Again], 3, Duration::ZERO, || {
+                n += 1;
+                Err(ErrorCode::Again)
+            });
+        assert_eq!(res, Err(ErrorCode::Again));
+        assert_eq!(n, 2);
+
+        let mut n = 0;
+        let res: Result<()> =
+            Err(ErrorCode::Timeout).retry_on(&[ErrorCode::Again], 3, Duration::ZERO, || {
+                n += 1;
+                Ok(())
+            });
+        assert_eq!(res, Err(ErrorCode::Timeout));
+        assert_eq!(n, 0);
+    }
+}
@@ -0,0 +1,140 @@
+// phidget-rs/src/events.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Time-stamped logs of sensor readings.
+//!
+//! An [`EventLog`] holds readings from any number of named channels, each
+//! stamped with its offset from the start of the log. Logs are produced by
+//! an acquisition [`Session`](crate::session::Session) or a test
+//! [`Recorder`](crate::testing::replay::Recorder), and can be saved and
+//! loaded as simple CSV text.
+//!
+//! The CSV form has one event per line, as `offset,channel,value`, where
+//! the offset is in seconds from the start of the log.
+
+use crate::{ErrorCode, Result};
+use std::{fmt::Write, time::Duration};
+
+/// A single recorded reading.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedEvent {
+    /// The time of the event from the start of the recording
+    pub offset: Duration,
+    /// The name of the channel that produced the event
+    pub channel: String,
+    /// The value of the reading
+    pub value: f64,
+}
+
+/// A log of recorded events, in time order.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EventLog {
+    events: Vec<RecordedEvent>,
+}
+
+impl EventLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an event to the log, keeping the log in time order.
+    pub fn push(&mut self, evt: RecordedEvent) {
+        let i = self.events.partition_point(|e| e.offset <= evt.offset);
+        self.events.insert(i, evt);
+    }
+
+    /// Gets the events in the log.
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Gets the number of events in the log.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Determines if the log is empty.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Gets the duration of the log, which is the offset of the last
+    /// event.
+    pub fn duration(&self) -> Duration {
+        self.events.last().map(|e| e.offset).unwrap_or_default()
+    }
+
+    /// Converts the log to CSV text.
+    pub fn to_csv(&self) -> String {
+        let mut s = String::new();
+        for evt in &self.events {
+            let _ = writeln!(
+                s,
+                "{},{},{}",
+                evt.offset.as_secs_f64(),
+                evt.channel,
+                evt.value
+            );
+        }
+        s
+    }
+
+    /// Parses a log from CSV text.
+    ///
+    /// Blank lines, and lines starting with '#', are ignored. Any other
+    /// malformed line gives an `InvalidArg` error.
+    pub fn from_csv(s: &str) -> Result<Self> {
+        let mut log = Self::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (offset, rest) = line.split_once(',').ok_or(ErrorCode::InvalidArg)?;
+            let (channel, value) = rest.rsplit_once(',').ok_or(ErrorCode::InvalidArg)?;
+
+            let offset = offset
+                .trim()
+                .parse()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .ok_or(ErrorCode::InvalidArg)?;
+            let value = value.trim().parse().map_err(|_| ErrorCode::InvalidArg)?;
+
+            log.push(RecordedEvent {
+                offset,
+                channel: channel.trim().to_string(),
+                value,
+            });
+        }
+        Ok(log)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let csv = "# test log\n0.5,boiler,21.5\n0,tank, 2,1.25\n\n";
+        let log = EventLog::from_csv(csv).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.events()[0].channel, "tank, 2");
+        assert_eq!(log.duration(), Duration::from_millis(500));
+        assert_eq!(EventLog::from_csv(&log.to_csv()), Ok(log));
+
+        assert!(EventLog::from_csv("0.5,boiler").is_err());
+        assert!(EventLog::from_csv("-1,boiler,1.0").is_err());
+    }
+}
@@ -0,0 +1,512 @@
+// phidget-rs/src/failsafe.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Failsafe keep-alive support for output devices.
+//!
+//! Many output channels (digital outputs, steppers, motor controllers)
+//! support a hardware failsafe. Once enabled, the channel must be sent a
+//! "reset failsafe" message before the failsafe time expires, otherwise
+//! the device puts the output into its safe state on its own.
+//!
+//! A [`Watchdog`] runs a single background thread that periodically
+//! resets the failsafe for all of the outputs registered with it, so an
+//! application only needs to keep one keep-alive task running for all of
+//! its actuators. If the application stops running, the resets stop, and
+//! the hardware drops the outputs.
+//!
+//! For a faster response to a crash, [`install_panic_hook()`] adds a
+//! panic hook that immediately releases every output registered with any
+//! watchdog, without waiting for the failsafe time to expire.
+
+use crate::{
+    devices::{
+        BldcMotor, DcMotor, DigitalOutput, MotorPositionController, PowerGuard, RcServo, Stepper,
+    },
+    Result,
+};
+use std::{
+    panic,
+    sync::{Arc, Condvar, Mutex, Once, TryLockError, Weak},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// A device with a hardware failsafe that must be periodically reset.
+pub trait Failsafe: Send {
+    /// Enables the failsafe for the channel with the specified timeout.
+    fn enable_failsafe(&self, time: Duration) -> Result<()>;
+
+    /// Resets the failsafe timer.
+    fn reset_failsafe(&self) -> Result<()>;
+
+    /// Puts the output into its safe state immediately.
+    /// For an output this turns it off; for a motor it lets go of it.
+    fn release(&self) -> Result<()>;
+}
+
+impl Failsafe for DigitalOutput {
+    fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        DigitalOutput::enable_failsafe(self, time)
+    }
+
+    fn reset_failsafe(&self) -> Result<()> {
+        self.set_reset_failsafe()
+    }
+
+    fn release(&self) -> Result<()> {
+        self.set_state(0)
+    }
+}
+
+impl Failsafe for Stepper {
+    fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        Stepper::enable_failsafe(self, time)
+    }
+
+    fn reset_failsafe(&self) -> Result<()> {
+        self.set_reset_failsafe()
+    }
+
+    fn release(&self) -> Result<()> {
+        self.set_engaged(false)
+    }
+}
+
+impl Failsafe for BldcMotor {
+    fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        BldcMotor::enable_failsafe(self, time)
+    }
+
+    fn reset_failsafe(&self) -> Result<()> {
+        BldcMotor::reset_failsafe(self)
+    }
+
+    fn release(&self) -> Result<()> {
+        self.set_target_velocity(0.0)
+    }
+}
+
+impl Failsafe for DcMotor {
+    fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        DcMotor::enable_failsafe(self, time)
+    }
+
+    fn reset_failsafe(&self) -> Result<()> {
+        DcMotor::reset_failsafe(self)
+    }
+
+    fn release(&self) -> Result<()> {
+        self.set_target_velocity(0.0)
+    }
+}
+
+impl Failsafe for RcServo {
+    fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        RcServo::enable_failsafe(self, time)
+    }
+
+    fn reset_failsafe(&self) -> Result<()> {
+        RcServo::reset_failsafe(self)
+    }
+
+    fn release(&self) -> Result<()> {
+        self.set_engaged(false)
+    }
+}
+
+impl Failsafe for MotorPositionController {
+    fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        MotorPositionController::enable_failsafe(self, time)
+    }
+
+    fn reset_failsafe(&self) -> Result<()> {
+        MotorPositionController::reset_failsafe(self)
+    }
+
+    fn release(&self) -> Result<()> {
+        self.set_engaged(false)
+    }
+}
+
+impl Failsafe for PowerGuard {
+    fn enable_failsafe(&self, time: Duration) -> Result<()> {
+        PowerGuard::enable_failsafe(self, time)
+    }
+
+    fn reset_failsafe(&self) -> Result<()> {
+        PowerGuard::reset_failsafe(self)
+    }
+
+    fn release(&self) -> Result<()> {
+        self.set_power_enabled(false)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// A shared handle to a failsafe device.
+pub type SharedFailsafe = Arc<Mutex<dyn Failsafe>>;
+
+// The outputs registered with any watchdog, released by the panic hook.
+static PANIC_RELEASE: Mutex<Vec<Weak<Mutex<dyn Failsafe>>>> = Mutex::new(Vec::new());
+
+// Guards the one-time installation of the panic hook.
+static PANIC_HOOK: Once = Once::new();
+
+// Runs a closure on a device, even if the lock was poisoned by a panic.
+// This will not block if the lock is held elsewhere.
+fn try_with_dev<F>(dev: &Mutex<dyn Failsafe>, f: F)
+where
+    F: FnOnce(&dyn Failsafe),
+{
+    match dev.try_lock() {
+        Ok(dev) => f(&*dev),
+        Err(TryLockError::Poisoned(err)) => f(&*err.into_inner()),
+        Err(TryLockError::WouldBlock) => (),
+    }
+}
+
+// Registers a device to be released by the panic hook.
+fn register_panic_release(dev: &SharedFailsafe) {
+    if let Ok(mut devs) = PANIC_RELEASE.lock() {
+        devs.retain(|dev| dev.strong_count() != 0);
+        devs.push(Arc::downgrade(dev));
+    }
+}
+
+// Resets the failsafe timers for a set of devices.
+fn reset_all(devs: &[SharedFailsafe]) {
+    for dev in devs {
+        if let Ok(dev) = dev.lock() {
+            let _ = dev.reset_failsafe();
+        }
+    }
+}
+
+/// Releases all the outputs registered with any watchdog.
+///
+/// This is what the panic hook does, but it can also be called directly
+/// as part of an emergency stop.
+///
+/// This never blocks, so a device that is locked at the time, such as by
+/// the thread that panicked while using it, is skipped. Such a device is
+/// left to its failsafe: the panic poisons its lock, and the watchdog
+/// doesn't reset a device with a poisoned lock, so the failsafe trips.
+pub fn release_all() {
+    let devs = match PANIC_RELEASE.try_lock() {
+        Ok(devs) => devs,
+        Err(TryLockError::Poisoned(err)) => err.into_inner(),
+        Err(TryLockError::WouldBlock) => return,
+    };
+    for dev in devs.iter().filter_map(Weak::upgrade) {
+        try_with_dev(&dev, |dev| {
+            let _ = dev.release();
+        });
+    }
+}
+
+/// Installs a panic hook that releases all the outputs registered with
+/// any watchdog before running the previously-installed hook.
+///
+/// See [`release_all()`] for the devices that can't be released from the
+/// hook.
+///
+/// This is safe to call multiple times; the hook is only installed once.
+pub fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let prev_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            release_all();
+            prev_hook(info);
+        }));
+    });
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// State shared between the watchdog and its thread.
+struct Shared {
+    // The registered devices
+    devs: Mutex<Vec<SharedFailsafe>>,
+    // Set true to stop the thread
+    stop: Mutex<bool>,
+    // Signaled to wake the thread for a stop
+    cv: Condvar,
+}
+
+/// A keep-alive task that periodically resets the failsafe of a set of
+/// output devices.
+///
+/// The failsafe of each device should be enabled after it is opened,
+/// with a time that is comfortably longer than the watchdog period.
+///
+/// ```rust,no_run
+/// use phidget::{devices::DigitalOutput, failsafe::Watchdog, Phidget};
+/// use std::{sync::{Arc, Mutex}, time::Duration};
+///
+/// let mut out = DigitalOutput::new();
+/// out.open_wait_default().unwrap();
+/// out.enable_failsafe(Duration::from_secs(1)).unwrap();
+///
+/// let out = Arc::new(Mutex::new(out));
+/// let wdog = Watchdog::new(Duration::from_millis(250));
+/// wdog.add(out.clone());
+/// ```
+pub struct Watchdog {
+    // The state shared with the thread
+    shared: Arc<Shared>,
+    // The keep-alive thread
+    thr: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Creates a watchdog that resets the failsafe of all of its devices
+    /// at the specified period, and starts its keep-alive thread.
+    pub fn new(period: Duration) -> Self {
+        let shared = Arc::new(Shared {
+            devs: Mutex::new(Vec::new()),
+            stop: Mutex::new(false),
+            cv: Condvar::new(),
+        });
+
+        let thr = thread::spawn({
+            let shared = Arc::clone(&shared);
+            move || Self::run(&shared, period)
+        });
+
+        Self {
+            shared,
+            thr: Some(thr),
+        }
+    }
+
+    // The keep-alive thread function.
+    fn run(shared: &Shared, period: Duration) {
+        loop {
+            let stop = shared.stop.lock().unwrap();
+            let (stop, _) = shared
+                .cv
+                .wait_timeout_while(stop, period, |stop| !*stop)
+                .unwrap();
+            if *stop {
+                break;
+            }
+            drop(stop);
+
+            // The devices are reset without holding the locks, so that
+            // adding or removing one doesn't wait on the library calls.
+            let devs = shared.devs.lock().unwrap().clone();
+            reset_all(&devs);
+        }
+    }
+
+    /// Adds a device to the watchdog.
+    ///
+    /// The watchdog keeps a reference to the device until the watchdog
+    /// is dropped. The device is also registered to be released by the
+    /// panic hook, if it is installed.
+    pub fn add<T>(&self, dev: Arc<Mutex<T>>)
+    where
+        T: Failsafe + 'static,
+    {
+        let dev: SharedFailsafe = dev;
+        register_panic_release(&dev);
+        self.shared.devs.lock().unwrap().push(dev);
+    }
+
+    /// Gets the number of devices being kept alive by the watchdog.
+    pub fn len(&self) -> usize {
+        self.shared.devs.lock().unwrap().len()
+    }
+
+    /// Determines if there are no devices registered with the watchdog.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Releases all the devices registered with this watchdog.
+    pub fn release(&self) {
+        for dev in self.shared.devs.lock().unwrap().iter() {
+            try_with_dev(dev, |dev| {
+                let _ = dev.release();
+            });
+        }
+    }
+
+    /// Stops the keep-alive thread.
+    ///
+    /// Once stopped, the devices will drop into their failsafe state when
+    /// their failsafe timers expire.
+    pub fn stop(&mut self) {
+        *self.shared.stop.lock().unwrap() = true;
+        self.shared.cv.notify_all();
+        if let Some(thr) = self.thr.take() {
+            let _ = thr.join();
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// An async keep-alive task that periodically resets the failsafe of a
+/// set of output devices.
+///
+/// This is the async counterpart to the [`Watchdog`]. Rather than running
+/// its own thread, the future returned by [`run()`](Self::run) resets the
+/// failsafes for as long as it is polled, so it is usually spawned as a
+/// tokio task. When the task is aborted, or the future is dropped, the
+/// resets stop, and the hardware drops the outputs.
+///
+/// This requires the `tokio` feature.
+///
+/// ```rust,no_run
+/// # async fn run() {
+/// use phidget::{devices::DigitalOutput, failsafe::KeepAlive, Phidget};
+/// use std::{sync::{Arc, Mutex}, time::Duration};
+///
+/// let mut out = DigitalOutput::new();
+/// out.open_wait_default().unwrap();
+/// out.enable_failsafe(Duration::from_secs(1)).unwrap();
+///
+/// let mut keep_alive = KeepAlive::new(Duration::from_millis(250));
+/// keep_alive.add(Arc::new(Mutex::new(out)));
+/// let task = tokio::spawn(keep_alive.run());
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub struct KeepAlive {
+    // The registered devices
+    devs: Vec<SharedFailsafe>,
+    // The reset period
+    period: Duration,
+}
+
+#[cfg(feature = "tokio")]
+impl KeepAlive {
+    /// Creates a keep-alive task that resets the failsafe of all of its
+    /// devices at the specified period.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            devs: Vec::new(),
+            period,
+        }
+    }
+
+    /// Adds a device to the keep-alive task.
+    ///
+    /// The device is also registered to be released by the panic hook, if
+    /// it is installed.
+    pub fn add<T>(&mut self, dev: Arc<Mutex<T>>)
+    where
+        T: Failsafe + 'static,
+    {
+        let dev: SharedFailsafe = dev;
+        register_panic_release(&dev);
+        self.devs.push(dev);
+    }
+
+    /// Gets the number of devices being kept alive.
+    pub fn len(&self) -> usize {
+        self.devs.len()
+    }
+
+    /// Determines if there are no devices registered.
+    pub fn is_empty(&self) -> bool {
+        self.devs.is_empty()
+    }
+
+    /// Resets the failsafes periodically, for as long as the future is
+    /// polled. This never completes on its own.
+    pub async fn run(self) {
+        let mut timer = tokio::time::interval(self.period);
+        timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            timer.tick().await;
+            reset_all(&self.devs);
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Default)]
+    struct Counter {
+        resets: AtomicU32,
+        released: AtomicU32,
+    }
+
+    impl Failsafe for Counter {
+        fn enable_failsafe(&self, _time: Duration) -> Result<()> {
+            Ok(())
+        }
+
+        fn reset_failsafe(&self) -> Result<()> {
+            self.resets.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn release(&self) -> Result<()> {
+            self.released.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_watchdog_resets() {
+        let dev = Arc::new(Mutex::new(Counter::default()));
+        let mut wdog = Watchdog::new(Duration::from_millis(5));
+        wdog.add(dev.clone());
+        assert_eq!(wdog.len(), 1);
+
+        thread::sleep(Duration::from_millis(50));
+        wdog.stop();
+
+        let n = dev.lock().unwrap().resets.load(Ordering::SeqCst);
+        assert!(n > 0);
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(n, dev.lock().unwrap().resets.load(Ordering::SeqCst));
+
+        wdog.release();
+        assert_eq!(1, dev.lock().unwrap().released.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_keep_alive() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+
+        let dev = Arc::new(Mutex::new(Counter::default()));
+        let mut keep_alive = KeepAlive::new(Duration::from_millis(5));
+        keep_alive.add(dev.clone());
+
+        rt.block_on(async {
+            let _ = tokio::time::timeout(Duration::from_millis(50), keep_alive.run()).await;
+        });
+
+        let n = dev.lock().unwrap().resets.load(Ordering::SeqCst);
+        assert!(n > 0);
+    }
+}
@@ -0,0 +1,190 @@
+// phidget-rs/src/firmware.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Firmware version checks for the attached devices.
+//!
+//! An installation may rely on fixes or features that are only in recent
+//! firmware. The [`FirmwareRequirements`] are a table of the minimum
+//! firmware version for each device model, which can be checked against
+//! the devices in a [`DeviceRegistry`] to find the ones that need to be
+//! upgraded:
+//!
+//! ```rust,no_run
+//! use phidget::{firmware::FirmwareRequirements, registry::DeviceRegistry, DeviceId};
+//!
+//! let reqs = FirmwareRequirements::new()
+//!     .require(DeviceId::Tmp1101, 200)
+//!     .require(DeviceId::Hum1001, 110);
+//!
+//! let reg = DeviceRegistry::new();
+//! // ...
+//! for rpt in reg.firmware_report(&reqs) {
+//!     if rpt.status.needs_upgrade() {
+//!         println!("{} [sn={}]: {:?}", rpt.device_sku, rpt.serial_number, rpt.status);
+//!     }
+//! }
+//! ```
+
+use crate::{manager::PhidgetInfo, DeviceId, HubPort, SerialNumber};
+use std::collections::BTreeMap;
+
+/// The minimum firmware versions for the device models.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirmwareRequirements {
+    // The minimum version for each model
+    min: BTreeMap<DeviceId, i32>,
+}
+
+impl FirmwareRequirements {
+    /// Creates an empty table, which has no requirements.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the minimum firmware version for a device model.
+    pub fn require(mut self, id: DeviceId, version: i32) -> Self {
+        self.min.insert(id, version);
+        self
+    }
+
+    /// Gets the minimum firmware version for a device model, if one was
+    /// set.
+    pub fn minimum(&self, id: DeviceId) -> Option<i32> {
+        self.min.get(&id).copied()
+    }
+
+    /// Checks the firmware of the device for a channel.
+    pub fn check(&self, info: &PhidgetInfo) -> FirmwareStatus {
+        match self.minimum(info.device_id) {
+            None => FirmwareStatus::Unchecked,
+            Some(minimum) if info.device_version < minimum => FirmwareStatus::NeedsUpgrade {
+                installed: info.device_version,
+                minimum,
+            },
+            Some(_) => FirmwareStatus::Current,
+        }
+    }
+}
+
+/// The status of the firmware of a device, against the requirements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FirmwareStatus {
+    /// There is no requirement for the device model
+    Unchecked,
+    /// The firmware meets the requirement
+    Current,
+    /// The firmware is older than required
+    NeedsUpgrade {
+        /// The installed version
+        installed: i32,
+        /// The minimum required version
+        minimum: i32,
+    },
+}
+
+impl FirmwareStatus {
+    /// Determines if the device needs a firmware upgrade.
+    pub fn needs_upgrade(&self) -> bool {
+        matches!(self, FirmwareStatus::NeedsUpgrade { .. })
+    }
+}
+
+/// The firmware status of one device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirmwareReport {
+    /// The serial number of the device, or of its hub for a VINT device
+    pub serial_number: SerialNumber,
+    /// The hub port of the device, or the wildcard, [`HubPort::ANY`], if
+    /// it is not on a VINT hub
+    pub hub_port: HubPort,
+    /// The ID of the device model
+    pub device_id: DeviceId,
+    /// The SKU (part number) of the device
+    pub device_sku: String,
+    /// The label of the device, if any
+    pub device_label: String,
+    /// The status of the firmware
+    pub status: FirmwareStatus,
+}
+
+/// Checks the firmware of the devices for a set of channels.
+///
+/// The channels are reduced to one report for each device, in the order
+/// of the device addresses.
+pub fn report<'a, I>(reqs: &FirmwareRequirements, chans: I) -> Vec<FirmwareReport>
+where
+    I: IntoIterator<Item = &'a PhidgetInfo>,
+{
+    let mut devs = BTreeMap::new();
+    for info in chans {
+        devs.entry((info.serial_number, info.hub_port, info.device_id))
+            .or_insert_with(|| FirmwareReport {
+                serial_number: info.serial_number,
+                hub_port: info.hub_port,
+                device_id: info.device_id,
+                device_sku: info.device_sku.clone(),
+                device_label: info.device_label.clone(),
+                status: reqs.check(info),
+            });
+    }
+    devs.into_values().collect()
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Channel, ChannelClass, DeviceClass};
+
+    fn info(channel: i32, device_version: i32) -> PhidgetInfo {
+        PhidgetInfo {
+            serial_number: SerialNumber::new(12345).unwrap(),
+            hub_port: HubPort::new(2).unwrap(),
+            channel: Channel::new(channel).unwrap(),
+            is_hub_port_device: false,
+            channel_class: ChannelClass::TemperatureSensor,
+            device_class: DeviceClass::Vint,
+            device_id: DeviceId::Tmp1101,
+            channel_name: String::new(),
+            device_name: String::new(),
+            device_sku: "TMP1101".into(),
+            device_version,
+            device_label: String::new(),
+            server_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_firmware_report() {
+        let reqs = FirmwareRequirements::new().require(DeviceId::Tmp1101, 200);
+        assert_eq!(reqs.check(&info(0, 200)), FirmwareStatus::Current);
+        assert_eq!(
+            FirmwareRequirements::new().check(&info(0, 100)),
+            FirmwareStatus::Unchecked
+        );
+
+        // The channels of one device give a single report
+        let rpts = report(&reqs, &[info(0, 100), info(1, 100)]);
+        assert_eq!(rpts.len(), 1);
+        assert_eq!(
+            rpts[0].status,
+            FirmwareStatus::NeedsUpgrade {
+                installed: 100,
+                minimum: 200
+            }
+        );
+        assert!(rpts[0].status.needs_upgrade());
+    }
+}
@@ -0,0 +1,477 @@
+// phidget-rs/src/health.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Device health monitoring.
+//!
+//! A [`HealthMonitor`] tracks a set of named devices, counting their error
+//! events and detaches, and the age of the last sample from each. From
+//! these, each device is given a [`Status`]:
+//!
+//! - `Healthy` - The device is attached and producing samples.
+//! - `Stale` - The device is attached, but no sample has been received
+//!   in the allowed time.
+//! - `Faulted` - The device is detached, or has reported an error event
+//!   that has not been followed by a good sample.
+//!
+//! Changes in status are reported to callbacks, or as a stream over a
+//! channel, which is the basis for alerting in unattended installations.
+//! Since a device goes stale by the passage of time, rather than by an
+//! event, the monitor must be checked periodically with
+//! [`HealthMonitor::check()`].
+//!
+//! ```rust,no_run
+//! use phidget::{
+//!     devices::TemperatureSensor, health::HealthMonitor, DeviceSpec, Phidget, SerialNumber,
+//! };
+//! use std::{thread, time::Duration};
+//!
+//! let monitor = HealthMonitor::new(Duration::from_secs(5));
+//! let sn = SerialNumber::new(12345).unwrap();
+//! monitor.add("boiler", DeviceSpec::new().serial_number(sn));
+//! monitor.connect_bus().unwrap();
+//! monitor.on_transition(|t| println!("{}: {:?} -> {:?}", t.name, t.from, t.to));
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! let sample = monitor.sampler("boiler");
+//! sensor.set_on_temperature_change_handler(move |_, _| sample()).unwrap();
+//! sensor.open_wait_default().unwrap();
+//!
+//! loop {
+//!     thread::sleep(Duration::from_secs(1));
+//!     monitor.check();
+//! }
+//! ```
+
+use crate::{
+    bus::{self, BusEvent, SubscriptionId},
+    DeviceSpec, ErrorEventCode, Result,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{mpsc, Arc, Mutex, MutexGuard, Weak},
+    time::{Duration, Instant},
+};
+
+/// The health status of a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Status {
+    /// The device is attached and producing samples
+    Healthy,
+    /// The device is attached, but has not produced a sample in time
+    Stale,
+    /// The device is detached, or reported an error
+    Faulted,
+}
+
+/// A snapshot of the health of a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceHealth {
+    /// The current status
+    pub status: Status,
+    /// Whether the device is attached
+    pub attached: bool,
+    /// The number of error events reported
+    pub error_count: u64,
+    /// The number of times the device detached
+    pub detach_count: u64,
+    /// The most recent error event, if not yet cleared by a good sample
+    pub last_error: Option<ErrorEventCode>,
+    /// The time since the last sample, if any were received
+    pub last_sample_age: Option<Duration>,
+}
+
+/// A change in the status of a device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    /// The name of the device
+    pub name: String,
+    /// The previous status
+    pub from: Status,
+    /// The new status
+    pub to: Status,
+}
+
+// The function type for a transition callback
+type TransitionCallback = dyn Fn(&Transition) + Send + Sync + 'static;
+
+// The tracking information for a single device
+#[derive(Debug)]
+struct Entry {
+    spec: DeviceSpec,
+    attached: bool,
+    // When the device last attached
+    since: Instant,
+    last_sample: Option<Instant>,
+    last_error: Option<ErrorEventCode>,
+    error_count: u64,
+    detach_count: u64,
+    status: Status,
+}
+
+impl Entry {
+    fn new(spec: DeviceSpec, now: Instant) -> Self {
+        Self {
+            spec,
+            attached: false,
+            since: now,
+            last_sample: None,
+            last_error: None,
+            error_count: 0,
+            detach_count: 0,
+            status: Status::Faulted,
+        }
+    }
+
+    // Works out the status of the device at the specified time.
+    fn eval(&self, stale_after: Duration, now: Instant) -> Status {
+        if !self.attached || self.last_error.is_some() {
+            Status::Faulted
+        }
+        else if now.saturating_duration_since(self.last_sample.unwrap_or(self.since))
+            > stale_after
+        {
+            Status::Stale
+        }
+        else {
+            Status::Healthy
+        }
+    }
+}
+
+// The mutable state of the monitor
+#[derive(Default)]
+struct Inner {
+    devices: BTreeMap<String, Entry>,
+    callbacks: Vec<Arc<TransitionCallback>>,
+    senders: Vec<mpsc::Sender<Transition>>,
+    subs: Vec<SubscriptionId>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        for id in self.subs.drain(..) {
+            bus::bus().unsubscribe(id);
+        }
+    }
+}
+
+/// A monitor of the health of a set of devices.
+///
+/// The monitor is a cheap, cloneable handle, so that it can be shared with
+/// the callbacks of several devices.
+#[derive(Clone)]
+pub struct HealthMonitor {
+    // The time without a sample before a device is stale
+    stale_after: Duration,
+    // The shared state
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl HealthMonitor {
+    /// Creates a monitor, in which a device is considered stale if it has
+    /// not produced a sample in the specified time.
+    pub fn new(stale_after: Duration) -> Self {
+        Self {
+            stale_after,
+            inner: Arc::new(Mutex::new(Inner::default())),
+        }
+    }
+
+    /// Adds a device to be monitored.
+    ///
+    /// The device starts out as detached, and so `Faulted`, until it is
+    /// reported as attached. The specification is used to match the
+    /// events from the bus, if connected.
+    pub fn add(&self, name: &str, spec: DeviceSpec) {
+        self.inner
+            .lock()
+            .unwrap()
+            .devices
+            .insert(name.into(), Entry::new(spec, Instant::now()));
+    }
+
+    /// Removes a device from the monitor.
+    pub fn remove(&self, name: &str) -> bool {
+        self.inner.lock().unwrap().devices.remove(name).is_some()
+    }
+
+    /// Subscribes to the application [event bus](crate::bus) to receive the
+    /// attach, detach, and error events for the monitored devices.
+    ///
+    /// Only the devices added before this call are subscribed.
+    pub fn connect_bus(&self) -> Result<()> {
+        let specs: Vec<_> = self
+            .inner
+            .lock()
+            .unwrap()
+            .devices
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.spec.clone()))
+            .collect();
+
+        for (name, spec) in specs {
+            // Only hold a weak reference, so the monitor can be dropped.
+            let weak = Arc::downgrade(&self.inner);
+            let stale_after = self.stale_after;
+            let id = bus::bus().subscribe(spec, move |evt| {
+                if let Some(mon) = Self::upgrade(&weak, stale_after) {
+                    match evt {
+                        BusEvent::Attached(_) => mon.attached(&name),
+                        BusEvent::Detached(_) => mon.detached(&name),
+                        BusEvent::Error { code, .. } => mon.error(&name, *code),
+                    }
+                }
+            })?;
+            self.inner.lock().unwrap().subs.push(id);
+        }
+        Ok(())
+    }
+
+    // Gets a monitor handle from a weak reference to the state.
+    fn upgrade(weak: &Weak<Mutex<Inner>>, stale_after: Duration) -> Option<Self> {
+        weak.upgrade().map(|inner| Self { stale_after, inner })
+    }
+
+    /// Registers a callback for changes in the status of any device.
+    ///
+    /// The callback is made in the context of the thread that reported
+    /// the change, without the monitor being locked.
+    pub fn on_transition<F>(&self, cb: F)
+    where
+        F: Fn(&Transition) + Send + Sync + 'static,
+    {
+        self.inner.lock().unwrap().callbacks.push(Arc::new(cb));
+    }
+
+    /// Gets a stream of the changes in the status of any device.
+    pub fn transitions(&self) -> mpsc::Receiver<Transition> {
+        let (tx, rx) = mpsc::channel();
+        self.inner.lock().unwrap().senders.push(tx);
+        rx
+    }
+
+    /// Gets a function that records a sample from the named device.
+    ///
+    /// This is meant to be called from a device's change handler.
+    pub fn sampler(&self, name: &str) -> impl Fn() + Send + 'static {
+        let mon = self.clone();
+        let name = name.to_string();
+        move || mon.sample(&name)
+    }
+
+    /// Records a sample from the named device.
+    ///
+    /// A good sample clears any previous error event.
+    pub fn sample(&self, name: &str) {
+        self.update(name, Instant::now(), |entry, now| {
+            entry.last_sample = Some(now);
+            entry.last_error = None;
+        });
+    }
+
+    /// Records that the named device attached.
+    pub fn attached(&self, name: &str) {
+        self.update(name, Instant::now(), |entry, now| {
+            entry.attached = true;
+            entry.since = now;
+            entry.last_sample = None;
+        });
+    }
+
+    /// Records that the named device detached.
+    pub fn detached(&self, name: &str) {
+        self.update(name, Instant::now(), |entry, _| {
+            if entry.attached {
+                entry.attached = false;
+                entry.detach_count += 1;
+            }
+        });
+    }
+
+    /// Records an error event from the named device.
+    ///
+    /// An `Ok` event, which signals that a previous error condition has
+    /// cleared, clears the error rather than counting as one.
+    pub fn error(&self, name: &str, code: ErrorEventCode) {
+        self.update(name, Instant::now(), |entry, _| {
+            if code == ErrorEventCode::Ok {
+                entry.last_error = None;
+            }
+            else {
+                entry.error_count += 1;
+                entry.last_error = Some(code);
+            }
+        });
+    }
+
+    /// Re-evaluates the status of all the devices, reporting any that
+    /// have gone stale.
+    ///
+    /// This should be called periodically, at an interval shorter than
+    /// the stale time.
+    pub fn check(&self) {
+        self.check_at(Instant::now());
+    }
+
+    // Re-evaluates the status of all the devices at the specified time.
+    fn check_at(&self, now: Instant) {
+        let mut inner = self.inner.lock().unwrap();
+        let mut changes = Vec::new();
+        for (name, entry) in inner.devices.iter_mut() {
+            if let Some(t) = Self::transition(name, entry, self.stale_after, now) {
+                changes.push(t);
+            }
+        }
+        self.notify(inner, changes);
+    }
+
+    /// Gets the current status of the named device.
+    pub fn status(&self, name: &str) -> Option<Status> {
+        self.inner
+            .lock()
+            .unwrap()
+            .devices
+            .get(name)
+            .map(|entry| entry.status)
+    }
+
+    /// Gets a snapshot of the health of the named device.
+    pub fn health(&self, name: &str) -> Option<DeviceHealth> {
+        let now = Instant::now();
+        self.inner
+            .lock()
+            .unwrap()
+            .devices
+            .get(name)
+            .map(|entry| DeviceHealth {
+                status: entry.status,
+                attached: entry.attached,
+                error_count: entry.error_count,
+                detach_count: entry.detach_count,
+                last_error: entry.last_error,
+                last_sample_age: entry.last_sample.map(|t| now.saturating_duration_since(t)),
+            })
+    }
+
+    /// Gets the current status of all the devices, by name.
+    pub fn statuses(&self) -> BTreeMap<String, Status> {
+        self.inner
+            .lock()
+            .unwrap()
+            .devices
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.status))
+            .collect()
+    }
+
+    // Applies a change to the named device, and reports any transition.
+    fn update<F>(&self, name: &str, now: Instant, f: F)
+    where
+        F: FnOnce(&mut Entry, Instant),
+    {
+        let mut inner = self.inner.lock().unwrap();
+        let change = inner.devices.get_mut(name).and_then(|entry| {
+            f(entry, now);
+            Self::transition(name, entry, self.stale_after, now)
+        });
+        self.notify(inner, change.into_iter().collect());
+    }
+
+    // Updates the status of the device, returning the transition, if any.
+    fn transition(
+        name: &str,
+        entry: &mut Entry,
+        stale_after: Duration,
+        now: Instant,
+    ) -> Option<Transition> {
+        let status = entry.eval(stale_after, now);
+        if status == entry.status {
+            return None;
+        }
+        let from = std::mem::replace(&mut entry.status, status);
+        Some(Transition {
+            name: name.into(),
+            from,
+            to: status,
+        })
+    }
+
+    // Reports the transitions to the callbacks and streams, releasing the
+    // lock before making the callbacks.
+    fn notify(&self, mut inner: MutexGuard<Inner>, changes: Vec<Transition>) {
+        if changes.is_empty() {
+            return;
+        }
+        for t in &changes {
+            inner.senders.retain(|tx| tx.send(t.clone()).is_ok());
+        }
+        let cbs = inner.callbacks.clone();
+        drop(inner);
+
+        for t in &changes {
+            for cb in &cbs {
+                cb(t);
+            }
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        let mon = HealthMonitor::new(Duration::from_secs(5));
+        let rx = mon.transitions();
+
+        mon.add("boiler", DeviceSpec::new());
+        assert_eq!(mon.status("boiler"), Some(Status::Faulted));
+        assert_eq!(mon.status("door"), None);
+
+        mon.attached("boiler");
+        assert_eq!(mon.status("boiler"), Some(Status::Healthy));
+        assert_eq!(rx.try_recv().unwrap().to, Status::Healthy);
+
+        mon.check_at(Instant::now() + Duration::from_secs(10));
+        assert_eq!(mon.status("boiler"), Some(Status::Stale));
+        assert_eq!(rx.try_recv().unwrap().to, Status::Stale);
+
+        mon.sample("boiler");
+        assert_eq!(mon.status("boiler"), Some(Status::Healthy));
+
+        mon.error("boiler", ErrorEventCode::OutOfRange);
+        assert_eq!(mon.status("boiler"), Some(Status::Faulted));
+        mon.error("boiler", ErrorEventCode::Ok);
+        assert_eq!(mon.status("boiler"), Some(Status::Healthy));
+
+        mon.detached("boiler");
+        let health = mon.health("boiler").unwrap();
+        assert_eq!(health.status, Status::Faulted);
+        assert_eq!(health.error_count, 1);
+        assert_eq!(health.detach_count, 1);
+        assert!(!health.attached);
+
+        let from: Vec<_> = rx.try_iter().map(|t| t.from).collect();
+        assert_eq!(
+            from,
+            [
+                Status::Stale,
+                Status::Healthy,
+                Status::Faulted,
+                Status::Healthy
+            ]
+        );
+    }
+}
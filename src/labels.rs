@@ -0,0 +1,93 @@
+// phidget-rs/src/labels.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Device label utilities.
+//!
+//! A label is a short string that can be written into the flash memory
+//! of a Phidget device. Labels are the sane way to address one of several
+//! identical sensors, since unlike a hub port or serial number, the label
+//! follows the device when it gets moved or replaced.
+
+use crate::{ErrorCode, Phidget, Result};
+use std::time::Duration;
+
+/// The maximum length of a device label, in characters.
+///
+/// The devices store the label as UTF-16, so a character outside of the
+/// Basic Multilingual Plane, like an emoji, counts as two. Some older
+/// devices are limited to 7 characters.
+pub const MAX_LABEL_LEN: usize = 10;
+
+/// Checks that a label can be written to a device.
+///
+/// The label must be non-empty, no longer than [`MAX_LABEL_LEN`]
+/// characters, and can not contain control characters. Leading or
+/// trailing whitespace is also rejected, since it can't be seen in most
+/// tools and makes the label hard to match. Returns an `InvalidArg`
+/// error if the label is not valid.
+pub fn validate(label: &str) -> Result<()> {
+    let n = label.encode_utf16().count();
+    if n == 0 || n > MAX_LABEL_LEN || label.chars().any(char::is_control) || label.trim() != label {
+        return Err(ErrorCode::InvalidArg);
+    }
+    Ok(())
+}
+
+/// Determines if the label is valid to be written to a device.
+pub fn is_valid(label: &str) -> bool {
+    validate(label).is_ok()
+}
+
+/// Finds the device with the specified label, and opens the channel of
+/// the requested type on it, waiting a limited time for it to attach.
+///
+/// Any other filters, such as the channel number, can be set afterward
+/// on a closed channel, but it's usually simpler to create the device
+/// and use [`Phidget::set_device_label()`] directly for that.
+///
+/// ```rust,no_run
+/// use phidget::{devices::TemperatureSensor, labels};
+/// use std::time::Duration;
+///
+/// let sensor: TemperatureSensor = labels::find_by_label("boiler", Duration::from_secs(5)).unwrap();
+/// println!("{}", sensor.temperature().unwrap());
+/// ```
+pub fn find_by_label<P>(label: &str, to: Duration) -> Result<P>
+where
+    P: Phidget + Default,
+{
+    let mut dev = P::default();
+    dev.set_device_label(label)?;
+    dev.open_wait(to)?;
+    Ok(dev)
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(is_valid("boiler"));
+        assert!(is_valid("tank 2"));
+        assert!(is_valid("0123456789"));
+        assert!(is_valid("chaudière"));
+
+        assert!(!is_valid(""));
+        assert!(!is_valid("01234567890"));
+        assert!(!is_valid(" boiler"));
+        assert!(!is_valid("boiler\n"));
+        assert!(!is_valid("a\0b"));
+        assert!(!is_valid("🔥🔥🔥🔥🔥🔥"));
+    }
+}
@@ -17,14 +17,11 @@
 //! This example shows how to access a simple Digital Input, connected to the first available channel of a Vint HUB.
 //! See the `examples` directory for more thorough code snippets.
 //! ```rust,no_run
-//! use phidget::{DigitalOutput, Phidget};
+//! use phidget::{DigitalOutput, HubPort, Phidget};
 //! # use std::time::Duration;
 //!
-//! // Create a handle to a Digital Output device
-//! let mut out = DigitalOutput::new();
-//! // Before opening the device, set its VINT hub port
-//! out.set_is_hub_port_device(true).unwrap();
-//! out.set_hub_port(0).unwrap();
+//! // Create a handle to a Digital Output on the first VINT hub port
+//! let mut out = DigitalOutput::hub_port(HubPort::new(0).unwrap()).unwrap();
 //!
 //! // Start connection. Make sure to handle the result
 //! // to check the device is available
@@ -53,20 +50,20 @@
 //! Sync container, such as a [Mutex](std::sync::Mutex).
 //!
 //! ```rust,no_run
-//! # use phidget::{Phidget, DigitalOutput, DigitalInput};
+//! # use phidget::{Channel, Phidget, DigitalOutput, DigitalInput};
 //! # use std::sync::Mutex;
 //! # fn main()
 //! # {
 //! #    // Open a digitalInput to detect a button
 //!     let mut button = DigitalInput::new();
-//! #   button.set_channel(0).unwrap();
+//! #   button.set_channel(Channel::new(0).unwrap()).unwrap();
 //!     // Open the digital output where
 //!     // a LED is connected to.
 //!     // In this example, it is initialized
 //!     // and wrapped in a Mutex
 //!     let led = Mutex::new({
 //!         let mut tmp = DigitalOutput::new();
-//!         tmp.set_channel(1).unwrap();
+//!         tmp.set_channel(Channel::new(1).unwrap()).unwrap();
 //!         tmp.open_wait_default().unwrap();
 //!         tmp
 //!     });
@@ -97,8 +94,10 @@
 
 use std::{
     ffi::CStr,
+    fmt,
     os::raw::{c_char, c_uint, c_void},
     ptr,
+    str::FromStr,
     time::Duration,
 };
 
@@ -113,15 +112,124 @@ pub use crate::errors::*;
 
 /// The main Phidget trait
 pub mod phidget;
-pub use crate::phidget::{AttachCallback, DetachCallback, GenericPhidget, Phidget};
+#[cfg(feature = "tokio")]
+pub use crate::phidget::wait_all_attached_async;
+pub use crate::phidget::{
+    wait_all_attached, AttachCallback, DetachCallback, ErrorCallback, GenericPhidget, LastError,
+    MeshMode, Phidget, Rate, SamplingConfig,
+};
 
 /// Network API
 pub mod net;
 pub use crate::net::ServerType;
 
+/// Automatic reconnection to remote servers
+pub mod reconnect;
+
 /// Module containing all implemented devices
 pub mod devices;
 
+/// Failsafe keep-alive support for output devices
+pub mod failsafe;
+
+/// Common motion control for the different types of actuators
+pub mod motion;
+
+/// Device label utilities
+pub mod labels;
+
+/// Firmware version checks for the attached devices
+pub mod firmware;
+
+/// Resetting the phidget22 library
+pub mod lifecycle;
+
+/// The Phidget Manager, for discovering attached devices
+pub mod manager;
+pub use crate::manager::{Manager, PhidgetInfo};
+
+/// A registry of the attached devices, fed by the manager
+pub mod registry;
+
+/// Hot-plug notifications delivered over a channel
+pub mod watcher;
+
+/// An application-wide bus for device events
+pub mod bus;
+pub use crate::bus::bus;
+
+/// Health monitoring for unattended installations
+pub mod health;
+
+/// Recovery from bad power faults on hub ports
+pub mod recovery;
+
+/// Mapping of the devices in an installation
+pub mod topology;
+
+/// Device addressing specifications
+pub mod spec;
+pub use crate::spec::{Channel, DeviceSpec, HubPort, SerialNumber};
+
+/// Named device aliases
+pub mod aliases;
+pub use crate::aliases::open_named;
+
+/// Compact binary encoding of device information and events
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+pub mod codec;
+
+/// Persistent configuration profiles for channels
+pub mod profile;
+
+/// Introspection of the settings of a channel
+pub mod properties;
+
+/// Time-stamped logs of sensor readings
+pub mod events;
+
+/// Long-running acquisition sessions
+pub mod session;
+
+/// Rate-limiting and coalescing adapters for event callbacks
+pub mod throttle;
+
+/// A latest-value cache for sensor readings
+pub mod watch;
+
+/// Lock-free ring buffers for high-rate streaming
+pub mod ring;
+
+/// Capability traits for sensors and outputs
+pub mod sources;
+
+/// Utilities for testing applications, like mock devices
+pub mod testing;
+
+/// A high-level view of a VINT hub and its sensors
+pub mod sensor_hub;
+
+/// Async device actors for use with tokio
+#[cfg(feature = "tokio")]
+pub mod actor;
+
+/// Shutdown on Ctrl-C and termination signals
+#[cfg(feature = "signals")]
+pub mod signals;
+#[cfg(feature = "signals")]
+pub use crate::signals::run_until_shutdown;
+
+/// A WebSocket server for device events
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+/// Exposing sensors over D-Bus, on Linux
+#[cfg(all(feature = "dbus", target_os = "linux"))]
+pub mod dbus;
+
+/// The most commonly used types and traits
+pub mod prelude;
+
 // For v0.1.x compatibility, sensors available at the root
 pub use crate::devices::{
     digital_input::DigitalInput, digital_output::DigitalOutput, hub::Hub,
@@ -140,7 +248,19 @@ pub const TIMEOUT_DEFAULT: Duration = Duration::from_millis(PHIDGET_TIMEOUT_DEFA
 /// Gets a string from a phidget22 call.
 /// This can be any function that takes a pointer to a c-str as the lone
 /// argument.
-pub(crate) fn get_ffi_string<F>(mut f: F) -> Result<String>
+pub(crate) fn get_ffi_string<F>(f: F) -> Result<String>
+where
+    F: FnMut(*mut *const c_char) -> c_uint,
+{
+    let mut s = String::new();
+    get_ffi_string_into(&mut s, f)?;
+    Ok(s)
+}
+
+/// Gets a string from a phidget22 call into an existing buffer.
+/// The buffer is cleared first, but keeps its allocation, so repeated
+/// reads of a short string don't allocate.
+pub(crate) fn get_ffi_string_into<F>(buf: &mut String, mut f: F) -> Result<()>
 where
     F: FnMut(*mut *const c_char) -> c_uint,
 {
@@ -148,10 +268,12 @@ where
         let mut ver: *const c_char = ptr::null_mut();
         ReturnCode::result(f(&mut ver))?;
         if ver.is_null() {
-            return Err(ReturnCode::NoMemory);
+            return Err(ErrorCode::NoMemory);
         }
         let s = CStr::from_ptr(ver);
-        Ok(s.to_string_lossy().into())
+        buf.clear();
+        buf.push_str(&s.to_string_lossy());
+        Ok(())
     }
 }
 
@@ -164,6 +286,7 @@ pub(crate) fn drop_cb<P: ?Sized>(cb: Option<*mut c_void>) {
 
 /// Phidget channel class
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 #[allow(missing_docs)]
 pub enum ChannelClass {
@@ -259,13 +382,124 @@ impl TryFrom<u32> for ChannelClass {
             ffi::Phidget_ChannelClass_PHIDCHCLASS_VOLTAGEINPUT => Ok(VoltageInput), // 29
             ffi::Phidget_ChannelClass_PHIDCHCLASS_VOLTAGEOUTPUT => Ok(VoltageOutput), // 30
             ffi::Phidget_ChannelClass_PHIDCHCLASS_VOLTAGERATIOINPUT => Ok(VoltageRatioInput), // 31
-            _ => Err(ReturnCode::InvalidArg),
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+impl ChannelClass {
+    /// Gets the name of the channel class as used by the phidget22 library,
+    /// like "PHIDCHCLASS_TEMPERATURESENSOR".
+    pub fn name(&self) -> &'static str {
+        use ChannelClass::*;
+        match self {
+            Nothing => "PHIDCHCLASS_NOTHING",
+            Accelerometer => "PHIDCHCLASS_ACCELEROMETER",
+            BldcMotor => "PHIDCHCLASS_BLDCMOTOR",
+            CaptiveTouch => "PHIDCHCLASS_CAPACITIVETOUCH",
+            CurrentInput => "PHIDCHCLASS_CURRENTINPUT",
+            CurrentOutput => "PHIDCHCLASS_CURRENTOUTPUT",
+            DataAdapter => "PHIDCHCLASS_DATAADAPTER",
+            DcMotor => "PHIDCHCLASS_DCMOTOR",
+            Dictionary => "PHIDCHCLASS_DICTIONARY",
+            DigitalInput => "PHIDCHCLASS_DIGITALINPUT",
+            DigitalOutput => "PHIDCHCLASS_DIGITALOUTPUT",
+            DistanceSensor => "PHIDCHCLASS_DISTANCESENSOR",
+            Encoder => "PHIDCHCLASS_ENCODER",
+            FirmwareUpgrade => "PHIDCHCLASS_FIRMWAREUPGRADE",
+            FrequencyCounter => "PHIDCHCLASS_FREQUENCYCOUNTER",
+            Generic => "PHIDCHCLASS_GENERIC",
+            Gps => "PHIDCHCLASS_GPS",
+            Gyroscope => "PHIDCHCLASS_GYROSCOPE",
+            Hub => "PHIDCHCLASS_HUB",
+            HumiditySensor => "PHIDCHCLASS_HUMIDITYSENSOR",
+            Ir => "PHIDCHCLASS_IR",
+            Lcd => "PHIDCHCLASS_LCD",
+            LightSensor => "PHIDCHCLASS_LIGHTSENSOR",
+            Magnetometer => "PHIDCHCLASS_MAGNETOMETER",
+            MeshDongle => "PHIDCHCLASS_MESHDONGLE",
+            MotorPositionController => "PHIDCHCLASS_MOTORPOSITIONCONTROLLER",
+            MotorVelocityController => "PHIDCHCLASS_MOTORVELOCITYCONTROLLER",
+            PhSensor => "PHIDCHCLASS_PHSENSOR",
+            PowerGuard => "PHIDCHCLASS_POWERGUARD",
+            PressureSensor => "PHIDCHCLASS_PRESSURESENSOR",
+            RcServo => "PHIDCHCLASS_RCSERVO",
+            ResistanceInput => "PHIDCHCLASS_RESISTANCEINPUT",
+            Rfid => "PHIDCHCLASS_RFID",
+            SoundSensor => "PHIDCHCLASS_SOUNDSENSOR",
+            Spatial => "PHIDCHCLASS_SPATIAL",
+            Stepper => "PHIDCHCLASS_STEPPER",
+            TemperatureSensor => "PHIDCHCLASS_TEMPERATURESENSOR",
+            VoltageInput => "PHIDCHCLASS_VOLTAGEINPUT",
+            VoltageOutput => "PHIDCHCLASS_VOLTAGEOUTPUT",
+            VoltageRatioInput => "PHIDCHCLASS_VOLTAGERATIOINPUT",
+        }
+    }
+}
+
+impl fmt::Display for ChannelClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ChannelClass {
+    type Err = Error;
+
+    /// Parses the library name of the channel class. The match is not case
+    /// sensitive, and the "PHIDCHCLASS_" prefix is optional.
+    fn from_str(s: &str) -> Result<Self> {
+        use ChannelClass::*;
+        let s = s.trim().to_ascii_uppercase();
+        match s.strip_prefix("PHIDCHCLASS_").unwrap_or(&s) {
+            "NOTHING" => Ok(Nothing),
+            "ACCELEROMETER" => Ok(Accelerometer),
+            "BLDCMOTOR" => Ok(BldcMotor),
+            "CAPACITIVETOUCH" => Ok(CaptiveTouch),
+            "CURRENTINPUT" => Ok(CurrentInput),
+            "CURRENTOUTPUT" => Ok(CurrentOutput),
+            "DATAADAPTER" => Ok(DataAdapter),
+            "DCMOTOR" => Ok(DcMotor),
+            "DICTIONARY" => Ok(Dictionary),
+            "DIGITALINPUT" => Ok(DigitalInput),
+            "DIGITALOUTPUT" => Ok(DigitalOutput),
+            "DISTANCESENSOR" => Ok(DistanceSensor),
+            "ENCODER" => Ok(Encoder),
+            "FIRMWAREUPGRADE" => Ok(FirmwareUpgrade),
+            "FREQUENCYCOUNTER" => Ok(FrequencyCounter),
+            "GENERIC" => Ok(Generic),
+            "GPS" => Ok(Gps),
+            "GYROSCOPE" => Ok(Gyroscope),
+            "HUB" => Ok(Hub),
+            "HUMIDITYSENSOR" => Ok(HumiditySensor),
+            "IR" => Ok(Ir),
+            "LCD" => Ok(Lcd),
+            "LIGHTSENSOR" => Ok(LightSensor),
+            "MAGNETOMETER" => Ok(Magnetometer),
+            "MESHDONGLE" => Ok(MeshDongle),
+            "MOTORPOSITIONCONTROLLER" => Ok(MotorPositionController),
+            "MOTORVELOCITYCONTROLLER" => Ok(MotorVelocityController),
+            "PHSENSOR" => Ok(PhSensor),
+            "POWERGUARD" => Ok(PowerGuard),
+            "PRESSURESENSOR" => Ok(PressureSensor),
+            "RCSERVO" => Ok(RcServo),
+            "RESISTANCEINPUT" => Ok(ResistanceInput),
+            "RFID" => Ok(Rfid),
+            "SOUNDSENSOR" => Ok(SoundSensor),
+            "SPATIAL" => Ok(Spatial),
+            "STEPPER" => Ok(Stepper),
+            "TEMPERATURESENSOR" => Ok(TemperatureSensor),
+            "VOLTAGEINPUT" => Ok(VoltageInput),
+            "VOLTAGEOUTPUT" => Ok(VoltageOutput),
+            "VOLTAGERATIOINPUT" => Ok(VoltageRatioInput),
+            _ => Err(ErrorCode::InvalidArg),
         }
     }
 }
 
 /// Phidget device class
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 #[allow(missing_docs)]
 pub enum DeviceClass {
@@ -329,7 +563,810 @@ impl TryFrom<u32> for DeviceClass {
             ffi::Phidget_DeviceClass_PHIDCLASS_TEMPERATURESENSOR => Ok(TemperatreSensor), // 19
             ffi::Phidget_DeviceClass_PHIDCLASS_TEXTLCD => Ok(TextLcd), // 20
             ffi::Phidget_DeviceClass_PHIDCLASS_VINT => Ok(Vint),       // 21
-            _ => Err(ReturnCode::InvalidArg),
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+impl DeviceClass {
+    /// Gets the name of the device class as used by the phidget22 library,
+    /// like "PHIDCLASS_VINT".
+    pub fn name(&self) -> &'static str {
+        use DeviceClass::*;
+        match self {
+            Nothing => "PHIDCLASS_NOTHING",
+            Accelerometer => "PHIDCLASS_ACCELEROMETER",
+            AdvancedServo => "PHIDCLASS_ADVANCEDSERVO",
+            Analog => "PHIDCLASS_ANALOG",
+            Bridge => "PHIDCLASS_BRIDGE",
+            DataAdapter => "PHIDCLASS_DATAADAPTER",
+            Dictionary => "PHIDCLASS_DICTIONARY",
+            Encoder => "PHIDCLASS_ENCODER",
+            FirmwareUpgrade => "PHIDCLASS_FIRMWAREUPGRADE",
+            FrequencyCounter => "PHIDCLASS_FREQUENCYCOUNTER",
+            Generic => "PHIDCLASS_GENERIC",
+            Gps => "PHIDCLASS_GPS",
+            Hub => "PHIDCLASS_HUB",
+            InterfaceKit => "PHIDCLASS_INTERFACEKIT",
+            Ir => "PHIDCLASS_IR",
+            Led => "PHIDCLASS_LED",
+            MeshDongle => "PHIDCLASS_MESHDONGLE",
+            MotorControl => "PHIDCLASS_MOTORCONTROL",
+            PhSensor => "PHIDCLASS_PHSENSOR",
+            Rfid => "PHIDCLASS_RFID",
+            Servo => "PHIDCLASS_SERVO",
+            Spatial => "PHIDCLASS_SPATIAL",
+            Steper => "PHIDCLASS_STEPPER",
+            TemperatreSensor => "PHIDCLASS_TEMPERATURESENSOR",
+            TextLcd => "PHIDCLASS_TEXTLCD",
+            Vint => "PHIDCLASS_VINT",
+        }
+    }
+}
+
+impl fmt::Display for DeviceClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for DeviceClass {
+    type Err = Error;
+
+    /// Parses the library name of the device class. The match is not case
+    /// sensitive, and the "PHIDCLASS_" prefix is optional.
+    fn from_str(s: &str) -> Result<Self> {
+        use DeviceClass::*;
+        let s = s.trim().to_ascii_uppercase();
+        match s.strip_prefix("PHIDCLASS_").unwrap_or(&s) {
+            "NOTHING" => Ok(Nothing),
+            "ACCELEROMETER" => Ok(Accelerometer),
+            "ADVANCEDSERVO" => Ok(AdvancedServo),
+            "ANALOG" => Ok(Analog),
+            "BRIDGE" => Ok(Bridge),
+            "DATAADAPTER" => Ok(DataAdapter),
+            "DICTIONARY" => Ok(Dictionary),
+            "ENCODER" => Ok(Encoder),
+            "FIRMWAREUPGRADE" => Ok(FirmwareUpgrade),
+            "FREQUENCYCOUNTER" => Ok(FrequencyCounter),
+            "GENERIC" => Ok(Generic),
+            "GPS" => Ok(Gps),
+            "HUB" => Ok(Hub),
+            "INTERFACEKIT" => Ok(InterfaceKit),
+            "IR" => Ok(Ir),
+            "LED" => Ok(Led),
+            "MESHDONGLE" => Ok(MeshDongle),
+            "MOTORCONTROL" => Ok(MotorControl),
+            "PHSENSOR" => Ok(PhSensor),
+            "RFID" => Ok(Rfid),
+            "SERVO" => Ok(Servo),
+            "SPATIAL" => Ok(Spatial),
+            "STEPPER" => Ok(Steper),
+            "TEMPERATURESENSOR" => Ok(TemperatreSensor),
+            "TEXTLCD" => Ok(TextLcd),
+            "VINT" => Ok(Vint),
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// Phidget device ID
+///
+/// This identifies the specific model of a device. The variants for the
+/// newer devices are named after the SKU (part number) of the device,
+/// and the older ones after their numeric model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+#[allow(missing_docs)]
+pub enum DeviceId {
+    Nothing = ffi::Phidget_DeviceID_PHIDID_NOTHING, // 0
+    Unknown = ffi::Phidget_DeviceID_PHIDID_UNKNOWN, // 125
+    DigitalInputPort = ffi::Phidget_DeviceID_PHIDID_DIGITALINPUT_PORT, // 95
+    DigitalOutputPort = ffi::Phidget_DeviceID_PHIDID_DIGITALOUTPUT_PORT, // 96
+    VoltageInputPort = ffi::Phidget_DeviceID_PHIDID_VOLTAGEINPUT_PORT, // 97
+    VoltageRatioInputPort = ffi::Phidget_DeviceID_PHIDID_VOLTAGERATIOINPUT_PORT, // 98
+    Dictionary = ffi::Phidget_DeviceID_PHIDID_DICTIONARY, // 111
+    Id1000 = ffi::Phidget_DeviceID_PHIDID_1000,     // 2
+    Id1001 = ffi::Phidget_DeviceID_PHIDID_1001,     // 3
+    Id1002 = ffi::Phidget_DeviceID_PHIDID_1002,     // 4
+    Id1008 = ffi::Phidget_DeviceID_PHIDID_1008,     // 5
+    Id1010_1013_1018_1019 = ffi::Phidget_DeviceID_PHIDID_1010_1013_1018_1019, // 6
+    Id1011 = ffi::Phidget_DeviceID_PHIDID_1011,     // 7
+    Id1012 = ffi::Phidget_DeviceID_PHIDID_1012,     // 8
+    Id1014 = ffi::Phidget_DeviceID_PHIDID_1014,     // 9
+    Id1015 = ffi::Phidget_DeviceID_PHIDID_1015,     // 10
+    Id1016 = ffi::Phidget_DeviceID_PHIDID_1016,     // 11
+    Id1017 = ffi::Phidget_DeviceID_PHIDID_1017,     // 12
+    Id1023 = ffi::Phidget_DeviceID_PHIDID_1023,     // 13
+    Id1024 = ffi::Phidget_DeviceID_PHIDID_1024,     // 14
+    Id1030 = ffi::Phidget_DeviceID_PHIDID_1030,     // 15
+    Id1031 = ffi::Phidget_DeviceID_PHIDID_1031,     // 16
+    Id1032 = ffi::Phidget_DeviceID_PHIDID_1032,     // 17
+    Id1040 = ffi::Phidget_DeviceID_PHIDID_1040,     // 18
+    Id1041 = ffi::Phidget_DeviceID_PHIDID_1041,     // 19
+    Id1042 = ffi::Phidget_DeviceID_PHIDID_1042,     // 20
+    Id1043 = ffi::Phidget_DeviceID_PHIDID_1043,     // 21
+    Id1044 = ffi::Phidget_DeviceID_PHIDID_1044,     // 22
+    Id1045 = ffi::Phidget_DeviceID_PHIDID_1045,     // 23
+    Id1046 = ffi::Phidget_DeviceID_PHIDID_1046,     // 24
+    Id1047 = ffi::Phidget_DeviceID_PHIDID_1047,     // 25
+    Id1048 = ffi::Phidget_DeviceID_PHIDID_1048,     // 26
+    Id1049 = ffi::Phidget_DeviceID_PHIDID_1049,     // 27
+    Id1051 = ffi::Phidget_DeviceID_PHIDID_1051,     // 28
+    Id1052 = ffi::Phidget_DeviceID_PHIDID_1052,     // 29
+    Id1053 = ffi::Phidget_DeviceID_PHIDID_1053,     // 30
+    Id1054 = ffi::Phidget_DeviceID_PHIDID_1054,     // 31
+    Id1055 = ffi::Phidget_DeviceID_PHIDID_1055,     // 32
+    Id1056 = ffi::Phidget_DeviceID_PHIDID_1056,     // 33
+    Id1057 = ffi::Phidget_DeviceID_PHIDID_1057,     // 34
+    Id1058 = ffi::Phidget_DeviceID_PHIDID_1058,     // 35
+    Id1059 = ffi::Phidget_DeviceID_PHIDID_1059,     // 36
+    Id1060 = ffi::Phidget_DeviceID_PHIDID_1060,     // 37
+    Id1061 = ffi::Phidget_DeviceID_PHIDID_1061,     // 38
+    Id1062 = ffi::Phidget_DeviceID_PHIDID_1062,     // 39
+    Id1063 = ffi::Phidget_DeviceID_PHIDID_1063,     // 40
+    Id1064 = ffi::Phidget_DeviceID_PHIDID_1064,     // 41
+    Id1065 = ffi::Phidget_DeviceID_PHIDID_1065,     // 42
+    Id1066 = ffi::Phidget_DeviceID_PHIDID_1066,     // 43
+    Id1067 = ffi::Phidget_DeviceID_PHIDID_1067,     // 44
+    Id1202_1203 = ffi::Phidget_DeviceID_PHIDID_1202_1203, // 45
+    Id1204 = ffi::Phidget_DeviceID_PHIDID_1204,     // 46
+    Id1215_1218 = ffi::Phidget_DeviceID_PHIDID_1215__1218, // 47
+    Id1219_1222 = ffi::Phidget_DeviceID_PHIDID_1219__1222, // 48
+    Adp1000 = ffi::Phidget_DeviceID_PHIDID_ADP1000, // 49
+    Daq1000 = ffi::Phidget_DeviceID_PHIDID_DAQ1000, // 51
+    Daq1200 = ffi::Phidget_DeviceID_PHIDID_DAQ1200, // 52
+    Daq1300 = ffi::Phidget_DeviceID_PHIDID_DAQ1300, // 53
+    Daq1301 = ffi::Phidget_DeviceID_PHIDID_DAQ1301, // 54
+    Daq1400 = ffi::Phidget_DeviceID_PHIDID_DAQ1400, // 55
+    Daq1500 = ffi::Phidget_DeviceID_PHIDID_DAQ1500, // 56
+    Dcc1000 = ffi::Phidget_DeviceID_PHIDID_DCC1000, // 57
+    Dcc1001 = ffi::Phidget_DeviceID_PHIDID_DCC1001, // 110
+    Dcc1002 = ffi::Phidget_DeviceID_PHIDID_DCC1002, // 117
+    Dcc1003 = ffi::Phidget_DeviceID_PHIDID_DCC1003, // 120
+    Dcc1100 = ffi::Phidget_DeviceID_PHIDID_DCC1100, // 108
+    Dst1000 = ffi::Phidget_DeviceID_PHIDID_DST1000, // 58
+    Dst1001 = ffi::Phidget_DeviceID_PHIDID_DST1001, // 121
+    Dst1002 = ffi::Phidget_DeviceID_PHIDID_DST1002, // 126
+    Dst1200 = ffi::Phidget_DeviceID_PHIDID_DST1200, // 59
+    Enc1000 = ffi::Phidget_DeviceID_PHIDID_ENC1000, // 60
+    Enc1001 = ffi::Phidget_DeviceID_PHIDID_ENC1001, // 155
+    FirmwareUpgradeSpi = ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_SPI, // 104
+    FirmwareUpgradeStm32F0 = ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_STM32F0, // 102
+    FirmwareUpgradeStm32F3 = ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_STM32F3, // 145
+    FirmwareUpgradeStm32G0 = ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_STM32G0, // 143
+    FirmwareUpgradeStm8S = ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_STM8S, // 103
+    FirmwareUpgradeUsb = ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_USB, // 101
+    Hin1000 = ffi::Phidget_DeviceID_PHIDID_HIN1000, // 61
+    Hin1001 = ffi::Phidget_DeviceID_PHIDID_HIN1001, // 62
+    Hin1100 = ffi::Phidget_DeviceID_PHIDID_HIN1100, // 63
+    Hin1101 = ffi::Phidget_DeviceID_PHIDID_HIN1101, // 109
+    Hub0000 = ffi::Phidget_DeviceID_PHIDID_HUB0000, // 64
+    Hub0001 = ffi::Phidget_DeviceID_PHIDID_HUB0001, // 142
+    Hub0002 = ffi::Phidget_DeviceID_PHIDID_HUB0002, // 147
+    Hub0004 = ffi::Phidget_DeviceID_PHIDID_HUB0004, // 67
+    Hub0007 = ffi::Phidget_DeviceID_PHIDID_HUB0007, // 148
+    Hub5000 = ffi::Phidget_DeviceID_PHIDID_HUB5000, // 123
+    Hum1000 = ffi::Phidget_DeviceID_PHIDID_HUM1000, // 69
+    Hum1001 = ffi::Phidget_DeviceID_PHIDID_HUM1001, // 127
+    Hum1100 = ffi::Phidget_DeviceID_PHIDID_HUM1100, // 136
+    InterfaceKit488 = ffi::Phidget_DeviceID_PHIDID_INTERFACEKIT_4_8_8, // 1
+    Lcd1100 = ffi::Phidget_DeviceID_PHIDID_LCD1100, // 70
+    Led1000 = ffi::Phidget_DeviceID_PHIDID_LED1000, // 71
+    Lux1000 = ffi::Phidget_DeviceID_PHIDID_LUX1000, // 72
+    Mot0100 = ffi::Phidget_DeviceID_PHIDID_MOT0100, // 146
+    Mot0109 = ffi::Phidget_DeviceID_PHIDID_MOT0109, // 140
+    Mot0110 = ffi::Phidget_DeviceID_PHIDID_MOT0110, // 141
+    Mot1100 = ffi::Phidget_DeviceID_PHIDID_MOT1100, // 73
+    Mot1101 = ffi::Phidget_DeviceID_PHIDID_MOT1101, // 74
+    Mot1102 = ffi::Phidget_DeviceID_PHIDID_MOT1102, // 137
+    Out1000 = ffi::Phidget_DeviceID_PHIDID_OUT1000, // 75
+    Out1001 = ffi::Phidget_DeviceID_PHIDID_OUT1001, // 76
+    Out1002 = ffi::Phidget_DeviceID_PHIDID_OUT1002, // 77
+    Out1100 = ffi::Phidget_DeviceID_PHIDID_OUT1100, // 78
+    Pre1000 = ffi::Phidget_DeviceID_PHIDID_PRE1000, // 79
+    Rcc0004 = ffi::Phidget_DeviceID_PHIDID_RCC0004, // 124
+    Rcc1000 = ffi::Phidget_DeviceID_PHIDID_RCC1000, // 80
+    Rel1000 = ffi::Phidget_DeviceID_PHIDID_REL1000, // 81
+    Rel1100 = ffi::Phidget_DeviceID_PHIDID_REL1100, // 82
+    Rel1101 = ffi::Phidget_DeviceID_PHIDID_REL1101, // 83
+    Saf1000 = ffi::Phidget_DeviceID_PHIDID_SAF1000, // 84
+    Snd1000 = ffi::Phidget_DeviceID_PHIDID_SND1000, // 85
+    Stc1000 = ffi::Phidget_DeviceID_PHIDID_STC1000, // 86
+    Stc1001 = ffi::Phidget_DeviceID_PHIDID_STC1001, // 115
+    Stc1002 = ffi::Phidget_DeviceID_PHIDID_STC1002, // 118
+    Stc1003 = ffi::Phidget_DeviceID_PHIDID_STC1003, // 119
+    Stc1005 = ffi::Phidget_DeviceID_PHIDID_STC1005, // 149
+    Tmp1000 = ffi::Phidget_DeviceID_PHIDID_TMP1000, // 87
+    Tmp1100 = ffi::Phidget_DeviceID_PHIDID_TMP1100, // 88
+    Tmp1101 = ffi::Phidget_DeviceID_PHIDID_TMP1101, // 89
+    Tmp1200 = ffi::Phidget_DeviceID_PHIDID_TMP1200, // 90
+    Vcp1000 = ffi::Phidget_DeviceID_PHIDID_VCP1000, // 92
+    Vcp1001 = ffi::Phidget_DeviceID_PHIDID_VCP1001, // 93
+    Vcp1002 = ffi::Phidget_DeviceID_PHIDID_VCP1002, // 94
+    Vcp1100 = ffi::Phidget_DeviceID_PHIDID_VCP1100, // 105
+}
+
+impl TryFrom<u32> for DeviceId {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use DeviceId::*;
+        match val {
+            ffi::Phidget_DeviceID_PHIDID_NOTHING => Ok(Nothing), // 0
+            ffi::Phidget_DeviceID_PHIDID_UNKNOWN => Ok(Unknown), // 125
+            ffi::Phidget_DeviceID_PHIDID_DIGITALINPUT_PORT => Ok(DigitalInputPort), // 95
+            ffi::Phidget_DeviceID_PHIDID_DIGITALOUTPUT_PORT => Ok(DigitalOutputPort), // 96
+            ffi::Phidget_DeviceID_PHIDID_VOLTAGEINPUT_PORT => Ok(VoltageInputPort), // 97
+            ffi::Phidget_DeviceID_PHIDID_VOLTAGERATIOINPUT_PORT => Ok(VoltageRatioInputPort), // 98
+            ffi::Phidget_DeviceID_PHIDID_DICTIONARY => Ok(Dictionary), // 111
+            ffi::Phidget_DeviceID_PHIDID_1000 => Ok(Id1000),     // 2
+            ffi::Phidget_DeviceID_PHIDID_1001 => Ok(Id1001),     // 3
+            ffi::Phidget_DeviceID_PHIDID_1002 => Ok(Id1002),     // 4
+            ffi::Phidget_DeviceID_PHIDID_1008 => Ok(Id1008),     // 5
+            ffi::Phidget_DeviceID_PHIDID_1010_1013_1018_1019 => Ok(Id1010_1013_1018_1019), // 6
+            ffi::Phidget_DeviceID_PHIDID_1011 => Ok(Id1011),     // 7
+            ffi::Phidget_DeviceID_PHIDID_1012 => Ok(Id1012),     // 8
+            ffi::Phidget_DeviceID_PHIDID_1014 => Ok(Id1014),     // 9
+            ffi::Phidget_DeviceID_PHIDID_1015 => Ok(Id1015),     // 10
+            ffi::Phidget_DeviceID_PHIDID_1016 => Ok(Id1016),     // 11
+            ffi::Phidget_DeviceID_PHIDID_1017 => Ok(Id1017),     // 12
+            ffi::Phidget_DeviceID_PHIDID_1023 => Ok(Id1023),     // 13
+            ffi::Phidget_DeviceID_PHIDID_1024 => Ok(Id1024),     // 14
+            ffi::Phidget_DeviceID_PHIDID_1030 => Ok(Id1030),     // 15
+            ffi::Phidget_DeviceID_PHIDID_1031 => Ok(Id1031),     // 16
+            ffi::Phidget_DeviceID_PHIDID_1032 => Ok(Id1032),     // 17
+            ffi::Phidget_DeviceID_PHIDID_1040 => Ok(Id1040),     // 18
+            ffi::Phidget_DeviceID_PHIDID_1041 => Ok(Id1041),     // 19
+            ffi::Phidget_DeviceID_PHIDID_1042 => Ok(Id1042),     // 20
+            ffi::Phidget_DeviceID_PHIDID_1043 => Ok(Id1043),     // 21
+            ffi::Phidget_DeviceID_PHIDID_1044 => Ok(Id1044),     // 22
+            ffi::Phidget_DeviceID_PHIDID_1045 => Ok(Id1045),     // 23
+            ffi::Phidget_DeviceID_PHIDID_1046 => Ok(Id1046),     // 24
+            ffi::Phidget_DeviceID_PHIDID_1047 => Ok(Id1047),     // 25
+            ffi::Phidget_DeviceID_PHIDID_1048 => Ok(Id1048),     // 26
+            ffi::Phidget_DeviceID_PHIDID_1049 => Ok(Id1049),     // 27
+            ffi::Phidget_DeviceID_PHIDID_1051 => Ok(Id1051),     // 28
+            ffi::Phidget_DeviceID_PHIDID_1052 => Ok(Id1052),     // 29
+            ffi::Phidget_DeviceID_PHIDID_1053 => Ok(Id1053),     // 30
+            ffi::Phidget_DeviceID_PHIDID_1054 => Ok(Id1054),     // 31
+            ffi::Phidget_DeviceID_PHIDID_1055 => Ok(Id1055),     // 32
+            ffi::Phidget_DeviceID_PHIDID_1056 => Ok(Id1056),     // 33
+            ffi::Phidget_DeviceID_PHIDID_1057 => Ok(Id1057),     // 34
+            ffi::Phidget_DeviceID_PHIDID_1058 => Ok(Id1058),     // 35
+            ffi::Phidget_DeviceID_PHIDID_1059 => Ok(Id1059),     // 36
+            ffi::Phidget_DeviceID_PHIDID_1060 => Ok(Id1060),     // 37
+            ffi::Phidget_DeviceID_PHIDID_1061 => Ok(Id1061),     // 38
+            ffi::Phidget_DeviceID_PHIDID_1062 => Ok(Id1062),     // 39
+            ffi::Phidget_DeviceID_PHIDID_1063 => Ok(Id1063),     // 40
+            ffi::Phidget_DeviceID_PHIDID_1064 => Ok(Id1064),     // 41
+            ffi::Phidget_DeviceID_PHIDID_1065 => Ok(Id1065),     // 42
+            ffi::Phidget_DeviceID_PHIDID_1066 => Ok(Id1066),     // 43
+            ffi::Phidget_DeviceID_PHIDID_1067 => Ok(Id1067),     // 44
+            ffi::Phidget_DeviceID_PHIDID_1202_1203 => Ok(Id1202_1203), // 45
+            ffi::Phidget_DeviceID_PHIDID_1204 => Ok(Id1204),     // 46
+            ffi::Phidget_DeviceID_PHIDID_1215__1218 => Ok(Id1215_1218), // 47
+            ffi::Phidget_DeviceID_PHIDID_1219__1222 => Ok(Id1219_1222), // 48
+            ffi::Phidget_DeviceID_PHIDID_ADP1000 => Ok(Adp1000), // 49
+            ffi::Phidget_DeviceID_PHIDID_DAQ1000 => Ok(Daq1000), // 51
+            ffi::Phidget_DeviceID_PHIDID_DAQ1200 => Ok(Daq1200), // 52
+            ffi::Phidget_DeviceID_PHIDID_DAQ1300 => Ok(Daq1300), // 53
+            ffi::Phidget_DeviceID_PHIDID_DAQ1301 => Ok(Daq1301), // 54
+            ffi::Phidget_DeviceID_PHIDID_DAQ1400 => Ok(Daq1400), // 55
+            ffi::Phidget_DeviceID_PHIDID_DAQ1500 => Ok(Daq1500), // 56
+            ffi::Phidget_DeviceID_PHIDID_DCC1000 => Ok(Dcc1000), // 57
+            ffi::Phidget_DeviceID_PHIDID_DCC1001 => Ok(Dcc1001), // 110
+            ffi::Phidget_DeviceID_PHIDID_DCC1002 => Ok(Dcc1002), // 117
+            ffi::Phidget_DeviceID_PHIDID_DCC1003 => Ok(Dcc1003), // 120
+            ffi::Phidget_DeviceID_PHIDID_DCC1100 => Ok(Dcc1100), // 108
+            ffi::Phidget_DeviceID_PHIDID_DST1000 => Ok(Dst1000), // 58
+            ffi::Phidget_DeviceID_PHIDID_DST1001 => Ok(Dst1001), // 121
+            ffi::Phidget_DeviceID_PHIDID_DST1002 => Ok(Dst1002), // 126
+            ffi::Phidget_DeviceID_PHIDID_DST1200 => Ok(Dst1200), // 59
+            ffi::Phidget_DeviceID_PHIDID_ENC1000 => Ok(Enc1000), // 60
+            ffi::Phidget_DeviceID_PHIDID_ENC1001 => Ok(Enc1001), // 155
+            ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_SPI => Ok(FirmwareUpgradeSpi), // 104
+            ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_STM32F0 => Ok(FirmwareUpgradeStm32F0), // 102
+            ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_STM32F3 => Ok(FirmwareUpgradeStm32F3), // 145
+            ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_STM32G0 => Ok(FirmwareUpgradeStm32G0), // 143
+            ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_STM8S => Ok(FirmwareUpgradeStm8S), // 103
+            ffi::Phidget_DeviceID_PHIDID_FIRMWARE_UPGRADE_USB => Ok(FirmwareUpgradeUsb),     // 101
+            ffi::Phidget_DeviceID_PHIDID_HIN1000 => Ok(Hin1000),                             // 61
+            ffi::Phidget_DeviceID_PHIDID_HIN1001 => Ok(Hin1001),                             // 62
+            ffi::Phidget_DeviceID_PHIDID_HIN1100 => Ok(Hin1100),                             // 63
+            ffi::Phidget_DeviceID_PHIDID_HIN1101 => Ok(Hin1101),                             // 109
+            ffi::Phidget_DeviceID_PHIDID_HUB0000 => Ok(Hub0000),                             // 64
+            ffi::Phidget_DeviceID_PHIDID_HUB0001 => Ok(Hub0001),                             // 142
+            ffi::Phidget_DeviceID_PHIDID_HUB0002 => Ok(Hub0002),                             // 147
+            ffi::Phidget_DeviceID_PHIDID_HUB0004 => Ok(Hub0004),                             // 67
+            ffi::Phidget_DeviceID_PHIDID_HUB0007 => Ok(Hub0007),                             // 148
+            ffi::Phidget_DeviceID_PHIDID_HUB5000 => Ok(Hub5000),                             // 123
+            ffi::Phidget_DeviceID_PHIDID_HUM1000 => Ok(Hum1000),                             // 69
+            ffi::Phidget_DeviceID_PHIDID_HUM1001 => Ok(Hum1001),                             // 127
+            ffi::Phidget_DeviceID_PHIDID_HUM1100 => Ok(Hum1100),                             // 136
+            ffi::Phidget_DeviceID_PHIDID_INTERFACEKIT_4_8_8 => Ok(InterfaceKit488),          // 1
+            ffi::Phidget_DeviceID_PHIDID_LCD1100 => Ok(Lcd1100),                             // 70
+            ffi::Phidget_DeviceID_PHIDID_LED1000 => Ok(Led1000),                             // 71
+            ffi::Phidget_DeviceID_PHIDID_LUX1000 => Ok(Lux1000),                             // 72
+            ffi::Phidget_DeviceID_PHIDID_MOT0100 => Ok(Mot0100),                             // 146
+            ffi::Phidget_DeviceID_PHIDID_MOT0109 => Ok(Mot0109),                             // 140
+            ffi::Phidget_DeviceID_PHIDID_MOT0110 => Ok(Mot0110),                             // 141
+            ffi::Phidget_DeviceID_PHIDID_MOT1100 => Ok(Mot1100),                             // 73
+            ffi::Phidget_DeviceID_PHIDID_MOT1101 => Ok(Mot1101),                             // 74
+            ffi::Phidget_DeviceID_PHIDID_MOT1102 => Ok(Mot1102),                             // 137
+            ffi::Phidget_DeviceID_PHIDID_OUT1000 => Ok(Out1000),                             // 75
+            ffi::Phidget_DeviceID_PHIDID_OUT1001 => Ok(Out1001),                             // 76
+            ffi::Phidget_DeviceID_PHIDID_OUT1002 => Ok(Out1002),                             // 77
+            ffi::Phidget_DeviceID_PHIDID_OUT1100 => Ok(Out1100),                             // 78
+            ffi::Phidget_DeviceID_PHIDID_PRE1000 => Ok(Pre1000),                             // 79
+            ffi::Phidget_DeviceID_PHIDID_RCC0004 => Ok(Rcc0004),                             // 124
+            ffi::Phidget_DeviceID_PHIDID_RCC1000 => Ok(Rcc1000),                             // 80
+            ffi::Phidget_DeviceID_PHIDID_REL1000 => Ok(Rel1000),                             // 81
+            ffi::Phidget_DeviceID_PHIDID_REL1100 => Ok(Rel1100),                             // 82
+            ffi::Phidget_DeviceID_PHIDID_REL1101 => Ok(Rel1101),                             // 83
+            ffi::Phidget_DeviceID_PHIDID_SAF1000 => Ok(Saf1000),                             // 84
+            ffi::Phidget_DeviceID_PHIDID_SND1000 => Ok(Snd1000),                             // 85
+            ffi::Phidget_DeviceID_PHIDID_STC1000 => Ok(Stc1000),                             // 86
+            ffi::Phidget_DeviceID_PHIDID_STC1001 => Ok(Stc1001),                             // 115
+            ffi::Phidget_DeviceID_PHIDID_STC1002 => Ok(Stc1002),                             // 118
+            ffi::Phidget_DeviceID_PHIDID_STC1003 => Ok(Stc1003),                             // 119
+            ffi::Phidget_DeviceID_PHIDID_STC1005 => Ok(Stc1005),                             // 149
+            ffi::Phidget_DeviceID_PHIDID_TMP1000 => Ok(Tmp1000),                             // 87
+            ffi::Phidget_DeviceID_PHIDID_TMP1100 => Ok(Tmp1100),                             // 88
+            ffi::Phidget_DeviceID_PHIDID_TMP1101 => Ok(Tmp1101),                             // 89
+            ffi::Phidget_DeviceID_PHIDID_TMP1200 => Ok(Tmp1200),                             // 90
+            ffi::Phidget_DeviceID_PHIDID_VCP1000 => Ok(Vcp1000),                             // 92
+            ffi::Phidget_DeviceID_PHIDID_VCP1001 => Ok(Vcp1001),                             // 93
+            ffi::Phidget_DeviceID_PHIDID_VCP1002 => Ok(Vcp1002),                             // 94
+            ffi::Phidget_DeviceID_PHIDID_VCP1100 => Ok(Vcp1100),                             // 105
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+impl DeviceId {
+    /// Gets the name of the device ID as used by the phidget22 library,
+    /// like "PHIDID_TMP1101".
+    pub fn name(&self) -> &'static str {
+        use DeviceId::*;
+        match self {
+            Nothing => "PHIDID_NOTHING",
+            Unknown => "PHIDID_UNKNOWN",
+            DigitalInputPort => "PHIDID_DIGITALINPUT_PORT",
+            DigitalOutputPort => "PHIDID_DIGITALOUTPUT_PORT",
+            VoltageInputPort => "PHIDID_VOLTAGEINPUT_PORT",
+            VoltageRatioInputPort => "PHIDID_VOLTAGERATIOINPUT_PORT",
+            Dictionary => "PHIDID_DICTIONARY",
+            Id1000 => "PHIDID_1000",
+            Id1001 => "PHIDID_1001",
+            Id1002 => "PHIDID_1002",
+            Id1008 => "PHIDID_1008",
+            Id1010_1013_1018_1019 => "PHIDID_1010_1013_1018_1019",
+            Id1011 => "PHIDID_1011",
+            Id1012 => "PHIDID_1012",
+            Id1014 => "PHIDID_1014",
+            Id1015 => "PHIDID_1015",
+            Id1016 => "PHIDID_1016",
+            Id1017 => "PHIDID_1017",
+            Id1023 => "PHIDID_1023",
+            Id1024 => "PHIDID_1024",
+            Id1030 => "PHIDID_1030",
+            Id1031 => "PHIDID_1031",
+            Id1032 => "PHIDID_1032",
+            Id1040 => "PHIDID_1040",
+            Id1041 => "PHIDID_1041",
+            Id1042 => "PHIDID_1042",
+            Id1043 => "PHIDID_1043",
+            Id1044 => "PHIDID_1044",
+            Id1045 => "PHIDID_1045",
+            Id1046 => "PHIDID_1046",
+            Id1047 => "PHIDID_1047",
+            Id1048 => "PHIDID_1048",
+            Id1049 => "PHIDID_1049",
+            Id1051 => "PHIDID_1051",
+            Id1052 => "PHIDID_1052",
+            Id1053 => "PHIDID_1053",
+            Id1054 => "PHIDID_1054",
+            Id1055 => "PHIDID_1055",
+            Id1056 => "PHIDID_1056",
+            Id1057 => "PHIDID_1057",
+            Id1058 => "PHIDID_1058",
+            Id1059 => "PHIDID_1059",
+            Id1060 => "PHIDID_1060",
+            Id1061 => "PHIDID_1061",
+            Id1062 => "PHIDID_1062",
+            Id1063 => "PHIDID_1063",
+            Id1064 => "PHIDID_1064",
+            Id1065 => "PHIDID_1065",
+            Id1066 => "PHIDID_1066",
+            Id1067 => "PHIDID_1067",
+            Id1202_1203 => "PHIDID_1202_1203",
+            Id1204 => "PHIDID_1204",
+            Id1215_1218 => "PHIDID_1215__1218",
+            Id1219_1222 => "PHIDID_1219__1222",
+            Adp1000 => "PHIDID_ADP1000",
+            Daq1000 => "PHIDID_DAQ1000",
+            Daq1200 => "PHIDID_DAQ1200",
+            Daq1300 => "PHIDID_DAQ1300",
+            Daq1301 => "PHIDID_DAQ1301",
+            Daq1400 => "PHIDID_DAQ1400",
+            Daq1500 => "PHIDID_DAQ1500",
+            Dcc1000 => "PHIDID_DCC1000",
+            Dcc1001 => "PHIDID_DCC1001",
+            Dcc1002 => "PHIDID_DCC1002",
+            Dcc1003 => "PHIDID_DCC1003",
+            Dcc1100 => "PHIDID_DCC1100",
+            Dst1000 => "PHIDID_DST1000",
+            Dst1001 => "PHIDID_DST1001",
+            Dst1002 => "PHIDID_DST1002",
+            Dst1200 => "PHIDID_DST1200",
+            Enc1000 => "PHIDID_ENC1000",
+            Enc1001 => "PHIDID_ENC1001",
+            FirmwareUpgradeSpi => "PHIDID_FIRMWARE_UPGRADE_SPI",
+            FirmwareUpgradeStm32F0 => "PHIDID_FIRMWARE_UPGRADE_STM32F0",
+            FirmwareUpgradeStm32F3 => "PHIDID_FIRMWARE_UPGRADE_STM32F3",
+            FirmwareUpgradeStm32G0 => "PHIDID_FIRMWARE_UPGRADE_STM32G0",
+            FirmwareUpgradeStm8S => "PHIDID_FIRMWARE_UPGRADE_STM8S",
+            FirmwareUpgradeUsb => "PHIDID_FIRMWARE_UPGRADE_USB",
+            Hin1000 => "PHIDID_HIN1000",
+            Hin1001 => "PHIDID_HIN1001",
+            Hin1100 => "PHIDID_HIN1100",
+            Hin1101 => "PHIDID_HIN1101",
+            Hub0000 => "PHIDID_HUB0000",
+            Hub0001 => "PHIDID_HUB0001",
+            Hub0002 => "PHIDID_HUB0002",
+            Hub0004 => "PHIDID_HUB0004",
+            Hub0007 => "PHIDID_HUB0007",
+            Hub5000 => "PHIDID_HUB5000",
+            Hum1000 => "PHIDID_HUM1000",
+            Hum1001 => "PHIDID_HUM1001",
+            Hum1100 => "PHIDID_HUM1100",
+            InterfaceKit488 => "PHIDID_INTERFACEKIT_4_8_8",
+            Lcd1100 => "PHIDID_LCD1100",
+            Led1000 => "PHIDID_LED1000",
+            Lux1000 => "PHIDID_LUX1000",
+            Mot0100 => "PHIDID_MOT0100",
+            Mot0109 => "PHIDID_MOT0109",
+            Mot0110 => "PHIDID_MOT0110",
+            Mot1100 => "PHIDID_MOT1100",
+            Mot1101 => "PHIDID_MOT1101",
+            Mot1102 => "PHIDID_MOT1102",
+            Out1000 => "PHIDID_OUT1000",
+            Out1001 => "PHIDID_OUT1001",
+            Out1002 => "PHIDID_OUT1002",
+            Out1100 => "PHIDID_OUT1100",
+            Pre1000 => "PHIDID_PRE1000",
+            Rcc0004 => "PHIDID_RCC0004",
+            Rcc1000 => "PHIDID_RCC1000",
+            Rel1000 => "PHIDID_REL1000",
+            Rel1100 => "PHIDID_REL1100",
+            Rel1101 => "PHIDID_REL1101",
+            Saf1000 => "PHIDID_SAF1000",
+            Snd1000 => "PHIDID_SND1000",
+            Stc1000 => "PHIDID_STC1000",
+            Stc1001 => "PHIDID_STC1001",
+            Stc1002 => "PHIDID_STC1002",
+            Stc1003 => "PHIDID_STC1003",
+            Stc1005 => "PHIDID_STC1005",
+            Tmp1000 => "PHIDID_TMP1000",
+            Tmp1100 => "PHIDID_TMP1100",
+            Tmp1101 => "PHIDID_TMP1101",
+            Tmp1200 => "PHIDID_TMP1200",
+            Vcp1000 => "PHIDID_VCP1000",
+            Vcp1001 => "PHIDID_VCP1001",
+            Vcp1002 => "PHIDID_VCP1002",
+            Vcp1100 => "PHIDID_VCP1100",
+        }
+    }
+}
+
+impl DeviceId {
+    /// Gets the SKU (part number) of the device model, like "TMP1101".
+    ///
+    /// Where a single ID covers several older models, this is the first of
+    /// them. This returns `None` for IDs that are not a specific product,
+    /// such as the VINT hub port modes, or the firmware upgrade channels.
+    pub fn sku(&self) -> Option<&'static str> {
+        use DeviceId::*;
+        let sku = match self {
+            Id1000 => "1000",
+            Id1001 => "1001",
+            Id1002 => "1002",
+            Id1008 => "1008",
+            Id1010_1013_1018_1019 => "1010",
+            Id1011 => "1011",
+            Id1012 => "1012",
+            Id1014 => "1014",
+            Id1015 => "1015",
+            Id1016 => "1016",
+            Id1017 => "1017",
+            Id1023 => "1023",
+            Id1024 => "1024",
+            Id1030 => "1030",
+            Id1031 => "1031",
+            Id1032 => "1032",
+            Id1040 => "1040",
+            Id1041 => "1041",
+            Id1042 => "1042",
+            Id1043 => "1043",
+            Id1044 => "1044",
+            Id1045 => "1045",
+            Id1046 => "1046",
+            Id1047 => "1047",
+            Id1048 => "1048",
+            Id1049 => "1049",
+            Id1051 => "1051",
+            Id1052 => "1052",
+            Id1053 => "1053",
+            Id1054 => "1054",
+            Id1055 => "1055",
+            Id1056 => "1056",
+            Id1057 => "1057",
+            Id1058 => "1058",
+            Id1059 => "1059",
+            Id1060 => "1060",
+            Id1061 => "1061",
+            Id1062 => "1062",
+            Id1063 => "1063",
+            Id1064 => "1064",
+            Id1065 => "1065",
+            Id1066 => "1066",
+            Id1067 => "1067",
+            Id1202_1203 => "1202",
+            Id1204 => "1204",
+            Id1215_1218 => "1215",
+            Id1219_1222 => "1219",
+            Adp1000 => "ADP1000",
+            Daq1000 => "DAQ1000",
+            Daq1200 => "DAQ1200",
+            Daq1300 => "DAQ1300",
+            Daq1301 => "DAQ1301",
+            Daq1400 => "DAQ1400",
+            Daq1500 => "DAQ1500",
+            Dcc1000 => "DCC1000",
+            Dcc1001 => "DCC1001",
+            Dcc1002 => "DCC1002",
+            Dcc1003 => "DCC1003",
+            Dcc1100 => "DCC1100",
+            Dst1000 => "DST1000",
+            Dst1001 => "DST1001",
+            Dst1002 => "DST1002",
+            Dst1200 => "DST1200",
+            Enc1000 => "ENC1000",
+            Enc1001 => "ENC1001",
+            Hin1000 => "HIN1000",
+            Hin1001 => "HIN1001",
+            Hin1100 => "HIN1100",
+            Hin1101 => "HIN1101",
+            Hub0000 => "HUB0000",
+            Hub0001 => "HUB0001",
+            Hub0002 => "HUB0002",
+            Hub0004 => "HUB0004",
+            Hub0007 => "HUB0007",
+            Hub5000 => "HUB5000",
+            Hum1000 => "HUM1000",
+            Hum1001 => "HUM1001",
+            Hum1100 => "HUM1100",
+            Lcd1100 => "LCD1100",
+            Led1000 => "LED1000",
+            Lux1000 => "LUX1000",
+            Mot0100 => "MOT0100",
+            Mot0109 => "MOT0109",
+            Mot0110 => "MOT0110",
+            Mot1100 => "MOT1100",
+            Mot1101 => "MOT1101",
+            Mot1102 => "MOT1102",
+            Out1000 => "OUT1000",
+            Out1001 => "OUT1001",
+            Out1002 => "OUT1002",
+            Out1100 => "OUT1100",
+            Pre1000 => "PRE1000",
+            Rcc0004 => "RCC0004",
+            Rcc1000 => "RCC1000",
+            Rel1000 => "REL1000",
+            Rel1100 => "REL1100",
+            Rel1101 => "REL1101",
+            Saf1000 => "SAF1000",
+            Snd1000 => "SND1000",
+            Stc1000 => "STC1000",
+            Stc1001 => "STC1001",
+            Stc1002 => "STC1002",
+            Stc1003 => "STC1003",
+            Stc1005 => "STC1005",
+            Tmp1000 => "TMP1000",
+            Tmp1100 => "TMP1100",
+            Tmp1101 => "TMP1101",
+            Tmp1200 => "TMP1200",
+            Vcp1000 => "VCP1000",
+            Vcp1001 => "VCP1001",
+            Vcp1002 => "VCP1002",
+            Vcp1100 => "VCP1100",
+            Nothing
+            | Unknown
+            | DigitalInputPort
+            | DigitalOutputPort
+            | VoltageInputPort
+            | VoltageRatioInputPort
+            | Dictionary
+            | FirmwareUpgradeSpi
+            | FirmwareUpgradeStm32F0
+            | FirmwareUpgradeStm32F3
+            | FirmwareUpgradeStm32G0
+            | FirmwareUpgradeStm8S
+            | FirmwareUpgradeUsb
+            | InterfaceKit488 => return None,
+        };
+        Some(sku)
+    }
+
+    /// Looks up the device ID for an SKU (part number), like "TMP1101".
+    ///
+    /// The match is not case sensitive, and a board revision suffix, as in
+    /// "TMP1101_0", is ignored. The older models that share an ID can be
+    /// looked up by any of their model numbers.
+    pub fn try_from_sku(sku: &str) -> Result<Self> {
+        use DeviceId::*;
+        let sku = sku.trim().to_ascii_uppercase();
+        let sku = match sku.rsplit_once('_') {
+            Some((base, rev)) if !rev.is_empty() && rev.bytes().all(|b| b.is_ascii_digit()) => base,
+            _ => &sku,
+        };
+        match sku {
+            "1010" | "1013" | "1018" | "1019" => Ok(Id1010_1013_1018_1019),
+            "1202" | "1203" => Ok(Id1202_1203),
+            "1215" | "1216" | "1217" | "1218" => Ok(Id1215_1218),
+            "1219" | "1220" | "1221" | "1222" => Ok(Id1219_1222),
+            _ => match Self::from_str(sku) {
+                Ok(id) if id.sku() == Some(sku) => Ok(id),
+                _ => Err(ErrorCode::InvalidArg),
+            },
+        }
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for DeviceId {
+    type Err = Error;
+
+    /// Parses the library name of the device ID. The match is not case
+    /// sensitive, and the "PHIDID_" prefix is optional.
+    fn from_str(s: &str) -> Result<Self> {
+        use DeviceId::*;
+        let s = s.trim().to_ascii_uppercase();
+        match s.strip_prefix("PHIDID_").unwrap_or(&s) {
+            "NOTHING" => Ok(Nothing),
+            "UNKNOWN" => Ok(Unknown),
+            "DIGITALINPUT_PORT" => Ok(DigitalInputPort),
+            "DIGITALOUTPUT_PORT" => Ok(DigitalOutputPort),
+            "VOLTAGEINPUT_PORT" => Ok(VoltageInputPort),
+            "VOLTAGERATIOINPUT_PORT" => Ok(VoltageRatioInputPort),
+            "DICTIONARY" => Ok(Dictionary),
+            "1000" => Ok(Id1000),
+            "1001" => Ok(Id1001),
+            "1002" => Ok(Id1002),
+            "1008" => Ok(Id1008),
+            "1010_1013_1018_1019" => Ok(Id1010_1013_1018_1019),
+            "1011" => Ok(Id1011),
+            "1012" => Ok(Id1012),
+            "1014" => Ok(Id1014),
+            "1015" => Ok(Id1015),
+            "1016" => Ok(Id1016),
+            "1017" => Ok(Id1017),
+            "1023" => Ok(Id1023),
+            "1024" => Ok(Id1024),
+            "1030" => Ok(Id1030),
+            "1031" => Ok(Id1031),
+            "1032" => Ok(Id1032),
+            "1040" => Ok(Id1040),
+            "1041" => Ok(Id1041),
+            "1042" => Ok(Id1042),
+            "1043" => Ok(Id1043),
+            "1044" => Ok(Id1044),
+            "1045" => Ok(Id1045),
+            "1046" => Ok(Id1046),
+            "1047" => Ok(Id1047),
+            "1048" => Ok(Id1048),
+            "1049" => Ok(Id1049),
+            "1051" => Ok(Id1051),
+            "1052" => Ok(Id1052),
+            "1053" => Ok(Id1053),
+            "1054" => Ok(Id1054),
+            "1055" => Ok(Id1055),
+            "1056" => Ok(Id1056),
+            "1057" => Ok(Id1057),
+            "1058" => Ok(Id1058),
+            "1059" => Ok(Id1059),
+            "1060" => Ok(Id1060),
+            "1061" => Ok(Id1061),
+            "1062" => Ok(Id1062),
+            "1063" => Ok(Id1063),
+            "1064" => Ok(Id1064),
+            "1065" => Ok(Id1065),
+            "1066" => Ok(Id1066),
+            "1067" => Ok(Id1067),
+            "1202_1203" => Ok(Id1202_1203),
+            "1204" => Ok(Id1204),
+            "1215__1218" => Ok(Id1215_1218),
+            "1219__1222" => Ok(Id1219_1222),
+            "ADP1000" => Ok(Adp1000),
+            "DAQ1000" => Ok(Daq1000),
+            "DAQ1200" => Ok(Daq1200),
+            "DAQ1300" => Ok(Daq1300),
+            "DAQ1301" => Ok(Daq1301),
+            "DAQ1400" => Ok(Daq1400),
+            "DAQ1500" => Ok(Daq1500),
+            "DCC1000" => Ok(Dcc1000),
+            "DCC1001" => Ok(Dcc1001),
+            "DCC1002" => Ok(Dcc1002),
+            "DCC1003" => Ok(Dcc1003),
+            "DCC1100" => Ok(Dcc1100),
+            "DST1000" => Ok(Dst1000),
+            "DST1001" => Ok(Dst1001),
+            "DST1002" => Ok(Dst1002),
+            "DST1200" => Ok(Dst1200),
+            "ENC1000" => Ok(Enc1000),
+            "ENC1001" => Ok(Enc1001),
+            "FIRMWARE_UPGRADE_SPI" => Ok(FirmwareUpgradeSpi),
+            "FIRMWARE_UPGRADE_STM32F0" => Ok(FirmwareUpgradeStm32F0),
+            "FIRMWARE_UPGRADE_STM32F3" => Ok(FirmwareUpgradeStm32F3),
+            "FIRMWARE_UPGRADE_STM32G0" => Ok(FirmwareUpgradeStm32G0),
+            "FIRMWARE_UPGRADE_STM8S" => Ok(FirmwareUpgradeStm8S),
+            "FIRMWARE_UPGRADE_USB" => Ok(FirmwareUpgradeUsb),
+            "HIN1000" => Ok(Hin1000),
+            "HIN1001" => Ok(Hin1001),
+            "HIN1100" => Ok(Hin1100),
+            "HIN1101" => Ok(Hin1101),
+            "HUB0000" => Ok(Hub0000),
+            "HUB0001" => Ok(Hub0001),
+            "HUB0002" => Ok(Hub0002),
+            "HUB0004" => Ok(Hub0004),
+            "HUB0007" => Ok(Hub0007),
+            "HUB5000" => Ok(Hub5000),
+            "HUM1000" => Ok(Hum1000),
+            "HUM1001" => Ok(Hum1001),
+            "HUM1100" => Ok(Hum1100),
+            "INTERFACEKIT_4_8_8" => Ok(InterfaceKit488),
+            "LCD1100" => Ok(Lcd1100),
+            "LED1000" => Ok(Led1000),
+            "LUX1000" => Ok(Lux1000),
+            "MOT0100" => Ok(Mot0100),
+            "MOT0109" => Ok(Mot0109),
+            "MOT0110" => Ok(Mot0110),
+            "MOT1100" => Ok(Mot1100),
+            "MOT1101" => Ok(Mot1101),
+            "MOT1102" => Ok(Mot1102),
+            "OUT1000" => Ok(Out1000),
+            "OUT1001" => Ok(Out1001),
+            "OUT1002" => Ok(Out1002),
+            "OUT1100" => Ok(Out1100),
+            "PRE1000" => Ok(Pre1000),
+            "RCC0004" => Ok(Rcc0004),
+            "RCC1000" => Ok(Rcc1000),
+            "REL1000" => Ok(Rel1000),
+            "REL1100" => Ok(Rel1100),
+            "REL1101" => Ok(Rel1101),
+            "SAF1000" => Ok(Saf1000),
+            "SND1000" => Ok(Snd1000),
+            "STC1000" => Ok(Stc1000),
+            "STC1001" => Ok(Stc1001),
+            "STC1002" => Ok(Stc1002),
+            "STC1003" => Ok(Stc1003),
+            "STC1005" => Ok(Stc1005),
+            "TMP1000" => Ok(Tmp1000),
+            "TMP1100" => Ok(Tmp1100),
+            "TMP1101" => Ok(Tmp1101),
+            "TMP1200" => Ok(Tmp1200),
+            "VCP1000" => Ok(Vcp1000),
+            "VCP1001" => Ok(Vcp1001),
+            "VCP1002" => Ok(Vcp1002),
+            "VCP1100" => Ok(Vcp1100),
+            _ => Err(ErrorCode::InvalidArg),
         }
     }
 }
@@ -351,8 +1388,66 @@ pub fn library_version_number() -> Result<String> {
 
 #[cfg(test)]
 mod tests {
-    //use super::*;
+    use super::*;
 
     #[test]
     fn it_works() {}
+
+    #[test]
+    fn test_ffi_string_into() {
+        let mut buf = String::with_capacity(32);
+        let cap = buf.capacity();
+
+        get_ffi_string_into(&mut buf, |s| {
+            unsafe { *s = b"HUM1001_0\0".as_ptr() as *const c_char };
+            0
+        })
+        .unwrap();
+        assert_eq!(buf, "HUM1001_0");
+        assert_eq!(buf.capacity(), cap);
+
+        let res = get_ffi_string_into(&mut buf, |_| ReturnCode::NoEnt as c_uint);
+        assert_eq!(res, Err(ErrorCode::NoEnt));
+    }
+
+    #[test]
+    fn test_rate() {
+        let rate = Rate::from_interval(Duration::from_millis(250));
+        assert_eq!(rate, Rate::hz(4.0));
+        assert_eq!(rate.interval(), Duration::from_millis(250));
+        assert_eq!(Rate::hz(0.0).interval(), Duration::MAX);
+        assert_eq!(Rate::hz(2.5).to_string(), "2.5 Hz");
+    }
+
+    #[test]
+    fn test_name_round_trip() {
+        assert_eq!(DeviceId::Tmp1101.to_string(), "PHIDID_TMP1101");
+        assert_eq!("PHIDID_TMP1101".parse(), Ok(DeviceId::Tmp1101));
+        assert_eq!("tmp1101".parse(), Ok(DeviceId::Tmp1101));
+        assert_eq!("PHIDID_1215__1218".parse(), Ok(DeviceId::Id1215_1218));
+        assert!("TMP9999".parse::<DeviceId>().is_err());
+
+        let cls = ChannelClass::TemperatureSensor;
+        assert_eq!(cls.to_string().parse(), Ok(cls));
+        assert_eq!("temperaturesensor".parse(), Ok(cls));
+        assert_eq!(DeviceClass::Vint.to_string(), "PHIDCLASS_VINT");
+        assert_eq!("PHIDCLASS_VINT".parse(), Ok(DeviceClass::Vint));
+    }
+
+    #[test]
+    fn test_sku() {
+        assert_eq!(DeviceId::Tmp1101.sku(), Some("TMP1101"));
+        assert_eq!(DeviceId::Id1010_1013_1018_1019.sku(), Some("1010"));
+        assert_eq!(DeviceId::DigitalInputPort.sku(), None);
+
+        assert_eq!(DeviceId::try_from_sku("TMP1101"), Ok(DeviceId::Tmp1101));
+        assert_eq!(DeviceId::try_from_sku("tmp1101_0"), Ok(DeviceId::Tmp1101));
+        assert_eq!(
+            DeviceId::try_from_sku("1018_2"),
+            Ok(DeviceId::Id1010_1013_1018_1019)
+        );
+        assert_eq!(DeviceId::try_from_sku("1000"), Ok(DeviceId::Id1000));
+        assert!(DeviceId::try_from_sku("DICTIONARY").is_err());
+        assert!(DeviceId::try_from_sku("TMP9999").is_err());
+    }
 }
@@ -0,0 +1,146 @@
+// phidget-rs/src/lifecycle.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Resetting the phidget22 library.
+//!
+//! The phidget22 library can be reset to its initial state, which closes
+//! and frees every channel and manager handle it has created. This is
+//! needed by some host environments, like game engines and instrument
+//! software, which load a plugin once and then start and stop it many
+//! times in the same process.
+//!
+//! Resetting the library out from under live Rust wrappers would leave
+//! them holding freed handles, which they would then close and delete
+//! again when dropped. To prevent this, each device and manager created
+//! by this crate registers its handle here:
+//!
+//! - [`reset_library()`] only resets the library once all of the
+//!   registered wrappers have been dropped.
+//! - [`reset_library_unchecked()`] closes every registered handle, marks
+//!   them invalid, and resets the library. A wrapper with an invalid
+//!   handle skips the library when it's dropped.
+
+use crate::{ErrorCode, Result, ReturnCode};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetManagerHandle};
+use std::{
+    collections::BTreeMap,
+    mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+// The kind of handle, which determines how to close it.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Channel,
+    Manager,
+}
+
+// The ID for the next registration
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+// The live handles, by registration ID.
+// The handles are kept as integers so that the map is Send.
+static LIVE: Mutex<BTreeMap<u64, (usize, Kind)>> = Mutex::new(BTreeMap::new());
+
+// Gets the map of live handles, even if a thread panicked with it locked.
+fn live() -> MutexGuard<'static, BTreeMap<u64, (usize, Kind)>> {
+    LIVE.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// The registration of a library handle owned by a wrapper.
+///
+/// Wrappers created from a raw handle, like those passed to callbacks,
+/// are not registered, and always consider their handle valid.
+#[derive(Debug, Default)]
+pub(crate) struct Registration(Option<u64>);
+
+impl Registration {
+    // Registers a handle of the specified kind.
+    fn new(handle: usize, kind: Kind) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        live().insert(id, (handle, kind));
+        Self(Some(id))
+    }
+
+    /// Registers a channel handle.
+    pub(crate) fn channel(handle: PhidgetHandle) -> Self {
+        Self::new(handle as usize, Kind::Channel)
+    }
+
+    /// Registers a manager handle.
+    pub(crate) fn manager(handle: PhidgetManagerHandle) -> Self {
+        Self::new(handle as usize, Kind::Manager)
+    }
+
+    /// Removes the registration, returning whether the handle is still
+    /// valid, and so should be closed and deleted by the wrapper.
+    pub(crate) fn release(&mut self) -> bool {
+        match self.0.take() {
+            Some(id) => match live().remove(&id) {
+                Some((handle, _)) => {
+                    crate::phidget::forget_errors(handle);
+                    true
+                }
+                None => false,
+            },
+            None => true,
+        }
+    }
+}
+
+/// Gets the number of live device and manager wrappers.
+pub fn live_count() -> usize {
+    live().len()
+}
+
+/// Resets the phidget22 library to its initial state.
+///
+/// This fails with a `Busy` error if any devices or managers created by
+/// this crate are still alive. They should all be dropped first.
+pub fn reset_library() -> Result<()> {
+    let live = live();
+    if !live.is_empty() {
+        return Err(ErrorCode::Busy);
+    }
+    ReturnCode::result(unsafe { ffi::Phidget_resetLibrary() })
+}
+
+/// Closes all of the live devices and managers, and resets the phidget22
+/// library to its initial state.
+///
+/// The live wrappers are marked invalid, so that they don't touch the
+/// library again when they are dropped.
+///
+/// # Safety
+///
+/// A wrapper that is alive across the reset must not be used again,
+/// other than to drop it.
+pub unsafe fn reset_library_unchecked() -> Result<()> {
+    // The handles are closed without the lock, since the detach callbacks
+    // fired by closing them may create or drop wrappers.
+    let handles = mem::take(&mut *live());
+    for (handle, kind) in handles.values() {
+        match kind {
+            Kind::Channel => {
+                let _ = ffi::Phidget_close(*handle as PhidgetHandle);
+            }
+            Kind::Manager => {
+                let _ = ffi::PhidgetManager_close(*handle as PhidgetManagerHandle);
+            }
+        }
+    }
+    crate::phidget::forget_all_errors();
+    crate::devices::stepper::forget_all_soft_limits();
+    ReturnCode::result(ffi::Phidget_resetLibrary())
+}
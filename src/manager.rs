@@ -0,0 +1,224 @@
+// phidget-rs/src/manager.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! The Phidget Manager.
+//!
+//! The manager is notified whenever any Phidget channel is attached to, or
+//! detached from, the system, whether or not the application has opened
+//! it. This is the way to discover the devices that are available.
+
+use crate::{
+    lifecycle::Registration,
+    spec::{Channel, HubPort, SerialNumber},
+    AttachCallback, ChannelClass, DetachCallback, DeviceClass, DeviceId, GenericPhidget, Phidget,
+    Result, ReturnCode,
+};
+use phidget_sys::{self as ffi, PhidgetHandle, PhidgetManagerHandle as ManagerHandle};
+use std::{os::raw::c_void, ptr};
+
+// Low-level, unsafe callback for manager attach events
+unsafe extern "C" fn on_attach(_mgr: ManagerHandle, ctx: *mut c_void, phid: PhidgetHandle) {
+    if !ctx.is_null() {
+        let cb: &mut Box<AttachCallback> = &mut *(ctx as *mut _);
+        let ph = GenericPhidget::from(phid);
+        cb(&ph);
+    }
+}
+
+// Low-level, unsafe callback for manager detach events
+unsafe extern "C" fn on_detach(_mgr: ManagerHandle, ctx: *mut c_void, phid: PhidgetHandle) {
+    if !ctx.is_null() {
+        let cb: &mut Box<DetachCallback> = &mut *(ctx as *mut _);
+        let ph = GenericPhidget::from(phid);
+        cb(&ph);
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// A snapshot of the identifying information for an attached channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhidgetInfo {
+    /// The serial number of the device
+    pub serial_number: SerialNumber,
+    /// The hub port of the device, or the wildcard, [`HubPort::ANY`], if
+    /// it is not on a VINT hub
+    pub hub_port: HubPort,
+    /// The channel index on the device
+    pub channel: Channel,
+    /// Whether the channel is a VINT hub port channel
+    pub is_hub_port_device: bool,
+    /// The class of the channel
+    pub channel_class: ChannelClass,
+    /// The class of the device
+    pub device_class: DeviceClass,
+    /// The ID of the device model
+    pub device_id: DeviceId,
+    /// The name of the channel
+    pub channel_name: String,
+    /// The name of the device
+    pub device_name: String,
+    /// The SKU (part number) of the device
+    pub device_sku: String,
+    /// The firmware version of the device
+    pub device_version: i32,
+    /// The label of the device, if any
+    pub device_label: String,
+    /// The name of the server for a remote channel, or empty if it is local
+    pub server_name: String,
+}
+
+impl PhidgetInfo {
+    /// Reads the information from an attached channel.
+    pub fn new<P>(ph: &mut P) -> Result<Self>
+    where
+        P: Phidget + ?Sized,
+    {
+        Ok(Self {
+            serial_number: ph.serial_number()?,
+            hub_port: ph.hub_port().unwrap_or_default(),
+            channel: ph.channel()?,
+            is_hub_port_device: ph.is_hub_port_device().unwrap_or_default(),
+            channel_class: ph.channel_class()?,
+            device_class: ph.device_class()?,
+            device_id: ph.device_id().unwrap_or(DeviceId::Unknown),
+            channel_name: ph.channel_name().unwrap_or_default(),
+            device_name: ph.device_name().unwrap_or_default(),
+            device_sku: ph.device_sku().unwrap_or_default(),
+            device_version: ph.device_version().unwrap_or_default(),
+            device_label: ph.device_label().unwrap_or_default(),
+            server_name: if ph.is_remote().unwrap_or_default() {
+                ph.server_name().unwrap_or_default()
+            }
+            else {
+                String::new()
+            },
+        })
+    }
+
+    /// Gets the typed address of the channel: the serial number, the hub
+    /// port, or `None` if the device is not on a VINT hub, and the channel
+    /// index.
+    pub fn address(&self) -> (SerialNumber, Option<HubPort>, Channel) {
+        (
+            self.serial_number,
+            (!self.hub_port.is_any()).then_some(self.hub_port),
+            self.channel,
+        )
+    }
+}
+
+impl TryFrom<&GenericPhidget> for PhidgetInfo {
+    type Error = crate::Error;
+
+    fn try_from(ph: &GenericPhidget) -> Result<Self> {
+        Self::new(&mut GenericPhidget::from(*ph.as_channel()))
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// The Phidget Manager
+///
+/// The attach and detach handlers should be set before the manager is
+/// opened. When it is opened, an attach event is fired for each of the
+/// channels that are already attached to the system.
+pub struct Manager {
+    // Handle to the manager in the phidget22 library
+    mgr: ManagerHandle,
+    // Double-boxed attach callback, if registered
+    attach_cb: Option<*mut c_void>,
+    // Double-boxed detach callback, if registered
+    detach_cb: Option<*mut c_void>,
+    // Registration of the handle, for resetting the library
+    life: Registration,
+}
+
+impl Manager {
+    /// Creates a new Phidget Manager.
+    pub fn new() -> Self {
+        let mut mgr: ManagerHandle = ptr::null_mut();
+        unsafe {
+            ffi::PhidgetManager_create(&mut mgr);
+        }
+        Self {
+            mgr,
+            attach_cb: None,
+            detach_cb: None,
+            life: Registration::manager(mgr),
+        }
+    }
+
+    /// Opens the manager, to start receiving attach and detach events.
+    pub fn open(&mut self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetManager_open(self.mgr) })
+    }
+
+    /// Closes the manager.
+    pub fn close(&mut self) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::PhidgetManager_close(self.mgr) })
+    }
+
+    /// Sets a handler to receive attach events for all channels.
+    pub fn set_on_attach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<AttachCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetManager_setOnAttachHandler(self.mgr, Some(on_attach), ctx)
+        })?;
+        self.attach_cb = Some(ctx);
+        Ok(())
+    }
+
+    /// Sets a handler to receive detach events for all channels.
+    pub fn set_on_detach_handler<F>(&mut self, cb: F) -> Result<()>
+    where
+        F: Fn(&GenericPhidget) + Send + 'static,
+    {
+        // 1st box is fat ptr, 2nd is regular pointer.
+        let cb: Box<Box<DetachCallback>> = Box::new(Box::new(cb));
+        let ctx = Box::into_raw(cb) as *mut c_void;
+
+        ReturnCode::result(unsafe {
+            ffi::PhidgetManager_setOnDetachHandler(self.mgr, Some(on_detach), ctx)
+        })?;
+        self.detach_cb = Some(ctx);
+        Ok(())
+    }
+}
+
+unsafe impl Send for Manager {}
+
+impl Default for Manager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Manager {
+    fn drop(&mut self) {
+        // A handle invalidated by a library reset is already gone
+        if self.life.release() {
+            let _ = self.close();
+            unsafe {
+                ffi::PhidgetManager_delete(&mut self.mgr);
+            }
+        }
+        crate::drop_cb::<AttachCallback>(self.attach_cb.take());
+        crate::drop_cb::<DetachCallback>(self.detach_cb.take());
+    }
+}
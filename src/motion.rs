@@ -0,0 +1,303 @@
+// phidget-rs/src/motion.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Common motion control for the different types of actuators.
+//!
+//! A machine often mixes steppers, servos, and motors, which each have
+//! their own API. The [`MotionControl`] trait covers the operations they
+//! share, so that logic like jogging, homing, and emergency stops can be
+//! written once:
+//!
+//! ```rust,no_run
+//! use phidget::motion::MotionControl;
+//!
+//! // Stops everything, and lets go of the motors.
+//! fn estop(axes: &[&dyn MotionControl]) {
+//!     for axis in axes {
+//!         let _ = axis.emergency_stop();
+//!     }
+//! }
+//! ```
+
+use crate::{
+    devices::{
+        stepper::ControlMode, BldcMotor, DcMotor, MotorPositionController, RcServo, Stepper,
+    },
+    Result,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+// How often to check whether an actuator has stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The operations shared by the channels that move something.
+///
+/// The meaning of the target, and of the position, depends on the type of
+/// channel. For a channel that moves to a position, like a stepper or a
+/// servo, the target is a position. For a motor that is driven at a
+/// speed, it is the velocity.
+pub trait MotionControl: Send {
+    /// Determines if the actuator is engaged.
+    fn engaged(&self) -> Result<bool>;
+
+    /// Engages or disengages the actuator.
+    ///
+    /// A disengaged actuator doesn't move, and is free to turn.
+    fn set_engaged(&self, on: bool) -> Result<()>;
+
+    /// Sets the target position or velocity.
+    fn set_target(&self, target: f64) -> Result<()>;
+
+    /// Stops the motion, and holds the current position, if possible.
+    fn stop(&self) -> Result<()>;
+
+    /// Determines if the actuator is moving.
+    fn is_moving(&self) -> Result<bool>;
+
+    /// Gets the current position.
+    fn position(&self) -> Result<f64>;
+
+    /// Stops the motion, then disengages the actuator.
+    ///
+    /// The actuator is disengaged even if stopping it fails, in which case
+    /// the error from stopping it is returned.
+    fn emergency_stop(&self) -> Result<()> {
+        let res = self.stop();
+        self.set_engaged(false).and(res)
+    }
+
+    /// Waits a limited time for the actuator to stop moving.
+    ///
+    /// Returns a `Timeout` error if it's still moving when the time is up.
+    fn wait_stopped(&self, to: Duration) -> Result<()> {
+        let deadline = Instant::now() + to;
+        while self.is_moving()? {
+            if Instant::now() >= deadline {
+                return Err(crate::ErrorCode::Timeout);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        Ok(())
+    }
+}
+
+impl MotionControl for Stepper {
+    fn engaged(&self) -> Result<bool> {
+        Stepper::engaged(self)
+    }
+
+    fn set_engaged(&self, on: bool) -> Result<()> {
+        Stepper::set_engaged(self, on)
+    }
+
+    /// In Run mode, the target is the velocity limit. Otherwise it's the
+    /// target position.
+    fn set_target(&self, target: f64) -> Result<()> {
+        match self.control_mode()? {
+            ControlMode::Run => self.set_velocity_limit(target),
+            ControlMode::Step => self.set_target_position(target),
+        }
+    }
+
+    fn stop(&self) -> Result<()> {
+        match self.control_mode()? {
+            ControlMode::Run => self.set_velocity_limit(0.0),
+            ControlMode::Step => self.set_target_position(self.position()?),
+        }
+    }
+
+    fn is_moving(&self) -> Result<bool> {
+        Stepper::is_moving(self)
+    }
+
+    fn position(&self) -> Result<f64> {
+        Stepper::position(self)
+    }
+}
+
+impl MotionControl for RcServo {
+    fn engaged(&self) -> Result<bool> {
+        RcServo::engaged(self)
+    }
+
+    fn set_engaged(&self, on: bool) -> Result<()> {
+        RcServo::set_engaged(self, on)
+    }
+
+    fn set_target(&self, target: f64) -> Result<()> {
+        self.set_target_position(target)
+    }
+
+    fn stop(&self) -> Result<()> {
+        self.set_target_position(self.position()?)
+    }
+
+    fn is_moving(&self) -> Result<bool> {
+        RcServo::is_moving(self)
+    }
+
+    fn position(&self) -> Result<f64> {
+        RcServo::position(self)
+    }
+}
+
+/// A DC motor has no engaged state, or position. It's driven whenever the
+/// target velocity is non-zero, so it's always reported as engaged, and
+/// disengaging it lets it coast to a stop. The position is `Unsupported`.
+impl MotionControl for DcMotor {
+    fn engaged(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn set_engaged(&self, on: bool) -> Result<()> {
+        if !on {
+            self.set_target_braking_strength(0.0)?;
+            self.set_target_velocity(0.0)?;
+        }
+        Ok(())
+    }
+
+    fn set_target(&self, target: f64) -> Result<()> {
+        self.set_target_velocity(target)
+    }
+
+    /// Brakes the motor to a stop.
+    fn stop(&self) -> Result<()> {
+        self.set_target_braking_strength(1.0)?;
+        self.set_target_velocity(0.0)
+    }
+
+    fn is_moving(&self) -> Result<bool> {
+        Ok(self.velocity()? != 0.0)
+    }
+
+    fn position(&self) -> Result<f64> {
+        Err(crate::ErrorCode::Unsupported)
+    }
+}
+
+/// Like a DC motor, a BLDC motor has no engaged state. It's always
+/// reported as engaged, and disengaging it lets it coast to a stop.
+impl MotionControl for BldcMotor {
+    fn engaged(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn set_engaged(&self, on: bool) -> Result<()> {
+        if !on {
+            self.set_target_braking_strength(0.0)?;
+            self.set_target_velocity(0.0)?;
+        }
+        Ok(())
+    }
+
+    fn set_target(&self, target: f64) -> Result<()> {
+        self.set_target_velocity(target)
+    }
+
+    /// Brakes the motor to a stop.
+    fn stop(&self) -> Result<()> {
+        self.set_target_braking_strength(1.0)?;
+        self.set_target_velocity(0.0)
+    }
+
+    fn is_moving(&self) -> Result<bool> {
+        Ok(self.velocity()? != 0.0)
+    }
+
+    fn position(&self) -> Result<f64> {
+        BldcMotor::position(self)
+    }
+}
+
+impl MotionControl for MotorPositionController {
+    fn engaged(&self) -> Result<bool> {
+        MotorPositionController::engaged(self)
+    }
+
+    fn set_engaged(&self, on: bool) -> Result<()> {
+        MotorPositionController::set_engaged(self, on)
+    }
+
+    fn set_target(&self, target: f64) -> Result<()> {
+        self.set_target_position(target)
+    }
+
+    fn stop(&self) -> Result<()> {
+        self.set_target_position(self.position()?)
+    }
+
+    fn is_moving(&self) -> Result<bool> {
+        MotorPositionController::is_moving(self)
+    }
+
+    fn position(&self) -> Result<f64> {
+        MotorPositionController::position(self)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // An actuator that can't be stopped
+    struct Runaway {
+        engaged: AtomicBool,
+    }
+
+    impl MotionControl for Runaway {
+        fn engaged(&self) -> Result<bool> {
+            Ok(self.engaged.load(Ordering::SeqCst))
+        }
+
+        fn set_engaged(&self, on: bool) -> Result<()> {
+            self.engaged.store(on, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn set_target(&self, _target: f64) -> Result<()> {
+            Ok(())
+        }
+
+        fn stop(&self) -> Result<()> {
+            Err(ErrorCode::NotAttached)
+        }
+
+        fn is_moving(&self) -> Result<bool> {
+            self.engaged()
+        }
+
+        fn position(&self) -> Result<f64> {
+            Ok(0.0)
+        }
+    }
+
+    #[test]
+    fn test_emergency_stop() {
+        let axis = Runaway {
+            engaged: AtomicBool::new(true),
+        };
+        assert_eq!(
+            axis.wait_stopped(Duration::from_millis(20)),
+            Err(ErrorCode::Timeout)
+        );
+        assert_eq!(axis.emergency_stop(), Err(ErrorCode::NotAttached));
+        assert_eq!(axis.engaged(), Ok(false));
+        assert_eq!(axis.wait_stopped(Duration::ZERO), Ok(()));
+    }
+}
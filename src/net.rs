@@ -14,10 +14,11 @@
 //! This contains routines to attacth to remote Phidget servers to control
 //! devices across a network,
 
-use crate::{Error, Result, ReturnCode};
+use crate::{Error, ErrorCode, Result, ReturnCode};
 use phidget_sys as ffi;
 use std::{
     ffi::{CStr, CString},
+    fmt,
     os::raw::{c_char, c_int, c_void},
 };
 
@@ -51,7 +52,7 @@ impl TryFrom<u32> for ServerType {
             ffi::PhidgetServerType_PHIDGETSERVER_WWW => Ok(Www),   // 5
             ffi::PhidgetServerType_PHIDGETSERVER_WWWREMOTE => Ok(WwwRemote), // 6
             ffi::PhidgetServerType_PHIDGETSERVER_SBC => Ok(Sbc),   // 7
-            _ => Err(ReturnCode::InvalidArg),
+            _ => Err(ErrorCode::InvalidArg),
         }
     }
 }
@@ -107,6 +108,42 @@ impl TryFrom<&ffi::PhidgetServer> for Server {
     }
 }
 
+/// The protocol version of a Phidget network server.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerVersion {
+    /// The major version
+    pub major: i32,
+    /// The minor version
+    pub minor: i32,
+}
+
+impl fmt::Display for ServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The server that a remote channel is coming from, and the state of its
+/// connection.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RemoteInfo {
+    /// The name of the server
+    pub server_name: String,
+    /// The unique name of the server, which tells apart servers that
+    /// share a name
+    pub unique_name: String,
+    /// The host name of the server
+    pub hostname: String,
+    /// The network address and port of the server
+    pub peer_name: String,
+    /// The protocol version of the server, if known
+    pub version: Option<ServerVersion>,
+    /// Whether the channel is attached through the server
+    pub connected: bool,
+}
+
 /////////////////////////////////////////////////////////////////////////////
 
 /// Register a server to which the client will try to connect.
@@ -6,11 +6,25 @@
 // to those terms.
 //
 
-use crate::{ChannelClass, DeviceClass, Result, ReturnCode};
+use crate::{
+    devices::Bounds,
+    net::{RemoteInfo, ServerVersion},
+    properties::{self, Properties},
+    spec::{Channel, HubPort, SerialNumber},
+    AttachError, ChannelClass, DeviceClass, DeviceId, DeviceSpec, Error, ErrorCode, ErrorEventCode,
+    Range, Result, ReturnCode,
+};
 use phidget_sys::{self as ffi, PhidgetHandle};
 use std::{
-    os::raw::{c_int, c_void},
-    time::Duration,
+    borrow::Cow,
+    collections::BTreeMap,
+    ffi::{CStr, CString},
+    fmt, mem,
+    os::raw::{c_char, c_int, c_void},
+    ptr,
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+    time::{Duration, Instant},
 };
 
 /// The signature for device attach callbacks
@@ -19,6 +33,17 @@ pub type AttachCallback = dyn Fn(&GenericPhidget) + Send + 'static;
 /// The signature for device detach callbacks
 pub type DetachCallback = dyn Fn(&GenericPhidget) + Send + 'static;
 
+/// The signature for device error event callbacks
+pub type ErrorCallback = dyn Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static;
+
+/// The signature for the fault hooks that devices build over the error
+/// events. This gets the new fault, or `None` when the fault clears.
+pub(crate) type FaultHook = dyn Fn(PhidgetHandle, Option<ErrorEventCode>) + Send + 'static;
+
+/// An error event from a channel: the code, the description, and when it
+/// was received.
+pub type LastError = (ErrorEventCode, String, Instant);
+
 // Low-level, unsafe callback for device attach events
 unsafe extern "C" fn on_attach(phid: PhidgetHandle, ctx: *mut c_void) {
     if !ctx.is_null() {
@@ -37,6 +62,170 @@ unsafe extern "C" fn on_detach(phid: PhidgetHandle, ctx: *mut c_void) {
     }
 }
 
+// Low-level, unsafe callback for device error events.
+// Every channel created by the crate has this handler, so that the errors
+// reach the event bus, even if the application didn't register a callback.
+unsafe extern "C" fn on_error(
+    phid: PhidgetHandle,
+    ctx: *mut c_void,
+    code: ffi::Phidget_ErrorEventCode,
+    descr: *const c_char,
+) {
+    let code = ErrorEventCode::try_from(code).unwrap_or(ErrorEventCode::Failure);
+    let descr = if descr.is_null() {
+        Cow::Borrowed("")
+    }
+    else {
+        CStr::from_ptr(descr).to_string_lossy()
+    };
+    let ph = GenericPhidget::from(phid);
+    let change = error_states()
+        .entry(phid as usize)
+        .or_default()
+        .update(code, &descr);
+
+    // The lock is released before calling out to the device
+    if let Some((hook, fault)) = change {
+        call_fault_hook(&hook, phid, fault);
+    }
+
+    if !ctx.is_null() {
+        let cb: &mut Box<ErrorCallback> = &mut *(ctx as *mut _);
+        cb(&ph, code, &descr);
+    }
+    crate::bus::publish_error(&ph, code, &descr);
+}
+
+// A fault hook, shared so that it can be called without holding the lock
+// on the error states.
+type SharedFaultHook = Arc<Mutex<Box<FaultHook>>>;
+
+// A change in the fault of a channel: the hook to call, and the new fault.
+type FaultChange = (SharedFaultHook, Option<ErrorEventCode>);
+
+// The fault of a channel, for devices that latch some error events as
+// faults until the channel reports that they cleared.
+struct Faults {
+    // Determines which error events are faults
+    is_fault: fn(ErrorEventCode) -> bool,
+    // The present fault, if any
+    fault: Option<ErrorEventCode>,
+    // The device fault hook, if set
+    hook: Option<SharedFaultHook>,
+}
+
+impl Faults {
+    // Sets the fault, returning the change to report, if any.
+    fn set(&mut self, fault: Option<ErrorEventCode>) -> Option<FaultChange> {
+        let prev = mem::replace(&mut self.fault, fault);
+        match &self.hook {
+            Some(hook) if prev != fault => Some((Arc::clone(hook), fault)),
+            _ => None,
+        }
+    }
+}
+
+// The error state of a channel, from its error events.
+#[derive(Default)]
+struct ErrorState {
+    // The most recent error event
+    last: Option<LastError>,
+    // The range status of the reading
+    range: Range,
+    // The fault, if the device tracks them
+    faults: Option<Faults>,
+}
+
+impl ErrorState {
+    // Records an error event, returning the change in the fault to report,
+    // if any.
+    fn update(&mut self, code: ErrorEventCode, descr: &str) -> Option<FaultChange> {
+        self.last = Some((code, descr.to_string(), Instant::now()));
+        if let Some(range) = Range::from_event(code) {
+            self.range = range;
+        }
+
+        let faults = self.faults.as_mut()?;
+        if code == ErrorEventCode::Ok {
+            faults.set(None)
+        }
+        else if (faults.is_fault)(code) {
+            faults.set(Some(code))
+        }
+        else {
+            None
+        }
+    }
+}
+
+// The error state of each channel, by handle.
+// The handles are kept as integers so that the map is Send.
+static ERROR_STATES: Mutex<BTreeMap<usize, ErrorState>> = Mutex::new(BTreeMap::new());
+
+// Gets the map of the error states, even if a thread panicked with it
+// locked.
+fn error_states() -> MutexGuard<'static, BTreeMap<usize, ErrorState>> {
+    ERROR_STATES.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Forgets the error state of a channel, when its handle is deleted.
+pub(crate) fn forget_errors(phid: usize) {
+    error_states().remove(&phid);
+}
+
+/// Forgets the error states of all the channels.
+pub(crate) fn forget_all_errors() {
+    error_states().clear();
+}
+
+/// Starts latching the error events of a channel that are faults, as
+/// determined by the `is_fault` function.
+pub(crate) fn track_faults(phid: PhidgetHandle, is_fault: fn(ErrorEventCode) -> bool) {
+    error_states().entry(phid as usize).or_default().faults = Some(Faults {
+        is_fault,
+        fault: None,
+        hook: None,
+    });
+}
+
+/// Gets the present fault of a channel, if any.
+pub(crate) fn fault(phid: PhidgetHandle) -> Option<ErrorEventCode> {
+    error_states()
+        .get(&(phid as usize))
+        .and_then(|st| st.faults.as_ref())
+        .and_then(|faults| faults.fault)
+}
+
+/// Sets the hook that is called when the fault of a channel changes.
+///
+/// This does nothing if the faults of the channel are not tracked.
+pub(crate) fn set_fault_hook(phid: PhidgetHandle, hook: Box<FaultHook>) {
+    if let Some(faults) = error_states()
+        .get_mut(&(phid as usize))
+        .and_then(|st| st.faults.as_mut())
+    {
+        faults.hook = Some(Arc::new(Mutex::new(hook)));
+    }
+}
+
+// Calls a fault hook, even if a thread panicked in it.
+fn call_fault_hook(hook: &SharedFaultHook, phid: PhidgetHandle, fault: Option<ErrorEventCode>) {
+    let hook = hook.lock().unwrap_or_else(|err| err.into_inner());
+    hook(phid, fault);
+}
+
+/// Clears the fault of a channel, like when it gives a valid reading.
+pub(crate) fn clear_fault(phid: PhidgetHandle) {
+    let change = error_states()
+        .get_mut(&(phid as usize))
+        .and_then(|st| st.faults.as_mut())
+        .and_then(|faults| faults.set(None));
+
+    if let Some((hook, fault)) = change {
+        call_fault_hook(&hook, phid, fault);
+    }
+}
+
 // ----- Callbacks -----
 
 /// Assigns a handler that will be called when the Attach event occurs for
@@ -73,8 +262,67 @@ where
     Ok(ctx)
 }
 
+/// Assigns a handler that will be called when an error event occurs for a
+/// phidget.
+pub fn set_on_error_handler<P, F>(ph: &mut P, cb: F) -> Result<*mut c_void>
+where
+    P: Phidget,
+    F: Fn(&GenericPhidget, ErrorEventCode, &str) + Send + 'static,
+{
+    // 1st box is fat ptr, 2nd is regular pointer.
+    let cb: Box<Box<ErrorCallback>> = Box::new(Box::new(cb));
+    let ctx = Box::into_raw(cb) as *mut c_void;
+
+    ReturnCode::result(unsafe {
+        ffi::Phidget_setOnErrorHandler(ph.as_handle(), Some(on_error), ctx)
+    })?;
+    Ok(ctx)
+}
+
+/// Installs the error handler, without an application callback, on a
+/// newly-created channel.
+pub(crate) fn init_error_handler(phid: PhidgetHandle) {
+    unsafe {
+        ffi::Phidget_setOnErrorHandler(phid, Some(on_error), ptr::null_mut());
+    }
+}
+
 /////////////////////////////////////////////////////////////////////////////
 
+/// The mesh network mode of a device, for the wireless mesh dongles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum MeshMode {
+    /// The device routes traffic for other devices in the mesh
+    Router = ffi::Phidget_MeshMode_MESHMODE_ROUTER, // 1
+    /// A low-power end device that sleeps between transmissions
+    SleepyEndDevice = ffi::Phidget_MeshMode_MESHMODE_SLEEPYENDDEVICE, // 2
+}
+
+impl TryFrom<u32> for MeshMode {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use MeshMode::*;
+        match val {
+            ffi::Phidget_MeshMode_MESHMODE_ROUTER => Ok(Router), // 1
+            ffi::Phidget_MeshMode_MESHMODE_SLEEPYENDDEVICE => Ok(SleepyEndDevice), // 2
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// Closes a channel when dropped, for `Phidget::with_open()`.
+struct CloseGuard<'a, P: Phidget>(&'a mut P);
+
+impl<P: Phidget> Drop for CloseGuard<'_, P> {
+    fn drop(&mut self) {
+        let _ = self.0.close();
+    }
+}
+
 /// The base trait and implementation for Phidgets
 pub trait Phidget: Send {
     /// Get the phidget handle for the device
@@ -98,6 +346,101 @@ pub trait Phidget: Send {
         self.open_wait(crate::TIMEOUT_DEFAULT)
     }
 
+    /// Attempt to open the channel, waiting a limited time for it to
+    /// connect, and on failure, report the filters that were used.
+    ///
+    /// This is the same as [`open_wait()`](Phidget::open_wait), but the
+    /// error says which channel could not be found, giving the channel
+    /// class, the time spent waiting, and the filters that were set, like
+    /// `[sn=12345,port=2,ch=0,hpd=false]`.
+    fn open_wait_with_context(&mut self, to: Duration) -> std::result::Result<(), AttachError> {
+        self.open_wait(to).map_err(|code| AttachError {
+            code,
+            class: self.channel_class().ok(),
+            filter: self.filter(),
+            timeout: to,
+        })
+    }
+
+    /// Creates a channel on the device with the specified label, and opens
+    /// it, waiting a limited time for it to attach.
+    ///
+    /// Labels are the recommended way to tell apart several identical
+    /// devices, since they follow the device when it's moved to another
+    /// port or hub.
+    ///
+    /// ```rust,no_run
+    /// use phidget::{devices::TemperatureSensor, Phidget};
+    /// use std::time::Duration;
+    ///
+    /// let sensor = TemperatureSensor::open_by_label("boiler", Duration::from_secs(5)).unwrap();
+    /// ```
+    fn open_by_label(label: &str, to: Duration) -> Result<Self>
+    where
+        Self: Sized + Default,
+    {
+        crate::labels::find_by_label(label, to)
+    }
+
+    /// Sets a handler to be called when the channel attaches, then opens
+    /// the channel, waiting a limited time for it to connect.
+    ///
+    /// The handler receives the attached channel. Since it is registered
+    /// before the channel is opened, any settings applied in it (data
+    /// interval, thermocouple type, bridge gain, etc) take effect before
+    /// the first data event, and are re-applied if the channel detaches
+    /// and re-attaches. This replaces any attach handler that was set.
+    fn open_with<F>(&mut self, to: Duration, on_attach: F) -> Result<()>
+    where
+        Self: Sized,
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        self.set_attach_hook(on_attach)?;
+        self.open_wait(to)
+    }
+
+    /// Sets a handler to be called with the channel each time it
+    /// attaches, to configure it.
+    ///
+    /// This can be set before the channel is opened, so that settings
+    /// applied in it take effect before the first data event. This
+    /// replaces any attach handler that was set.
+    ///
+    /// Only the device types override this, as they own the memory for
+    /// the handler. The default returns an `Unsupported` error.
+    fn set_attach_hook<F>(&mut self, _hook: F) -> Result<()>
+    where
+        Self: Sized,
+        F: Fn(&mut Self) + Send + 'static,
+    {
+        Err(ErrorCode::Unsupported)
+    }
+
+    /// Opens the channel, waiting a limited time for it to connect, runs
+    /// the closure on it, then closes it.
+    ///
+    /// The channel is closed however the closure exits, including by an
+    /// early return of an error, or a panic.
+    ///
+    /// ```rust,no_run
+    /// use phidget::{devices::TemperatureSensor, Phidget};
+    ///
+    /// let mut sensor = TemperatureSensor::new();
+    /// let t = sensor
+    ///     .with_open(phidget::TIMEOUT_DEFAULT, |sensor| sensor.temperature())
+    ///     .unwrap();
+    /// println!("{}", t);
+    /// ```
+    fn with_open<F, R>(&mut self, to: Duration, f: F) -> Result<R>
+    where
+        Self: Sized,
+        F: FnOnce(&mut Self) -> Result<R>,
+    {
+        self.open_wait(to)?;
+        let guard = CloseGuard(self);
+        f(guard.0)
+    }
+
     /// Closes the channel
     fn close(&mut self) -> Result<()> {
         ReturnCode::result(unsafe { ffi::Phidget_close(self.as_handle()) })
@@ -117,6 +460,50 @@ pub trait Phidget: Send {
         Ok(attached != 0)
     }
 
+    /// Gets the most recent error event from the channel, if any, with the
+    /// time that it was received.
+    ///
+    /// This lets polling code notice conditions like saturation or a
+    /// tripped failsafe without registering an error handler. An event
+    /// with the code [`ErrorEventCode::Ok`] means that the previous error
+    /// condition has cleared.
+    fn last_error(&mut self) -> Option<LastError> {
+        error_states()
+            .get(&(self.as_handle() as usize))
+            .and_then(|st| st.last.clone())
+    }
+
+    /// Gets whether the reading of the channel is within range, as
+    /// reported by its error events.
+    ///
+    /// This is [`Range::Ok`] until the channel reports a saturated or
+    /// out-of-range reading, and again once it reports that the condition
+    /// has cleared.
+    fn range_status(&mut self) -> Range {
+        error_states()
+            .get(&(self.as_handle() as usize))
+            .map(|st| st.range)
+            .unwrap_or_default()
+    }
+
+    /// Determines if the handle refers to a channel, as opposed to a
+    /// device.
+    ///
+    /// Handles passed to the manager and to some events can refer to a
+    /// whole device rather than one of its channels. Only channel handles
+    /// support the channel-specific operations.
+    fn is_channel(&mut self) -> Result<bool> {
+        let mut is_chan: c_int = 0;
+        ReturnCode::result(unsafe { ffi::Phidget_getIsChannel(self.as_handle(), &mut is_chan) })?;
+        Ok(is_chan != 0)
+    }
+
+    /// Determines if the handle refers to a device, as opposed to one of
+    /// its channels.
+    fn is_device(&mut self) -> Result<bool> {
+        Ok(!self.is_channel()?)
+    }
+
     /// Determines if the channel is open locally (not over a network).
     fn is_local(&mut self) -> Result<bool> {
         let mut local: c_int = 0;
@@ -143,6 +530,79 @@ pub trait Phidget: Send {
         ReturnCode::result(unsafe { ffi::Phidget_setIsRemote(self.as_handle(), rem) })
     }
 
+    /// Gets the name of the server that a remote channel is attached
+    /// through.
+    fn server_name(&mut self) -> Result<String> {
+        crate::get_ffi_string(|s| unsafe { ffi::Phidget_getServerName(self.as_handle(), s) })
+    }
+
+    /// Gets the unique name of the server that a remote channel is
+    /// attached through.
+    fn server_unique_name(&mut self) -> Result<String> {
+        crate::get_ffi_string(|s| unsafe { ffi::Phidget_getServerUniqueName(self.as_handle(), s) })
+    }
+
+    /// Gets the host name of the server that a remote channel is attached
+    /// through.
+    fn server_hostname(&mut self) -> Result<String> {
+        crate::get_ffi_string(|s| unsafe { ffi::Phidget_getServerHostname(self.as_handle(), s) })
+    }
+
+    /// Gets the network address and port of the server that a remote
+    /// channel is attached through.
+    fn server_peer_name(&mut self) -> Result<String> {
+        crate::get_ffi_string(|s| unsafe { ffi::Phidget_getServerPeerName(self.as_handle(), s) })
+    }
+
+    /// Gets the protocol version of the server that a remote channel is
+    /// attached through.
+    fn server_version(&mut self) -> Result<ServerVersion> {
+        let (mut major, mut minor): (c_int, c_int) = (0, 0);
+        ReturnCode::result(unsafe {
+            ffi::Phidget_getServerVersion(self.as_handle(), &mut major, &mut minor)
+        })?;
+        Ok(ServerVersion {
+            major: major as i32,
+            minor: minor as i32,
+        })
+    }
+
+    /// Gets the server that a remote channel is coming from, and whether
+    /// it's connected, or `None` for a local channel.
+    ///
+    /// Items of the server that can't be read, as when the channel is
+    /// not attached, are left empty.
+    fn remote_info(&mut self) -> Result<Option<RemoteInfo>> {
+        if !self.is_remote()? {
+            return Ok(None);
+        }
+        Ok(Some(RemoteInfo {
+            server_name: self.server_name().unwrap_or_default(),
+            unique_name: self.server_unique_name().unwrap_or_default(),
+            hostname: self.server_hostname().unwrap_or_default(),
+            peer_name: self.server_peer_name().unwrap_or_default(),
+            version: self.server_version().ok(),
+            connected: self.is_attached().unwrap_or_default(),
+        }))
+    }
+
+    /// Gets the mesh mode of the device.
+    ///
+    /// This is only supported by devices that can join a wireless mesh
+    /// network, and requires a version of the phidget22 library with mesh
+    /// support. Other devices return an error.
+    fn mesh_mode(&mut self) -> Result<MeshMode> {
+        let mut mode = 0;
+        ReturnCode::result(unsafe { ffi::Phidget_getMeshMode(self.as_handle(), &mut mode) })?;
+        MeshMode::try_from(mode)
+    }
+
+    /// Sets the mesh mode of the device.
+    /// This must be set before the channel is opened.
+    fn set_mesh_mode(&mut self, mode: MeshMode) -> Result<()> {
+        ReturnCode::result(unsafe { ffi::Phidget_setMeshMode(self.as_handle(), mode as u32) })
+    }
+
     /// Gets the data interval for the device, if supported.
     fn data_interval(&mut self) -> Result<Duration> {
         let mut ms: u32 = 0;
@@ -170,6 +630,17 @@ pub trait Phidget: Send {
         Ok(Duration::from_millis(ms as u64))
     }
 
+    /// Sets the data interval, limited to the range supported by the
+    /// channel, and returns the interval that was actually applied.
+    ///
+    /// The channel must be attached to read its limits.
+    fn set_data_interval_clamped(&mut self, interval: Duration) -> Result<Duration> {
+        let limits = Bounds::new(self.min_data_interval()?, self.max_data_interval()?);
+        let interval = limits.clamp(interval);
+        self.set_data_interval(interval)?;
+        Ok(interval)
+    }
+
     /// Gets the data update rate for the device, if supported.
     fn data_rate(&mut self) -> Result<f64> {
         let mut freq: f64 = 0.0;
@@ -196,6 +667,74 @@ pub trait Phidget: Send {
         Ok(freq)
     }
 
+    /// Gets the sampling rate of the channel.
+    ///
+    /// This is the data rate, or for channels that don't support it, the
+    /// rate of the data interval.
+    fn sample_rate(&mut self) -> Result<Rate> {
+        match self.data_rate() {
+            Err(ErrorCode::Unsupported) => Ok(Rate::from_interval(self.data_interval()?)),
+            res => res.map(Rate::hz),
+        }
+    }
+
+    /// Sets the sampling rate of the channel, and returns the rate that
+    /// was actually applied.
+    ///
+    /// This sets the data rate, or for channels that don't support it, the
+    /// data interval, which is rounded down to whole milliseconds.
+    fn set_sample_rate(&mut self, rate: Rate) -> Result<Rate> {
+        match self.set_data_rate(rate.as_hz()) {
+            Err(ErrorCode::Unsupported) => self.set_data_interval(rate.interval())?,
+            res => res?,
+        }
+        self.sample_rate()
+    }
+
+    /// Gets the change trigger for the main value of the channel, if
+    /// supported.
+    ///
+    /// Change events are only fired when the value changes by at least
+    /// this amount. Channel types with a change trigger override this;
+    /// the default returns an `Unsupported` error.
+    fn change_trigger(&mut self) -> Result<f64> {
+        Err(ErrorCode::Unsupported)
+    }
+
+    /// Sets the change trigger for the main value of the channel, if
+    /// supported.
+    fn set_change_trigger(&mut self, _trigger: f64) -> Result<()> {
+        Err(ErrorCode::Unsupported)
+    }
+
+    /// Gets the minimum change trigger for the channel, if supported.
+    fn min_change_trigger(&mut self) -> Result<f64> {
+        Err(ErrorCode::Unsupported)
+    }
+
+    /// Gets the maximum change trigger for the channel, if supported.
+    fn max_change_trigger(&mut self) -> Result<f64> {
+        Err(ErrorCode::Unsupported)
+    }
+
+    /// Validates the sampling configuration against the limits of the
+    /// channel, then applies it.
+    ///
+    /// The channel must be attached. Nothing is applied if any of the
+    /// settings are out of range. If the configuration has a data rate,
+    /// it's applied in place of the interval.
+    fn apply_sampling(&mut self, cfg: &SamplingConfig) -> Result<()> {
+        cfg.validate(self)?;
+        match cfg.rate {
+            Some(rate) => self.set_data_rate(rate)?,
+            None => self.set_data_interval(cfg.interval)?,
+        }
+        if let Some(trigger) = cfg.trigger {
+            self.set_change_trigger(trigger)?;
+        }
+        Ok(())
+    }
+
     /// Get the number of channels of the specified class on the device.
     fn device_channel_count(&mut self, cls: ChannelClass) -> Result<u32> {
         let mut n: u32 = 0;
@@ -206,6 +745,22 @@ pub trait Phidget: Send {
         Ok(n)
     }
 
+    /// Gets a snapshot of all the readable settings that the channel
+    /// supports, with their limits, where known.
+    ///
+    /// Channel types with their own settings override this to add them to
+    /// the ones common to all channels.
+    fn properties(&mut self) -> Result<Properties> {
+        properties::common(self)
+    }
+
+    /// Gets the number of VINT ports on the hub device.
+    fn hub_port_count(&mut self) -> Result<i32> {
+        let mut n = 0;
+        ReturnCode::result(unsafe { ffi::Phidget_getHubPortCount(self.as_handle(), &mut n) })?;
+        Ok(n)
+    }
+
     /// Gets class of the channel
     fn channel_class(&mut self) -> Result<ChannelClass> {
         let mut cls = ffi::Phidget_ChannelClass_PHIDCHCLASS_NOTHING;
@@ -223,6 +778,16 @@ pub trait Phidget: Send {
         crate::get_ffi_string(|s| unsafe { ffi::Phidget_getChannelName(self.as_handle(), s) })
     }
 
+    /// Gets the channel's name into an existing string, reusing its memory.
+    ///
+    /// This avoids an allocation for each query, as when the channel's name is
+    /// read repeatedly.
+    fn channel_name_into(&mut self, buf: &mut String) -> Result<()> {
+        crate::get_ffi_string_into(buf, |s| unsafe {
+            ffi::Phidget_getChannelName(self.as_handle(), s)
+        })
+    }
+
     /// Gets class of the device
     fn device_class(&mut self) -> Result<DeviceClass> {
         let mut cls = ffi::Phidget_DeviceClass_PHIDCLASS_NOTHING;
@@ -235,8 +800,67 @@ pub trait Phidget: Send {
         crate::get_ffi_string(|s| unsafe { ffi::Phidget_getDeviceClassName(self.as_handle(), s) })
     }
 
+    /// Gets the ID of the device model.
+    fn device_id(&mut self) -> Result<DeviceId> {
+        let mut id = ffi::Phidget_DeviceID_PHIDID_NOTHING;
+        ReturnCode::result(unsafe { ffi::Phidget_getDeviceID(self.as_handle(), &mut id) })?;
+        DeviceId::try_from(id)
+    }
+
+    /// Gets the name of the device.
+    fn device_name(&mut self) -> Result<String> {
+        crate::get_ffi_string(|s| unsafe { ffi::Phidget_getDeviceName(self.as_handle(), s) })
+    }
+
+    /// Gets the name of the device into an existing string, reusing its memory.
+    ///
+    /// This avoids an allocation for each query, as when the name of the device is
+    /// read repeatedly.
+    fn device_name_into(&mut self, buf: &mut String) -> Result<()> {
+        crate::get_ffi_string_into(buf, |s| unsafe {
+            ffi::Phidget_getDeviceName(self.as_handle(), s)
+        })
+    }
+
+    /// Gets the SKU (part number) of the device.
+    fn device_sku(&mut self) -> Result<String> {
+        crate::get_ffi_string(|s| unsafe { ffi::Phidget_getDeviceSKU(self.as_handle(), s) })
+    }
+
+    /// Gets the SKU of the device into an existing string, reusing its memory.
+    ///
+    /// This avoids an allocation for each query, as when the SKU of the device is
+    /// read repeatedly.
+    fn device_sku_into(&mut self, buf: &mut String) -> Result<()> {
+        crate::get_ffi_string_into(buf, |s| unsafe {
+            ffi::Phidget_getDeviceSKU(self.as_handle(), s)
+        })
+    }
+
+    /// Gets the firmware version of the device.
+    fn device_version(&mut self) -> Result<i32> {
+        let mut ver: c_int = 0;
+        ReturnCode::result(unsafe { ffi::Phidget_getDeviceVersion(self.as_handle(), &mut ver) })?;
+        Ok(ver as i32)
+    }
+
     // ----- Filters -----
 
+    /// Gets the filters set on the channel, as a device specification.
+    ///
+    /// Filters left as a wildcard, like [`SerialNumber::ANY`], are
+    /// `None` in the specification. On an open channel this reports the
+    /// address of the attached device.
+    fn filter(&mut self) -> DeviceSpec {
+        DeviceSpec {
+            serial_number: self.serial_number().unwrap_or_default(),
+            hub_port: self.hub_port().unwrap_or_default(),
+            channel: self.channel().unwrap_or_default(),
+            is_hub_port_device: self.is_hub_port_device().ok(),
+            label: self.device_label().ok().filter(|label| !label.is_empty()),
+        }
+    }
+
     /// Determines whether this channel is a VINT Hub port channel, or part
     /// of a VINT device attached to a hub port.
     fn is_hub_port_device(&mut self) -> Result<bool> {
@@ -254,53 +878,379 @@ pub trait Phidget: Send {
     }
 
     /// Gets the index of the port on the VINT Hub to which the channel is attached.
-    fn hub_port(&mut self) -> Result<i32> {
+    fn hub_port(&mut self) -> Result<HubPort> {
         let mut port: c_int = 0;
         ReturnCode::result(unsafe { ffi::Phidget_getHubPort(self.as_handle(), &mut port) })?;
-        Ok(port as i32)
+        HubPort::new(port as i32)
     }
 
     /// Gets the index of the port on the VINT Hub to which the channel is attached.
-    /// Set to PHIDGET_HUBPORT_ANY to open the channel on any port of the hub.
+    /// Set to [`HubPort::ANY`] to open the channel on any port of the hub.
+    /// This must be set before the channel is opened.
+    fn set_hub_port(&mut self, port: HubPort) -> Result<()> {
+        let port = port.value() as c_int;
+        ReturnCode::result(unsafe { ffi::Phidget_setHubPort(self.as_handle(), port) })
+    }
+
+    /// Sets the index of the port on the VINT Hub to be opened, where
+    /// `None` opens the channel on any port of the hub.
     /// This must be set before the channel is opened.
-    fn set_hub_port(&mut self, port: i32) -> Result<()> {
-        ReturnCode::result(unsafe { ffi::Phidget_setHubPort(self.as_handle(), port as c_int) })
+    fn set_hub_port_opt(&mut self, port: Option<HubPort>) -> Result<()> {
+        self.set_hub_port(port.unwrap_or_default())
     }
 
     /// Gets the channel index of the device.
-    fn channel(&mut self) -> Result<i32> {
+    fn channel(&mut self) -> Result<Channel> {
         let mut ch: c_int = 0;
         ReturnCode::result(unsafe { ffi::Phidget_getChannel(self.as_handle(), &mut ch) })?;
-        Ok(ch as i32)
+        Channel::new(ch as i32)
     }
 
     /// Sets the channel index to be opened.
-    /// The default channel is 0. Set to PHIDGET_CHANNEL_ANY to open any
+    /// The default channel is 0. Set to [`Channel::ANY`] to open any
     /// channel on the specified device. This must be set before the channel
     /// is opened.
-    fn set_channel(&mut self, chan: i32) -> Result<()> {
-        ReturnCode::result(unsafe { ffi::Phidget_setChannel(self.as_handle(), chan as c_int) })
+    fn set_channel(&mut self, chan: Channel) -> Result<()> {
+        let chan = chan.value() as c_int;
+        ReturnCode::result(unsafe { ffi::Phidget_setChannel(self.as_handle(), chan) })
+    }
+
+    /// Sets the channel index to be opened, where `None` opens any
+    /// channel on the specified device.
+    /// This must be set before the channel is opened.
+    fn set_channel_opt(&mut self, chan: Option<Channel>) -> Result<()> {
+        self.set_channel(chan.unwrap_or_default())
     }
 
     /// Gets the serial number of the device.
     /// If the channel is part of a VINT device, this is the serial number
     /// of the VINT Hub to which the device is attached.
-    fn serial_number(&mut self) -> Result<i32> {
+    fn serial_number(&mut self) -> Result<SerialNumber> {
         let mut n = 0;
         ReturnCode::result(unsafe {
             ffi::Phidget_getDeviceSerialNumber(self.as_handle(), &mut n)
         })?;
-        Ok(n)
+        SerialNumber::new(n)
+    }
+
+    /// Gets the label of the device.
+    fn device_label(&mut self) -> Result<String> {
+        crate::get_ffi_string(|s| unsafe { ffi::Phidget_getDeviceLabel(self.as_handle(), s) })
+    }
+
+    /// Gets the label of the device into an existing string, reusing its memory.
+    ///
+    /// This avoids an allocation for each query, as when the label of the device is
+    /// read repeatedly.
+    fn device_label_into(&mut self, buf: &mut String) -> Result<()> {
+        crate::get_ffi_string_into(buf, |s| unsafe {
+            ffi::Phidget_getDeviceLabel(self.as_handle(), s)
+        })
+    }
+
+    /// Sets the label of the device to be opened.
+    /// Labels are the recommended way to address multiple identical
+    /// devices. This must be set before the channel is opened.
+    fn set_device_label(&mut self, label: &str) -> Result<()> {
+        let label = CString::new(label).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe { ffi::Phidget_setDeviceLabel(self.as_handle(), label.as_ptr()) })
+    }
+
+    /// Writes a label to the flash memory of the attached device.
+    ///
+    /// The label is checked with [`labels::validate()`](crate::labels::validate)
+    /// before it is written. Note that the flash memory has a limited
+    /// number of write cycles, so this should not be done repeatedly.
+    fn write_device_label(&mut self, label: &str) -> Result<()> {
+        crate::labels::validate(label)?;
+        let label = CString::new(label).map_err(|_| ErrorCode::InvalidArg)?;
+        ReturnCode::result(unsafe {
+            ffi::Phidget_writeDeviceLabel(self.as_handle(), label.as_ptr())
+        })
+    }
+
+    /// Writes a label to the flash memory of the attached device, and
+    /// checks that it took.
+    ///
+    /// After the label is written, the channel is closed and reopened on
+    /// the same device, filtered by the new label, and the label is read
+    /// back. A mislabeled device is hard to find again, so this fails with
+    /// an `Unexpected` error if the label read back doesn't match, or the
+    /// error from reopening the channel, waiting up to `to` for it.
+    fn write_and_verify_label(&mut self, label: &str, to: Duration) -> Result<()> {
+        crate::labels::validate(label)?;
+        let spec = self.filter();
+
+        self.write_device_label(label)?;
+        self.close()?;
+
+        if !spec.serial_number.is_any() {
+            self.set_serial_number(spec.serial_number)?;
+        }
+        if !spec.hub_port.is_any() {
+            self.set_hub_port(spec.hub_port)?;
+        }
+        if !spec.channel.is_any() {
+            self.set_channel(spec.channel)?;
+        }
+        if let Some(on) = spec.is_hub_port_device {
+            self.set_is_hub_port_device(on)?;
+        }
+        self.set_device_label(label)?;
+        self.open_wait(to)?;
+
+        if self.device_label()? != label {
+            return Err(ErrorCode::Unexpected);
+        }
+        Ok(())
     }
 
     /// Sets the device serial number to be opened.
-    /// Leave un-set, or set to PHIDGET_SERIALNUMBER_ANY to open any serial
+    /// Leave un-set, or set to [`SerialNumber::ANY`] to open any serial
     /// number. If the channel is part of a VINT device, this is the serial
     /// number of the VINT Hub to which the device is attached.
     /// This must be set before the channel is opened.
-    fn set_serial_number(&mut self, sn: i32) -> Result<()> {
-        ReturnCode::result(unsafe { ffi::Phidget_setDeviceSerialNumber(self.as_handle(), sn) })
+    fn set_serial_number(&mut self, sn: SerialNumber) -> Result<()> {
+        ReturnCode::result(unsafe {
+            ffi::Phidget_setDeviceSerialNumber(self.as_handle(), sn.value())
+        })
+    }
+
+    /// Sets the device serial number to be opened, where `None` opens any
+    /// serial number.
+    /// This must be set before the channel is opened.
+    fn set_serial_number_opt(&mut self, sn: Option<SerialNumber>) -> Result<()> {
+        self.set_serial_number(sn.unwrap_or_default())
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// A sampling rate, in Hz.
+///
+/// A channel can be set to sample at a data interval, in whole
+/// milliseconds, or at a data rate, in Hz, which can be faster than 1kHz.
+/// A `Rate` converts between the two, so that the rate can be specified
+/// the same way for any channel with [`Phidget::set_sample_rate()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rate(f64);
+
+impl Rate {
+    /// Creates a rate from a frequency, in Hz.
+    pub fn hz(hz: f64) -> Self {
+        Self(hz)
+    }
+
+    /// Creates a rate from a sampling period.
+    pub fn from_interval(interval: Duration) -> Self {
+        Self(1.0 / interval.as_secs_f64())
+    }
+
+    /// Gets the frequency, in Hz.
+    pub fn as_hz(&self) -> f64 {
+        self.0
+    }
+
+    /// Gets the sampling period.
+    ///
+    /// A rate that isn't positive has the maximum period.
+    pub fn interval(&self) -> Duration {
+        Duration::try_from_secs_f64(1.0 / self.0).unwrap_or(Duration::MAX)
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} Hz", self.0)
+    }
+}
+
+/// The sampling settings for a channel.
+///
+/// This gathers the data interval, and the optional data rate and change
+/// trigger, so that they can be validated and applied together with
+/// [`Phidget::apply_sampling()`].
+///
+/// ```rust,no_run
+/// use phidget::{devices::TemperatureSensor, Phidget, SamplingConfig};
+/// use std::time::Duration;
+///
+/// let mut sensor = TemperatureSensor::new();
+/// sensor.open_wait_default().unwrap();
+///
+/// let cfg = SamplingConfig::new(Duration::from_millis(500)).with_trigger(0.1);
+/// sensor.apply_sampling(&cfg).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingConfig {
+    /// The data interval (sampling period)
+    pub interval: Duration,
+    /// The data rate, in Hz.
+    /// If set, this is applied in place of the interval, which is then
+    /// ignored. It can specify rates faster than a 1ms interval allows.
+    pub rate: Option<f64>,
+    /// The change trigger.
+    /// Change events are only fired when the value changes by at least
+    /// this amount.
+    pub trigger: Option<f64>,
+}
+
+impl SamplingConfig {
+    /// Creates a sampling configuration with the specified data interval.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            rate: None,
+            trigger: None,
+        }
+    }
+
+    /// Sets the data rate, in Hz, to use in place of the interval.
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Sets the change trigger.
+    pub fn with_trigger(mut self, trigger: f64) -> Self {
+        self.trigger = Some(trigger);
+        self
+    }
+
+    /// Checks the settings against the minimum and maximum values
+    /// supported by the channel, returning an `InvalidArg` error if any
+    /// are out of range.
+    ///
+    /// The channel must be attached to read its limits.
+    pub fn validate<P>(&self, ph: &mut P) -> Result<()>
+    where
+        P: Phidget + ?Sized,
+    {
+        fn check<T: PartialOrd>(val: T, min: T, max: T) -> Result<()> {
+            if val < min || val > max {
+                Err(ErrorCode::InvalidArg)
+            }
+            else {
+                Ok(())
+            }
+        }
+
+        // A rate takes the place of the interval
+        match self.rate {
+            Some(rate) => check(rate, ph.min_data_rate()?, ph.max_data_rate()?)?,
+            None => check(
+                self.interval,
+                ph.min_data_interval()?,
+                ph.max_data_interval()?,
+            )?,
+        }
+        if let Some(trigger) = self.trigger {
+            check(trigger, ph.min_change_trigger()?, ph.max_change_trigger()?)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(250))
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// The interval at which to poll channels while waiting for them to attach.
+const ATTACH_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// Opens a set of channels without waiting, returning the state of each.
+// A `None` state means the channel is waiting to attach.
+fn open_all(chans: &mut [&mut dyn Phidget]) -> Vec<Option<Result<()>>> {
+    chans
+        .iter_mut()
+        .map(|ph| ph.open().err().map(Err))
+        .collect()
+}
+
+// Updates the state of the channels that are waiting to attach,
+// returning whether any are still waiting.
+fn poll_attached(chans: &mut [&mut dyn Phidget], states: &mut [Option<Result<()>>]) -> bool {
+    let mut waiting = false;
+    for (ph, state) in chans.iter_mut().zip(states.iter_mut()) {
+        if state.is_none() {
+            match ph.is_attached() {
+                Ok(true) => *state = Some(Ok(())),
+                Ok(false) => waiting = true,
+                Err(err) => *state = Some(Err(err)),
+            }
+        }
+    }
+    waiting
+}
+
+// Closes the channels that failed to attach, and gets the final results.
+fn finish_all(chans: &mut [&mut dyn Phidget], states: Vec<Option<Result<()>>>) -> Vec<Result<()>> {
+    chans
+        .iter_mut()
+        .zip(states)
+        .map(|(ph, state)| {
+            let res = state.unwrap_or(Err(ErrorCode::Timeout));
+            if res.is_err() {
+                let _ = ph.close();
+            }
+            res
+        })
+        .collect()
+}
+
+/// Opens a set of channels and waits a limited time for all of them to
+/// attach.
+///
+/// The channels are opened together, so the total wait is bounded by the
+/// timeout, rather than by the timeout for each channel in turn. This
+/// returns a result for each channel, in the same order. A channel that
+/// fails to open or attach in time is closed again, and reports its error,
+/// which is `Timeout` if it didn't attach.
+///
+/// ```rust,no_run
+/// use phidget::{devices::{HumiditySensor, TemperatureSensor}, Phidget};
+/// use std::time::Duration;
+///
+/// let mut temp = TemperatureSensor::new();
+/// let mut hum = HumiditySensor::new();
+///
+/// let res = phidget::wait_all_attached(&mut [&mut temp, &mut hum], Duration::from_secs(5));
+/// for (i, res) in res.iter().enumerate() {
+///     if let Err(err) = res {
+///         eprintln!("Channel {} failed: {}", i, err);
+///     }
+/// }
+/// ```
+pub fn wait_all_attached(chans: &mut [&mut dyn Phidget], to: Duration) -> Vec<Result<()>> {
+    let deadline = Instant::now() + to;
+    let mut states = open_all(chans);
+    while poll_attached(chans, &mut states) && Instant::now() < deadline {
+        thread::sleep(ATTACH_POLL_INTERVAL);
+    }
+    finish_all(chans, states)
+}
+
+/// Opens a set of channels and waits a limited time for all of them to
+/// attach, without blocking the async runtime.
+///
+/// This is the async version of [`wait_all_attached()`], and requires the
+/// `tokio` feature.
+#[cfg(feature = "tokio")]
+pub async fn wait_all_attached_async(
+    chans: &mut [&mut dyn Phidget],
+    to: Duration,
+) -> Vec<Result<()>> {
+    let deadline = Instant::now() + to;
+    let mut states = open_all(chans);
+    while poll_attached(chans, &mut states) && Instant::now() < deadline {
+        tokio::time::sleep(ATTACH_POLL_INTERVAL).await;
     }
+    finish_all(chans, states)
 }
 
 /////////////////////////////////////////////////////////////////////////////
@@ -324,6 +1274,11 @@ impl GenericPhidget {
     pub fn new(phid: PhidgetHandle) -> Self {
         Self { phid }
     }
+
+    /// Get a reference to the underlying phidget handle
+    pub fn as_channel(&self) -> &PhidgetHandle {
+        &self.phid
+    }
 }
 
 impl Phidget for GenericPhidget {
@@ -340,3 +1295,38 @@ impl From<PhidgetHandle> for GenericPhidget {
         Self::new(phid)
     }
 }
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_events() {
+        use ErrorEventCode::*;
+
+        let mut state = ErrorState {
+            faults: Some(Faults {
+                is_fault: |code| code == BadConnection,
+                fault: None,
+                hook: Some(Arc::new(Mutex::new(Box::new(|_, _| {})))),
+            }),
+            ..ErrorState::default()
+        };
+        let fault = |st: &ErrorState| st.faults.as_ref().unwrap().fault;
+
+        let change = state.update(BadConnection, "");
+        assert_eq!(change.map(|(_, f)| f), Some(Some(BadConnection)));
+        assert_eq!(fault(&state), Some(BadConnection));
+
+        // Unrelated events and repeats leave the fault as is, unreported
+        assert!(state.update(PacketLost, "").is_none());
+        assert!(state.update(BadConnection, "").is_none());
+        assert_eq!(fault(&state), Some(BadConnection));
+
+        let change = state.update(Ok, "");
+        assert_eq!(change.map(|(_, f)| f), Some(None));
+        assert_eq!(fault(&state), None);
+    }
+}
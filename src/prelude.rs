@@ -0,0 +1,44 @@
+// phidget-rs/src/prelude.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! The most commonly used types and traits, for a glob import.
+//!
+//! Most of the functionality of a device comes from traits, like
+//! [`Phidget`] for opening the channel and setting the data interval, so
+//! those need to be in scope for their methods to be found. Importing the
+//! prelude brings in the traits, the common devices, and the error types:
+//!
+//! ```rust,no_run
+//! use phidget::prelude::*;
+//!
+//! fn main() -> Result<()> {
+//!     let mut sensor = TemperatureSensor::new();
+//!     sensor.open_wait_default()?;
+//!     println!("{:.1}{}", sensor.read()?, sensor.unit().symbol());
+//!     Ok(())
+//! }
+//! ```
+
+pub use crate::{
+    devices::{
+        BldcMotor, CapacitiveTouch, CurrentInput, DcMotor, Dictionary, DigitalInput, DigitalOutput,
+        DistanceSensor, Encoder, FrequencyCounter, Gyroscope, Hub, HumiditySensor, Ir, Lcd,
+        LightSensor, Magnetometer, MotorPositionController, PhSensor, PowerGuard, PressureSensor,
+        RcServo, ResistanceInput, Rfid, SoundSensor, Spatial, Stepper, TemperatureSensor,
+        VoltageInput, VoltageOutput, VoltageRatioInput,
+    },
+    failsafe::Failsafe,
+    motion::MotionControl,
+    profile::Profiled,
+    sources::{Output, Sensor, Unit},
+    DeviceSpec, Error, ErrorCode, ErrorEventCode, GenericPhidget, Manager, Phidget, Range, Rate,
+    Result, ResultExt, TIMEOUT_DEFAULT,
+};
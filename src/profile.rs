@@ -0,0 +1,257 @@
+// phidget-rs/src/profile.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Persistent configuration profiles for channels.
+//!
+//! A profile captures the settings of a channel that are lost when it is
+//! closed or detached, such as the data interval, the change trigger, and
+//! the type of sensor attached to an input. A profile can be read from a
+//! channel that was configured by hand, stored, and then applied to a
+//! channel after it is opened, so that field devices can be provisioned
+//! the same way every time.
+//!
+//! With the `serde` feature, the profiles can be serialized to any format
+//! supported by serde:
+//!
+//! ```rust,ignore
+//! use phidget::{devices::TemperatureSensor, profile::Profiled, Phidget};
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! sensor.open_wait_default()?;
+//!
+//! let profile = sensor.save_profile()?;
+//! let doc = serde_json::to_string(&profile)?;
+//!
+//! // ...later, possibly on another system
+//! let profile = serde_json::from_str(&doc)?;
+//! sensor.apply_profile(&profile)?;
+//! ```
+//!
+//! A profile can also be staged on a channel before it is opened, to be
+//! applied automatically whenever the channel attaches:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::{TemperatureSensor, ThermocoupleType}, profile::{ChannelProfile, Profiled}, Phidget};
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! sensor
+//!     .stage_profile(ChannelProfile {
+//!         data_interval: Some(250),
+//!         thermocouple_type: Some(ThermocoupleType::K),
+//!         ..ChannelProfile::default()
+//!     })
+//!     .unwrap();
+//! sensor.open_wait_default().unwrap();
+//! ```
+
+use crate::{
+    devices::{
+        DigitalInput, Hub, HubPortMode, HumiditySensor, TemperatureSensor, ThermocoupleType,
+        VoltageInput, VoltageRatioInput,
+    },
+    errors::supported,
+    HubPort, Phidget, Result,
+};
+use std::{collections::BTreeMap, time::Duration};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A device that can save and restore its settings to a profile.
+pub trait Profiled {
+    /// The type of profile for the device.
+    type Profile;
+
+    /// Reads the current settings of the device into a profile.
+    ///
+    /// The device must be open and attached.
+    fn save_profile(&mut self) -> Result<Self::Profile>;
+
+    /// Applies the settings in a profile to the device.
+    ///
+    /// The device must be open and attached. Settings that are missing
+    /// from the profile are left unchanged.
+    fn apply_profile(&mut self, profile: &Self::Profile) -> Result<()>;
+
+    /// Stages a profile to be applied each time the device attaches.
+    ///
+    /// This is normally called before the device is opened, so that the
+    /// settings take effect before the first data event, and are restored
+    /// after a detach and re-attach. It replaces any attach handler that
+    /// was set. Errors applying the profile in the handler are ignored, so
+    /// the settings can be read back after opening to confirm them.
+    fn stage_profile(&mut self, profile: Self::Profile) -> Result<()>
+    where
+        Self: Phidget + Sized,
+        Self::Profile: Send + 'static,
+    {
+        self.set_attach_hook(move |dev| {
+            let _ = dev.apply_profile(&profile);
+        })
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// The settings for a single sensor channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct ChannelProfile {
+    /// The data interval, in milliseconds
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub data_interval: Option<u32>,
+    /// The change trigger for the main value of the channel
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub change_trigger: Option<f64>,
+    /// The type of thermocouple, for thermocouple inputs
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub thermocouple_type: Option<ThermocoupleType>,
+}
+
+impl ChannelProfile {
+    // Reads the settings common to all sensor channels.
+    fn save<P: Phidget + ?Sized>(ph: &mut P) -> Result<Self> {
+        let data_interval = supported(ph.data_interval())?.map(|d| d.as_millis() as u32);
+        let change_trigger = supported(ph.change_trigger())?;
+        Ok(Self {
+            data_interval,
+            change_trigger,
+            ..Self::default()
+        })
+    }
+
+    // Applies the settings common to all sensor channels.
+    fn apply<P: Phidget + ?Sized>(&self, ph: &mut P) -> Result<()> {
+        if let Some(ms) = self.data_interval {
+            ph.set_data_interval(Duration::from_millis(ms as u64))?;
+        }
+        if let Some(trigger) = self.change_trigger {
+            ph.set_change_trigger(trigger)?;
+        }
+        Ok(())
+    }
+}
+
+impl Profiled for TemperatureSensor {
+    type Profile = ChannelProfile;
+
+    fn save_profile(&mut self) -> Result<ChannelProfile> {
+        let mut profile = ChannelProfile::save(self)?;
+        profile.thermocouple_type = supported(self.thermocouple_type())?;
+        Ok(profile)
+    }
+
+    fn apply_profile(&mut self, profile: &ChannelProfile) -> Result<()> {
+        // The sensor type goes first, as it may change the valid ranges
+        // of the other settings.
+        if let Some(tc_type) = profile.thermocouple_type {
+            self.set_thermocouple_type(tc_type)?;
+        }
+        profile.apply(self)
+    }
+}
+
+macro_rules! channel_profiled {
+    ($($dev:ty),+) => {
+        $(
+            impl Profiled for $dev {
+                type Profile = ChannelProfile;
+
+                fn save_profile(&mut self) -> Result<ChannelProfile> {
+                    ChannelProfile::save(self)
+                }
+
+                fn apply_profile(&mut self, profile: &ChannelProfile) -> Result<()> {
+                    profile.apply(self)
+                }
+            }
+        )+
+    };
+}
+
+channel_profiled!(
+    HumiditySensor,
+    VoltageInput,
+    VoltageRatioInput,
+    DigitalInput
+);
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// The settings for a VINT hub.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct HubProfile {
+    /// The mode of each port, by port number
+    pub port_modes: BTreeMap<HubPort, HubPortMode>,
+}
+
+impl Profiled for Hub {
+    type Profile = HubProfile;
+
+    fn save_profile(&mut self) -> Result<HubProfile> {
+        let mut port_modes = BTreeMap::new();
+        for port in 0..self.hub_port_count()? {
+            let port = HubPort::new(port)?;
+            port_modes.insert(port, self.port_mode(port)?);
+        }
+        Ok(HubProfile { port_modes })
+    }
+
+    fn apply_profile(&mut self, profile: &HubProfile) -> Result<()> {
+        for (&port, &mode) in &profile.port_modes {
+            self.set_port_mode(port, mode)?;
+        }
+        Ok(())
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_profile_serde() {
+        let profile = ChannelProfile {
+            data_interval: Some(250),
+            thermocouple_type: Some(ThermocoupleType::K),
+            ..ChannelProfile::default()
+        };
+
+        let doc = serde_json::to_string(&profile).unwrap();
+        assert_eq!(doc, r#"{"data_interval":250,"thermocouple_type":"K"}"#);
+        assert_eq!(
+            serde_json::from_str::<ChannelProfile>(&doc).unwrap(),
+            profile
+        );
+
+        // Missing settings are left as `None`
+        let profile: ChannelProfile = serde_json::from_str("{}").unwrap();
+        assert_eq!(profile, ChannelProfile::default());
+    }
+
+    #[test]
+    fn test_hub_profile_serde() {
+        let mut profile = HubProfile::default();
+        profile
+            .port_modes
+            .insert(HubPort::new(0).unwrap(), HubPortMode::Vint);
+        profile
+            .port_modes
+            .insert(HubPort::new(3).unwrap(), HubPortMode::VoltageInput);
+
+        let doc = serde_json::to_string(&profile).unwrap();
+        assert!(doc.contains(r#""3":"#));
+        assert_eq!(serde_json::from_str::<HubProfile>(&doc).unwrap(), profile);
+    }
+}
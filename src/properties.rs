@@ -0,0 +1,265 @@
+// phidget-rs/src/properties.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Introspection of the settings of a channel.
+//!
+//! [`Phidget::properties()`] returns a snapshot of all the readable
+//! settings that a channel supports, along with their limits, where the
+//! library reports them. The snapshot is keyed by the name of the setting,
+//! and can be displayed generically, or compared against a later snapshot
+//! with [`diff()`] to see what changed.
+//!
+//! ```rust,no_run
+//! use phidget::{devices::VoltageInput, Phidget};
+//!
+//! let mut vin = VoltageInput::new();
+//! vin.open_wait_default().unwrap();
+//!
+//! for (name, prop) in vin.properties().unwrap() {
+//!     println!("{}: {}", name, prop);
+//! }
+//! ```
+
+use crate::{errors::supported, Phidget, Result};
+use std::{collections::BTreeMap, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of the settings of a channel, by name.
+pub type Properties = BTreeMap<String, Property>;
+
+/// The value of a single setting.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(untagged))]
+pub enum PropertyValue {
+    /// A boolean setting
+    Bool(bool),
+    /// An integer setting
+    Int(i64),
+    /// A floating-point setting
+    Float(f64),
+    /// A setting with named values, like a mode
+    Text(String),
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyValue::Bool(val) => write!(f, "{}", val),
+            PropertyValue::Int(val) => write!(f, "{}", val),
+            PropertyValue::Float(val) => write!(f, "{}", val),
+            PropertyValue::Text(val) => write!(f, "{}", val),
+        }
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(val: bool) -> Self {
+        PropertyValue::Bool(val)
+    }
+}
+
+impl From<i64> for PropertyValue {
+    fn from(val: i64) -> Self {
+        PropertyValue::Int(val)
+    }
+}
+
+impl From<u32> for PropertyValue {
+    fn from(val: u32) -> Self {
+        PropertyValue::Int(val.into())
+    }
+}
+
+impl From<f64> for PropertyValue {
+    fn from(val: f64) -> Self {
+        PropertyValue::Float(val)
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(val: String) -> Self {
+        PropertyValue::Text(val)
+    }
+}
+
+/// A single setting of a channel, with its limits, if known.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Property {
+    /// The current value
+    pub value: PropertyValue,
+    /// The minimum value, if the setting has one
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub min: Option<f64>,
+    /// The maximum value, if the setting has one
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub max: Option<f64>,
+}
+
+impl Property {
+    /// Creates a property without limits.
+    pub fn new<V: Into<PropertyValue>>(value: V) -> Self {
+        Self {
+            value: value.into(),
+            min: None,
+            max: None,
+        }
+    }
+
+    /// Sets the limits of the property.
+    pub fn with_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value)?;
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => write!(f, " [{}, {}]", min, max),
+            (Some(min), None) => write!(f, " [{}, ..]", min),
+            (None, Some(max)) => write!(f, " [.., {}]", max),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// A difference in a single setting between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyChange {
+    /// The name of the setting
+    pub name: String,
+    /// The setting in the old snapshot, if it was there
+    pub old: Option<Property>,
+    /// The setting in the new snapshot, if it is there
+    pub new: Option<Property>,
+}
+
+/// Gets the settings that differ between two snapshots.
+pub fn diff(old: &Properties, new: &Properties) -> Vec<PropertyChange> {
+    let mut changes = Vec::new();
+    for (name, prop) in old {
+        if new.get(name) != Some(prop) {
+            changes.push(PropertyChange {
+                name: name.clone(),
+                old: Some(prop.clone()),
+                new: new.get(name).cloned(),
+            });
+        }
+    }
+    for (name, prop) in new {
+        if !old.contains_key(name) {
+            changes.push(PropertyChange {
+                name: name.clone(),
+                old: None,
+                new: Some(prop.clone()),
+            });
+        }
+    }
+    changes
+}
+
+// Adds a setting to the snapshot, if the channel supports it.
+pub(crate) fn add<V: Into<PropertyValue>>(
+    props: &mut Properties,
+    name: &str,
+    value: Result<V>,
+) -> Result<()> {
+    if let Some(val) = supported(value)? {
+        props.insert(name.into(), Property::new(val));
+    }
+    Ok(())
+}
+
+// Adds a setting and its limits to the snapshot, if the channel
+// supports it.
+pub(crate) fn add_ranged<V: Into<PropertyValue>>(
+    props: &mut Properties,
+    name: &str,
+    value: Result<V>,
+    min: Result<f64>,
+    max: Result<f64>,
+) -> Result<()> {
+    if let Some(val) = supported(value)? {
+        let prop = Property::new(val).with_range(supported(min)?, supported(max)?);
+        props.insert(name.into(), prop);
+    }
+    Ok(())
+}
+
+// Reads the settings that are common to all channel classes.
+pub(crate) fn common<P: Phidget + ?Sized>(ph: &mut P) -> Result<Properties> {
+    let ms = |d: std::time::Duration| d.as_millis() as f64;
+
+    let mut props = Properties::new();
+    add_ranged(
+        &mut props,
+        "data_interval",
+        ph.data_interval().map(|d| d.as_millis() as u32),
+        ph.min_data_interval().map(ms),
+        ph.max_data_interval().map(ms),
+    )?;
+    add_ranged(
+        &mut props,
+        "data_rate",
+        ph.data_rate(),
+        ph.min_data_rate(),
+        ph.max_data_rate(),
+    )?;
+    add_ranged(
+        &mut props,
+        "change_trigger",
+        ph.change_trigger(),
+        ph.min_change_trigger(),
+        ph.max_change_trigger(),
+    )?;
+    Ok(props)
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let mut old = Properties::new();
+        old.insert("data_interval".into(), Property::new(250u32));
+        old.insert("change_trigger".into(), Property::new(0.0));
+
+        let mut new = old.clone();
+        assert!(diff(&old, &new).is_empty());
+
+        new.insert("data_interval".into(), Property::new(100u32));
+        new.remove("change_trigger");
+        new.insert("state".into(), Property::new(true));
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 3);
+
+        let names: Vec<_> = changes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["change_trigger", "data_interval", "state"]);
+        assert_eq!(changes[0].new, None);
+        assert_eq!(changes[1].new, Some(Property::new(100u32)));
+        assert_eq!(changes[2].old, None);
+    }
+
+    #[test]
+    fn test_display() {
+        let prop = Property::new(250u32).with_range(Some(20.0), Some(60000.0));
+        assert_eq!(prop.to_string(), "250 [20, 60000]");
+    }
+}
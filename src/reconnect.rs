@@ -0,0 +1,349 @@
+// phidget-rs/src/reconnect.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Automatic reconnection to remote Phidget servers.
+//!
+//! The phidget22 library retries the connection to a registered server on
+//! its own, but a KeepAlive timeout or other network failure can leave the
+//! remote channels closed, or the server registration in a bad state, so
+//! that a short WAN hiccup permanently kills the remote sensors.
+//!
+//! A [`Reconnector`] holds the registration details for a server and the
+//! remote channels opened on it. When one of the channels reports a
+//! network error, or is detached, or when [`Reconnector::trigger()`] is
+//! called after a `KeepAlive` error, it removes and re-registers the server
+//! and reopens the channels, retrying with an exponential [`Backoff`]
+//! until it succeeds. The changes in the [`ConnectionState`] are reported
+//! to callbacks.
+//!
+//! ```rust,no_run
+//! use phidget::{devices::TemperatureSensor, reconnect::{Backoff, Reconnector, ServerConfig}, Phidget};
+//! use std::{sync::{Arc, Mutex}, time::Duration};
+//!
+//! let cfg = ServerConfig::new("shed", "192.168.1.20", 5661);
+//! let recon = Reconnector::new(cfg, Backoff::default()).unwrap();
+//! recon.on_state_change(|state| println!("shed: {:?}", state));
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! sensor.set_remote(true).unwrap();
+//! sensor.open_wait(Duration::from_secs(10)).unwrap();
+//! recon.add_channel(Arc::new(Mutex::new(sensor)));
+//! ```
+
+use crate::{
+    bus::{bus, BusEvent, SubscriptionId},
+    net, ErrorEventCode, Phidget, Result, TIMEOUT_DEFAULT,
+};
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// The state of the connection to a remote server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The server is registered and the channels are open
+    Connected,
+    /// The connection was lost
+    Disconnected,
+    /// Reconnection is in progress, with the number of the attempt,
+    /// starting at one.
+    Reconnecting(u32),
+}
+
+/// The rules for the delay between reconnection attempts.
+///
+/// The delay starts at the initial value, and is multiplied by the
+/// factor after each failed attempt, up to the maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// The delay after the first failed attempt
+    pub initial: Duration,
+    /// The longest delay between attempts
+    pub max: Duration,
+    /// The multiplier for the delay after each failed attempt
+    pub factor: u32,
+}
+
+impl Backoff {
+    /// Gets the delay after the specified failed attempt, starting at one.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let mut delay = self.initial;
+        for _ in 1..attempt {
+            delay = delay.saturating_mul(self.factor);
+            if delay >= self.max {
+                break;
+            }
+        }
+        delay.min(self.max)
+    }
+}
+
+impl Default for Backoff {
+    /// The default backoff starts at one second and doubles, up to one
+    /// minute.
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+            factor: 2,
+        }
+    }
+}
+
+/// The registration details for a remote server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// The name of the server
+    pub name: String,
+    /// The network address of the server
+    pub address: String,
+    /// The network port of the server
+    pub port: i32,
+    /// The password for the server
+    pub password: String,
+}
+
+impl ServerConfig {
+    /// Creates the details for a server without a password.
+    pub fn new(name: &str, address: &str, port: i32) -> Self {
+        Self {
+            name: name.into(),
+            address: address.into(),
+            port,
+            password: String::new(),
+        }
+    }
+
+    /// Sets the password for the server.
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = password.into();
+        self
+    }
+
+    /// Registers the server with the library.
+    pub fn register(&self) -> Result<()> {
+        net::add_server(&self.name, &self.address, self.port, &self.password)
+    }
+}
+
+// A channel shared with the reconnector
+type SharedChannel = Arc<Mutex<dyn Phidget + Send>>;
+
+// The function type for a state callback
+type StateCallback = dyn Fn(ConnectionState) + Send + 'static;
+
+// The shared state of a reconnector
+struct Inner {
+    state: ConnectionState,
+    chans: Vec<SharedChannel>,
+    callbacks: Vec<Box<StateCallback>>,
+}
+
+impl Inner {
+    // Changes the state, reporting it to the callbacks if it changed.
+    fn set_state(&mut self, state: ConnectionState) {
+        if state != self.state {
+            self.state = state;
+            for cb in &self.callbacks {
+                cb(state);
+            }
+        }
+    }
+}
+
+/// Automatic reconnection for the channels of a remote server.
+///
+/// Reconnection stops when this object is dropped.
+pub struct Reconnector {
+    // The shared state
+    inner: Arc<Mutex<Inner>>,
+    // The subscription to the event bus
+    sub: SubscriptionId,
+    // Sender to trigger a reconnect
+    tx: Option<mpsc::Sender<()>>,
+    // The reconnection thread
+    thr: Option<JoinHandle<()>>,
+}
+
+impl Reconnector {
+    /// Registers the server, and starts watching for connection failures.
+    pub fn new(cfg: ServerConfig, backoff: Backoff) -> Result<Self> {
+        Self::with_timeout(cfg, backoff, TIMEOUT_DEFAULT)
+    }
+
+    /// Registers the server, and starts watching for connection failures,
+    /// using the specified timeout when reopening each channel.
+    pub fn with_timeout(cfg: ServerConfig, backoff: Backoff, to: Duration) -> Result<Self> {
+        cfg.register()?;
+
+        let inner = Arc::new(Mutex::new(Inner {
+            state: ConnectionState::Connected,
+            chans: Vec::new(),
+            callbacks: Vec::new(),
+        }));
+
+        let (tx, rx) = mpsc::channel();
+        let bus_tx = tx.clone();
+        let name = cfg.name.clone();
+
+        let sub = bus().subscribe(Default::default(), move |evt| {
+            let lost = match evt {
+                BusEvent::Detached(_) => true,
+                BusEvent::Error { code, .. } => *code == ErrorEventCode::Network,
+                _ => false,
+            };
+            if lost && evt.info().server_name == name {
+                let _ = bus_tx.send(());
+            }
+        })?;
+
+        let thr_inner = Arc::clone(&inner);
+
+        // The thread exits when the subscription and the reconnector, and
+        // thus the senders, are dropped.
+        let thr = thread::spawn(move || {
+            while rx.recv().is_ok() {
+                thr_inner
+                    .lock()
+                    .unwrap()
+                    .set_state(ConnectionState::Disconnected);
+
+                let mut attempt = 1;
+                loop {
+                    thr_inner
+                        .lock()
+                        .unwrap()
+                        .set_state(ConnectionState::Reconnecting(attempt));
+
+                    if Self::reconnect(&cfg, &thr_inner, to).is_ok() {
+                        thr_inner
+                            .lock()
+                            .unwrap()
+                            .set_state(ConnectionState::Connected);
+                        break;
+                    }
+
+                    if !Self::wait(&rx, backoff.delay(attempt)) {
+                        return;
+                    }
+                    attempt += 1;
+                }
+
+                // Any events queued during the reconnect were for the same failure
+                while rx.try_recv().is_ok() {}
+            }
+        });
+
+        Ok(Self {
+            inner,
+            sub,
+            tx: Some(tx),
+            thr: Some(thr),
+        })
+    }
+
+    // Waits out the backoff delay, ignoring any further failure events.
+    // Returns false if the reconnector was dropped.
+    fn wait(rx: &mpsc::Receiver<()>, delay: Duration) -> bool {
+        let deadline = Instant::now() + delay;
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            if let Err(mpsc::RecvTimeoutError::Disconnected) = rx.recv_timeout(deadline - now) {
+                return false;
+            }
+        }
+    }
+
+    // Re-registers the server and reopens all the channels.
+    fn reconnect(cfg: &ServerConfig, inner: &Mutex<Inner>, to: Duration) -> Result<()> {
+        let _ = net::remove_server(&cfg.name);
+        cfg.register()?;
+
+        let chans = inner.lock().unwrap().chans.clone();
+        for ph in chans {
+            let mut ph = ph.lock().unwrap();
+            if !ph.is_attached().unwrap_or(false) {
+                let _ = ph.close();
+                ph.open_wait(to)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a remote channel to be reopened after a reconnect.
+    ///
+    /// The channel should be set as remote, and is normally already open.
+    pub fn add_channel<P>(&self, ph: Arc<Mutex<P>>)
+    where
+        P: Phidget + Send + 'static,
+    {
+        self.inner.lock().unwrap().chans.push(ph);
+    }
+
+    /// Starts a reconnect, as if a channel had reported a network error.
+    ///
+    /// This is useful when a call on a remote channel returns a
+    /// `KeepAlive` or other connection error, which doesn't otherwise
+    /// produce an event.
+    pub fn trigger(&self) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Registers a callback for changes in the state of the connection.
+    ///
+    /// The callback is made from the reconnection thread, and should not
+    /// call back into the reconnector.
+    pub fn on_state_change<F>(&self, cb: F)
+    where
+        F: Fn(ConnectionState) + Send + 'static,
+    {
+        self.inner.lock().unwrap().callbacks.push(Box::new(cb));
+    }
+
+    /// Gets the current state of the connection.
+    pub fn state(&self) -> ConnectionState {
+        self.inner.lock().unwrap().state
+    }
+}
+
+impl Drop for Reconnector {
+    fn drop(&mut self) {
+        bus().unsubscribe(self.sub);
+        self.tx = None;
+        if let Some(thr) = self.thr.take() {
+            let _ = thr.join();
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let backoff = Backoff::default();
+        assert_eq!(backoff.delay(1), Duration::from_secs(1));
+        assert_eq!(backoff.delay(2), Duration::from_secs(2));
+        assert_eq!(backoff.delay(4), Duration::from_secs(8));
+        assert_eq!(backoff.delay(7), Duration::from_secs(60));
+        assert_eq!(backoff.delay(1000), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,220 @@
+// phidget-rs/src/recovery.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Recovery from bad power faults on VINT hub ports.
+//!
+//! When a VINT device reports a `BadPower` error event, or a call returns
+//! a `BadPower` or `PowerCycle` error, the documented remedy is to turn
+//! off the power to its hub port, turn it back on, and reopen the channel.
+//!
+//! [`power_cycle()`] does this once, for a hub that the application has
+//! already opened. A [`PowerRecovery`] is the opt-in, automatic version.
+//! It watches the [event bus](crate::bus) for bad power events from a
+//! shared channel, and runs the recovery in a background thread.
+//!
+//! ```rust,no_run
+//! use phidget::{devices::TemperatureSensor, recovery::PowerRecovery, HubPort, Phidget};
+//! use std::{sync::{Arc, Mutex}, time::Duration};
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! sensor.set_hub_port(HubPort::new(2).unwrap()).unwrap();
+//! sensor.open_wait_default().unwrap();
+//!
+//! let sensor = Arc::new(Mutex::new(sensor));
+//! let _recovery = PowerRecovery::spawn(Arc::clone(&sensor), Duration::from_secs(5)).unwrap();
+//! ```
+
+use crate::{
+    bus::{bus, BusEvent, SubscriptionId},
+    Error, ErrorCode, Hub, HubPort, Phidget, Result, SerialNumber,
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// The default time that a hub port is left off when power cycling.
+pub const DEFAULT_POWER_OFF_TIME: Duration = Duration::from_millis(500);
+
+/// Power cycles the hub port of a VINT device, and reopens the channel,
+/// waiting a limited time for it to attach.
+///
+/// The channel should be open, so that its hub port is known. The hub
+/// must be the open hub channel of the device that the channel is
+/// attached to. Returns an `Unsupported` error if the channel is not
+/// part of a VINT device.
+pub fn power_cycle<P>(ph: &mut P, hub: &Hub, off_time: Duration, to: Duration) -> Result<()>
+where
+    P: Phidget + ?Sized,
+{
+    let port = ph.hub_port()?;
+    if port.is_any() {
+        return Err(ErrorCode::Unsupported);
+    }
+    let _ = ph.close();
+    hub.power_cycle_port(port, off_time)?;
+    ph.open_wait(to)
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// The interval at which to poll the channel while it reattaches.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// Locks the shared channel, even if a thread panicked with it locked.
+fn lock<P>(ph: &Mutex<P>) -> MutexGuard<'_, P> {
+    ph.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+// The results of the recoveries, shared with the recovery thread.
+#[derive(Default)]
+struct Stats {
+    // The number of recoveries attempted
+    count: AtomicU64,
+    // The error from the most recent recovery that failed
+    last_error: Mutex<Option<Error>>,
+}
+
+/// Automatic recovery from bad power events for a shared channel.
+///
+/// This subscribes to the event bus for the error events of the channel,
+/// and when it reports bad power, opens the hub of the device, power
+/// cycles the port, and reopens the channel, all from a background
+/// thread. The hub is opened only for the recovery, so it can not be
+/// held open elsewhere in the application. In that case, use
+/// [`power_cycle()`] directly.
+///
+/// The channel is only locked while it is closed and reopened, and while
+/// checking if it has attached, so other threads can keep using the
+/// shared channel during the recovery. A recovery that fails is not
+/// retried until the next bad power event, but its error is kept, and
+/// can be read with [`last_error()`](Self::last_error).
+///
+/// Recovery stops when this object is dropped.
+pub struct PowerRecovery {
+    // The subscription to the event bus
+    sub: SubscriptionId,
+    // The results of the recoveries
+    stats: Arc<Stats>,
+    // The recovery thread
+    thr: Option<JoinHandle<()>>,
+}
+
+impl PowerRecovery {
+    /// Starts watching an open channel for bad power events.
+    ///
+    /// The time is used as the timeout for opening the hub and reopening
+    /// the channel. Returns an `Unsupported` error if the channel is not
+    /// part of a VINT device.
+    pub fn spawn<P>(ph: Arc<Mutex<P>>, to: Duration) -> Result<Self>
+    where
+        P: Phidget + Send + 'static,
+    {
+        let (spec, sn, port) = {
+            let mut dev = lock(&ph);
+            (dev.filter(), dev.serial_number()?, dev.hub_port()?)
+        };
+        if port.is_any() {
+            return Err(ErrorCode::Unsupported);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+
+        let sub = bus().subscribe(spec, move |evt| {
+            if let BusEvent::Error { code, .. } = evt {
+                if code.needs_power_cycle() {
+                    let _ = tx.lock().unwrap().send(());
+                }
+            }
+        })?;
+
+        let stats = Arc::new(Stats::default());
+        let thr_stats = Arc::clone(&stats);
+
+        // The thread exits when the subscription, and thus the sender, is dropped.
+        let thr = thread::spawn(move || {
+            while rx.recv().is_ok() {
+                if let Err(err) = Self::recover(&ph, sn, port, to, &thr_stats.count) {
+                    *lock(&thr_stats.last_error) = Some(err);
+                }
+
+                // Any events queued during the recovery were for the same fault
+                while rx.try_recv().is_ok() {}
+            }
+        });
+
+        Ok(Self {
+            sub,
+            stats,
+            thr: Some(thr),
+        })
+    }
+
+    // Runs one recovery, counting it once the hub is open and the port is
+    // about to be power cycled.
+    fn recover<P>(
+        ph: &Mutex<P>,
+        sn: SerialNumber,
+        port: HubPort,
+        to: Duration,
+        count: &AtomicU64,
+    ) -> Result<()>
+    where
+        P: Phidget,
+    {
+        let mut hub = Hub::new();
+        hub.set_serial_number(sn)?;
+        hub.open_wait(to)?;
+        count.fetch_add(1, Ordering::Relaxed);
+
+        let _ = lock(ph).close();
+        let res = hub.power_cycle_port(port, DEFAULT_POWER_OFF_TIME);
+
+        // The channel is reopened even if the power cycle failed
+        lock(ph).open()?;
+        let deadline = Instant::now() + to;
+        while !lock(ph).is_attached()? {
+            if Instant::now() >= deadline {
+                return Err(ErrorCode::Timeout);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        res
+    }
+
+    /// Gets the number of recoveries that were attempted.
+    ///
+    /// This counts the recoveries that got as far as opening the hub of
+    /// the device, whether or not they succeeded.
+    pub fn count(&self) -> u64 {
+        self.stats.count.load(Ordering::Relaxed)
+    }
+
+    /// Gets the error from the most recent recovery that failed, if any,
+    /// including one that failed to open the hub.
+    pub fn last_error(&self) -> Option<Error> {
+        *lock(&self.stats.last_error)
+    }
+}
+
+impl Drop for PowerRecovery {
+    fn drop(&mut self) {
+        bus().unsubscribe(self.sub);
+        if let Some(thr) = self.thr.take() {
+            let _ = thr.join();
+        }
+    }
+}
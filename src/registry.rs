@@ -0,0 +1,325 @@
+// phidget-rs/src/registry.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! A registry of the channels attached to the system.
+//!
+//! The [`DeviceRegistry`] is fed by the attach and detach events from a
+//! [`Manager`], and keeps an up-to-date map of the system topology. It
+//! reports each change as a [`RegistryEvent`], and can take snapshots of
+//! the topology that can later be compared with [`diff()`].
+
+use crate::{
+    firmware::{self, FirmwareReport, FirmwareRequirements},
+    manager::{Manager, PhidgetInfo},
+    Channel, ChannelClass, HubPort, Result, SerialNumber,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// The key that uniquely identifies a channel in the registry.
+///
+/// This is the (serial number, hub port, channel) address of the channel
+/// plus its class, since a single device can have several channels of
+/// different classes with the same index, such as the humidity and
+/// temperature channels of a HUM1001.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DeviceKey {
+    /// The serial number of the device
+    pub serial_number: SerialNumber,
+    /// The hub port of the device, or the wildcard, [`HubPort::ANY`], if
+    /// it is not on a VINT hub
+    pub hub_port: HubPort,
+    /// The channel index on the device
+    pub channel: Channel,
+    /// The class of the channel
+    pub channel_class: ChannelClass,
+}
+
+impl From<&PhidgetInfo> for DeviceKey {
+    fn from(info: &PhidgetInfo) -> Self {
+        Self {
+            serial_number: info.serial_number,
+            hub_port: info.hub_port,
+            channel: info.channel,
+            channel_class: info.channel_class,
+        }
+    }
+}
+
+/// A change in the registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryEvent {
+    /// A channel was added
+    Added(PhidgetInfo),
+    /// A channel was removed
+    Removed(PhidgetInfo),
+    /// The information for a channel changed, such as a new label or
+    /// firmware version.
+    Changed {
+        /// The previous information for the channel
+        old: PhidgetInfo,
+        /// The current information for the channel
+        new: PhidgetInfo,
+    },
+}
+
+/// A point-in-time copy of the registry contents.
+pub type Snapshot = BTreeMap<DeviceKey, PhidgetInfo>;
+
+/// The signature for registry event callbacks
+pub type RegistryCallback = dyn Fn(&RegistryEvent) + Send + 'static;
+
+/// Gets the events that would turn the `old` snapshot into the `new` one.
+///
+/// The removals are reported first, then the additions and changes, each
+/// in key order.
+pub fn diff(old: &Snapshot, new: &Snapshot) -> Vec<RegistryEvent> {
+    let mut evts: Vec<_> = old
+        .iter()
+        .filter(|(key, _)| !new.contains_key(key))
+        .map(|(_, info)| RegistryEvent::Removed(info.clone()))
+        .collect();
+
+    for (key, info) in new {
+        match old.get(key) {
+            None => evts.push(RegistryEvent::Added(info.clone())),
+            Some(prev) if prev != info => evts.push(RegistryEvent::Changed {
+                old: prev.clone(),
+                new: info.clone(),
+            }),
+            _ => (),
+        }
+    }
+    evts
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// The state shared by the clones of a registry.
+#[derive(Default)]
+struct Inner {
+    // The channels currently attached
+    devs: Mutex<Snapshot>,
+    // The callbacks for registry events
+    listeners: Mutex<Vec<Box<RegistryCallback>>>,
+}
+
+/// A registry of the channels attached to the system.
+///
+/// This is a cheap, cloneable handle to the shared registry, so a clone
+/// can be moved into the manager callbacks, or out to other threads.
+///
+/// ```rust,no_run
+/// use phidget::{manager::Manager, registry::DeviceRegistry};
+///
+/// let reg = DeviceRegistry::new();
+/// reg.on_event(|evt| println!("{:?}", evt));
+///
+/// let mut mgr = Manager::new();
+/// reg.connect(&mut mgr).unwrap();
+/// mgr.open().unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct DeviceRegistry {
+    inner: Arc<Inner>,
+}
+
+impl DeviceRegistry {
+    /// Creates a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connects the registry to a manager, by setting the manager's
+    /// attach and detach handlers.
+    ///
+    /// This should be done before the manager is opened, so that the
+    /// registry sees the channels that are already attached.
+    pub fn connect(&self, mgr: &mut Manager) -> Result<()> {
+        let reg = self.clone();
+        mgr.set_on_attach_handler(move |ph| {
+            if let Ok(info) = PhidgetInfo::try_from(ph) {
+                reg.insert(info);
+            }
+        })?;
+
+        let reg = self.clone();
+        mgr.set_on_detach_handler(move |ph| {
+            if let Ok(info) = PhidgetInfo::try_from(ph) {
+                reg.remove(&DeviceKey::from(&info));
+            }
+        })
+    }
+
+    /// Adds a callback that is fired for each change to the registry.
+    ///
+    /// The callback runs in the context of the manager's event thread. It
+    /// can query the registry, but can not register other callbacks.
+    pub fn on_event<F>(&self, cb: F)
+    where
+        F: Fn(&RegistryEvent) + Send + 'static,
+    {
+        self.inner.listeners.lock().unwrap().push(Box::new(cb));
+    }
+
+    // Sends an event to all the listeners.
+    fn notify(&self, evt: &RegistryEvent) {
+        for cb in self.inner.listeners.lock().unwrap().iter() {
+            cb(evt);
+        }
+    }
+
+    /// Adds or updates a channel in the registry.
+    ///
+    /// This returns the resulting event, if the registry changed.
+    pub fn insert(&self, info: PhidgetInfo) -> Option<RegistryEvent> {
+        let prev = self
+            .inner
+            .devs
+            .lock()
+            .unwrap()
+            .insert(DeviceKey::from(&info), info.clone());
+
+        let evt = match prev {
+            None => RegistryEvent::Added(info),
+            Some(old) if old != info => RegistryEvent::Changed { old, new: info },
+            _ => return None,
+        };
+        self.notify(&evt);
+        Some(evt)
+    }
+
+    /// Removes a channel from the registry.
+    ///
+    /// This returns the resulting event, if the channel was in the
+    /// registry.
+    pub fn remove(&self, key: &DeviceKey) -> Option<RegistryEvent> {
+        let info = self.inner.devs.lock().unwrap().remove(key)?;
+        let evt = RegistryEvent::Removed(info);
+        self.notify(&evt);
+        Some(evt)
+    }
+
+    /// Gets the information for a channel, if it's attached.
+    pub fn get(&self, key: &DeviceKey) -> Option<PhidgetInfo> {
+        self.inner.devs.lock().unwrap().get(key).cloned()
+    }
+
+    /// Gets the number of channels in the registry.
+    pub fn len(&self) -> usize {
+        self.inner.devs.lock().unwrap().len()
+    }
+
+    /// Determines if the registry is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets a copy of the current contents of the registry.
+    pub fn snapshot(&self) -> Snapshot {
+        self.inner.devs.lock().unwrap().clone()
+    }
+
+    /// Gets all the channels in the registry, in key order.
+    pub fn channels(&self) -> Vec<PhidgetInfo> {
+        self.inner.devs.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Gets the channels of the device with the specified serial number,
+    /// including any VINT devices attached to it, if it is a hub.
+    pub fn device_channels(&self, serial_number: SerialNumber) -> Vec<PhidgetInfo> {
+        self.inner
+            .devs
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|info| info.serial_number == serial_number)
+            .cloned()
+            .collect()
+    }
+
+    /// Checks the firmware of the attached devices against the
+    /// requirements, giving one report for each device.
+    pub fn firmware_report(&self, reqs: &FirmwareRequirements) -> Vec<FirmwareReport> {
+        firmware::report(reqs, self.inner.devs.lock().unwrap().values())
+    }
+
+    /// Gets the attached devices that need a firmware upgrade to meet the
+    /// requirements.
+    pub fn needs_firmware_upgrade(&self, reqs: &FirmwareRequirements) -> Vec<FirmwareReport> {
+        self.firmware_report(reqs)
+            .into_iter()
+            .filter(|rpt| rpt.status.needs_upgrade())
+            .collect()
+    }
+
+    /// Gets the changes to the registry since the snapshot was taken.
+    pub fn changes_since(&self, snapshot: &Snapshot) -> Vec<RegistryEvent> {
+        diff(snapshot, &self.snapshot())
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DeviceClass, DeviceId};
+
+    fn info(channel: i32, label: &str) -> PhidgetInfo {
+        PhidgetInfo {
+            serial_number: SerialNumber::new(12345).unwrap(),
+            hub_port: HubPort::new(0).unwrap(),
+            channel: Channel::new(channel).unwrap(),
+            is_hub_port_device: false,
+            channel_class: ChannelClass::TemperatureSensor,
+            device_class: DeviceClass::Vint,
+            device_id: DeviceId::Tmp1101,
+            channel_name: String::new(),
+            device_name: String::new(),
+            device_sku: String::new(),
+            device_version: 100,
+            device_label: label.into(),
+            server_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_registry_diff() {
+        let reg = DeviceRegistry::new();
+        assert!(matches!(
+            reg.insert(info(0, "")),
+            Some(RegistryEvent::Added(_))
+        ));
+        assert!(reg.insert(info(0, "")).is_none());
+        let snap = reg.snapshot();
+
+        reg.insert(info(1, ""));
+        reg.insert(info(0, "boiler"));
+        assert_eq!(reg.len(), 2);
+
+        let evts = reg.changes_since(&snap);
+        assert_eq!(evts.len(), 2);
+        assert!(
+            matches!(&evts[0], RegistryEvent::Changed { new, .. } if new.device_label == "boiler")
+        );
+        assert_eq!(evts[1], RegistryEvent::Added(info(1, "")));
+
+        let snap = reg.snapshot();
+        reg.remove(&DeviceKey::from(&info(1, "")));
+        assert_eq!(
+            reg.changes_since(&snap),
+            vec![RegistryEvent::Removed(info(1, ""))]
+        );
+    }
+}
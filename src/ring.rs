@@ -0,0 +1,304 @@
+// phidget-rs/src/ring.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! A lock-free ring buffer for high-rate streaming.
+//!
+//! This is a fixed-capacity, single-producer, single-consumer (SPSC) queue.
+//! The producer side is meant to be fed from a device change handler, and
+//! never blocks or takes a lock, so that acquisition at 1 kHz or more
+//! doesn't contend on a mutex with the consumer thread. When the buffer is
+//! full, new values are dropped and counted.
+//!
+//! The consumer does not block either. It should poll the buffer at an
+//! interval that keeps up with the data rate, and can stop once the
+//! producer is gone and the buffer is empty.
+//!
+//! The voltage inputs create these with their `ring_buffer()` methods:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::VoltageInput, Phidget};
+//! use std::{thread, time::Duration};
+//!
+//! let mut vin = VoltageInput::new();
+//! let mut rx = vin.ring_buffer(4096).unwrap();
+//! vin.open_wait_default().unwrap();
+//! vin.set_data_interval(Duration::from_millis(1)).unwrap();
+//!
+//! let mut samples = Vec::new();
+//! loop {
+//!     thread::sleep(Duration::from_millis(100));
+//!     rx.drain_into(&mut samples);
+//!     println!("{} samples, {} dropped", samples.len(), rx.dropped());
+//!     samples.clear();
+//! }
+//! ```
+
+use std::{
+    cell::{Cell, UnsafeCell},
+    marker::PhantomData,
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+// The state shared between the producer and consumer.
+//
+// The head and tail are free-running counts of the values read and
+// written, so the number of values in the buffer is their difference.
+struct Shared<T> {
+    buf: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // The count of values read. Only written by the consumer.
+    head: AtomicUsize,
+    // The count of values written. Only written by the producer.
+    tail: AtomicUsize,
+    // The number of values dropped because the buffer was full
+    dropped: AtomicU64,
+    // Whether the producer was dropped
+    disconnected: AtomicBool,
+}
+
+// Each slot is only accessed by one side at a time, as arbitrated by the
+// head and tail counts.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn slot(&self, n: usize) -> *mut MaybeUninit<T> {
+        self.buf[n % self.buf.len()].get()
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        for n in head..tail {
+            unsafe { (*self.slot(n)).assume_init_drop() };
+        }
+    }
+}
+
+/// Creates a ring buffer with the specified capacity, returning the
+/// producer and consumer halves.
+///
+/// # Panics
+///
+/// Panics if the capacity is zero.
+pub fn channel<T: Send>(capacity: usize) -> (RingProducer<T>, RingConsumer<T>) {
+    assert!(capacity > 0, "ring buffer capacity must be non-zero");
+    let buf = (0..capacity)
+        .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+        .collect();
+
+    let shared = Arc::new(Shared {
+        buf,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        dropped: AtomicU64::new(0),
+        disconnected: AtomicBool::new(false),
+    });
+
+    let tx = RingProducer {
+        shared: Arc::clone(&shared),
+        _not_sync: PhantomData,
+    };
+    let rx = RingConsumer {
+        shared,
+        _not_sync: PhantomData,
+    };
+    (tx, rx)
+}
+
+/// The producing half of a ring buffer.
+///
+/// This can be moved to another thread, but not shared, so there is only
+/// ever a single producer.
+pub struct RingProducer<T> {
+    shared: Arc<Shared<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> RingProducer<T> {
+    /// Pushes a value into the buffer, without blocking.
+    ///
+    /// If the buffer is full, the value is dropped and counted, and
+    /// returned as an error.
+    pub fn push(&self, val: T) -> Result<(), T> {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+
+        if tail.wrapping_sub(head) == shared.buf.len() {
+            shared.dropped.fetch_add(1, Ordering::Relaxed);
+            return Err(val);
+        }
+
+        unsafe { (*shared.slot(tail)).write(val) };
+        shared.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Gets the capacity of the buffer.
+    pub fn capacity(&self) -> usize {
+        self.shared.buf.len()
+    }
+}
+
+impl<T> Drop for RingProducer<T> {
+    fn drop(&mut self) {
+        self.shared.disconnected.store(true, Ordering::Release);
+    }
+}
+
+/// The consuming half of a ring buffer.
+///
+/// This can be moved to another thread, but not shared, so there is only
+/// ever a single consumer.
+pub struct RingConsumer<T> {
+    shared: Arc<Shared<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> RingConsumer<T> {
+    /// Removes the oldest value from the buffer, without blocking.
+    pub fn pop(&mut self) -> Option<T> {
+        let shared = &*self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+
+        if head == tail {
+            return None;
+        }
+
+        let val = unsafe { (*shared.slot(head)).assume_init_read() };
+        shared.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(val)
+    }
+
+    /// Moves all the values currently in the buffer to the end of the
+    /// vector, returning the number of values moved.
+    pub fn drain_into(&mut self, v: &mut Vec<T>) -> usize {
+        let n = self.len();
+        v.reserve(n);
+        for _ in 0..n {
+            match self.pop() {
+                Some(val) => v.push(val),
+                None => break,
+            }
+        }
+        n
+    }
+
+    /// Gets the number of values in the buffer.
+    pub fn len(&self) -> usize {
+        let head = self.shared.head.load(Ordering::Relaxed);
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    /// Determines if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Gets the capacity of the buffer.
+    pub fn capacity(&self) -> usize {
+        self.shared.buf.len()
+    }
+
+    /// Gets the number of values dropped because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Determines if the producer is gone, so that no more values will
+    /// arrive. Any values still in the buffer can be read.
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.disconnected.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Iterator for RingConsumer<T> {
+    type Item = T;
+
+    /// Gets the next value, returning `None` when the buffer is empty.
+    fn next(&mut self) -> Option<T> {
+        self.pop()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_push_pop() {
+        let (tx, mut rx) = channel(3);
+        assert!(rx.is_empty());
+
+        for i in 0..3 {
+            assert_eq!(tx.push(i), Ok(()));
+        }
+        assert_eq!(tx.push(3), Err(3));
+        assert_eq!(rx.len(), 3);
+        assert_eq!(rx.dropped(), 1);
+
+        assert_eq!(rx.pop(), Some(0));
+        assert_eq!(tx.push(4), Ok(()));
+
+        let mut v = Vec::new();
+        assert_eq!(rx.drain_into(&mut v), 3);
+        assert_eq!(v, [1, 2, 4]);
+        assert_eq!(rx.pop(), None);
+
+        assert!(!rx.is_disconnected());
+        tx.push(5).unwrap();
+        drop(tx);
+        assert!(rx.is_disconnected());
+        assert_eq!(rx.pop(), Some(5));
+
+        // Values left in the buffer are dropped with it
+        let (tx, rx) = channel(2);
+        tx.push(String::from("left")).unwrap();
+        drop((tx, rx));
+    }
+
+    #[test]
+    fn test_threads() {
+        const N: u64 = 10_000;
+        let (tx, mut rx) = channel(64);
+
+        let thr = thread::spawn(move || {
+            for i in 0..N {
+                while tx.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < N {
+            match rx.pop() {
+                Some(i) => {
+                    assert_eq!(i, expected);
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        thr.join().unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+// phidget-rs/src/sensor_hub.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! A high-level view of a VINT hub and the sensors attached to it.
+//!
+//! The most common topology is a single hub with a sensor on each port.
+//! A [`SensorHub`] opens the hub by its serial number, keeps track of the
+//! channels attached to its ports, and opens them by port without the
+//! filter boilerplate:
+//!
+//! ```rust,no_run
+//! use phidget::{sensor_hub::SensorHub, HubPort, SerialNumber};
+//! use std::time::Duration;
+//!
+//! let sn = SerialNumber::new(12345).unwrap();
+//! let hub = SensorHub::open(sn, Duration::from_secs(5)).unwrap();
+//! let sensor = hub.port(HubPort::new(3).unwrap()).as_temperature().unwrap();
+//! println!("{}", sensor.temperature().unwrap());
+//! ```
+
+use crate::{
+    devices::{
+        DigitalInput, DigitalOutput, HumiditySensor, Stepper, TemperatureSensor, VoltageInput,
+        VoltageOutput, VoltageRatioInput,
+    },
+    manager::{Manager, PhidgetInfo},
+    registry::DeviceRegistry,
+    spec, Channel, ChannelClass, Hub, Phidget, Result, SerialNumber,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A VINT hub and the channels attached to its ports.
+pub struct SensorHub {
+    // The hub device
+    hub: Hub,
+    // The serial number of the hub
+    serial_number: SerialNumber,
+    // The time to wait for channels to attach
+    timeout: Duration,
+    // The attached channels, kept up to date by the manager
+    reg: DeviceRegistry,
+    // The manager that feeds the registry
+    _mgr: Manager,
+}
+
+impl SensorHub {
+    /// Opens the hub with the specified serial number, waiting a limited
+    /// time for it to attach.
+    ///
+    /// The same timeout is used when opening the channels on the ports.
+    pub fn open(serial_number: SerialNumber, to: Duration) -> Result<Self> {
+        let reg = DeviceRegistry::new();
+        let mut mgr = Manager::new();
+        reg.connect(&mut mgr)?;
+        mgr.open()?;
+
+        let mut hub = Hub::new();
+        hub.set_serial_number(serial_number)?;
+        hub.open_wait(to)?;
+
+        let sensor_hub = Self {
+            hub,
+            serial_number,
+            timeout: to,
+            reg,
+            _mgr: mgr,
+        };
+
+        // Give the manager a chance to report the hub itself, which
+        // comes along with its ports.
+        let deadline = Instant::now() + to;
+        while !sensor_hub.is_enumerated() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        Ok(sensor_hub)
+    }
+
+    // Determines if the manager has reported the hub channel.
+    fn is_enumerated(&self) -> bool {
+        self.reg
+            .device_channels(self.serial_number)
+            .iter()
+            .any(|info| info.channel_class == ChannelClass::Hub)
+    }
+
+    /// Gets the hub device.
+    pub fn hub(&self) -> &Hub {
+        &self.hub
+    }
+
+    /// Gets the serial number of the hub.
+    pub fn serial_number(&self) -> SerialNumber {
+        self.serial_number
+    }
+
+    /// Gets all the channels attached to the ports of the hub.
+    pub fn channels(&self) -> Vec<PhidgetInfo> {
+        self.reg
+            .device_channels(self.serial_number)
+            .into_iter()
+            .filter(|info| !info.hub_port.is_any() && info.channel_class != ChannelClass::Hub)
+            .collect()
+    }
+
+    /// Gets an accessor for one of the ports of the hub.
+    pub fn port(&self, port: spec::HubPort) -> HubPort<'_> {
+        HubPort { hub: self, port }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// An accessor for the channels on a single port of a [`SensorHub`].
+#[derive(Clone, Copy)]
+pub struct HubPort<'a> {
+    hub: &'a SensorHub,
+    port: spec::HubPort,
+}
+
+impl HubPort<'_> {
+    /// Gets the port number.
+    pub fn port(&self) -> spec::HubPort {
+        self.port
+    }
+
+    /// Gets the channels attached to the port.
+    pub fn channels(&self) -> Vec<PhidgetInfo> {
+        self.hub
+            .channels()
+            .into_iter()
+            .filter(|info| info.hub_port == self.port)
+            .collect()
+    }
+
+    /// Opens a channel of the requested type on the port.
+    ///
+    /// If the port is known to have a matching channel, this picks up
+    /// whether it is a VINT device or the hub port itself, in one of its
+    /// port modes. Otherwise it looks for a VINT device.
+    pub fn open<P>(&self, cls: ChannelClass, chan: Channel) -> Result<P>
+    where
+        P: Phidget + Default,
+    {
+        let is_hub_port_device = self
+            .channels()
+            .iter()
+            .find(|info| info.channel_class == cls && info.channel == chan)
+            .is_some_and(|info| info.is_hub_port_device);
+
+        let mut dev = P::default();
+        dev.set_serial_number(self.hub.serial_number)?;
+        dev.set_hub_port(self.port)?;
+        dev.set_is_hub_port_device(is_hub_port_device)?;
+        dev.set_channel(chan)?;
+        dev.open_wait(self.hub.timeout)?;
+        Ok(dev)
+    }
+
+    /// Opens the temperature sensor on the port.
+    pub fn as_temperature(&self) -> Result<TemperatureSensor> {
+        self.open(ChannelClass::TemperatureSensor, Channel::new(0)?)
+    }
+
+    /// Opens the humidity sensor on the port.
+    pub fn as_humidity(&self) -> Result<HumiditySensor> {
+        self.open(ChannelClass::HumiditySensor, Channel::new(0)?)
+    }
+
+    /// Opens the voltage input on the port.
+    pub fn as_voltage_input(&self) -> Result<VoltageInput> {
+        self.open(ChannelClass::VoltageInput, Channel::new(0)?)
+    }
+
+    /// Opens the voltage ratio input on the port.
+    pub fn as_voltage_ratio_input(&self) -> Result<VoltageRatioInput> {
+        self.open(ChannelClass::VoltageRatioInput, Channel::new(0)?)
+    }
+
+    /// Opens the digital input on the port.
+    pub fn as_digital_input(&self) -> Result<DigitalInput> {
+        self.open(ChannelClass::DigitalInput, Channel::new(0)?)
+    }
+
+    /// Opens the digital output on the port.
+    pub fn as_digital_output(&self) -> Result<DigitalOutput> {
+        self.open(ChannelClass::DigitalOutput, Channel::new(0)?)
+    }
+
+    /// Opens the voltage output on the port.
+    pub fn as_voltage_output(&self) -> Result<VoltageOutput> {
+        self.open(ChannelClass::VoltageOutput, Channel::new(0)?)
+    }
+
+    /// Opens the stepper controller on the port.
+    pub fn as_stepper(&self) -> Result<Stepper> {
+        self.open(ChannelClass::Stepper, Channel::new(0)?)
+    }
+}
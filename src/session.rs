@@ -0,0 +1,473 @@
+// phidget-rs/src/session.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Long-running acquisition sessions.
+//!
+//! A [`Session`] takes ownership of a set of named sensors, and collects
+//! their readings into a bounded buffer, stamped with the time since the
+//! session started. It can be paused, resumed and stopped, and keeps
+//! summary statistics of the samples taken, the events dropped when the
+//! buffer overflows, and the errors reported by the sensors.
+//!
+//! The buffered readings are drained as an [`EventLog`], so a captured
+//! session can be saved as CSV and replayed later.
+//!
+//! ```rust,no_run
+//! use phidget::{devices::TemperatureSensor, session::Session, Phidget};
+//! use std::{thread, time::Duration};
+//!
+//! let mut session = Session::new();
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! sensor.open_wait_default().unwrap();
+//! session.add("boiler", sensor).unwrap();
+//!
+//! thread::sleep(Duration::from_secs(10));
+//! session.stop();
+//!
+//! println!("{:?}", session.stats());
+//! print!("{}", session.drain().to_csv());
+//! ```
+
+use crate::{
+    events::{EventLog, RecordedEvent},
+    sources::Sensor,
+    ErrorCode, ErrorEventCode, Result,
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// The default number of readings held in the buffer of a session.
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// The run state of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    /// Readings are being collected
+    Running,
+    /// Readings are ignored until the session is resumed
+    Paused,
+    /// Readings are ignored permanently
+    Stopped,
+}
+
+/// What to do with a reading when the buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Discard the oldest reading in the buffer to make room
+    DropOldest,
+    /// Discard the new reading
+    DropNewest,
+}
+
+/// The buffering rules for a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferPolicy {
+    /// The maximum number of readings held in the buffer.
+    /// With a capacity of zero, every reading is dropped.
+    pub capacity: usize,
+    /// What to do when the buffer is full
+    pub overflow: Overflow,
+}
+
+impl Default for BufferPolicy {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            overflow: Overflow::DropOldest,
+        }
+    }
+}
+
+/// Summary statistics for a single channel in a session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelStats {
+    /// The number of readings taken
+    pub samples: u64,
+    /// The number of errors reported
+    pub errors: u64,
+    /// The smallest reading
+    pub min: f64,
+    /// The largest reading
+    pub max: f64,
+    /// The most recent reading
+    pub last: f64,
+}
+
+impl Default for ChannelStats {
+    fn default() -> Self {
+        Self {
+            samples: 0,
+            errors: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            last: f64::NAN,
+        }
+    }
+}
+
+/// Summary statistics for a session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionStats {
+    /// The number of readings taken while running
+    pub samples: u64,
+    /// The number of readings lost to buffer overflow
+    pub dropped: u64,
+    /// The number of errors reported while running
+    pub errors: u64,
+    /// The statistics for each channel, by name
+    pub channels: BTreeMap<String, ChannelStats>,
+}
+
+// The mutable state of the session
+#[derive(Debug)]
+struct Inner {
+    state: SessionState,
+    buf: VecDeque<RecordedEvent>,
+    stats: SessionStats,
+}
+
+// The state shared with the handlers of the sensors
+#[derive(Debug)]
+struct Shared {
+    // The start time of the session
+    start: Instant,
+    // The buffering rules
+    policy: BufferPolicy,
+    // The mutable state
+    inner: Mutex<Inner>,
+}
+
+impl Shared {
+    fn record(&self, channel: &str, value: f64) {
+        let offset = self.start.elapsed();
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != SessionState::Running {
+            return;
+        }
+
+        inner.stats.samples += 1;
+        let chan = inner.stats.channels.entry(channel.into()).or_default();
+        chan.samples += 1;
+        chan.min = chan.min.min(value);
+        chan.max = chan.max.max(value);
+        chan.last = value;
+
+        if inner.buf.len() >= self.policy.capacity {
+            inner.stats.dropped += 1;
+            // An empty, full buffer has no older reading to make room
+            if self.policy.overflow == Overflow::DropNewest || inner.buf.pop_front().is_none() {
+                return;
+            }
+        }
+
+        inner.buf.push_back(RecordedEvent {
+            offset,
+            channel: channel.into(),
+            value,
+        });
+    }
+
+    fn record_error(&self, channel: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state == SessionState::Running {
+            inner.stats.errors += 1;
+            inner
+                .stats
+                .channels
+                .entry(channel.into())
+                .or_default()
+                .errors += 1;
+        }
+    }
+}
+
+/// A long-running acquisition session.
+///
+/// The session owns the sensors added to it, and closes them when it is
+/// dropped.
+pub struct Session {
+    // The sensors, by channel name
+    sensors: BTreeMap<String, Box<dyn Sensor>>,
+    // The state shared with the handlers
+    shared: Arc<Shared>,
+}
+
+impl Session {
+    /// Creates a running session with the default buffering rules.
+    pub fn new() -> Self {
+        Self::with_policy(BufferPolicy::default())
+    }
+
+    /// Creates a running session with the specified buffering rules.
+    pub fn with_policy(policy: BufferPolicy) -> Self {
+        let inner = Inner {
+            state: SessionState::Running,
+            buf: VecDeque::new(),
+            stats: SessionStats::default(),
+        };
+        Self {
+            sensors: BTreeMap::new(),
+            shared: Arc::new(Shared {
+                start: Instant::now(),
+                policy,
+                inner: Mutex::new(inner),
+            }),
+        }
+    }
+
+    /// Adds a sensor to the session, under the specified channel name.
+    ///
+    /// This installs the change and error handlers of the sensor, replacing
+    /// any that were already set, to record its readings and errors. A
+    /// sensor that can't report errors only has its readings recorded.
+    /// Any sensor previously added under the same name is dropped.
+    pub fn add<S>(&mut self, channel: &str, mut sensor: S) -> Result<()>
+    where
+        S: Sensor + 'static,
+    {
+        sensor.set_on_change(Box::new(self.subscribe(channel)))?;
+
+        let shared = Arc::clone(&self.shared);
+        let name = channel.to_string();
+        let res = sensor.set_on_error(Box::new(move |code, _| {
+            if code != ErrorEventCode::Ok {
+                shared.record_error(&name);
+            }
+        }));
+        match res {
+            Ok(()) | Err(ErrorCode::Unsupported) => (),
+            Err(err) => return Err(err),
+        }
+
+        self.sensors.insert(channel.into(), Box::new(sensor));
+        Ok(())
+    }
+
+    /// Gets the sensor added under the specified channel name.
+    pub fn sensor(&self, channel: &str) -> Option<&dyn Sensor> {
+        self.sensors.get(channel).map(|s| &**s)
+    }
+
+    /// Gets the names of the channels of the sensors in the session.
+    pub fn channels(&self) -> impl Iterator<Item = &str> {
+        self.sensors.keys().map(String::as_str)
+    }
+
+    /// Gets a function that records readings for the named channel.
+    ///
+    /// This is for devices that aren't a [`Sensor`], and is meant to be
+    /// called from the device's change handler.
+    pub fn subscribe(&self, channel: &str) -> impl Fn(f64) + Send + 'static {
+        let shared = Arc::clone(&self.shared);
+        let channel = channel.to_string();
+        move |value| shared.record(&channel, value)
+    }
+
+    /// Records a reading for the named channel.
+    ///
+    /// The reading is ignored unless the session is running.
+    pub fn record(&self, channel: &str, value: f64) {
+        self.shared.record(channel, value)
+    }
+
+    /// Records an error reported by the named channel.
+    ///
+    /// The error is ignored unless the session is running.
+    pub fn record_error(&self, channel: &str) {
+        self.shared.record_error(channel)
+    }
+
+    /// Gets the run state of the session.
+    pub fn state(&self) -> SessionState {
+        self.shared.inner.lock().unwrap().state
+    }
+
+    /// Pauses the session, if it is running.
+    pub fn pause(&self) {
+        let mut inner = self.shared.inner.lock().unwrap();
+        if inner.state == SessionState::Running {
+            inner.state = SessionState::Paused;
+        }
+    }
+
+    /// Resumes a paused session.
+    pub fn resume(&self) {
+        let mut inner = self.shared.inner.lock().unwrap();
+        if inner.state == SessionState::Paused {
+            inner.state = SessionState::Running;
+        }
+    }
+
+    /// Stops the session permanently.
+    ///
+    /// Readings already in the buffer can still be drained.
+    pub fn stop(&self) {
+        self.shared.inner.lock().unwrap().state = SessionState::Stopped;
+    }
+
+    /// Gets the number of readings in the buffer.
+    pub fn len(&self) -> usize {
+        self.shared.inner.lock().unwrap().buf.len()
+    }
+
+    /// Determines if the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all the readings from the buffer, returning them as a log.
+    pub fn drain(&self) -> EventLog {
+        let mut log = EventLog::new();
+        for evt in self.shared.inner.lock().unwrap().buf.drain(..) {
+            log.push(evt);
+        }
+        log
+    }
+
+    /// Gets a copy of the summary statistics for the session.
+    pub fn stats(&self) -> SessionStats {
+        self.shared.inner.lock().unwrap().stats.clone()
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("sensors", &self.sensors.keys().collect::<Vec<_>>())
+            .field("shared", &self.shared)
+            .finish()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::{SensorCallback, SensorErrorCallback, Unit};
+
+    // A sensor that lets the test fire the handlers installed by a session
+    #[derive(Default, Clone)]
+    struct FakeSensor {
+        on_change: Arc<Mutex<Option<SensorCallback>>>,
+        on_error: Arc<Mutex<Option<SensorErrorCallback>>>,
+    }
+
+    impl Sensor for FakeSensor {
+        fn read(&self) -> Result<f64> {
+            Ok(0.0)
+        }
+
+        fn unit(&self) -> Unit {
+            Unit::None
+        }
+
+        fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+            *self.on_change.lock().unwrap() = Some(cb);
+            Ok(())
+        }
+
+        fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+            *self.on_error.lock().unwrap() = Some(cb);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_add_sensor() {
+        let sensor = FakeSensor::default();
+        let mut session = Session::new();
+        session.add("tank", sensor.clone()).unwrap();
+        assert_eq!(session.channels().collect::<Vec<_>>(), ["tank"]);
+        assert!(session.sensor("tank").is_some());
+
+        (sensor.on_change.lock().unwrap().as_ref().unwrap())(1.5);
+        let on_error = sensor.on_error.lock().unwrap();
+        (on_error.as_ref().unwrap())(ErrorEventCode::Saturation, "saturated");
+        (on_error.as_ref().unwrap())(ErrorEventCode::Ok, "cleared");
+
+        let stats = session.stats();
+        assert_eq!((stats.samples, stats.errors), (1, 1));
+        assert_eq!(stats.channels["tank"].last, 1.5);
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let session = Session::new();
+        let rec = session.subscribe("boiler");
+
+        rec(20.0);
+        session.pause();
+        rec(99.0);
+        session.record_error("boiler");
+        session.resume();
+        rec(21.0);
+        session.record_error("boiler");
+        session.stop();
+        rec(22.0);
+
+        assert_eq!(session.state(), SessionState::Stopped);
+
+        let stats = session.stats();
+        assert_eq!(stats.samples, 2);
+        assert_eq!(stats.errors, 1);
+
+        let boiler = stats.channels["boiler"];
+        assert_eq!((boiler.min, boiler.max, boiler.last), (20.0, 21.0, 21.0));
+
+        let vals: Vec<_> = session.drain().events().iter().map(|e| e.value).collect();
+        assert_eq!(vals, [20.0, 21.0]);
+        assert!(session.is_empty());
+    }
+
+    #[test]
+    fn test_overflow() {
+        for (overflow, expected) in [
+            (Overflow::DropOldest, [2.0, 3.0]),
+            (Overflow::DropNewest, [0.0, 1.0]),
+        ] {
+            let session = Session::with_policy(BufferPolicy {
+                capacity: 2,
+                overflow,
+            });
+            for i in 0..4 {
+                session.record("ch", i as f64);
+            }
+
+            let stats = session.stats();
+            assert_eq!((stats.samples, stats.dropped), (4, 2));
+
+            let vals: Vec<_> = session.drain().events().iter().map(|e| e.value).collect();
+            assert_eq!(vals, expected);
+        }
+
+        for overflow in [Overflow::DropOldest, Overflow::DropNewest] {
+            let session = Session::with_policy(BufferPolicy {
+                capacity: 0,
+                overflow,
+            });
+            session.record("ch", 1.0);
+            assert_eq!(session.stats().dropped, 1);
+            assert!(session.is_empty());
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// phidget-rs/src/signals.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Shutdown on Ctrl-C and termination signals.
+//!
+//! Most applications that stream data from Phidgets set their callbacks,
+//! then park the main thread until the user hits ^C, and close the
+//! devices on the way out. [`run_until_shutdown()`] does all of that:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::TemperatureSensor, Phidget};
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! sensor.open_wait_default().unwrap();
+//! sensor
+//!     .set_on_temperature_change_handler(|_, t| println!("{:.1}°C", t))
+//!     .unwrap();
+//!
+//! phidget::run_until_shutdown(&mut [&mut sensor], || println!("\nExiting...")).unwrap();
+//! ```
+//!
+//! This requires the `signals` feature. The handler is installed for
+//! SIGINT, SIGTERM, and SIGHUP on Unix, and for Ctrl-C on Windows. Only one
+//! handler can be installed in a process, so this can only be called once.
+
+use crate::{ErrorCode, Phidget, Result};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+/// Blocks the calling thread until a Ctrl-C or termination signal is
+/// received.
+///
+/// Returns a `Duplicate` error if a signal handler was already installed.
+pub fn wait_for_shutdown() -> Result<()> {
+    let done = Arc::new(AtomicBool::new(false));
+
+    ctrlc::set_handler({
+        let done = Arc::clone(&done);
+        let thr = thread::current();
+        move || {
+            done.store(true, Ordering::SeqCst);
+            thr.unpark();
+        }
+    })
+    .map_err(|err| match err {
+        ctrlc::Error::MultipleHandlers => ErrorCode::Duplicate,
+        _ => ErrorCode::Unexpected,
+    })?;
+
+    // Parking can wake up spuriously
+    while !done.load(Ordering::SeqCst) {
+        thread::park();
+    }
+    Ok(())
+}
+
+/// Blocks the calling thread until a Ctrl-C or termination signal is
+/// received, then shuts down.
+///
+/// The shutdown calls the closure, then closes the devices, in reverse
+/// order. Errors closing the devices are ignored, so that all of them
+/// get closed.
+pub fn run_until_shutdown<F>(devices: &mut [&mut dyn Phidget], on_shutdown: F) -> Result<()>
+where
+    F: FnOnce(),
+{
+    wait_for_shutdown()?;
+    on_shutdown();
+    for dev in devices.iter_mut().rev() {
+        let _ = dev.close();
+    }
+    Ok(())
+}
@@ -0,0 +1,505 @@
+// phidget-rs/src/sources.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Capability traits for sensors and outputs.
+//!
+//! These are small traits that describe what a channel can do, rather
+//! than what it is. Application logic written against them can be driven
+//! by the real devices, or by the mocks in the [`testing`](crate::testing)
+//! module.
+//!
+//! ```rust
+//! use phidget::{sources::{SwitchSink, TemperatureSource}, Result};
+//!
+//! // Turns the heater on when it's too cold.
+//! fn thermostat(temp: &dyn TemperatureSource, heater: &dyn SwitchSink) -> Result<()> {
+//!     heater.set_switch(temp.temperature()? < 18.0)
+//! }
+//! ```
+//!
+//! The [`Sensor`] trait is a more general view of any channel with an
+//! analog reading, with its units, for polling and logging a mixed array
+//! of sensors:
+//!
+//! ```rust
+//! use phidget::{sources::Sensor, Result};
+//!
+//! fn log(sensors: &[&dyn Sensor]) -> Result<()> {
+//!     for sensor in sensors {
+//!         println!("{} {}", sensor.read()?, sensor.unit().symbol());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    devices::{
+        BldcMotor, CurrentInput, DcMotor, DigitalOutput, DistanceSensor, HumiditySensor,
+        LightSensor, PhSensor, PressureSensor, RcServo, SoundSensor, Stepper, TemperatureSensor,
+        VoltageInput, VoltageOutput, VoltageRatioInput,
+    },
+    motion::MotionControl,
+    Error, ErrorCode, ErrorEventCode, Result,
+};
+use phidget_sys as ffi;
+
+/// The units of a sensor reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum Unit {
+    /// No units, like a ratio
+    None = ffi::Phidget_Unit_PHIDUNIT_NONE, // 0
+    /// A boolean value
+    Boolean = ffi::Phidget_Unit_PHIDUNIT_BOOLEAN, // 1
+    /// Percent, like relative humidity
+    Percent = ffi::Phidget_Unit_PHIDUNIT_PERCENT, // 2
+    /// Decibels
+    Decibel = ffi::Phidget_Unit_PHIDUNIT_DECIBEL, // 3
+    /// Millimeters
+    Millimeter = ffi::Phidget_Unit_PHIDUNIT_MILLIMETER, // 4
+    /// Centimeters
+    Centimeter = ffi::Phidget_Unit_PHIDUNIT_CENTIMETER, // 5
+    /// Meters
+    Meter = ffi::Phidget_Unit_PHIDUNIT_METER, // 6
+    /// Grams
+    Gram = ffi::Phidget_Unit_PHIDUNIT_GRAM, // 7
+    /// Kilograms
+    Kilogram = ffi::Phidget_Unit_PHIDUNIT_KILOGRAM, // 8
+    /// Milliamperes
+    Milliampere = ffi::Phidget_Unit_PHIDUNIT_MILLIAMPERE, // 9
+    /// Amperes
+    Ampere = ffi::Phidget_Unit_PHIDUNIT_AMPERE, // 10
+    /// Kilopascals
+    Kilopascal = ffi::Phidget_Unit_PHIDUNIT_KILOPASCAL, // 11
+    /// Volts
+    Volt = ffi::Phidget_Unit_PHIDUNIT_VOLT, // 12
+    /// Degrees Celsius
+    DegreeCelsius = ffi::Phidget_Unit_PHIDUNIT_DEGREE_CELCIUS, // 13
+    /// Lux
+    Lux = ffi::Phidget_Unit_PHIDUNIT_LUX, // 14
+    /// Gauss
+    Gauss = ffi::Phidget_Unit_PHIDUNIT_GAUSS, // 15
+    /// pH
+    Ph = ffi::Phidget_Unit_PHIDUNIT_PH, // 16
+    /// Watts
+    Watt = ffi::Phidget_Unit_PHIDUNIT_WATT, // 17
+}
+
+impl Unit {
+    /// Gets the symbol for the unit, like "°C", which is empty for
+    /// [`Unit::None`].
+    pub fn symbol(&self) -> &'static str {
+        use Unit::*;
+        match self {
+            None => "",
+            Boolean => "",
+            Percent => "%",
+            Decibel => "dB",
+            Millimeter => "mm",
+            Centimeter => "cm",
+            Meter => "m",
+            Gram => "g",
+            Kilogram => "kg",
+            Milliampere => "mA",
+            Ampere => "A",
+            Kilopascal => "kPa",
+            Volt => "V",
+            DegreeCelsius => "°C",
+            Lux => "lx",
+            Gauss => "G",
+            Ph => "pH",
+            Watt => "W",
+        }
+    }
+}
+
+impl TryFrom<u32> for Unit {
+    type Error = Error;
+
+    fn try_from(val: u32) -> Result<Self> {
+        use Unit::*;
+        match val {
+            ffi::Phidget_Unit_PHIDUNIT_NONE => Ok(None),       // 0
+            ffi::Phidget_Unit_PHIDUNIT_BOOLEAN => Ok(Boolean), // 1
+            ffi::Phidget_Unit_PHIDUNIT_PERCENT => Ok(Percent), // 2
+            ffi::Phidget_Unit_PHIDUNIT_DECIBEL => Ok(Decibel), // 3
+            ffi::Phidget_Unit_PHIDUNIT_MILLIMETER => Ok(Millimeter), // 4
+            ffi::Phidget_Unit_PHIDUNIT_CENTIMETER => Ok(Centimeter), // 5
+            ffi::Phidget_Unit_PHIDUNIT_METER => Ok(Meter),     // 6
+            ffi::Phidget_Unit_PHIDUNIT_GRAM => Ok(Gram),       // 7
+            ffi::Phidget_Unit_PHIDUNIT_KILOGRAM => Ok(Kilogram), // 8
+            ffi::Phidget_Unit_PHIDUNIT_MILLIAMPERE => Ok(Milliampere), // 9
+            ffi::Phidget_Unit_PHIDUNIT_AMPERE => Ok(Ampere),   // 10
+            ffi::Phidget_Unit_PHIDUNIT_KILOPASCAL => Ok(Kilopascal), // 11
+            ffi::Phidget_Unit_PHIDUNIT_VOLT => Ok(Volt),       // 12
+            ffi::Phidget_Unit_PHIDUNIT_DEGREE_CELCIUS => Ok(DegreeCelsius), // 13
+            ffi::Phidget_Unit_PHIDUNIT_LUX => Ok(Lux),         // 14
+            ffi::Phidget_Unit_PHIDUNIT_GAUSS => Ok(Gauss),     // 15
+            ffi::Phidget_Unit_PHIDUNIT_PH => Ok(Ph),           // 16
+            ffi::Phidget_Unit_PHIDUNIT_WATT => Ok(Watt),       // 17
+            _ => Err(ErrorCode::InvalidArg),
+        }
+    }
+}
+
+/// Any output that can be driven to a value, like a digital or analog
+/// output, or the target of a motor.
+///
+/// This lets a sequencing engine drive a mix of outputs from a recipe of
+/// values.
+pub trait Output: Send {
+    /// Sets the output to a value.
+    ///
+    /// This is the duty cycle of a digital output, the voltage of a
+    /// voltage output, or the target of a motor.
+    fn set_value(&self, val: f64) -> Result<()>;
+
+    /// Turns the output fully on or off.
+    ///
+    /// Outputs that don't have on and off states return an `Unsupported`
+    /// error.
+    fn set_bool(&self, _on: bool) -> Result<()> {
+        Err(ErrorCode::Unsupported)
+    }
+
+    /// Gets the value that puts the output into its safe state.
+    fn safe_value(&self) -> f64 {
+        0.0
+    }
+
+    /// Puts the output into its safe state.
+    fn set_safe(&self) -> Result<()> {
+        self.set_value(self.safe_value())
+    }
+}
+
+/// A handler for the readings of a [`Sensor`].
+pub type SensorCallback = Box<dyn Fn(f64) + Send + 'static>;
+
+/// A handler for the errors reported by a [`Sensor`].
+pub type SensorErrorCallback = Box<dyn Fn(ErrorEventCode, &str) + Send + 'static>;
+
+/// Any channel with an analog reading.
+pub trait Sensor: Send {
+    /// Gets the most recent reading.
+    fn read(&self) -> Result<f64>;
+
+    /// Gets the units of the readings.
+    fn unit(&self) -> Unit;
+
+    /// Sets a handler to receive the readings when they change.
+    ///
+    /// This replaces the change handler of the channel.
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()>;
+
+    /// Sets a handler to receive the errors reported by the channel.
+    ///
+    /// This replaces the error handler of the channel. Sensors that can't
+    /// report errors return an `Unsupported` error.
+    fn set_on_error(&mut self, _cb: SensorErrorCallback) -> Result<()> {
+        Err(ErrorCode::Unsupported)
+    }
+}
+
+/// A source of temperature readings, in degrees Celsius.
+pub trait TemperatureSource: Send {
+    /// Gets the most recent temperature.
+    fn temperature(&self) -> Result<f64>;
+}
+
+/// A source of relative humidity readings, in percent.
+pub trait HumiditySource: Send {
+    /// Gets the most recent humidity.
+    fn humidity(&self) -> Result<f64>;
+}
+
+/// A source of generic analog readings, like a voltage or voltage ratio.
+pub trait AnalogSource: Send {
+    /// Gets the most recent reading.
+    fn value(&self) -> Result<f64>;
+}
+
+/// An on/off output, like a relay or LED.
+pub trait SwitchSink: Send {
+    /// Turns the output on or off.
+    fn set_switch(&self, on: bool) -> Result<()>;
+}
+
+impl TemperatureSource for TemperatureSensor {
+    fn temperature(&self) -> Result<f64> {
+        TemperatureSensor::temperature(self)
+    }
+}
+
+impl HumiditySource for HumiditySensor {
+    fn humidity(&self) -> Result<f64> {
+        HumiditySensor::humidity(self)
+    }
+}
+
+impl AnalogSource for VoltageInput {
+    fn value(&self) -> Result<f64> {
+        self.voltage()
+    }
+}
+
+impl AnalogSource for VoltageRatioInput {
+    fn value(&self) -> Result<f64> {
+        self.voltage_ratio()
+    }
+}
+
+impl SwitchSink for DigitalOutput {
+    fn set_switch(&self, on: bool) -> Result<()> {
+        self.set_state_bool(on)
+    }
+}
+
+impl Sensor for TemperatureSensor {
+    fn read(&self) -> Result<f64> {
+        self.temperature()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::DegreeCelsius
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_temperature_change_handler(move |_, val| cb(val))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for HumiditySensor {
+    fn read(&self) -> Result<f64> {
+        self.humidity()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Percent
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_humidity_change_handler(move |_, val| cb(val))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for PressureSensor {
+    fn read(&self) -> Result<f64> {
+        self.pressure()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Kilopascal
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_pressure_change_handler(move |_, val| cb(val))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for LightSensor {
+    fn read(&self) -> Result<f64> {
+        self.illuminance()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Lux
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_illuminance_change_handler(move |_, val| cb(val))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for SoundSensor {
+    fn read(&self) -> Result<f64> {
+        self.db()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Decibel
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_spl_change_handler(move |_, spl| cb(spl.db))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for DistanceSensor {
+    fn read(&self) -> Result<f64> {
+        self.distance().map(f64::from)
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Millimeter
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_distance_change_handler(move |_, val| cb(val.into()))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for PhSensor {
+    fn read(&self) -> Result<f64> {
+        self.ph()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Ph
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_ph_change_handler(move |_, val| cb(val))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for CurrentInput {
+    fn read(&self) -> Result<f64> {
+        self.current()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Ampere
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_current_change_handler(move |_, val| cb(val))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for VoltageInput {
+    fn read(&self) -> Result<f64> {
+        self.voltage()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::Volt
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_voltage_change_handler(move |_, val| cb(val))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Sensor for VoltageRatioInput {
+    fn read(&self) -> Result<f64> {
+        self.voltage_ratio()
+    }
+
+    fn unit(&self) -> Unit {
+        Unit::None
+    }
+
+    fn set_on_change(&mut self, cb: SensorCallback) -> Result<()> {
+        self.set_on_voltage_ratio_change_handler(move |_, val| cb(val))
+    }
+
+    fn set_on_error(&mut self, cb: SensorErrorCallback) -> Result<()> {
+        self.set_on_error_handler(move |_, code, msg| cb(code, msg))
+    }
+}
+
+impl Output for DigitalOutput {
+    fn set_value(&self, val: f64) -> Result<()> {
+        self.set_duty_cycle(val)
+    }
+
+    fn set_bool(&self, on: bool) -> Result<()> {
+        self.set_state_bool(on)
+    }
+}
+
+impl Output for VoltageOutput {
+    fn set_value(&self, val: f64) -> Result<()> {
+        self.set_voltage(val)
+    }
+}
+
+impl Output for Stepper {
+    fn set_value(&self, val: f64) -> Result<()> {
+        self.set_target(val)
+    }
+
+    /// The safe state of a motor is disengaged.
+    fn set_safe(&self) -> Result<()> {
+        MotionControl::set_engaged(self, false)
+    }
+}
+
+impl Output for BldcMotor {
+    fn set_value(&self, val: f64) -> Result<()> {
+        self.set_target(val)
+    }
+
+    /// The safe state of a motor is disengaged.
+    fn set_safe(&self) -> Result<()> {
+        MotionControl::set_engaged(self, false)
+    }
+}
+
+impl Output for DcMotor {
+    fn set_value(&self, val: f64) -> Result<()> {
+        self.set_target(val)
+    }
+
+    /// The safe state of a motor is disengaged.
+    fn set_safe(&self) -> Result<()> {
+        MotionControl::set_engaged(self, false)
+    }
+}
+
+impl Output for RcServo {
+    fn set_value(&self, val: f64) -> Result<()> {
+        self.set_target(val)
+    }
+
+    /// The safe state of a servo is disengaged.
+    fn set_safe(&self) -> Result<()> {
+        MotionControl::set_engaged(self, false)
+    }
+}
@@ -0,0 +1,392 @@
+// phidget-rs/src/spec.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Device addressing specifications.
+//!
+//! A [`DeviceSpec`] gathers the filters used to pick out a channel, and can
+//! be parsed from a compact string form, which is handy for command-line
+//! options and environment variables. The string is a comma-separated list
+//! of `key=value` pairs, like:
+//!
+//! ```text
+//! sn=12345,port=2,ch=0
+//! label=boiler
+//! ```
+//!
+//! The keys are:
+//!
+//! - `sn` or `serial` - The device serial number
+//! - `port` or `hub_port` - The VINT hub port
+//! - `ch` or `channel` - The channel index
+//! - `hub_port_device` or `hpd` - Whether to open the hub port itself
+//!   (`true`/`false` or `1`/`0`)
+//! - `label` - The device label. The label runs to the end of the string,
+//!   so it can contain commas, but must be the last item if present.
+
+use crate::{
+    labels, manager::PhidgetInfo, Error, ErrorCode, Phidget, Result, PHIDGET_CHANNEL_ANY,
+    PHIDGET_HUBPORT_ANY, PHIDGET_SERIALNUMBER_ANY,
+};
+use std::{fmt, str::FromStr, time::Duration};
+
+// Defines a validated newtype for one of the parts of the address of a
+// channel, which has a wildcard value and a minimum valid value.
+macro_rules! address_type {
+    ($(#[$attr:meta])* $name:ident, $any:expr, $min:expr) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(try_from = "i32", into = "i32")
+        )]
+        pub struct $name(i32);
+
+        impl $name {
+            /// The wildcard, which matches any value.
+            pub const ANY: Self = Self($any);
+
+            /// Creates a value, checking that it's either valid or the
+            /// wildcard.
+            pub fn new(val: i32) -> Result<Self> {
+                if val == $any || val >= $min {
+                    Ok(Self(val))
+                }
+                else {
+                    Err(ErrorCode::InvalidArg)
+                }
+            }
+
+            /// Determines if this is the wildcard.
+            pub fn is_any(&self) -> bool {
+                self.0 == $any
+            }
+
+            /// Determines if a value matches this one, which is true for
+            /// any value when this is the wildcard.
+            pub fn matches(&self, val: Self) -> bool {
+                self.is_any() || *self == val
+            }
+
+            /// Gets the value, or `None` for the wildcard.
+            pub fn get(&self) -> Option<i32> {
+                (!self.is_any()).then_some(self.0)
+            }
+
+            /// Gets the raw value, as used by the phidget22 library.
+            pub fn value(&self) -> i32 {
+                self.0
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::ANY
+            }
+        }
+
+        impl TryFrom<i32> for $name {
+            type Error = Error;
+
+            fn try_from(val: i32) -> Result<Self> {
+                Self::new(val)
+            }
+        }
+
+        impl From<$name> for i32 {
+            fn from(val: $name) -> Self {
+                val.0
+            }
+        }
+
+        impl TryFrom<Option<i32>> for $name {
+            type Error = Error;
+
+            /// Converts an optional value, where `None` is the wildcard.
+            fn try_from(val: Option<i32>) -> Result<Self> {
+                val.map_or(Ok(Self::ANY), Self::new)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.get() {
+                    Some(val) => write!(f, "{}", val),
+                    None => write!(f, "any"),
+                }
+            }
+        }
+    };
+}
+
+address_type!(
+    /// The serial number of a device.
+    ///
+    /// For a VINT device, this is the serial number of its hub.
+    SerialNumber,
+    PHIDGET_SERIALNUMBER_ANY,
+    0
+);
+
+address_type!(
+    /// The index of a port on a VINT hub.
+    HubPort,
+    PHIDGET_HUBPORT_ANY,
+    0
+);
+
+address_type!(
+    /// The index of a channel on a device.
+    Channel,
+    PHIDGET_CHANNEL_ANY,
+    0
+);
+
+/// The specification of the address of a channel.
+///
+/// Any item that is not set is left as a wildcard.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct DeviceSpec {
+    /// The serial number of the device
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "SerialNumber::is_any"))]
+    pub serial_number: SerialNumber,
+    /// The VINT hub port
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "HubPort::is_any"))]
+    pub hub_port: HubPort,
+    /// The channel index
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Channel::is_any"))]
+    pub channel: Channel,
+    /// Whether the channel is a hub port channel
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub is_hub_port_device: Option<bool>,
+    /// The label of the device
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub label: Option<String>,
+}
+
+impl DeviceSpec {
+    /// Creates a specification that matches any channel.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the serial number of the device.
+    ///
+    /// The wildcard, [`SerialNumber::ANY`], leaves the item unset.
+    pub fn serial_number(mut self, sn: SerialNumber) -> Self {
+        self.serial_number = sn;
+        self
+    }
+
+    /// Sets the VINT hub port.
+    ///
+    /// The wildcard, [`HubPort::ANY`], leaves the item unset.
+    pub fn hub_port(mut self, port: HubPort) -> Self {
+        self.hub_port = port;
+        self
+    }
+
+    /// Sets the channel index.
+    ///
+    /// The wildcard, [`Channel::ANY`], leaves the item unset.
+    pub fn channel(mut self, chan: Channel) -> Self {
+        self.channel = chan;
+        self
+    }
+
+    /// Sets whether the channel is a hub port channel.
+    pub fn is_hub_port_device(mut self, on: bool) -> Self {
+        self.is_hub_port_device = Some(on);
+        self
+    }
+
+    /// Sets the label of the device.
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Sets the filters of the specification on a channel.
+    /// This must be done before the channel is opened.
+    pub fn apply<P>(&self, ph: &mut P) -> Result<()>
+    where
+        P: Phidget + ?Sized,
+    {
+        if !self.serial_number.is_any() {
+            ph.set_serial_number(self.serial_number)?;
+        }
+        if !self.hub_port.is_any() {
+            ph.set_hub_port(self.hub_port)?;
+        }
+        if !self.channel.is_any() {
+            ph.set_channel(self.channel)?;
+        }
+        if let Some(on) = self.is_hub_port_device {
+            ph.set_is_hub_port_device(on)?;
+        }
+        if let Some(label) = &self.label {
+            ph.set_device_label(label)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a channel of the requested type, sets the filters of the
+    /// specification on it, and opens it, waiting a limited time for it to
+    /// attach.
+    pub fn open<P>(&self, to: Duration) -> Result<P>
+    where
+        P: Phidget + Default,
+    {
+        let mut dev = P::default();
+        self.apply(&mut dev)?;
+        dev.open_wait(to)?;
+        Ok(dev)
+    }
+
+    /// Determines if an attached channel matches the specification.
+    pub fn matches(&self, info: &PhidgetInfo) -> bool {
+        self.serial_number.matches(info.serial_number)
+            && self.hub_port.matches(info.hub_port)
+            && self.channel.matches(info.channel)
+            && self
+                .is_hub_port_device
+                .map_or(true, |on| on == info.is_hub_port_device)
+            && self
+                .label
+                .as_ref()
+                .map_or(true, |label| *label == info.device_label)
+    }
+}
+
+impl fmt::Display for DeviceSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut items = Vec::new();
+        if !self.serial_number.is_any() {
+            items.push(format!("sn={}", self.serial_number));
+        }
+        if !self.hub_port.is_any() {
+            items.push(format!("port={}", self.hub_port));
+        }
+        if !self.channel.is_any() {
+            items.push(format!("ch={}", self.channel));
+        }
+        if let Some(on) = self.is_hub_port_device {
+            items.push(format!("hpd={}", on));
+        }
+        if let Some(label) = &self.label {
+            items.push(format!("label={}", label));
+        }
+        write!(f, "{}", items.join(","))
+    }
+}
+
+impl FromStr for DeviceSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        fn int(val: &str) -> Result<i32> {
+            val.trim().parse().map_err(|_| ErrorCode::InvalidArg)
+        }
+
+        let mut spec = Self::new();
+        let mut rest = s.trim();
+
+        while !rest.is_empty() {
+            let (key, val) = rest.split_once('=').ok_or(ErrorCode::InvalidArg)?;
+            let key = key.trim().to_ascii_lowercase();
+
+            // The label consumes the rest of the string
+            if key == "label" {
+                labels::validate(val)?;
+                spec.label = Some(val.to_string());
+                break;
+            }
+
+            let (val, tail) = val.split_once(',').unwrap_or((val, ""));
+            rest = tail.trim_start();
+
+            match key.as_str() {
+                "sn" | "serial" => spec.serial_number = SerialNumber::new(int(val)?)?,
+                "port" | "hub_port" => spec.hub_port = HubPort::new(int(val)?)?,
+                "ch" | "channel" => spec.channel = Channel::new(int(val)?)?,
+                "hpd" | "hub_port_device" => {
+                    spec.is_hub_port_device = Some(match val.trim() {
+                        "true" | "1" => true,
+                        "false" | "0" => false,
+                        _ => return Err(ErrorCode::InvalidArg),
+                    })
+                }
+                _ => return Err(ErrorCode::InvalidArg),
+            }
+        }
+        Ok(spec)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let spec: DeviceSpec = "sn=12345, port=2,ch=0".parse().unwrap();
+        assert_eq!(
+            spec,
+            DeviceSpec::new()
+                .serial_number(SerialNumber::new(12345).unwrap())
+                .hub_port(HubPort::new(2).unwrap())
+                .channel(Channel::new(0).unwrap())
+        );
+        assert_eq!(spec.to_string(), "sn=12345,port=2,ch=0");
+
+        let spec: DeviceSpec = "ch=1,label=tank, 2".parse().unwrap();
+        assert_eq!(
+            spec,
+            DeviceSpec::new()
+                .channel(Channel::new(1).unwrap())
+                .label("tank, 2")
+        );
+        assert_eq!(spec.to_string().parse(), Ok(spec));
+
+        assert_eq!("".parse(), Ok(DeviceSpec::new()));
+        assert!("sn=abc".parse::<DeviceSpec>().is_err());
+        assert!("port".parse::<DeviceSpec>().is_err());
+        assert!("color=red".parse::<DeviceSpec>().is_err());
+        assert!("port=-2".parse::<DeviceSpec>().is_err());
+    }
+
+    #[test]
+    fn test_address_types() {
+        assert_eq!(HubPort::new(2).unwrap().get(), Some(2));
+        assert!(HubPort::new(-2).is_err());
+        assert!(HubPort::ANY.is_any());
+        assert_eq!(HubPort::ANY.get(), None);
+        assert_eq!(HubPort::ANY.to_string(), "any");
+        assert!(HubPort::ANY.matches(HubPort::new(3).unwrap()));
+        assert!(!HubPort::new(2).unwrap().matches(HubPort::new(3).unwrap()));
+        assert_eq!(
+            Channel::try_from(None).map(i32::from),
+            Ok(PHIDGET_CHANNEL_ANY)
+        );
+        assert!(HubPort::try_from(Some(-5)).is_err());
+
+        // A wildcard leaves the filter unset
+        assert_eq!(DeviceSpec::new().hub_port(HubPort::ANY), DeviceSpec::new());
+        assert_eq!(DeviceSpec::new().hub_port(HubPort::ANY).to_string(), "");
+    }
+}
@@ -0,0 +1,99 @@
+// phidget-rs/src/testing/hardware.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Hardware requirements for integration tests.
+//!
+//! An integration test that needs real devices can declare them with the
+//! [`requires!`](crate::requires) macro. If any are not attached, the test
+//! prints a message saying what's missing and returns early, rather than
+//! failing later with a cryptic `Timeout` error.
+//!
+//! ```rust,no_run
+//! use phidget::{requires, ChannelClass};
+//!
+//! #[test]
+//! fn test_temperature() {
+//!     requires!(ChannelClass::TemperatureSensor, ChannelClass::HumiditySensor);
+//!     // ...
+//! }
+//! ```
+
+use crate::{manager::Manager, registry::DeviceRegistry, ChannelClass};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The default time to wait for the required hardware to be discovered.
+pub const REQUIRES_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Checks which of the required channel classes are not attached.
+///
+/// This runs a manager for up to the timeout, waiting for at least one
+/// channel of each required class to be discovered. A class listed more
+/// than once requires that many channels. Returns the classes that are
+/// missing, which is all of them if the manager can't be opened.
+pub fn missing(required: &[ChannelClass], to: Duration) -> Vec<ChannelClass> {
+    let reg = DeviceRegistry::new();
+    let mut mgr = Manager::new();
+    if reg.connect(&mut mgr).and_then(|_| mgr.open()).is_err() {
+        return required.to_vec();
+    }
+
+    let deadline = Instant::now() + to;
+    loop {
+        let mut avail: Vec<_> = reg
+            .channels()
+            .iter()
+            .map(|info| info.channel_class)
+            .collect();
+
+        let missing: Vec<_> = required
+            .iter()
+            .filter(|cls| match avail.iter().position(|c| c == *cls) {
+                Some(i) => {
+                    avail.swap_remove(i);
+                    false
+                }
+                None => true,
+            })
+            .copied()
+            .collect();
+
+        if missing.is_empty() || Instant::now() >= deadline {
+            return missing;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Skips the rest of a test if the required hardware isn't attached.
+///
+/// This takes a list of [`ChannelClass`] values. If any of them are not
+/// discovered within [`REQUIRES_TIMEOUT`], it prints the missing classes
+/// and returns from the enclosing function, which must return `()`.
+#[macro_export]
+macro_rules! requires {
+    ($($cls:expr),+ $(,)?) => {
+        let missing = $crate::testing::hardware::missing(
+            &[$($cls),+],
+            $crate::testing::hardware::REQUIRES_TIMEOUT,
+        );
+        if !missing.is_empty() {
+            eprintln!(
+                "Skipping {}: required hardware not attached: {:?}",
+                module_path!(),
+                missing
+            );
+            return;
+        }
+    };
+}
@@ -0,0 +1,158 @@
+// phidget-rs/src/testing/loopback.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Loopback harnesses for on-hardware integration tests.
+//!
+//! A loopback pairs an output with an input that is wired to it, like a
+//! digital output on one hub port looped back to a digital input on
+//! another. The test sets the output, then expects the matching event on
+//! the input within a timeout.
+//!
+//! ```rust,no_run
+//! use phidget::{testing::loopback::DigitalLoopback, DeviceSpec};
+//! use std::time::Duration;
+//!
+//! const TO: Duration = Duration::from_secs(1);
+//!
+//! let out: DeviceSpec = "port=0,hpd=true".parse().unwrap();
+//! let inp: DeviceSpec = "port=1,hpd=true".parse().unwrap();
+//!
+//! let mut lb = DigitalLoopback::open(&out, &inp, Duration::from_secs(5)).unwrap();
+//! lb.set_and_expect(true, TO).unwrap();
+//! lb.set_and_expect(false, TO).unwrap();
+//! ```
+
+use crate::{
+    devices::{DigitalInput, DigitalOutput, VoltageInput, VoltageOutput},
+    watch::WatchReceiver,
+    DeviceSpec, ErrorCode, Phidget, Result,
+};
+use std::time::{Duration, Instant};
+
+// Waits for a reading that satisfies the predicate, checking the latest
+// value first. Returns a `Timeout` error if none arrives in time.
+fn expect_reading<T, F>(rx: &mut WatchReceiver<T>, to: Duration, f: F) -> Result<()>
+where
+    T: Clone,
+    F: Fn(&T) -> bool,
+{
+    if rx.value().as_ref().is_some_and(&f) {
+        return Ok(());
+    }
+
+    let deadline = Instant::now() + to;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.changed(remaining) {
+            Some(rd) if f(&rd.value) => return Ok(()),
+            Some(_) => (),
+            None => return Err(ErrorCode::Timeout),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// A digital output wired back to a digital input.
+pub struct DigitalLoopback {
+    /// The output side of the loopback
+    pub output: DigitalOutput,
+    /// The input side of the loopback
+    pub input: DigitalInput,
+    // The state of the input
+    rx: WatchReceiver<u8>,
+}
+
+impl DigitalLoopback {
+    /// Opens the output and input channels at the specified addresses,
+    /// waiting a limited time for each to attach.
+    pub fn open(output: &DeviceSpec, input: &DeviceSpec, to: Duration) -> Result<Self> {
+        let output: DigitalOutput = output.open(to)?;
+
+        let mut inp = DigitalInput::new();
+        input.apply(&mut inp)?;
+        let rx = inp.watch()?;
+        inp.open_wait(to)?;
+
+        Ok(Self {
+            output,
+            input: inp,
+            rx,
+        })
+    }
+
+    /// Sets the state of the output.
+    pub fn set(&self, on: bool) -> Result<()> {
+        self.output.set_state_bool(on)
+    }
+
+    /// Waits a limited time for the input to be in the specified state,
+    /// returning a `Timeout` error if it isn't.
+    pub fn expect(&mut self, on: bool, to: Duration) -> Result<()> {
+        expect_reading(&mut self.rx, to, |&state| (state != 0) == on)
+    }
+
+    /// Sets the state of the output, and waits a limited time for the
+    /// input to follow it.
+    pub fn set_and_expect(&mut self, on: bool, to: Duration) -> Result<()> {
+        self.set(on)?;
+        self.expect(on, to)
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// A voltage output wired back to a voltage input.
+pub struct VoltageLoopback {
+    /// The output side of the loopback
+    pub output: VoltageOutput,
+    /// The input side of the loopback
+    pub input: VoltageInput,
+    // The voltage of the input
+    rx: WatchReceiver<f64>,
+}
+
+impl VoltageLoopback {
+    /// Opens the output and input channels at the specified addresses,
+    /// waiting a limited time for each to attach.
+    pub fn open(output: &DeviceSpec, input: &DeviceSpec, to: Duration) -> Result<Self> {
+        let output: VoltageOutput = output.open(to)?;
+
+        let mut inp = VoltageInput::new();
+        input.apply(&mut inp)?;
+        let rx = inp.watch()?;
+        inp.open_wait(to)?;
+
+        Ok(Self {
+            output,
+            input: inp,
+            rx,
+        })
+    }
+
+    /// Sets the voltage of the output.
+    pub fn set(&self, v: f64) -> Result<()> {
+        self.output.set_voltage(v)
+    }
+
+    /// Waits a limited time for the input to read within the tolerance of
+    /// the specified voltage, returning a `Timeout` error if it doesn't.
+    pub fn expect(&mut self, v: f64, tol: f64, to: Duration) -> Result<()> {
+        expect_reading(&mut self.rx, to, |&val| (val - v).abs() <= tol)
+    }
+
+    /// Sets the voltage of the output, and waits a limited time for the
+    /// input to follow it, within the tolerance.
+    pub fn set_and_expect(&mut self, v: f64, tol: f64, to: Duration) -> Result<()> {
+        self.set(v)?;
+        self.expect(v, tol, to)
+    }
+}
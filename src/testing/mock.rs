@@ -0,0 +1,141 @@
+// phidget-rs/src/testing/mock.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! In-memory mock devices.
+//!
+//! These implement the capability traits from the
+//! [`sources`](crate::sources) module without any hardware, so that
+//! application logic can be unit tested.
+
+use crate::{
+    sources::{AnalogSource, HumiditySource, SwitchSink, TemperatureSource},
+    ErrorCode, Result,
+};
+use std::sync::Mutex;
+
+/// A mock sensor that reports a value set by the test.
+///
+/// Until a value is set, reads return an `UnknownVal` error, like a real
+/// channel that has just attached.
+#[derive(Debug, Default)]
+pub struct MockSensor {
+    value: Mutex<Option<f64>>,
+}
+
+impl MockSensor {
+    /// Creates a mock sensor with no value.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a mock sensor with an initial value.
+    pub fn with_value(val: f64) -> Self {
+        Self {
+            value: Mutex::new(Some(val)),
+        }
+    }
+
+    /// Sets the value that the sensor reports.
+    pub fn set(&self, val: f64) {
+        *self.value.lock().unwrap() = Some(val);
+    }
+
+    /// Clears the value, so that reads return an error.
+    pub fn clear(&self) {
+        *self.value.lock().unwrap() = None;
+    }
+
+    /// Gets the value of the sensor.
+    pub fn get(&self) -> Result<f64> {
+        self.value.lock().unwrap().ok_or(ErrorCode::UnknownVal)
+    }
+}
+
+impl TemperatureSource for MockSensor {
+    fn temperature(&self) -> Result<f64> {
+        self.get()
+    }
+}
+
+impl HumiditySource for MockSensor {
+    fn humidity(&self) -> Result<f64> {
+        self.get()
+    }
+}
+
+impl AnalogSource for MockSensor {
+    fn value(&self) -> Result<f64> {
+        self.get()
+    }
+}
+
+/// A mock on/off output that records the states it was set to.
+#[derive(Debug, Default)]
+pub struct MockSwitch {
+    history: Mutex<Vec<bool>>,
+}
+
+impl MockSwitch {
+    /// Creates a mock switch, which starts off.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the current state of the switch.
+    pub fn is_on(&self) -> bool {
+        self.history
+            .lock()
+            .unwrap()
+            .last()
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Gets all the states the switch was set to, in order.
+    pub fn history(&self) -> Vec<bool> {
+        self.history.lock().unwrap().clone()
+    }
+}
+
+impl SwitchSink for MockSwitch {
+    fn set_switch(&self, on: bool) -> Result<()> {
+        self.history.lock().unwrap().push(on);
+        Ok(())
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Turns the heater on when it's too cold.
+    fn thermostat(temp: &dyn TemperatureSource, heater: &dyn SwitchSink) -> Result<()> {
+        heater.set_switch(temp.temperature()? < 18.0)
+    }
+
+    #[test]
+    fn test_mocks() {
+        let temp = MockSensor::new();
+        let heater = MockSwitch::new();
+
+        assert_eq!(thermostat(&temp, &heater), Err(ErrorCode::UnknownVal));
+
+        temp.set(15.0);
+        thermostat(&temp, &heater).unwrap();
+        assert!(heater.is_on());
+
+        temp.set(21.0);
+        thermostat(&temp, &heater).unwrap();
+        assert_eq!(heater.history(), vec![true, false]);
+    }
+}
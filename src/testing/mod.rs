@@ -0,0 +1,25 @@
+// phidget-rs/src/testing/mod.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Utilities for testing applications built on the library.
+
+/// In-memory mock devices
+pub mod mock;
+pub use crate::testing::mock::{MockSensor, MockSwitch};
+
+/// Recording and replay of sensor event logs
+pub mod replay;
+
+/// Loopback harnesses for on-hardware integration tests
+pub mod loopback;
+
+/// Hardware requirements for integration tests
+pub mod hardware;
@@ -0,0 +1,209 @@
+// phidget-rs/src/testing/replay.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Recording and replay of sensor event logs.
+//!
+//! A [`Recorder`] captures readings from live devices into an
+//! [`EventLog`], which can be saved as simple CSV text. Later, a [`Replay`]
+//! feeds the log back into mock devices, or any other handler, with the
+//! original timing, so that data pipelines can be regression tested
+//! against real captured sensor behavior.
+//!
+//! The CSV form has one event per line, as `offset,channel,value`, where
+//! the offset is in seconds from the start of the recording.
+//!
+//! ```rust,no_run
+//! use phidget::{testing::{replay::{EventLog, Replay}, MockSensor}};
+//! use std::sync::Arc;
+//!
+//! let log = EventLog::from_csv(&std::fs::read_to_string("boiler.csv").unwrap()).unwrap();
+//! let temp = Arc::new(MockSensor::new());
+//!
+//! Replay::new(log).sensor("boiler", &temp).run();
+//! ```
+
+pub use crate::events::{EventLog, RecordedEvent};
+
+use crate::testing::MockSensor;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    thread,
+    time::Instant,
+};
+
+/// Records readings into an event log, stamped with the time since the
+/// recorder was created.
+///
+/// The recorder can be shared, via an `Arc`, with the callbacks of several
+/// devices.
+///
+/// ```rust,no_run
+/// use phidget::{devices::TemperatureSensor, testing::replay::Recorder};
+/// use std::sync::Arc;
+///
+/// let rec = Arc::new(Recorder::new());
+/// let mut sensor = TemperatureSensor::new();
+/// sensor
+///     .set_on_temperature_change_handler({
+///         let rec = Arc::clone(&rec);
+///         move |_, t| rec.record("boiler", t)
+///     })
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Recorder {
+    // The start time of the recording
+    start: Instant,
+    // The recorded events
+    log: Mutex<EventLog>,
+}
+
+impl Recorder {
+    /// Creates a recorder, starting the clock.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            log: Mutex::new(EventLog::new()),
+        }
+    }
+
+    /// Records a reading for the named channel.
+    pub fn record(&self, channel: &str, value: f64) {
+        let evt = RecordedEvent {
+            offset: self.start.elapsed(),
+            channel: channel.to_string(),
+            value,
+        };
+        self.log.lock().unwrap().push(evt);
+    }
+
+    /// Gets a copy of the events recorded so far.
+    pub fn log(&self) -> EventLog {
+        self.log.lock().unwrap().clone()
+    }
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// A handler that receives the replayed values for a channel.
+type ReplayHandler = Box<dyn Fn(f64) + Send + 'static>;
+
+/// A driver that replays an event log with its original timing.
+///
+/// The events for each channel are sent to the handlers routed to it.
+/// Events for channels without a handler are skipped.
+pub struct Replay {
+    // The log to replay
+    log: EventLog,
+    // The handlers, by channel name
+    routes: HashMap<String, Vec<ReplayHandler>>,
+    // The playback speed multiplier
+    speed: f64,
+}
+
+impl Replay {
+    /// Creates a driver to replay the log at its original speed.
+    pub fn new(log: EventLog) -> Self {
+        Self {
+            log,
+            routes: HashMap::new(),
+            speed: 1.0,
+        }
+    }
+
+    /// Sends the events for the named channel to a handler.
+    pub fn route<F>(mut self, channel: &str, f: F) -> Self
+    where
+        F: Fn(f64) + Send + 'static,
+    {
+        self.routes
+            .entry(channel.to_string())
+            .or_default()
+            .push(Box::new(f));
+        self
+    }
+
+    /// Sets the value of a mock sensor from the events of the named
+    /// channel.
+    pub fn sensor(self, channel: &str, sensor: &Arc<MockSensor>) -> Self {
+        let sensor = Arc::clone(sensor);
+        self.route(channel, move |val| sensor.set(val))
+    }
+
+    /// Sets the playback speed as a multiple of the original, so that 2.0
+    /// plays back twice as fast. A speed of zero or less, or infinity,
+    /// replays all of the events without any delay.
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Replays the log, blocking until all the events have been sent.
+    pub fn run(&self) {
+        let paced = self.speed > 0.0 && self.speed.is_finite();
+        let start = Instant::now();
+
+        for evt in self.log.events() {
+            if paced {
+                let at = start + evt.offset.div_f64(self.speed);
+                let now = Instant::now();
+                if at > now {
+                    thread::sleep(at - now);
+                }
+            }
+            if let Some(handlers) = self.routes.get(&evt.channel) {
+                for f in handlers {
+                    f(evt.value);
+                }
+            }
+        }
+    }
+
+    /// Replays the log on a background thread.
+    pub fn spawn(self) -> thread::JoinHandle<()> {
+        thread::spawn(move || self.run())
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_replay() {
+        let log = EventLog::from_csv("0,a,1\n0.01,b,2\n0.02,a,3").unwrap();
+        let a = Arc::new(MockSensor::new());
+        let b = Arc::new(Mutex::new(Vec::new()));
+
+        let t = Instant::now();
+        Replay::new(log)
+            .sensor("a", &a)
+            .route("b", {
+                let b = Arc::clone(&b);
+                move |val| b.lock().unwrap().push(val)
+            })
+            .run();
+
+        assert!(t.elapsed() >= Duration::from_millis(20));
+        assert_eq!(a.get(), Ok(3.0));
+        assert_eq!(*b.lock().unwrap(), vec![2.0]);
+    }
+}
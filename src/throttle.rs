@@ -0,0 +1,400 @@
+// phidget-rs/src/throttle.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Rate-limiting and coalescing adapters for event callbacks.
+//!
+//! Some channels can fire change events far faster than a consumer needs
+//! them, like a 1 kHz bridge feeding a 30 Hz UI. These adapters sit between
+//! a device callback and the application:
+//!
+//! - [`throttle()`] wraps a callback so that it runs at most once per
+//!   interval, dropping the events in between.
+//! - A [`Coalescer`] keeps only the latest value received within each
+//!   window, and delivers it from a background thread, so the last value
+//!   of a burst is never lost.
+//! - A [`Batcher`] collects every value, and delivers them in chunks of a
+//!   fixed size, or after a fixed time, from a background thread. This
+//!   cuts the per-sample overhead of high-rate streaming.
+
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Wraps a callback so that it is called at most once per interval.
+///
+/// The first event is passed through, then any events that arrive within
+/// the interval after it are dropped.
+///
+/// ```rust,no_run
+/// use phidget::{devices::VoltageInput, throttle::throttle};
+/// use std::time::Duration;
+///
+/// let mut vin = VoltageInput::new();
+/// let cb = throttle(Duration::from_millis(33), |v: f64| println!("{}", v));
+/// vin.set_on_voltage_change_handler(move |_, v| cb(v)).unwrap();
+/// ```
+pub fn throttle<T, F>(interval: Duration, cb: F) -> impl Fn(T) + Send + 'static
+where
+    F: Fn(T) + Send + 'static,
+{
+    let last: Mutex<Option<Instant>> = Mutex::new(None);
+    move |val| {
+        let now = Instant::now();
+        let mut last = last.lock().unwrap();
+        if last.map_or(true, |t| now.duration_since(t) >= interval) {
+            *last = Some(now);
+            drop(last);
+            cb(val);
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// The state shared between the coalescer handles and its thread.
+struct Shared<T> {
+    // The latest value and the stop flag
+    state: Mutex<(Option<T>, bool)>,
+    // Signaled when a value arrives, or on a stop
+    cv: Condvar,
+}
+
+// The owner of the shared state, which stops the thread when dropped.
+struct Inner<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.1 = true;
+        }
+        self.shared.cv.notify_all();
+    }
+}
+
+/// An adapter that coalesces bursts of events, keeping the latest value.
+///
+/// Values are delivered to the callback from a background thread, at most
+/// once per window. A value that arrives while the callback is resting
+/// replaces any that was pending, so the consumer always gets the most
+/// recent one. The first value after a quiet period is delivered right
+/// away.
+///
+/// The handle is cheap to clone, so a copy can be moved into a device
+/// callback. The thread exits when the last handle is dropped, discarding
+/// any pending value.
+///
+/// ```rust,no_run
+/// use phidget::{devices::VoltageInput, throttle::Coalescer};
+/// use std::time::Duration;
+///
+/// let ui = Coalescer::new(Duration::from_millis(33), |v: f64| println!("{}", v));
+///
+/// let mut vin = VoltageInput::new();
+/// vin.set_on_voltage_change_handler(move |_, v| ui.push(v)).unwrap();
+/// ```
+pub struct Coalescer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Coalescer<T>
+where
+    T: Send + 'static,
+{
+    /// Creates a coalescer that delivers values to the callback at most
+    /// once per window, and starts its thread.
+    pub fn new<F>(window: Duration, cb: F) -> Self
+    where
+        F: Fn(T) + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            state: Mutex::new((None, false)),
+            cv: Condvar::new(),
+        });
+
+        thread::spawn({
+            let shared = Arc::clone(&shared);
+            move || Self::run(&shared, window, cb)
+        });
+
+        Self {
+            inner: Arc::new(Inner { shared }),
+        }
+    }
+
+    // The delivery thread function.
+    fn run<F>(shared: &Shared<T>, window: Duration, cb: F)
+    where
+        F: Fn(T),
+    {
+        let mut state = shared.state.lock().unwrap();
+        loop {
+            state = shared
+                .cv
+                .wait_while(state, |(val, stop)| val.is_none() && !*stop)
+                .unwrap();
+
+            let val = match state.0.take() {
+                Some(val) if !state.1 => val,
+                _ => break,
+            };
+
+            drop(state);
+            cb(val);
+
+            // Rest for the window, but wake up for a stop.
+            state = shared
+                .cv
+                .wait_timeout_while(shared.state.lock().unwrap(), window, |(_, stop)| !*stop)
+                .unwrap()
+                .0;
+        }
+    }
+
+    /// Submits a new value, replacing any that is pending.
+    pub fn push(&self, val: T) {
+        let shared = &self.inner.shared;
+        shared.state.lock().unwrap().0 = Some(val);
+        shared.cv.notify_all();
+    }
+}
+
+impl<T> Clone for Coalescer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+// The values collected by a batcher.
+struct BatchState<T> {
+    // The values in the current batch
+    buf: Vec<T>,
+    // The time that the first value of the current batch arrived
+    since: Option<Instant>,
+    // Set when the last handle is dropped
+    stop: bool,
+}
+
+// The state shared between the batcher handles and its thread.
+struct BatchShared<T> {
+    state: Mutex<BatchState<T>>,
+    // Signaled when a value arrives, or on a stop
+    cv: Condvar,
+}
+
+// The owner of the shared state, which stops the thread when dropped.
+struct BatchInner<T> {
+    shared: Arc<BatchShared<T>>,
+}
+
+impl<T> Drop for BatchInner<T> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.stop = true;
+        }
+        self.shared.cv.notify_all();
+    }
+}
+
+/// An adapter that delivers events in chunks.
+///
+/// Values are collected until there are `size` of them, or until `period`
+/// has passed since the first one arrived, whichever comes first. Then
+/// they are delivered to the callback as a slice, from a background
+/// thread. If the callback falls behind, the next slice can hold more
+/// than `size` values, but none are dropped.
+///
+/// The handle is cheap to clone, so a copy can be moved into a device
+/// callback. The thread exits when the last handle is dropped, after
+/// delivering any partial batch.
+///
+/// ```rust,no_run
+/// use phidget::{devices::VoltageInput, throttle::Batcher};
+/// use std::time::Duration;
+///
+/// let batcher = Batcher::new(100, Duration::from_millis(250), |v: &[f64]| {
+///     println!("{} samples", v.len());
+/// });
+///
+/// let mut vin = VoltageInput::new();
+/// vin.set_on_voltage_change_handler(move |_, v| batcher.push(v)).unwrap();
+/// ```
+pub struct Batcher<T> {
+    inner: Arc<BatchInner<T>>,
+}
+
+impl<T> Batcher<T>
+where
+    T: Send + 'static,
+{
+    /// Creates a batcher that delivers values to the callback in chunks of
+    /// the specified size, or after the specified period, and starts its
+    /// thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is zero.
+    pub fn new<F>(size: usize, period: Duration, cb: F) -> Self
+    where
+        F: FnMut(&[T]) + Send + 'static,
+    {
+        assert!(size > 0, "batch size must be non-zero");
+        let shared = Arc::new(BatchShared {
+            state: Mutex::new(BatchState {
+                buf: Vec::with_capacity(size),
+                since: None,
+                stop: false,
+            }),
+            cv: Condvar::new(),
+        });
+
+        thread::spawn({
+            let shared = Arc::clone(&shared);
+            move || Self::run(&shared, size, period, cb)
+        });
+
+        Self {
+            inner: Arc::new(BatchInner { shared }),
+        }
+    }
+
+    // The delivery thread function.
+    fn run<F>(shared: &BatchShared<T>, size: usize, period: Duration, mut cb: F)
+    where
+        F: FnMut(&[T]),
+    {
+        // The batches are swapped in and out, so the buffers are reused.
+        let mut batch = Vec::with_capacity(size);
+        let mut state = shared.state.lock().unwrap();
+        loop {
+            state = shared
+                .cv
+                .wait_while(state, |st| st.buf.is_empty() && !st.stop)
+                .unwrap();
+
+            if let Some(since) = state.since {
+                let remaining = period.saturating_sub(since.elapsed());
+                state = shared
+                    .cv
+                    .wait_timeout_while(state, remaining, |st| st.buf.len() < size && !st.stop)
+                    .unwrap()
+                    .0;
+            }
+
+            let stop = state.stop;
+            std::mem::swap(&mut state.buf, &mut batch);
+            state.since = None;
+            drop(state);
+
+            if !batch.is_empty() {
+                cb(&batch);
+                batch.clear();
+            }
+            if stop {
+                break;
+            }
+            state = shared.state.lock().unwrap();
+        }
+    }
+
+    /// Adds a value to the current batch.
+    pub fn push(&self, val: T) {
+        let shared = &self.inner.shared;
+        let mut state = shared.state.lock().unwrap();
+        if state.buf.is_empty() {
+            state.since = Some(Instant::now());
+        }
+        state.buf.push(val);
+        drop(state);
+        shared.cv.notify_all();
+    }
+}
+
+impl<T> Clone for Batcher<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_throttle() {
+        let n = Arc::new(Mutex::new(Vec::new()));
+        let cb = throttle(Duration::from_secs(60), {
+            let n = Arc::clone(&n);
+            move |v: i32| n.lock().unwrap().push(v)
+        });
+        for i in 0..10 {
+            cb(i);
+        }
+        assert_eq!(*n.lock().unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let (tx, rx) = mpsc::channel();
+        let tx = Mutex::new(tx);
+        let co = Coalescer::new(Duration::from_millis(100), move |v: i32| {
+            let _ = tx.lock().unwrap().send(v);
+        });
+
+        co.push(0);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(0));
+
+        // A burst within the window only delivers the last value
+        for i in 1..=10 {
+            co.push(i);
+        }
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(10));
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+
+    #[test]
+    fn test_batch() {
+        let (tx, rx) = mpsc::channel();
+        let batcher = Batcher::new(4, Duration::from_millis(100), move |v: &[i32]| {
+            let _ = tx.send(v.to_vec());
+        });
+
+        // A full batch is delivered right away
+        for i in 0..4 {
+            batcher.push(i);
+        }
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(1)),
+            Ok(vec![0, 1, 2, 3])
+        );
+
+        // A partial batch is delivered after the period
+        batcher.push(4);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(vec![4]));
+
+        // ...and the rest when the batcher is dropped
+        batcher.push(5);
+        drop(batcher);
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(vec![5]));
+    }
+}
@@ -0,0 +1,301 @@
+// phidget-rs/src/topology.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Mapping of the devices in an installation.
+//!
+//! [`scan()`] uses a [`Manager`] to find all the channels that are
+//! reachable, and arranges them in a tree of servers, devices, hub ports,
+//! and channels. Local channels are placed under a server with an empty
+//! name. VINT devices report the serial number of the hub they are plugged
+//! into, so they appear under that hub, on their port.
+//!
+//! With the `serde` feature, the tree can be serialized, so that an
+//! installation can be documented, and compared against a saved copy to
+//! validate it.
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//!
+//! let topo = phidget::topology::scan(Duration::from_secs(2)).unwrap();
+//! print!("{}", topo);
+//! ```
+
+use crate::{
+    registry::DeviceRegistry, DeviceClass, DeviceId, HubPort, Manager, PhidgetInfo, Result,
+    SerialNumber,
+};
+use std::{collections::BTreeMap, fmt, thread, time::Duration};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The channels on a single port of a VINT hub.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PortNode {
+    /// The port number
+    pub port: HubPort,
+    /// The channels attached to the port
+    pub channels: Vec<PhidgetInfo>,
+}
+
+/// A single device, with its channels and, for a hub, its ports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceNode {
+    /// The serial number of the device
+    pub serial_number: SerialNumber,
+    /// The class of the device
+    pub device_class: DeviceClass,
+    /// The ID of the device model
+    pub device_id: DeviceId,
+    /// The name of the device
+    pub device_name: String,
+    /// The SKU (part number) of the device
+    pub device_sku: String,
+    /// The label of the device, if any
+    pub device_label: String,
+    /// The channels of the device itself
+    pub channels: Vec<PhidgetInfo>,
+    /// The ports of a hub that have channels attached
+    pub ports: Vec<PortNode>,
+}
+
+/// The devices reachable through a single server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ServerNode {
+    /// The name of the server, or empty for local devices
+    pub name: String,
+    /// The devices reachable through the server
+    pub devices: Vec<DeviceNode>,
+}
+
+/// A tree of servers, devices, hub ports, and channels.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Topology {
+    /// The servers, with the local one, if any, first
+    pub servers: Vec<ServerNode>,
+}
+
+impl Topology {
+    /// Arranges a set of channels into a tree.
+    pub fn from_channels<I>(chans: I) -> Self
+    where
+        I: IntoIterator<Item = PhidgetInfo>,
+    {
+        type Ports = BTreeMap<HubPort, Vec<PhidgetInfo>>;
+        type Devices = BTreeMap<SerialNumber, (Vec<PhidgetInfo>, Ports)>;
+
+        let mut servers: BTreeMap<String, Devices> = BTreeMap::new();
+        for info in chans {
+            let (own, ports) = servers
+                .entry(info.server_name.clone())
+                .or_default()
+                .entry(info.serial_number)
+                .or_default();
+
+            if info.hub_port.is_any() {
+                own.push(info);
+            }
+            else {
+                ports.entry(info.hub_port).or_default().push(info);
+            }
+        }
+
+        let servers = servers
+            .into_iter()
+            .map(|(name, devices)| ServerNode {
+                name,
+                devices: devices
+                    .into_iter()
+                    .map(|(serial_number, (channels, ports))| {
+                        Self::device_node(serial_number, channels, ports)
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self { servers }
+    }
+
+    // Creates the node for a device from its channels.
+    fn device_node(
+        serial_number: SerialNumber,
+        mut channels: Vec<PhidgetInfo>,
+        ports: BTreeMap<HubPort, Vec<PhidgetInfo>>,
+    ) -> DeviceNode {
+        channels.sort_by_key(|info| (info.channel_class, info.channel));
+
+        let ports: Vec<_> = ports
+            .into_iter()
+            .map(|(port, mut channels)| {
+                channels.sort_by_key(|info| (info.channel_class, info.channel));
+                PortNode { port, channels }
+            })
+            .collect();
+
+        // The device is described by its own channels, like the channel of
+        // a hub. A device with only port channels is a hub that didn't
+        // report its own, so the best we can do is the serial number.
+        let (device_class, device_id, device_name, device_sku, device_label) =
+            match channels.first() {
+                Some(info) => (
+                    info.device_class,
+                    info.device_id,
+                    info.device_name.clone(),
+                    info.device_sku.clone(),
+                    info.device_label.clone(),
+                ),
+                None => (
+                    DeviceClass::Hub,
+                    DeviceId::Unknown,
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                ),
+            };
+
+        DeviceNode {
+            serial_number,
+            device_class,
+            device_id,
+            device_name,
+            device_sku,
+            device_label,
+            channels,
+            ports,
+        }
+    }
+
+    /// Finds a device by serial number, on any server.
+    pub fn device(&self, serial_number: SerialNumber) -> Option<&DeviceNode> {
+        self.servers
+            .iter()
+            .flat_map(|srv| srv.devices.iter())
+            .find(|dev| dev.serial_number == serial_number)
+    }
+
+    /// Iterates over all the channels in the tree.
+    pub fn channels(&self) -> impl Iterator<Item = &PhidgetInfo> {
+        self.servers
+            .iter()
+            .flat_map(|srv| srv.devices.iter())
+            .flat_map(|dev| {
+                dev.channels
+                    .iter()
+                    .chain(dev.ports.iter().flat_map(|port| port.channels.iter()))
+            })
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for srv in &self.servers {
+            if srv.name.is_empty() {
+                writeln!(f, "local")?;
+            }
+            else {
+                writeln!(f, "server '{}'", srv.name)?;
+            }
+            for dev in &srv.devices {
+                write!(f, "  {} [{}]", dev.device_sku, dev.serial_number)?;
+                if !dev.device_label.is_empty() {
+                    write!(f, " '{}'", dev.device_label)?;
+                }
+                writeln!(f, " {}", dev.device_name)?;
+
+                for info in &dev.channels {
+                    writeln!(f, "    ch {}: {}", info.channel, info.channel_name)?;
+                }
+                for port in &dev.ports {
+                    writeln!(f, "    port {}", port.port)?;
+                    for info in &port.channels {
+                        writeln!(
+                            f,
+                            "      {} ch {}: {}",
+                            info.device_sku, info.channel, info.channel_name
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scans for all the reachable channels, and arranges them into a tree.
+///
+/// The manager reports the channels as it finds them, so this collects
+/// them for the full timeout. Remote channels are only found if the
+/// network servers were added beforehand.
+pub fn scan(to: Duration) -> Result<Topology> {
+    let reg = DeviceRegistry::new();
+    let mut mgr = Manager::new();
+    reg.connect(&mut mgr)?;
+    mgr.open()?;
+    thread::sleep(to);
+    mgr.close()?;
+    Ok(Topology::from_channels(reg.channels()))
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Channel, ChannelClass};
+
+    fn info(hub_port: i32, cls: ChannelClass, sku: &str) -> PhidgetInfo {
+        PhidgetInfo {
+            serial_number: SerialNumber::new(12345).unwrap(),
+            hub_port: HubPort::new(hub_port).unwrap(),
+            channel: Channel::new(0).unwrap(),
+            is_hub_port_device: false,
+            channel_class: cls,
+            device_class: if hub_port < 0 {
+                DeviceClass::Hub
+            }
+            else {
+                DeviceClass::Vint
+            },
+            device_id: DeviceId::Unknown,
+            channel_name: String::new(),
+            device_name: String::new(),
+            device_sku: sku.into(),
+            device_version: 100,
+            device_label: String::new(),
+            server_name: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_from_channels() {
+        let topo = Topology::from_channels(vec![
+            info(3, ChannelClass::TemperatureSensor, "TMP1101"),
+            info(-1, ChannelClass::Hub, "HUB0000"),
+            info(0, ChannelClass::HumiditySensor, "HUM1001"),
+            info(0, ChannelClass::TemperatureSensor, "HUM1001"),
+        ]);
+
+        assert_eq!(topo.servers.len(), 1);
+        assert_eq!(topo.channels().count(), 4);
+
+        let hub = topo.device(SerialNumber::new(12345).unwrap()).unwrap();
+        assert_eq!(hub.device_sku, "HUB0000");
+        assert_eq!(hub.device_class, DeviceClass::Hub);
+
+        let ports: Vec<_> = hub.ports.iter().map(|p| p.port.value()).collect();
+        assert_eq!(ports, [0, 3]);
+        assert_eq!(hub.ports[0].channels.len(), 2);
+    }
+}
@@ -0,0 +1,206 @@
+// phidget-rs/src/watch.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! A latest-value cache for sensor readings.
+//!
+//! This is a small, synchronous take on a "watch" channel. The sender side
+//! is fed by a device change handler, and any number of receivers can read
+//! the most recent reading, with its timestamp, on demand, or block until
+//! a new one arrives. The receivers can tell when the sender is gone,
+//! such as when the device change handler that fed it was replaced.
+//!
+//! The sensors create these with their `watch()` methods:
+//!
+//! ```rust,no_run
+//! use phidget::{devices::TemperatureSensor, Phidget};
+//!
+//! let mut sensor = TemperatureSensor::new();
+//! let temp = sensor.watch().unwrap();
+//! sensor.open_wait_default().unwrap();
+//!
+//! if let Some(rd) = temp.latest() {
+//!     println!("{:.1} C, {:?} ago", rd.value, rd.timestamp.elapsed());
+//! }
+//! ```
+
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A reading and the time at which it was received.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading<T> {
+    /// The value of the reading
+    pub value: T,
+    /// The time at which the reading was received
+    pub timestamp: Instant,
+}
+
+// The state of the channel, under the lock.
+struct State<T> {
+    // The latest reading
+    latest: Option<Reading<T>>,
+    // The count of readings sent
+    count: u64,
+    // Whether the sender was dropped
+    closed: bool,
+}
+
+// The state shared between the sender and receivers.
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    // Signaled when a new reading is sent, or the sender is dropped
+    cv: Condvar,
+}
+
+/// Creates a new watch channel, returning the sender and receiver halves.
+pub fn channel<T>() -> (WatchSender<T>, WatchReceiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            latest: None,
+            count: 0,
+            closed: false,
+        }),
+        cv: Condvar::new(),
+    });
+    let tx = WatchSender {
+        shared: Arc::clone(&shared),
+    };
+    let rx = WatchReceiver { shared, seen: 0 };
+    (tx, rx)
+}
+
+/// The sending half of a watch channel.
+pub struct WatchSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> WatchSender<T> {
+    /// Sends a new value, stamped with the current time, replacing the
+    /// previous one.
+    pub fn send(&self, value: T) {
+        let rd = Reading {
+            value,
+            timestamp: Instant::now(),
+        };
+        let mut state = self.shared.state.lock().unwrap();
+        state.latest = Some(rd);
+        state.count += 1;
+        self.shared.cv.notify_all();
+    }
+}
+
+impl<T> Drop for WatchSender<T> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.closed = true;
+        }
+        self.shared.cv.notify_all();
+    }
+}
+
+/// The receiving half of a watch channel.
+///
+/// This can be cloned to share the readings with other consumers. Each
+/// clone tracks separately which readings it has seen.
+pub struct WatchReceiver<T> {
+    shared: Arc<Shared<T>>,
+    // The count of the last reading seen by this receiver
+    seen: u64,
+}
+
+impl<T: Clone> WatchReceiver<T> {
+    /// Gets the most recent reading, if there has been one.
+    pub fn latest(&self) -> Option<Reading<T>> {
+        self.shared.state.lock().unwrap().latest.clone()
+    }
+
+    /// Gets the most recent value, if there has been one.
+    pub fn value(&self) -> Option<T> {
+        self.latest().map(|rd| rd.value)
+    }
+
+    /// Determines if there is a reading that this receiver has not yet
+    /// seen through [`changed()`](Self::changed).
+    pub fn has_changed(&self) -> bool {
+        self.shared.state.lock().unwrap().count != self.seen
+    }
+
+    /// Determines if the sender is gone, so that no more readings will
+    /// arrive.
+    pub fn is_closed(&self) -> bool {
+        self.shared.state.lock().unwrap().closed
+    }
+
+    /// Waits a limited time for a reading that this receiver has not yet
+    /// seen, and returns it. Returns `None` on a timeout, or right away if
+    /// the sender is gone.
+    pub fn changed(&mut self, to: Duration) -> Option<Reading<T>> {
+        let seen = self.seen;
+        let (state, _) = self
+            .shared
+            .cv
+            .wait_timeout_while(self.shared.state.lock().unwrap(), to, |state| {
+                state.count == seen && !state.closed
+            })
+            .unwrap();
+
+        if state.count == seen {
+            return None;
+        }
+        self.seen = state.count;
+        state.latest.clone()
+    }
+}
+
+impl<T> Clone for WatchReceiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            seen: self.seen,
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_watch() {
+        let (tx, mut rx) = channel();
+        assert_eq!(rx.value(), None);
+        assert!(rx.changed(Duration::from_millis(1)).is_none());
+
+        tx.send(1.5);
+        tx.send(2.5);
+        assert_eq!(rx.value(), Some(2.5));
+        assert!(rx.has_changed());
+        assert_eq!(rx.changed(Duration::ZERO).map(|rd| rd.value), Some(2.5));
+        assert!(!rx.has_changed());
+
+        let thr = thread::spawn(move || tx.send(3.5));
+        assert_eq!(
+            rx.changed(Duration::from_secs(1)).map(|rd| rd.value),
+            Some(3.5)
+        );
+        thr.join().unwrap();
+
+        // The sender was dropped by the thread
+        assert!(rx.is_closed());
+        assert!(rx.changed(Duration::from_secs(10)).is_none());
+        assert_eq!(rx.value(), Some(3.5));
+    }
+}
@@ -0,0 +1,156 @@
+// phidget-rs/src/watcher.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! Hot-plug notifications delivered over a channel.
+//!
+//! The [`spawn()`] function opens a [`Manager`] which reports channels as
+//! they are attached to and detached from the system. The events are sent
+//! to the returned [`Watcher`], which can be read like any other channel:
+//!
+//! ```rust,no_run
+//! use phidget::watcher;
+//!
+//! for evt in watcher::spawn().unwrap() {
+//!     println!("{:?}", evt);
+//! }
+//! ```
+
+use crate::{Manager, PhidgetInfo, Result};
+use std::{
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+/// A hot-plug event for a single channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeviceEvent {
+    /// The channel was attached to the system
+    Attached(PhidgetInfo),
+    /// The channel was detached from the system
+    Detached(PhidgetInfo),
+}
+
+impl DeviceEvent {
+    /// Gets information about the channel that triggered the event.
+    pub fn info(&self) -> &PhidgetInfo {
+        match self {
+            DeviceEvent::Attached(info) | DeviceEvent::Detached(info) => info,
+        }
+    }
+
+    /// Determines if this is an attach event.
+    pub fn is_attached(&self) -> bool {
+        matches!(self, DeviceEvent::Attached(_))
+    }
+}
+
+// Creates a manager that sends its events to the callback.
+fn open_manager<F>(send: F) -> Result<Manager>
+where
+    F: Fn(DeviceEvent) + Clone + Send + 'static,
+{
+    let mut mgr = Manager::new();
+
+    let cb = send.clone();
+    mgr.set_on_attach_handler(move |ph| {
+        if let Ok(info) = PhidgetInfo::try_from(ph) {
+            cb(DeviceEvent::Attached(info));
+        }
+    })?;
+
+    mgr.set_on_detach_handler(move |ph| {
+        if let Ok(info) = PhidgetInfo::try_from(ph) {
+            send(DeviceEvent::Detached(info));
+        }
+    })?;
+
+    mgr.open()?;
+    Ok(mgr)
+}
+
+/// Starts watching for channels being attached and detached.
+///
+/// The manager reports from its own event thread, beginning with an
+/// attach event for each of the channels that are already attached.
+/// Watching stops when the [`Watcher`] is dropped.
+pub fn spawn() -> Result<Watcher> {
+    let (tx, rx) = mpsc::channel();
+    let mgr = open_manager(move |evt| {
+        let _ = tx.send(evt);
+    })?;
+    Ok(Watcher { rx, _mgr: mgr })
+}
+
+/// The receiving end of a hot-plug [`spawn()`].
+pub struct Watcher {
+    // The channel receiving the events
+    rx: Receiver<DeviceEvent>,
+    // The manager generating the events
+    _mgr: Manager,
+}
+
+impl Watcher {
+    /// Blocks until the next event arrives.
+    pub fn recv(&self) -> Option<DeviceEvent> {
+        self.rx.recv().ok()
+    }
+
+    /// Waits a limited time for the next event.
+    ///
+    /// Returns `None` if no event arrived in time.
+    pub fn recv_timeout(&self, to: Duration) -> Option<DeviceEvent> {
+        self.rx.recv_timeout(to).ok()
+    }
+
+    /// Gets the next event, if one is already waiting.
+    pub fn try_recv(&self) -> Option<DeviceEvent> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Iterator for Watcher {
+    type Item = DeviceEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+/// Starts watching for channels being attached and detached, delivering
+/// the events to an async task.
+#[cfg(feature = "tokio")]
+pub fn spawn_async() -> Result<AsyncWatcher> {
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let mgr = open_manager(move |evt| {
+        let _ = tx.send(evt);
+    })?;
+    Ok(AsyncWatcher { rx, _mgr: mgr })
+}
+
+/// The receiving end of a hot-plug [`spawn_async()`].
+#[cfg(feature = "tokio")]
+pub struct AsyncWatcher {
+    // The channel receiving the events
+    rx: tokio::sync::mpsc::UnboundedReceiver<DeviceEvent>,
+    // The manager generating the events
+    _mgr: Manager,
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncWatcher {
+    /// Waits for the next event.
+    pub async fn recv(&mut self) -> Option<DeviceEvent> {
+        self.rx.recv().await
+    }
+}
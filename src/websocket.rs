@@ -0,0 +1,264 @@
+// phidget-rs/src/websocket.rs
+//
+// Copyright (c) 2024, Frank Pagliughi
+//
+// This file is part of the 'phidget-rs' library.
+//
+// Licensed under the MIT license:
+//   <LICENSE or http://opensource.org/licenses/MIT>
+// This file may not be copied, modified, or distributed except according
+// to those terms.
+//
+//! A WebSocket server that pushes device events to connected clients.
+//!
+//! This gives a browser dashboard live data from the Phidgets, without an
+//! intermediate message broker. Each message is sent to every connected
+//! client as a JSON text frame. The server can forward:
+//!
+//! - Events from the [event bus](crate::bus), with
+//!   [`EventServer::subscribe()`], like `{"Attached":{"serial_number":...}}`
+//! - Anything serializable, like sensor readings, through an
+//!   [`EventSender`], which can be moved into a device callback.
+//!
+//! ```rust,no_run
+//! use phidget::{devices::VoltageInput, websocket::EventServer, DeviceSpec, Phidget};
+//!
+//! let server = EventServer::bind("0.0.0.0:8080").unwrap();
+//! server.subscribe(DeviceSpec::new()).unwrap();
+//!
+//! let tx = server.sender();
+//! let mut vin = VoltageInput::new();
+//! vin.set_on_voltage_change_handler(move |_, v| tx.send(&("vin0", v)))
+//!     .unwrap();
+//! vin.open_wait_default().unwrap();
+//! ```
+//!
+//! This requires the `websocket` feature. Messages from the clients are
+//! not read, and a client that can't keep up is disconnected.
+
+use crate::{
+    bus::{bus, SubscriptionId},
+    DeviceSpec, ErrorCode, Result,
+};
+use serde::Serialize;
+use std::{
+    io::ErrorKind,
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+use tungstenite::{Message, WebSocket};
+
+// How often the accept thread checks for a shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// The time allowed for a client to complete the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// The time allowed to send a message to a client
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// The connected clients
+type Clients = Arc<Mutex<Vec<WebSocket<TcpStream>>>>;
+
+// A command for the writer thread
+enum Cmd {
+    Send(String),
+    Stop,
+}
+
+/// A handle to send messages to the clients of an [`EventServer`].
+///
+/// This is cheap to clone, and can be moved into device callbacks. Sending
+/// never blocks, as the messages are written to the clients by a
+/// background thread. Messages sent after the server is dropped are
+/// discarded.
+#[derive(Clone)]
+pub struct EventSender {
+    tx: mpsc::Sender<Cmd>,
+}
+
+impl EventSender {
+    /// Sends a value to all the clients, as JSON.
+    pub fn send<T: Serialize + ?Sized>(&self, msg: &T) {
+        if let Ok(json) = serde_json::to_string(msg) {
+            self.send_text(json);
+        }
+    }
+
+    /// Sends a text message, like pre-formatted JSON, to all the clients.
+    pub fn send_text(&self, text: String) {
+        let _ = self.tx.send(Cmd::Send(text));
+    }
+}
+
+/// A WebSocket server for device events.
+///
+/// The server stops, and disconnects the clients, when this is dropped.
+pub struct EventServer {
+    // The address the server is listening on
+    addr: SocketAddr,
+    // The connected clients
+    clients: Clients,
+    // The sender for the writer thread
+    tx: EventSender,
+    // The subscriptions to the event bus
+    subs: Mutex<Vec<SubscriptionId>>,
+    // Flag to stop the accept thread
+    stop: Arc<AtomicBool>,
+    // The thread accepting new clients
+    accept_thr: Option<JoinHandle<()>>,
+    // The thread writing messages to the clients
+    write_thr: Option<JoinHandle<()>>,
+}
+
+impl EventServer {
+    /// Starts a server listening on the specified address.
+    ///
+    /// Returns an `Io` error if the address can't be bound.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let listener = TcpListener::bind(addr).map_err(|_| ErrorCode::Io)?;
+        let addr = listener.local_addr().map_err(|_| ErrorCode::Io)?;
+        listener.set_nonblocking(true).map_err(|_| ErrorCode::Io)?;
+
+        let clients = Clients::default();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let accept_thr = thread::spawn({
+            let clients = Arc::clone(&clients);
+            let stop = Arc::clone(&stop);
+            move || Self::accept(listener, &clients, &stop)
+        });
+
+        let (tx, rx) = mpsc::channel();
+        let write_thr = thread::spawn({
+            let clients = Arc::clone(&clients);
+            move || Self::write(rx, &clients)
+        });
+
+        Ok(Self {
+            addr,
+            clients,
+            tx: EventSender { tx },
+            subs: Mutex::new(Vec::new()),
+            stop,
+            accept_thr: Some(accept_thr),
+            write_thr: Some(write_thr),
+        })
+    }
+
+    // The accept thread function.
+    fn accept(
+        listener: TcpListener,
+        clients: &Mutex<Vec<WebSocket<TcpStream>>>,
+        stop: &AtomicBool,
+    ) {
+        while !stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Some(ws) = Self::handshake(stream) {
+                        clients.lock().unwrap().push(ws);
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(_) => thread::sleep(POLL_INTERVAL),
+            }
+        }
+    }
+
+    // Completes the WebSocket handshake with a new client.
+    fn handshake(stream: TcpStream) -> Option<WebSocket<TcpStream>> {
+        stream.set_nonblocking(false).ok()?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).ok()?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok()?;
+        tungstenite::accept(stream).ok()
+    }
+
+    // The writer thread function.
+    fn write(rx: mpsc::Receiver<Cmd>, clients: &Mutex<Vec<WebSocket<TcpStream>>>) {
+        while let Ok(Cmd::Send(text)) = rx.recv() {
+            clients
+                .lock()
+                .unwrap()
+                .retain_mut(|ws| ws.send(Message::Text(text.clone())).is_ok());
+        }
+        for mut ws in clients.lock().unwrap().drain(..) {
+            let _ = ws.close(None);
+            let _ = ws.flush();
+        }
+    }
+
+    /// Gets the address that the server is listening on.
+    ///
+    /// This is useful to find the port when binding to port zero.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Gets the number of connected clients.
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Gets a handle for sending messages to the clients.
+    pub fn sender(&self) -> EventSender {
+        self.tx.clone()
+    }
+
+    /// Forwards the bus events for the channels that match the filter to
+    /// the clients.
+    pub fn subscribe(&self, filter: DeviceSpec) -> Result<()> {
+        let tx = self.sender();
+        let id = bus().subscribe(filter, move |evt| tx.send(evt))?;
+        self.subs.lock().unwrap().push(id);
+        Ok(())
+    }
+}
+
+impl Drop for EventServer {
+    fn drop(&mut self) {
+        for id in self.subs.lock().unwrap().drain(..) {
+            bus().unsubscribe(id);
+        }
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.tx.tx.send(Cmd::Stop);
+
+        if let Some(thr) = self.accept_thr.take() {
+            let _ = thr.join();
+        }
+        if let Some(thr) = self.write_thr.take() {
+            let _ = thr.join();
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_send() {
+        let server = EventServer::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", server.local_addr());
+        let (mut client, _) = tungstenite::connect(url).unwrap();
+
+        let start = Instant::now();
+        while server.client_count() == 0 {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        server.sender().send(&("vin0", 1.5));
+        assert_eq!(
+            client.read().unwrap(),
+            Message::Text(r#"["vin0",1.5]"#.into())
+        );
+    }
+}